@@ -0,0 +1,67 @@
+//! Arrows and circles drawn over the board, like on an analysis board.
+//!
+//! These are purely visual and never touch the emulator. Coordinates are in rotchess units.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Annotation {
+    Arrow { from: (f32, f32), to: (f32, f32) },
+    Circle { at: (f32, f32) },
+}
+
+/// Annotations for each turn of the history, so flipping through a game shows the
+/// arrows that were drawn (and comments written) on each position.
+#[derive(Default)]
+pub struct Annotations {
+    by_turn: HashMap<usize, Vec<Annotation>>,
+    comments: HashMap<usize, String>,
+}
+
+impl Annotations {
+    /// Add an annotation to a turn, or take it away if that exact one is already there.
+    pub fn toggle(&mut self, turn: usize, annotation: Annotation) {
+        let annotations = self.by_turn.entry(turn).or_default();
+        if let Some(i) = annotations.iter().position(|a| *a == annotation) {
+            annotations.remove(i);
+        } else {
+            annotations.push(annotation);
+        }
+    }
+
+    pub fn get(&self, turn: usize) -> &[Annotation] {
+        self.by_turn.get(&turn).map_or(&[], Vec::as_slice)
+    }
+
+    /// Every annotation, along with the turn it's on.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Annotation)> {
+        self.by_turn
+            .iter()
+            .flat_map(|(turn, annotations)| annotations.iter().map(|a| (*turn, a)))
+    }
+
+    /// Clear the arrows and circles on a turn. Comments stay.
+    pub fn clear(&mut self, turn: usize) {
+        self.by_turn.remove(&turn);
+    }
+
+    pub fn comment(&self, turn: usize) -> Option<&str> {
+        self.comments.get(&turn).map(String::as_str)
+    }
+
+    /// Set the comment on a turn. An empty comment removes it.
+    pub fn set_comment(&mut self, turn: usize, comment: String) {
+        if comment.is_empty() {
+            self.comments.remove(&turn);
+        } else {
+            self.comments.insert(turn, comment);
+        }
+    }
+
+    /// Every comment, along with the turn it's on.
+    pub fn comments(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.comments
+            .iter()
+            .map(|(turn, comment)| (*turn, comment.as_str()))
+    }
+}
@@ -1,14 +1,24 @@
 //! An app that lets users play and see (update/draw) chess, computed with help from [`rotchess_core`] and macroquad.
 
-use std::{collections::HashMap, f32::consts::TAU, path::Path};
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    f32::consts::TAU,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use ggez::{
     Context, GameError, GameResult,
     event::EventHandler,
     glam::Vec2,
-    graphics::{Canvas, Color, DrawMode, DrawParam, Image, Mesh, MeshBuilder, Rect},
+    graphics::{
+        Canvas, Color, DrawMode, DrawParam, Drawable, Image, ImageEncodingFormat, ImageFormat,
+        Mesh, MeshBuilder, Rect, Text, TextFragment, TextLayout,
+    },
+    input::mouse::{self, CursorIcon},
     winit::{
-        keyboard::{Key, NamedKey},
+        keyboard::{Key, ModifiersState, NamedKey},
         platform::modifier_supplement::KeyEventExtModifierSupplement,
     },
 };
@@ -16,15 +26,62 @@ use rand::seq::SliceRandom;
 use rotchess_core::{
     RotchessEmulator,
     emulator::{self, Event, ThingHappened, TravelKind},
-    piece::{PIECE_RADIUS, Piece, Pieces},
+    piece::{PIECE_RADIUS, Piece, PieceKind, Pieces, Side},
 };
 use sfn_tpn::{Config, NetcodeInterface};
 use tokio::sync::oneshot;
 
-use crate::constants::*;
+#[cfg(feature = "hot-reload")]
+use crate::hot_reload::ResourceWatcher;
+use crate::{
+    annotation::{Annotation, Annotations},
+    assets::{self, DEFAULT_PIECE_SET, PieceImages},
+    audio::{Cue, DEFAULT_SOUND_SET, Sounds},
+    bandwidth::Bandwidth,
+    bench::{Bench, FRAMES_PER_SCRIPT_STEP, RenderCounters},
+    blunders::{self, Blunder},
+    book::Book,
+    bot::{MoveOption, Opponent},
+    clock::{ChessClock, format_clock},
+    constants::*,
+    effects::{self, Fade, MoveTrail, PieceAnimation, RejectedClick, RotationGhost},
+    engine::{self, Personality, Search, Suggestion},
+    exhibition::{DEFAULT_ACTION_DELAY, Exhibition},
+    game::{Game, GameEvent},
+    history::Action,
+    history_panel::HistoryPanel,
+    hitcircles,
+    keymap::{Command, Keymap},
+    layers::{Layer, Layers},
+    library::{self, LibraryEntry, SAVE_EXTENSION},
+    minimap::MiniMap,
+    netplay::{Netplay, Received},
+    notation::{self, PieceSnapshot},
+    notifications::Notifications,
+    outcome::{self, Outcome},
+    pass_screen::{PassScreen, pass_screen_from_args},
+    persistence::{Mode, SavedGame},
+    piece_history::{self, Stop},
+    playback::Playback,
+    profiling,
+    protocol::{self, Activity, Message},
+    replay::Replay,
+    rules::{self, GameRules},
+    settings::{SETTINGS_PATH, Settings},
+    snapping::SnapStep,
+    snapshots::{SNAPSHOT_SIZE, Scenario},
+    spectate::Relay,
+    stats::{self, GameStats},
+    theme::{self, DEFAULT_THEME, Theme},
+    ticker::Ticker,
+    transform::BoardTransform,
+    turn_phase::{TurnPhase, TurnPhaseMachine},
+};
 
 // TODO: pull this out into a sfn_tpn::get_netcode_interface_naive() or such.
-async fn get_netcode_interface() -> GameResult<NetcodeInterface<TURN_SIZE>> {
+/// The netcode asked for on the command line, or `None` if we're hosting and the relay
+/// couldn't be reached.
+async fn get_netcode_interface() -> GameResult<Option<NetcodeInterface<TURN_SIZE>>> {
     /// Return whether our process is a client.
     ///
     /// If not, we must be the server.
@@ -66,16 +123,20 @@ async fn get_netcode_interface() -> GameResult<NetcodeInterface<TURN_SIZE>> {
     }
 
     if is_client()? {
-        Ok(NetcodeInterface::new(Config::Ticket(ticket()?)))
+        Ok(Some(NetcodeInterface::new(Config::Ticket(ticket()?))))
     } else {
         let (send, recv) = oneshot::channel();
         let net = NetcodeInterface::<TURN_SIZE>::new(Config::TicketSender(send));
+        let Ok(ticket) = recv.await else {
+            // the netcode gave up before it had a ticket for us.
+            println!("Couldn't set up a game to host.");
+            return Ok(None);
+        };
         println!(
             "hosting game. another player may join with \n\n\
-            cargo run client --ticket={}",
-            recv.await.unwrap()
+            cargo run client --ticket={ticket}"
         );
-        Ok(net)
+        Ok(Some(net))
     }
 }
 
@@ -85,550 +146,5310 @@ enum ChessLayout {
 }
 
 impl ChessLayout {
-    fn get_pieces(&self) -> Pieces {
+    /// Get the pieces for a fresh board in this layout.
+    ///
+    /// For Chess960, also returns the back rank ordering we rolled, so the board can be
+    /// recreated later (see [`Replay`]).
+    fn get_pieces(&self) -> (Pieces, Option<[usize; 8]>) {
         match self {
-            ChessLayout::Standard => Pieces::standard_board(),
-            ChessLayout::Chess960 => Pieces::chess960_board(|| {
-                let mut ordering: [usize; 8] = std::array::from_fn(|i| i);
-                ordering.shuffle(&mut rand::rng());
-                ordering
-            }),
+            ChessLayout::Standard => (Pieces::standard_board(), None),
+            ChessLayout::Chess960 => {
+                let ordering = roll_chess960_ordering();
+                (Pieces::chess960_board(|| ordering), Some(ordering))
+            }
         }
     }
 }
 
-/// The ID for an image is the file stem from its file path.
-///
-/// See [`App::load_images`], where they are canonically generated.
-type ImageID = String;
-
-#[derive(PartialEq, Eq)]
-enum TurnPhase {
-    Move,
-    Rotate,
-    Wait,
+/// Whether to play a hot-seat game, from `--local`. See [`App::hot_seat`].
+pub fn hot_seat_from_args() -> bool {
+    std::env::args().any(|arg| arg == "--local")
 }
 
-pub struct App {
-    chess: RotchessEmulator,
-    runit_to_world_multiplier: f32,
-    images: HashMap<ImageID, Image>,
-    chess_layout: ChessLayout,
-    /// ERM TODO I FORGOR IF THIS IS ROT UNITS OR PX UNITS. DOUBLE CHECK ON ME WHERE IM INSTANTIATED.
-    mouse_pos: (f32, f32),
-    netcode: NetcodeInterface<TURN_SIZE>,
-    turn_phase: TurnPhase,
+/// Whether a game against the computer was asked for, with `--vs-bot`.
+pub fn vs_bot_from_args() -> bool {
+    std::env::args().any(|arg| arg == "--vs-bot")
 }
 
-/// Misc utility functions
-impl App {
-    pub async fn new(ctx: &mut Context) -> GameResult<Self> {
-        let mut s = Self {
-            chess: RotchessEmulator::with(Pieces::standard_board()),
-            runit_to_world_multiplier: 0.,
-            images: Self::load_images(ctx),
-            chess_layout: ChessLayout::Standard,
-            mouse_pos: (0., 0.),
-            netcode: get_netcode_interface().await?,
-            turn_phase: TurnPhase::Wait,
-        };
+/// The ticket of a game to watch, from `--spectate --ticket=...`.
+pub fn spectate_from_args() -> GameResult<Option<String>> {
+    if !std::env::args().any(|arg| arg == "--spectate") {
+        return Ok(None);
+    }
+    std::env::args()
+        .find_map(|arg| arg.strip_prefix("--ticket=").map(str::to_string))
+        .map(Some)
+        .ok_or_else(|| {
+            GameError::CustomError(
+                "No ticket provided. Spectators must provide a ticket to find a game.".to_string(),
+            )
+        })
+}
 
-        s.turn_phase = if s.netcode.my_turn() {
-            TurnPhase::Move
-        } else {
-            TurnPhase::Wait
-        };
+/// Whether a networked game was asked for on the command line, with `server` or `client`.
+/// Otherwise, we start at the main menu. See [`crate::scenes`].
+pub fn netcode_from_args() -> bool {
+    std::env::args().any(|arg| arg == "server" || arg == "client")
+}
 
-        s.update_runit_to_world_multiplier(STARTING_WINDOW_SIZE, STARTING_WINDOW_SIZE);
+/// The saved game to pick up, from `--load=path`.
+pub fn load_path_from_args() -> Option<PathBuf> {
+    std::env::args().find_map(|arg| match arg.split_once("=") {
+        Some(("--load", path)) => Some(PathBuf::from(path)),
+        _ => None,
+    })
+}
 
-        Ok(s)
-    }
+/// Whether we were started as a client but not given a `--ticket=...`, so have to ask for
+/// one.
+pub fn client_needs_ticket() -> bool {
+    std::env::args().any(|arg| arg == "client")
+        && !std::env::args().any(|arg| arg.starts_with("--ticket="))
+}
 
-    fn load_images(ctx: &mut Context) -> HashMap<ImageID, Image> {
-        const IMAGE_PATHS: [&str; 12] = [
-            "piece_bishopB1.png",
-            "piece_bishopW1.png",
-            "piece_kingB1.png",
-            "piece_kingW1.png",
-            "piece_knightB1.png",
-            "piece_knightW1.png",
-            "piece_pawnB1.png",
-            "piece_pawnW1.png",
-            "piece_queenB1.png",
-            "piece_queenW1.png",
-            "piece_rookB1.png",
-            "piece_rookW1.png",
-        ];
-        let image_dir = Path::new("pieces_png");
-
-        let mut images = HashMap::new();
-        for path in IMAGE_PATHS {
-            images.insert(
-                Path::new(path)
-                    .file_stem()
-                    .expect("Hardcoded file stems exist.")
-                    .to_str()
-                    .expect("Hardcoded utf8 file names should convert to str.")
-                    .to_string(),
-                Image::from_path(ctx, Path::new("/").join(image_dir.join(path)))
-                    .expect("Hardcoded file names/dir should yield a correct path."),
-            );
+/// A random legal Chess960 back rank ordering.
+pub fn roll_chess960_ordering() -> [usize; 8] {
+    // chess960_board rerolls until it gets a legal one, so the last roll is the one.
+    let rolled = Cell::new([0; 8]);
+    Pieces::chess960_board(|| {
+        let mut ordering: [usize; 8] = std::array::from_fn(|i| i);
+        // saves and the rules we send go by our own check, so only roll what passes it.
+        loop {
+            ordering.shuffle(&mut rand::rng());
+            if rules::is_chess960_ordering(&ordering) {
+                break;
+            }
         }
+        rolled.set(ordering);
+        ordering
+    });
+    rolled.get()
+}
 
-        images
+/// A fresh board in some layout.
+fn starting_pieces(chess960_ordering: Option<[usize; 8]>) -> Pieces {
+    match chess960_ordering {
+        Some(ordering) => Pieces::chess960_board(|| ordering),
+        None => Pieces::standard_board(),
     }
+}
 
-    fn update_runit_to_world_multiplier(&mut self, screen_width: f32, screen_height: f32) {
-        self.runit_to_world_multiplier = f32::min(screen_width, screen_height) / 8.;
-    }
+/// Where along the window a banner sits.
+enum BannerPosition {
+    Top,
+    /// Along the bottom of the board, just above the scrubber.
+    Bottom,
+}
 
-    /// Converts from a rotchess unit to world unit (pixel).
-    ///
-    /// Must be run after we update the ratio after any screen resize, lest the value be outdated.
-    fn cnv_r(&self, a: f32) -> f32 {
-        a * self.runit_to_world_multiplier
-    }
+/// Keys that do their thing again and again while held, at our own pace rather than
+/// the OS's key repeat rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RepeatingKey {
+    RotateView,
+    /// Turn the moved piece a step, during our rotation phase.
+    TurnPiece {
+        clockwise: bool,
+        step: RotationStep,
+    },
+}
 
-    /// Converts from a world unit (pixel) to rotchess unit.
-    ///
-    /// Must be run after we update the ratio after any screen resize, lest the value be outdated.
-    fn cnv_w(&self, a: f32) -> f32 {
-        a / self.runit_to_world_multiplier
+impl RepeatingKey {
+    fn from_command(command: Command, mods: ModifiersState) -> Option<Self> {
+        match command {
+            Command::RotateView => Some(Self::RotateView),
+            Command::TurnPieceCounterclockwise => Some(Self::TurnPiece {
+                clockwise: false,
+                step: RotationStep::from_mods(mods),
+            }),
+            Command::TurnPieceClockwise => Some(Self::TurnPiece {
+                clockwise: true,
+                step: RotationStep::from_mods(mods),
+            }),
+            _ => None,
+        }
     }
-}
 
-/// Netcode related stuff for our app.
-impl App {
-    /// Sends an event to our inner chess emulator, unless it is not our turn.
-    ///
-    /// If a thing happened under the hood, send it to the other player.
-    /// If we did an illegal turn phase action, revert it.
-    fn try_send_event(&mut self, e: Event) {
-        if self.netcode.my_turn()
-            && let Some(thing_happened) = self.chess.handle_event(e)
-        {
-            match thing_happened {
-                ThingHappened::Move(_, _, _) => {
-                    if let TurnPhase::Rotate = self.turn_phase {
-                        // disallow move on rotation phase
-                        println!(
-                            "Player turns consist of a move and a rotation in that order.
-                             No moving in your rotation phase!"
-                        );
-                        self.chess.handle_event(Event::PrevTurn);
-                        return;
-                    }
-                    self.turn_phase = TurnPhase::Rotate;
-                }
-                // if we rotated, use a little (evil) hack to deselect the piece
-                // that we're rotating. I, the dev of rotchess-core, know right button
-                // down can only select. so, we send a select click to narnia (-1000,-1000)
-                // Nothing should be selectable there, so we deselect.
-                ThingHappened::Rotate(_, _) => {
-                    if let TurnPhase::Move = self.turn_phase {
-                        // disallow rotation on move phase
-                        println!(
-                            "Player turns consist of a move and a rotation in that order.
-                             No rotating in your move phase!"
-                        );
-                        self.chess.handle_event(Event::PrevTurn);
-                        return;
-                    }
-                    debug_assert!(
-                        self.chess
-                            .handle_event(Event::ButtonDown {
-                                x: -1000.,
-                                y: -1000.,
-                                button: emulator::MouseButton::RIGHT,
-                            })
-                            .is_none(),
-                        "Nothing should have happened as detectable by the NothingHappened enum.",
-                    );
-                    self.turn_phase = TurnPhase::Wait;
-                }
-                _ => (),
-            };
-            self.netcode
-                .send_turn(&Self::ser_thing(Some(&thing_happened)));
+    /// Whether two presses are of the same key, whatever modifiers were held. The
+    /// modifiers might be let go first.
+    fn same_key(self, other: Self) -> bool {
+        match (self, other) {
+            (Self::RotateView, Self::RotateView) => true,
+            (Self::TurnPiece { clockwise: a, .. }, Self::TurnPiece { clockwise: b, .. }) => a == b,
+            _ => false,
         }
     }
+}
 
-    // yes, we're doing these manually. huzzah!
+/// How far the keys and scroll wheel turn a piece at a time, going by the modifiers held.
+/// The steps themselves are [`Settings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RotationStep {
+    Fine,
+    Normal,
+    Coarse,
+}
 
-    /// Serialize a Thing into a netcode byte buffer turn.
-    fn ser_thing(thing: Option<&ThingHappened>) -> [u8; TURN_SIZE] {
-        // we really don't need to have
-        // a usize be the piece index, we don't have enough pieces on
-        // the board. a single u8 is enough. but for type convenience,
-        // we're leaving it as a usize. If someone manages to get
-        // more than 256 pieces on the board, that probably violates
-        // some invariant somewhere. (aren't pieces supposed to not
-        // stack?)
-        let mut ans = [0; TURN_SIZE];
-        match thing {
-            Some(ThingHappened::FirstTurn) => ans[0] = 1,
-            Some(ThingHappened::PrevTurn) => ans[0] = 2,
-            Some(ThingHappened::NextTurn) => ans[0] = 3,
-            Some(ThingHappened::LastTurn) => ans[0] = 4,
-            Some(ThingHappened::Rotate(piece_idx, r)) => {
-                ans[0] = 5;
-                ans[1] = (*piece_idx).try_into().expect("See above");
-                ans[2..6].copy_from_slice(&r.to_be_bytes());
-            }
-            Some(ThingHappened::Move(piece_idx, x, y)) => {
-                ans[0] = 6;
-                ans[1] = (*piece_idx).try_into().expect("See above");
-                ans[2..6].copy_from_slice(&x.to_be_bytes());
-                ans[6..10].copy_from_slice(&y.to_be_bytes());
-            }
-            None => ans[0] = 7,
+impl RotationStep {
+    fn from_mods(mods: ModifiersState) -> Self {
+        if mods.control_key() {
+            Self::Fine
+        } else if mods.shift_key() {
+            Self::Coarse
+        } else {
+            Self::Normal
         }
-        ans
     }
+}
+
+/// The geometry around the selected piece, kept between frames until something changes.
+struct SelectionMeshes {
+    /// The [`App::scene_version`] these were built at.
+    version: u64,
+    /// The [`BoardTransform::scale`] these were built at.
+    built_at: f32,
+    /// Drawn under the pieces.
+    under: Option<Mesh>,
+    /// Drawn over the pieces.
+    over: Option<Mesh>,
+}
 
-    /// Deserialize a Thing from a netcode byte buffer turn.
-    fn de_thing(thing: &[u8; TURN_SIZE]) -> Option<ThingHappened> {
-        match thing[0] {
-            1 => Some(ThingHappened::FirstTurn),
-            2 => Some(ThingHappened::PrevTurn),
-            3 => Some(ThingHappened::NextTurn),
-            4 => Some(ThingHappened::LastTurn),
-            5 => {
-                let piece_idx = thing[1] as usize;
+/// The game library while it's open: the saved games, which one is picked, and a thumbnail
+/// of each once it's been drawn.
+struct LibraryView {
+    entries: Vec<LibraryEntry>,
+    selected: usize,
+    thumbnails: Vec<Option<Image>>,
+}
 
-                let mut r_bytes = [0; size_of::<f32>()];
-                r_bytes.copy_from_slice(&thing[2..6]);
-                let r = f32::from_be_bytes(r_bytes);
+/// The summary of a game's blunders while it's open, and which one is picked.
+struct BlunderReview {
+    blunders: Vec<Blunder>,
+    selected: usize,
+}
 
-                Some(ThingHappened::Rotate(piece_idx, r))
-            }
-            6 => {
-                let piece_idx = thing[1] as usize;
+/// How we take part in a networked game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    /// We play one side.
+    Player,
+    /// We only watch, following along with whatever the host relays. See [`crate::spectate`].
+    Spectator {
+        /// How many actions of a resync are still coming, to be played out quietly.
+        catching_up: usize,
+    },
+}
 
-                let mut x_bytes = [0; size_of::<f32>()];
-                x_bytes.copy_from_slice(&thing[2..6]);
-                let x = f32::from_be_bytes(x_bytes);
+/// Analysis mode: the search from the board we're looking at, and the actions that led
+/// there, so we know when it's out of date.
+struct Analysis {
+    actions: Vec<Action>,
+    /// `None` halfway through a turn, where there's no whole turn to suggest.
+    search: Option<Search>,
+}
 
-                let mut y_bytes = [0; size_of::<f32>()];
-                y_bytes.copy_from_slice(&thing[6..10]);
-                let y = f32::from_be_bytes(y_bytes);
+/// Write stats for some saved games to a CSV file in the working directory.
+fn export_stats(entries: &[LibraryEntry]) -> GameResult {
+    let stats: Vec<GameStats> = entries.iter().map(GameStats::of).collect();
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = PathBuf::from(format!("stats-{secs}.csv"));
+    std::fs::write(&path, stats::to_csv(&stats))?;
+    println!(
+        "exported stats for {} games to {}",
+        stats.len(),
+        path.display()
+    );
+    Ok(())
+}
 
-                Some(ThingHappened::Move(piece_idx, x, y))
-            }
-            7 => None,
-            _ => panic!("Received malformed data from opponent."),
-        }
+/// A bot to play against, the way `personality` likes to, knowing whatever openings it can.
+fn new_opponent(ctx: &Context, personality: Personality, resign_below: Option<f32>) -> Opponent {
+    let mut opponent = Opponent::new(rand::random(), personality);
+    opponent.resign_below = resign_below;
+    match Book::load(ctx) {
+        Ok(book) => opponent.book = Some(book),
+        Err(e) => println!("Couldn't read the opening book, so the computer won't use one: {e}"),
     }
+    opponent
 }
 
-#[cfg(test)]
-mod test_serde_thinghappened {
-    use super::App;
-    use parameterized::parameterized;
-    use rotchess_core::emulator::ThingHappened;
+/// A fresh game in some layout with some actions played on it, or `None` if an action is
+/// about a piece the board doesn't have.
+fn play_out(chess960_ordering: Option<[usize; 8]>, actions: &[Action]) -> Option<Game> {
+    Game::played_out(starting_pieces(chess960_ordering), actions)
+}
 
-    /// .
+pub struct App {
+    /// The board and its history. Everything that changes them goes through [`App::apply`].
+    game: Game,
+    /// Where the board is on screen. Only affects drawing, and where clicks land.
+    transform: BoardTransform,
+    /// The scale that fits the window, which `transform`'s eases towards.
+    target_scale: f32,
+    /// When the window was last resized, until the cached meshes are rebuilt for the new size.
+    resized_at: Option<Instant>,
+    images: PieceImages,
+    settings: Settings,
+    keymap: Keymap,
+    theme: Theme,
+    /// The ggez resource path `theme` was loaded from.
+    theme_file: PathBuf,
+    /// Whether we're presenting to a room: everything bigger, board coordinates shown, and
+    /// the last move always highlighted.
+    projector_mode: bool,
+    /// Whether to draw the hit circle debug layer. See [`crate::hitcircles`].
+    show_hitcircles: bool,
+    /// Which [layers](crate::layers) of the screen are hidden.
+    layers: Layers,
+    /// The board's tiles, built on the first draw after [`App::invalidate_meshes`], and the
+    /// [`BoardTransform::scale`] they were built at.
+    board_mesh: Option<(f32, Mesh)>,
+    /// Highlights and travel points for the selected piece, rebuilt when `scene_version` moves.
+    selection_meshes: Option<SelectionMeshes>,
+    /// Goes up whenever the selection, the mouse, or the board might have changed.
+    scene_version: u64,
+    /// What the players agreed to play by. Only networked and hot-seat games pick these.
+    rules: GameRules,
+    chess_layout: ChessLayout,
+    /// The back rank ordering of the board, if it's a Chess960 board.
+    chess960_ordering: Option<[usize; 8]>,
+    /// Last known mouse position, in rotchess units.
+    mouse_pos: (f32, f32),
+    /// The latest mouse motion the emulator hasn't heard about yet, in rotchess units.
     ///
-    /// Well, ThingHappened doesnt have PartialEq so I guess we're comparing
-    /// the byte buffers.
-    fn assert_deser_bijective(thing: Option<&ThingHappened>) {
-        assert_eq!(
-            App::ser_thing(App::de_thing(&App::ser_thing(thing)).as_ref()),
-            App::ser_thing(thing)
-        )
-    }
+    /// Mice can report motion many times a frame, so we only pass on the latest once a frame.
+    pending_motion: Option<(f32, f32)>,
+    /// The last mouse motion the emulator heard about, so we don't tell it the same thing twice.
+    last_sent_motion: Option<(f32, f32)>,
+    /// The turn phase from before each of the game's undone turns, newest last, to pick up
+    /// in when it's redone. [`App::apply`] forgets them when the game does.
+    undone_phases: Vec<TurnPhaseMachine>,
+    /// Whether the user is dragging the history scrubber.
+    scrubbing: bool,
+    /// Whether the user is dragging the board around with the middle mouse button.
+    panning: bool,
+    /// Whether the user is dragging the camera around the mini-map.
+    minimap_dragging: bool,
+    /// The turn the scrubber was dragged to, if we haven't gotten there yet.
+    scrub_target: Option<usize>,
+    /// Arrows and circles drawn over each turn.
+    annotations: Annotations,
+    /// The comment being typed for the current turn, if the user is typing one.
+    comment_draft: Option<String>,
+    /// Where the user started right-dragging an annotation, in rotchess units.
+    annotation_drag: Option<(f32, f32)>,
+    /// The past turn we're looking at, and when we'll be brought back to the live
+    /// position if we keep looking at it during our turn.
+    snap_back: Option<(usize, Instant)>,
+    /// The repeating key being held down, and when it fires next.
+    held_key: Option<(RepeatingKey, Instant)>,
+    /// When the window lost focus, if the game's paused for it. See
+    /// [`Settings::pause_when_unfocused`].
+    unfocused_since: Option<Instant>,
+    /// The board when the mouse was last pressed, i.e. from before any move or rotation
+    /// made since.
+    pressed_board: Vec<PieceSnapshot>,
+    /// The last rotated piece at its old angle, while it fades away.
+    rotation_ghost: Option<RotationGhost>,
+    /// How dragged rotations snap, while the modifiers for it are held.
+    snap_step: Option<SnapStep>,
+    /// Hides the board between turns of a hot-seat game, if that was asked for.
+    pass_screen: Option<PassScreen>,
+    /// The list of moves down the side of the window, when it's open.
+    history_panel: HistoryPanel,
+    /// The angle the keys or scroll wheel have turned the moved piece to, waiting on enter
+    /// to play it. See [`App::turn_piece`].
+    rotation_draft: Option<f32>,
+    /// A line along the last move, while it fades away.
+    move_trail: Option<MoveTrail>,
+    /// Everywhere a piece clicked while looking back went over the game, and every angle
+    /// it held there. Only drawn while we're looking back.
+    inspected_piece: Vec<Stop>,
+    /// Pieces sliding or turning into place after an action, at most one per piece.
+    animations: Vec<PieceAnimation>,
+    /// Small pictures of the board at each turn we've drawn it at, keyed by turn, for
+    /// finding positions from the scrubber.
+    thumbnails: BTreeMap<usize, Image>,
+    /// The x position (in pixels) of the mouse over the scrubber, if it's there.
+    scrubber_hover: Option<f32>,
+    /// The game library, while it's open.
+    library: Option<LibraryView>,
+    /// The blunders of the game so far, while they're being looked at.
+    blunder_review: Option<BlunderReview>,
+    /// Whether the game has ended and we've shown its blunders, so we don't again.
+    post_game_reviewed: bool,
+    /// How the game ended on the board, if it has: see [`outcome`].
+    board_outcome: Option<Outcome>,
+    /// How many actions the live game had, and the last of them, when `board_outcome` was
+    /// last worked out. It's worked out again when they change.
+    outcome_checked: (usize, Option<Action>),
+    /// The engine looking for a good turn from the board we're looking at, while analysis
+    /// mode (`n`) is on.
+    analysis: Option<Analysis>,
+    /// A flash where the last click wasn't allowed, while it fades away.
+    rejected_click: Option<RejectedClick>,
+    /// A flash on the HUD's turn phase after something was done out of turn, while it fades.
+    phase_warning: Option<Fade>,
+    /// What's been happening, in words.
+    ticker: Ticker,
+    /// Toasts about things the user just tried.
+    notifications: Notifications,
+    /// How long each action in the history took, from the one before it.
+    action_times: Vec<Duration>,
+    /// When the last action (or the game) started, to time the next one from.
+    last_action_at: Instant,
+    /// The cursor we last asked the window for, so we only ask when it changes.
+    cursor: CursorIcon,
+    /// The connection to the opponent, or `None` if both sides are played on this computer.
+    netcode: Option<NetcodeInterface<TURN_SIZE>>,
+    /// What's gone over the network so far.
+    bandwidth: Bandwidth,
+    /// The ticket to join our game with, while we're hosting and nobody has yet.
+    ticket: Option<String>,
+    /// Whether we play or only watch. Always [`Role::Player`] in local games.
+    role: Role,
+    /// The connection we relay the game to a spectator down, while we're hosting.
+    relay: Option<Relay>,
+    turn: TurnPhaseMachine,
+    /// The side we play. Whoever moves first plays white.
+    my_side: Side,
+    /// What the opponent's turns come to, and getting back in sync when they make no sense.
+    netplay: Netplay,
+    /// Whether the opponent told us they quit. Once set, we stop talking to them.
+    opponent_left: bool,
+    /// When the user last touched the mouse or keyboard.
+    last_input: Instant,
+    /// Whether we told the opponent we're away, and haven't said we're back yet.
+    away_sent: bool,
+    /// Whether the opponent told us they're away.
+    opponent_away: bool,
+    /// What we last told the opponent we're up to on our turn, and when.
+    activity_sent: (Activity, Instant),
+    /// What the opponent last told us they're up to on their turn.
+    opponent_activity: Activity,
+    sounds: Sounds,
+    /// Sounds to play next update, for things that happened without a [`Context`] around
+    /// to play them with.
+    cues_due: Vec<Cue>,
+    /// Whether we're asking the user if they really want to resign and quit.
+    ///
+    /// The board doesn't take input while this is up.
+    quit_dialog_open: bool,
+    /// Whether we want to take back our last turn. We can't say so while it's the opponent's
+    /// turn, so we ask in place of acknowledging their next move.
+    takeback_wanted: bool,
+    /// Whether we asked the opponent for a takeback and they haven't answered.
+    takeback_asked: bool,
+    /// Whether we're asking the user if the opponent may take back their last turn.
+    ///
+    /// The board doesn't take input while this is up.
+    takeback_dialog_open: bool,
+    /// Whether we want a rematch, once it's our turn to say so. See [`App::step_rematch`].
+    rematch_wanted: bool,
+    /// Whether we asked the opponent for a rematch and they haven't answered.
+    rematch_asked: bool,
+    /// Whether we're asking the user if they'll give the opponent a rematch.
+    rematch_dialog_open: bool,
+    /// How many actions in we asked the opponent to roll the game back to, while they
+    /// haven't answered.
+    rollback_asked: Option<usize>,
+    /// How many actions in the opponent wants to roll the game back to, while we're asking
+    /// the user if that's alright.
+    ///
+    /// The board doesn't take input while this is up.
+    rollback_offered: Option<usize>,
+    /// When we last handed the turn over after the game ended.
+    game_over_passed_at: Instant,
+    /// Whether our move and rotation stay on our side until we end our turn with Space,
+    /// from `--staged`. The opponent doesn't see a half-made turn that way.
+    staged_turns: bool,
+    /// Our actions this turn that the opponent hasn't heard about yet, if turns are staged.
+    staged: Vec<ThingHappened>,
+    /// The board from before the staged actions, to draw ghosts of where things were.
+    staged_board: Vec<PieceSnapshot>,
+    /// Whether the user already agreed to resign, so the next quit event should go through.
+    quit_confirmed: bool,
+    /// What it took to draw the current frame.
+    render_counters: RenderCounters,
+    /// The benchmark we're running, if we're in benchmark mode.
+    bench: Option<Bench>,
+    /// The chess clock, if the game has one.
+    clock: Option<ChessClock>,
+    /// The bots playing each other, if we're in exhibition mode.
+    exhibition: Option<Exhibition>,
+    /// The bot we're playing against, if it's a game against the computer.
+    opponent_bot: Option<Opponent>,
+    /// The replay being played back, if we're in playback mode.
+    playback: Option<Playback>,
+    /// Whether we're set up for passersby, with a demo game playing whenever nobody is.
+    kiosk: bool,
+    #[cfg(feature = "hot-reload")]
+    resource_watcher: Option<ResourceWatcher>,
+}
 
-    #[test]
-    fn none_serialization_is_bijective() {
-        assert_deser_bijective(None);
+/// Misc utility functions
+impl App {
+    /// The networked game asked for on the command line, or `None` if it was ours to host
+    /// and there was no relay to host it through.
+    pub async fn new(ctx: &mut Context) -> GameResult<Option<Self>> {
+        let rules = GameRules::from_args(roll_chess960_ordering)?;
+        Ok(get_netcode_interface()
+            .await?
+            .map(|netcode| Self::networked(ctx, netcode, rules, None)))
     }
 
-    #[test]
-    fn firstturn_serialization_is_bijective() {
-        assert_deser_bijective(Some(&ThingHappened::FirstTurn));
+    /// A game over the network. If we're hosting, it's played by `rules`, and `ticket` is
+    /// what the opponent joins with, to show until they do. If not, the host tells us the
+    /// rules.
+    pub fn networked(
+        ctx: &mut Context,
+        netcode: NetcodeInterface<TURN_SIZE>,
+        rules: GameRules,
+        ticket: Option<String>,
+    ) -> Self {
+        let mut s = Self::with_netcode(ctx, Some(netcode));
+        s.staged_turns = std::env::args().any(|arg| arg == "--staged");
+        if s.my_turn() {
+            // we're hosting, so we pick the rules. the game starts once the client agrees.
+            s.send_turn(&protocol::ser_message(&Message::Rules(rules)));
+            s.apply_rules(rules, rules.host_side);
+            s.ticket = ticket;
+            s.relay = Some(Relay::host(rules));
+        }
+        s
     }
 
-    #[test]
-    fn prevturn_serialization_is_bijective() {
-        assert_deser_bijective(Some(&ThingHappened::PrevTurn));
+    /// Watch a networked game, hosted by whoever gave out `ticket`. The host tells us the
+    /// rules, then everything that happens.
+    pub fn spectating(ctx: &mut Context, ticket: String) -> Self {
+        let mut s = Self::with_netcode(ctx, Some(NetcodeInterface::new(Config::Ticket(ticket))));
+        s.role = Role::Spectator { catching_up: 0 };
+        s
     }
 
-    #[test]
-    fn nextturn_serialization_is_bijective() {
-        assert_deser_bijective(Some(&ThingHappened::NextTurn));
+    /// A game where both sides are played on this computer.
+    pub fn local(ctx: &mut Context) -> Self {
+        Self::with_netcode(ctx, None)
     }
 
-    #[test]
-    fn lastturn_serialization_is_bijective() {
-        assert_deser_bijective(Some(&ThingHappened::LastTurn));
+    /// A hot-seat game: two players taking turns at this computer, with no network at all.
+    pub fn hot_seat(ctx: &mut Context, rules: GameRules) -> Self {
+        let mut s = Self::local(ctx);
+        s.apply_rules(rules, Side::White);
+        if pass_screen_from_args() {
+            s.pass_screen = Some(PassScreen::default());
+        }
+        s
     }
-    #[parameterized(rotate_thing = {
-        &ThingHappened::Rotate(2, 91.246876218913),
-        &ThingHappened::Rotate(6, 01.797548620909),
-        &ThingHappened::Rotate(5, 08.147878140881),
-        &ThingHappened::Rotate(1, 21.581176862643),
-        &ThingHappened::Rotate(7, 32.217517844368),
-        &ThingHappened::Rotate(4, 90.522625314885),
-        &ThingHappened::Rotate(1, 23.927154940674),
-        &ThingHappened::Rotate(8, 53.959229741122),
-        &ThingHappened::Rotate(8, 60.743439712343),
-        &ThingHappened::Rotate(8, 82.152850235763),
-    })]
-    fn rotate_serialization_is_bijective(rotate_thing: &ThingHappened) {
-        assert_deser_bijective(Some(rotate_thing));
+
+    /// A game against the computer, which plays whichever side `rules` doesn't give us the
+    /// way `personality` likes to.
+    pub fn vs_bot(
+        ctx: &mut Context,
+        rules: GameRules,
+        personality: Personality,
+        resign_below: Option<f32>,
+    ) -> Self {
+        let mut s = Self::local(ctx);
+        s.opponent_bot = Some(new_opponent(ctx, personality, resign_below));
+        s.apply_rules(rules, rules.host_side);
+        s
     }
 
-    #[parameterized(move_thing = {
-        &ThingHappened::Move(28, 11.352279394256, 81.647432982848),
-        &ThingHappened::Move(74, 30.000701136234, 90.218648211692),
-        &ThingHappened::Move(47, 56.161192888566, 02.448786090013),
-        &ThingHappened::Move(86, 54.106803274653, 61.299734032137),
-        &ThingHappened::Move(86, 28.528662175474, 48.520872175935),
-        &ThingHappened::Move(26, 66.300609468152, 85.435537391159),
-        &ThingHappened::Move(77, 73.688001636818, 68.715058900751),
-        &ThingHappened::Move(10, 68.328589705709, 11.444493994595),
-        &ThingHappened::Move(29, 65.925913814140, 87.078698941045),
-        &ThingHappened::Move(85, 38.747317527971, 20.528927188939),
-    })]
-    fn move_serialization_is_bijective(move_thing: &ThingHappened) {
-        assert_deser_bijective(Some(move_thing));
+    /// A game picked up from a save file, against whoever it was against.
+    pub fn loaded(ctx: &mut Context, path: &Path) -> GameResult<Self> {
+        let mut s = Self::local(ctx);
+        s.continue_game(ctx, &SavedGame::load(path)?)?;
+        Ok(s)
     }
-}
 
-/// Helper functions for drawing
-impl App {
-    fn draw_board(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
-        let mut mb = MeshBuilder::new();
-        mb.rectangle(
-            DrawMode::fill(),
-            Rect::new(0., 0., self.cnv_r(8.), self.cnv_r(8.)),
-            LIGHT_TILE_COLOR,
+    /// A replay (or saved game) playing back from the start, to watch.
+    pub fn playback(ctx: &mut Context, path: &Path) -> GameResult<Self> {
+        let game = SavedGame::load(path)?;
+        let mut s = Self::local(ctx);
+        // the annotations and comments, on the board the game started from.
+        s.continue_game(
+            ctx,
+            &SavedGame {
+                mode: Mode::HotSeat,
+                board: None,
+                replay: Replay {
+                    actions: Vec::new(),
+                    times: Vec::new(),
+                    annotations: game.replay.annotations.clone(),
+                    comments: game.replay.comments.clone(),
+                    ..game.replay
+                },
+            },
         )?;
+        s.playback = Some(Playback::new(&game.replay, Instant::now()));
+        Ok(s)
+    }
 
-        let mut top = 0;
-        let mut left = 1;
-        let mut next_row_immediate_dark = true;
+    /// A local game that plays itself for some frames, then reports how rendering went.
+    pub fn bench(ctx: &mut Context, frames: u32) -> Self {
+        let mut s = Self::local(ctx);
+        s.bench = Some(Bench::new(frames));
+        s
+    }
 
-        const NUM_TILES: u8 = 8 * 8;
-        const NUM_DARK_TILES: u8 = NUM_TILES / 2;
+    /// A local game two bots play against each other, waiting `delay` before each action.
+    pub fn exhibition(ctx: &mut Context, delay: Duration) -> Self {
+        let mut s = Self::local(ctx);
+        s.exhibition = Some(Exhibition::new(delay));
+        s
+    }
 
-        for _ in 0..NUM_DARK_TILES {
-            mb.rectangle(
-                DrawMode::fill(),
-                Rect::new(
-                    self.cnv_r(left as f32),
-                    self.cnv_r(top as f32),
-                    self.cnv_r(1.),
-                    self.cnv_r(1.),
-                ),
-                DARK_TILE_COLOR,
-            )?;
+    /// A local game that plays a demo of itself whenever it's left alone for a while.
+    pub fn kiosk(ctx: &mut Context) -> Self {
+        let mut s = Self::local(ctx);
+        s.kiosk = true;
+        s
+    }
 
-            left += 2;
-            if left >= 8 {
-                left = if next_row_immediate_dark { 0 } else { 1 };
-                next_row_immediate_dark = !next_row_immediate_dark;
-                top += 1;
+    fn with_netcode(ctx: &mut Context, netcode: Option<NetcodeInterface<TURN_SIZE>>) -> Self {
+        let goes_first = netcode.as_ref().is_none_or(NetcodeInterface::my_turn);
+        let hands_over = netcode.is_some();
+        let mut s = Self {
+            game: Game::new(Pieces::standard_board()),
+            transform: BoardTransform::default(),
+            target_scale: 0.,
+            resized_at: None,
+            images: PieceImages::load(ctx, Path::new(DEFAULT_PIECE_SET))
+                .expect("Hardcoded file names/dir should yield a correct path."),
+            settings: Settings::load(Path::new(SETTINGS_PATH)).unwrap_or_else(|e| {
+                println!("{e} Using the default settings.");
+                Settings::default()
+            }),
+            keymap: Keymap::load(&ctx.fs.user_data_dir()).unwrap_or_else(|e| {
+                println!("{e} Using the default keys.");
+                Keymap::default()
+            }),
+            theme: Theme::default(),
+            theme_file: PathBuf::from(DEFAULT_THEME),
+            projector_mode: false,
+            show_hitcircles: false,
+            layers: Layers::default(),
+            board_mesh: None,
+            selection_meshes: None,
+            scene_version: 0,
+            rules: GameRules::default(),
+            chess_layout: ChessLayout::Standard,
+            chess960_ordering: None,
+            mouse_pos: (0., 0.),
+            pending_motion: None,
+            last_sent_motion: None,
+            undone_phases: Vec::new(),
+            scrubbing: false,
+            minimap_dragging: false,
+            panning: false,
+            scrub_target: None,
+            annotations: Annotations::default(),
+            comment_draft: None,
+            annotation_drag: None,
+            snap_back: None,
+            held_key: None,
+            unfocused_since: None,
+            pressed_board: Vec::new(),
+            rotation_ghost: None,
+            rotation_draft: None,
+            pass_screen: None,
+            history_panel: HistoryPanel::default(),
+            snap_step: None,
+            move_trail: None,
+            inspected_piece: Vec::new(),
+            animations: Vec::new(),
+            thumbnails: BTreeMap::new(),
+            scrubber_hover: None,
+            library: None,
+            blunder_review: None,
+            post_game_reviewed: false,
+            board_outcome: None,
+            outcome_checked: (0, None),
+            analysis: None,
+            rejected_click: None,
+            phase_warning: None,
+            ticker: Ticker::default(),
+            notifications: Notifications::default(),
+            action_times: Vec::new(),
+            last_action_at: Instant::now(),
+            cursor: CursorIcon::Default,
+            netcode,
+            bandwidth: Bandwidth::default(),
+            ticket: None,
+            role: Role::Player,
+            relay: None,
+            turn: TurnPhaseMachine::new(goes_first, hands_over),
+            // whoever moves first plays white.
+            my_side: if goes_first { Side::White } else { Side::Black },
+            netplay: Netplay::default(),
+            opponent_left: false,
+            last_input: Instant::now(),
+            away_sent: false,
+            opponent_away: false,
+            activity_sent: (Activity::Thinking, Instant::now()),
+            opponent_activity: Activity::Thinking,
+            sounds: Sounds::default(),
+            cues_due: Vec::new(),
+            quit_dialog_open: false,
+            takeback_wanted: false,
+            takeback_asked: false,
+            takeback_dialog_open: false,
+            rematch_wanted: false,
+            rematch_asked: false,
+            rematch_dialog_open: false,
+            rollback_asked: None,
+            rollback_offered: None,
+            game_over_passed_at: Instant::now(),
+            staged_turns: false,
+            staged: Vec::new(),
+            staged_board: Vec::new(),
+            quit_confirmed: false,
+            render_counters: RenderCounters::default(),
+            bench: None,
+            clock: None,
+            exhibition: None,
+            opponent_bot: None,
+            playback: None,
+            kiosk: false,
+            #[cfg(feature = "hot-reload")]
+            resource_watcher: ResourceWatcher::new(),
+        };
+
+        s.transform.scale =
+            BoardTransform::fitting_scale(STARTING_WINDOW_SIZE, STARTING_WINDOW_SIZE);
+        s.target_scale = s.transform.scale;
+        s.sounds = Sounds::load(ctx, &s.settings, Path::new(DEFAULT_SOUND_SET));
+
+        s
+    }
+
+    /// Swap in fresh copies of any piece images that changed on disk.
+    ///
+    /// A broken image (e.g. one an artist is halfway through saving) is reported and skipped,
+    /// keeping the old image around until the next change.
+    #[cfg(feature = "hot-reload")]
+    fn hot_reload_images(&mut self, ctx: &mut Context) {
+        let Some(watcher) = &mut self.resource_watcher else {
+            return;
+        };
+        for path in watcher.poll() {
+            if path.extension().is_none_or(|ext| ext != "png") {
+                continue;
+            }
+            match self.images.reload(ctx, &path) {
+                Ok(true) => println!("hot-reloaded {path:?}"),
+                Ok(false) => (),
+                Err(e) => println!("could not hot-reload {path:?}: {e}"),
             }
         }
+    }
 
-        // TODO: creating new board mesh every frame.
-        let board_mesh = Mesh::from_data(ctx, mb.build());
-        canvas.draw(&board_mesh, Vec2::ZERO);
+    /// Change the game, whether it's us, the opponent, a bot or a replay changing it, and
+    /// return what happened, if anything. See [`Game::apply`].
+    fn apply(&mut self, event: GameEvent) -> Option<ThingHappened> {
+        let _span = profiling::span("apply");
+        self.scene_version += 1;
+        let thing = self.game.apply(event);
+        self.undone_phases.truncate(self.game.undone_turns());
+        if thing
+            .as_ref()
+            .is_some_and(|thing| Action::from_thing(thing).is_some())
+        {
+            // the game it went through isn't the game anymore.
+            self.inspected_piece.clear();
+        }
+        thing
+    }
 
-        Ok(())
+    /// Show what an action just did, given the board from before it: a trail along a move
+    /// or a ghost of the piece before a rotation, fading out, and a line on the ticker.
+    ///
+    /// Call this once the action is in the history. It's also when the action gets timed.
+    fn show_action(&mut self, before: &[PieceSnapshot], action: Action) {
+        self.action_times
+            .truncate(self.game.history().len().saturating_sub(1));
+        self.action_times.push(self.last_action_at.elapsed());
+        self.last_action_at = Instant::now();
+        let turn = self.game.history().current();
+        if self.bench.is_none() {
+            self.cues_due.push(match action {
+                Action::Move(piece_idx, x, y)
+                    if notation::captured_by(before, piece_idx, x, y).is_some() =>
+                {
+                    Cue::Capture
+                }
+                Action::Move(..) => Cue::Move,
+                Action::Rotate(..) => Cue::Rotate,
+            });
+        }
+        match action {
+            Action::Move(piece_idx, x, y) => {
+                if let Some(piece) = before.get(piece_idx) {
+                    self.move_trail = Some(MoveTrail {
+                        from: (piece.x, piece.y),
+                        to: (x, y),
+                        turn,
+                        fade: Fade::new(MOVE_TRAIL_DURATION),
+                    });
+                }
+            }
+            Action::Rotate(piece_idx, _) => {
+                if let Some(piece) = before.get(piece_idx) {
+                    self.rotation_ghost = Some(RotationGhost {
+                        piece_idx,
+                        angle: piece.angle,
+                        turn,
+                        fade: Fade::new(ROTATION_GHOST_DURATION),
+                    });
+                }
+            }
+        }
+        if let Some(line) = notation::describe(before, &action) {
+            self.ticker.announce(line);
+        }
+        self.animate(before, action);
     }
 
-    fn draw_piece_outline(
-        &self,
-        (ctx, canvas): (&mut Context, &mut Canvas),
-        x: f32,
-        y: f32,
-        color: Color,
-    ) -> GameResult {
-        canvas.draw(
-            &Mesh::new_circle(
-                ctx,
-                DrawMode::stroke(1.),
-                Vec2::ZERO,
-                self.cnv_r(PIECE_RADIUS),
-                CIRC_TOLERANCE,
-                color,
-            )?,
-            Vec2::new(self.cnv_r(x), self.cnv_r(y)),
-        );
-        Ok(())
+    /// Start a piece sliding or turning from where it was before an action to where it is.
+    ///
+    /// With reduced motion, pieces just jump there.
+    fn animate(&mut self, before: &[PieceSnapshot], action: Action) {
+        if self.settings.reduced_motion {
+            return;
+        }
+        let (Action::Move(piece_idx, ..) | Action::Rotate(piece_idx, _)) = action;
+        let Some(piece) = before.get(piece_idx) else {
+            return;
+        };
+        let to = match action {
+            Action::Move(_, x, y) => (x, y, piece.angle),
+            Action::Rotate(_, r) => (piece.x, piece.y, r),
+        };
+        // a piece still on its way picks up from wherever it's got to.
+        let from = match self
+            .animations
+            .iter()
+            .position(|animation| animation.is_at(piece.x, piece.y))
+        {
+            Some(i) => self.animations.swap_remove(i).pose(),
+            None => (piece.x, piece.y, piece.angle),
+        };
+        // and one that was on its way to a piece that just got captured is done.
+        self.animations
+            .retain(|animation| !animation.is_at(to.0, to.1));
+        self.animations.push(PieceAnimation::new(
+            from,
+            to,
+            self.game.history().current(),
+            PIECE_ANIMATION_DURATION,
+        ));
     }
 
-    fn draw_piece_highlight(
-        &self,
-        (ctx, canvas): (&mut Context, &mut Canvas),
-        x: f32,
-        y: f32,
-        color: Color,
-    ) -> GameResult {
-        /// Extra addition to the radius of the drawn circle.
-        ///
-        /// When highlighting a piece, there will be an outline over it. Without
-        /// extra tolerance, there will be background poking in between the highlight
-        /// and outline.
-        const TOLERANCE: f32 = 0.5;
-
-        canvas.draw(
-            &Mesh::new_circle(
-                ctx,
-                DrawMode::fill(),
-                Vec2::ZERO,
-                self.cnv_r(PIECE_RADIUS) + TOLERANCE,
-                CIRC_TOLERANCE,
-                color,
-            )?,
-            Vec2::new(self.cnv_r(x), self.cnv_r(y)),
-        );
-        Ok(())
+    /// Move piece animations along by a frame, and let go of finished ones.
+    fn step_animations(&mut self, delta: Duration) {
+        for animation in &mut self.animations {
+            animation.step(delta);
+        }
+        self.animations.retain(|animation| !animation.is_done());
     }
 
-    fn draw_movablepoint_indicator(
-        &self,
-        (ctx, canvas): (&mut Context, &mut Canvas),
-        x: f32,
-        y: f32,
-    ) -> GameResult {
-        canvas.draw(
-            &Mesh::new_circle(
-                ctx,
-                DrawMode::fill(),
-                Vec2::ZERO,
-                self.cnv_r(0.12),
-                CIRC_TOLERANCE,
-                MOVE_HIGHLIGHT_COLOR,
-            )?,
-            Vec2::new(self.cnv_r(x), self.cnv_r(y)),
-        );
-        Ok(())
+    /// Flash where a click was, in rotchess units, to show it was heard but not allowed.
+    fn reject_click(&mut self, x: f32, y: f32) {
+        self.rejected_click = Some(RejectedClick {
+            at: (x, y),
+            fade: Fade::new(REJECTED_CLICK_DURATION),
+        });
     }
 
-    fn draw_capturablepoint_indicator(
-        &self,
-        (ctx, canvas): (&mut Context, &mut Canvas),
-        x: f32,
-        y: f32,
-    ) -> GameResult {
-        let x = self.cnv_r(x);
-        let y = self.cnv_r(y);
-        let dist = self.cnv_r(0.12);
+    /// Whether a left click at some point, in rotchess units, is going to be turned away:
+    /// on a piece when we can't select anything, or on a travel point the selected piece
+    /// can't reach.
+    ///
+    /// Clicks that break the turn phase are caught once they've happened, in
+    /// [`App::try_send_event`].
+    fn click_rejected(&self, x: f32, y: f32) -> bool {
+        if !self.my_turn()
+            || self.turn.waiting()
+            || !self.game.history().at_live()
+            || self.game_over()
+        {
+            return self.piece_at(x, y);
+        }
+        let Some((_, travelpoints)) = self.game.chess().selected() else {
+            return false;
+        };
+        let mut hit = travelpoints
+            .iter()
+            .filter(|tp| Piece::collidepoint_generic(x, y, tp.x, tp.y))
+            .peekable();
+        hit.peek().is_some() && hit.all(|tp| !tp.travelable)
+    }
 
-        canvas.draw(
-            &Mesh::from_triangles(
-                ctx,
-                &[
-                    Vec2::new(x, y - dist),
-                    Vec2::new(x - dist / 2. * f32::sqrt(3.), y + dist / 2.),
-                    Vec2::new(x + dist / 2. * f32::sqrt(3.), y + dist / 2.),
-                ],
-                CAPTURE_HIGHLIGHT_COLOR,
-            )?,
-            DrawParam::new(),
+    /// Forget effects from a board that's gone.
+    fn clear_effects(&mut self) {
+        self.rotation_ghost = None;
+        self.move_trail = None;
+        self.animations.clear();
+        self.rejected_click = None;
+        self.phase_warning = None;
+        self.inspected_piece.clear();
+        self.ticker.clear();
+    }
+
+    /// Let go of effects that have faded away.
+    fn step_effects(&mut self) {
+        let now = Instant::now();
+        if self
+            .rotation_ghost
+            .is_some_and(|ghost| ghost.fade.is_done(now))
+        {
+            self.rotation_ghost = None;
+        }
+        if self.move_trail.is_some_and(|trail| trail.fade.is_done(now)) {
+            self.move_trail = None;
+        }
+        if self
+            .rejected_click
+            .is_some_and(|click| click.fade.is_done(now))
+        {
+            self.rejected_click = None;
+        }
+        if self.phase_warning.is_some_and(|fade| fade.is_done(now)) {
+            self.phase_warning = None;
+        }
+        if self.piece_to_rotate().is_none() {
+            // the rotation phase is over, one way or another.
+            self.rotation_draft = None;
+        }
+        self.ticker.step(now);
+        self.notifications.update(now);
+    }
+
+    /// Deselect whatever piece is selected.
+    ///
+    /// Use a little (evil) hack: I, the dev of rotchess-core, know right button
+    /// down can only select. so, we send a select click to narnia (-1000,-1000)
+    /// Nothing should be selectable there, so we deselect.
+    fn deselect(&mut self) {
+        let thing = self.apply(GameEvent::Input(Event::ButtonDown {
+            x: -1000.,
+            y: -1000.,
+            button: emulator::MouseButton::RIGHT,
+        }));
+        debug_assert!(
+            thing.is_none(),
+            "Nothing should have happened as detectable by the NothingHappened enum.",
         );
-        Ok(())
     }
 
-    fn draw_pieces(
-        &self,
-        (ctx, canvas): (&mut Context, &mut Canvas),
-        show_hitcircles: bool,
-    ) -> GameResult {
-        let tile_size_px = self.runit_to_world_multiplier; // I did the math.
-        const SHRINK: f32 = 0.9;
-        for piece in self.chess.pieces() {
-            // if (piece.angle() % PI).abs() > 0.001 {
-            //     // println!("{}", (piece.angle() % PI).abs());
-            //     println!("piece angle is not up or down: {}", piece.angle());
-            // }
-            canvas.draw(
-                self.images
-                    .get(&format!(
-                        "piece_{}{}1",
-                        piece.kind().to_file_desc(),
-                        piece.side().to_file_desc()
-                    ))
-                    .expect("Pieces should have correctly mapped to the file descrs."),
-                DrawParam::new()
-                    .dest_rect(Rect {
-                        x: self.cnv_r(piece.x()),                            // x
-                        y: self.cnv_r(piece.y()),                            // y
-                        w: tile_size_px / PIECE_PNG_SIZE_PX as f32 * SHRINK, // scale x multiplier
-                        h: tile_size_px / PIECE_PNG_SIZE_PX as f32 * SHRINK, // scale y multiplier
-                                                                             // again, I did the math.
-                    })
-                    .offset(Vec2::new(0.5, 0.5))
-                    .rotation(TAU - piece.angle()),
-            );
+    /// Switch to the next sound set there is, going back to the first after the last.
+    fn next_sound_set(&mut self, ctx: &mut Context) {
+        let mut sets: Vec<PathBuf> = assets::resource_dirs(ctx)
+            .iter()
+            .flat_map(|dir| assets::discover(dir).sound_sets)
+            .collect();
+        sets.sort();
+        sets.dedup();
+        let next = sets
+            .iter()
+            .position(|set| set == self.sounds.set())
+            .map_or(0, |i| (i + 1) % sets.len());
+        let Some(set) = sets.get(next) else {
+            return;
+        };
+        self.sounds = Sounds::load(ctx, &self.settings, set);
+        self.ticker
+            .announce(format!("Playing sounds from {}", set.display()));
+    }
 
-            if show_hitcircles {
-                self.draw_piece_outline((ctx, canvas), piece.x(), piece.y(), HITCIRCLE_COLOR)?;
+    /// Switch to the next theme there is, going back to the first after the last, along
+    /// with its pieces.
+    fn next_theme(&mut self, ctx: &mut Context) {
+        let mut themes: Vec<PathBuf> = assets::resource_dirs(ctx)
+            .iter()
+            .flat_map(|dir| theme::discover(dir))
+            .collect();
+        themes.sort();
+        themes.dedup();
+        let next = themes
+            .iter()
+            .position(|file| *file == self.theme_file)
+            .map_or(0, |i| (i + 1) % themes.len());
+        let Some(file) = themes.get(next) else {
+            return;
+        };
+        let switched = Theme::load(ctx, file).and_then(|theme| {
+            let pieces = theme
+                .pieces
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_PIECE_SET));
+            self.images.switch_set(ctx, &pieces)?;
+            Ok(theme)
+        });
+        match switched {
+            Ok(theme) => {
+                self.theme = theme;
+                self.theme_file = file.clone();
+                // the board and thumbnails were drawn in the old colors.
+                self.invalidate_meshes();
+                self.thumbnails.clear();
+                self.ticker
+                    .announce(format!("Using the theme {}", file.display()));
             }
+            Err(e) => self.ticker.announce(format!("{e}")),
         }
-        Ok(())
     }
-}
 
-/// On update events, forward events to the chess emulator. Then, draw.
-impl EventHandler for App {
-    fn key_down_event(
-        &mut self,
-        _ctx: &mut Context,
-        input: ggez::input::keyboard::KeyInput,
-        _repeated: bool,
-    ) -> GameResult {
-        match input.event.key_without_modifiers() {
-            Key::Named(NamedKey::ArrowLeft) => {
-                if input.mods.shift_key() {
-                    self.try_send_event(Event::FirstTurn);
-                } else {
-                    self.try_send_event(Event::PrevTurn);
-                }
-            }
-            Key::Named(NamedKey::ArrowRight) => {
-                if input.mods.shift_key() {
-                    self.try_send_event(Event::LastTurn);
-                } else {
-                    self.try_send_event(Event::NextTurn);
-                }
-            }
-            Key::Character(c) => match c.as_str() {
-                "9" => {
-                    self.chess_layout = ChessLayout::Chess960;
-                    self.chess = RotchessEmulator::with(self.chess_layout.get_pieces());
-                }
-                "0" => {
-                    self.chess_layout = ChessLayout::Standard;
-                    self.chess = RotchessEmulator::with(self.chess_layout.get_pieces());
-                }
-                "r" => {
-                    self.chess = RotchessEmulator::with(self.chess_layout.get_pieces());
-                }
-                _ => (),
-            },
-            _ => (),
-        }
+    /// Start over with a fresh board in the current layout.
+    fn reset_board(&mut self) {
+        let (pieces, chess960_ordering) = self.chess_layout.get_pieces();
+        self.start_over(pieces, chess960_ordering);
+    }
+
+    /// Start over from some pieces, forgetting everything about the game before.
+    fn start_over(&mut self, pieces: Pieces, chess960_ordering: Option<[usize; 8]>) {
+        self.apply(GameEvent::Reset(pieces));
+        self.chess960_ordering = chess960_ordering;
+        self.annotations = Annotations::default();
+        self.action_times.clear();
+        self.last_action_at = Instant::now();
+        self.thumbnails.clear();
+        self.clear_effects();
+        self.post_game_reviewed = false;
+        self.restart_analysis();
+        self.scene_version += 1;
+    }
 
+    /// Write the game so far, annotations and all, to a replay file in the working directory.
+    fn export_replay(&mut self) -> GameResult {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let path = PathBuf::from(format!("replay-{secs}.rotchess"));
+        self.replay().save(&path)?;
+        self.ticker
+            .announce(format!("Replay exported to {}", path.display()));
         Ok(())
     }
 
-    fn mouse_button_down_event(
+    /// Tell the user something they asked for didn't work, say a full disk when saving,
+    /// rather than closing the game over it.
+    fn report_failure(&mut self, what: &str, result: GameResult) {
+        if let Err(e) = result {
+            self.notifications.push(format!("Couldn't {what}: {e}"));
+        }
+    }
+
+    /// Save the game so far to the working directory, where the library (`c`) finds it.
+    fn save_game(&mut self) -> GameResult {
+        let mode = match &self.opponent_bot {
+            _ if self.netcode.is_some() => Mode::Network { side: self.my_side },
+            Some(opponent) => Mode::Bot {
+                side: self.my_side,
+                personality: opponent.personality,
+            },
+            None => Mode::HotSeat,
+        };
+        let game = SavedGame {
+            mode,
+            board: Some(notation::snapshot(self.game.chess().pieces())),
+            replay: self.replay(),
+        };
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let path = PathBuf::from(format!("save-{secs}.{SAVE_EXTENSION}"));
+        game.save(&path)?;
+        self.ticker.announce(format!("Saved to {}", path.display()));
+        Ok(())
+    }
+
+    /// The game so far, annotations and all.
+    fn replay(&self) -> Replay {
+        Replay {
+            chess960_ordering: self.chess960_ordering,
+            actions: self.game.history().actions().to_vec(),
+            annotations: self
+                .annotations
+                .iter()
+                .map(|(turn, annotation)| (turn, *annotation))
+                .collect(),
+            comments: self
+                .annotations
+                .comments()
+                .map(|(turn, comment)| (turn, comment.to_string()))
+                .collect(),
+            times: self
+                .action_times
+                .iter()
+                .take(self.game.history().len())
+                .copied()
+                .collect(),
+            winner: self.winner(),
+        }
+    }
+
+    /// How the game ended, if it's over.
+    fn outcome(&self) -> Option<Outcome> {
+        if let Some(flagged) = self.clock.as_ref().and_then(ChessClock::flagged) {
+            return Some(Outcome::Win(match flagged {
+                Side::White => Side::Black,
+                Side::Black => Side::White,
+            }));
+        }
+        if (self.netcode.is_some() || self.opponent_bot.is_some())
+            && self.role == Role::Player
+            && self.opponent_left
+        {
+            // they resigned, by leaving or by the computer giving up.
+            return Some(Outcome::Win(self.my_side));
+        }
+        self.board_outcome
+    }
+
+    /// Who won, if the game is over and someone did.
+    fn winner(&self) -> Option<Side> {
+        match self.outcome()? {
+            Outcome::Win(side) => Some(side),
+            Outcome::Draw => None,
+        }
+    }
+
+    /// Whether the game ended on the board or on the clock, so nobody can move anymore,
+    /// though it can still be looked back on.
+    fn game_over(&self) -> bool {
+        self.board_outcome.is_some()
+            || self
+                .clock
+                .as_ref()
+                .is_some_and(|clock| clock.flagged().is_some())
+    }
+
+    /// Work out whether the live game is over on the board, if it's changed since we last
+    /// did.
+    fn check_outcome(&mut self) {
+        let actions = self.game.history().actions();
+        let checked = (actions.len(), actions.last().copied());
+        if checked == self.outcome_checked {
+            return;
+        }
+        self.outcome_checked = checked;
+        let to_move = match actions.last() {
+            Some(Action::Move(..)) => None,
+            _ if self.game.history().live_turns_played().is_multiple_of(2) => Some(Side::White),
+            _ => Some(Side::Black),
+        };
+        let outcome = play_out(self.chess960_ordering, actions)
+            .and_then(|game| outcome::board_outcome(&mut game.into_chess(), to_move));
+        if let Some(outcome) = outcome
+            && self.board_outcome.is_none()
+        {
+            println!("{}.", outcome.describe());
+            self.ticker.announce(outcome.describe());
+            self.deselect();
+        }
+        self.board_outcome = outcome;
+    }
+
+    /// Start the game over by the same rules, once it's over.
+    fn rematch(&mut self) {
+        if self.netcode.is_none() {
+            self.reset_board();
+        } else {
+            // both of us have to start from the same board, so no rolling a new layout.
+            let chess960_ordering = self.rules.chess960_ordering;
+            self.start_over(starting_pieces(chess960_ordering), chess960_ordering);
+            self.staged.clear();
+            self.staged_board.clear();
+            self.takeback_wanted = false;
+            self.takeback_asked = false;
+            self.rollback_asked = None;
+            self.rematch_wanted = false;
+            self.opponent_away = false;
+        }
+        self.clock = self.rules.clock.map(|(white, black)| {
+            ChessClock::new(white, black).with_increment(self.rules.increment)
+        });
+        self.take_side(self.my_side);
+        if let Some(opponent) = &mut self.opponent_bot {
+            opponent.new_game();
+            self.opponent_left = false;
+        }
+    }
+
+    /// Whether there's a piece at some point, in rotchess units.
+    fn piece_at(&self, x: f32, y: f32) -> bool {
+        self.piece_idx_at(x, y).is_some()
+    }
+
+    /// The index of the piece at some point, in rotchess units, if there is one.
+    fn piece_idx_at(&self, x: f32, y: f32) -> Option<usize> {
+        self.game
+            .chess()
+            .pieces()
+            .iter()
+            .position(|piece| Piece::collidepoint_generic(x, y, piece.x(), piece.y()))
+    }
+
+    /// Look back over the whole game of the piece at some point, in rotchess units, or stop
+    /// looking if there's no piece there.
+    fn inspect_piece_at(&mut self, x: f32, y: f32) {
+        self.inspected_piece = match self.piece_idx_at(x, y) {
+            Some(piece_idx) => piece_history::piece_history(
+                &self.boards_through_game(),
+                self.game.history().actions(),
+                self.game.history().current(),
+                piece_idx,
+            ),
+            None => Vec::new(),
+        };
+    }
+
+    /// The board after each number of actions, from the start to the live position.
+    fn boards_through_game(&self) -> Vec<Vec<PieceSnapshot>> {
+        let mut game = Game::new(starting_pieces(self.chess960_ordering));
+        let mut boards = vec![notation::snapshot(game.chess().pieces())];
+        for &action in self.game.history().actions() {
+            if game.apply(GameEvent::Play(action)).is_none() {
+                break;
+            }
+            boards.push(notation::snapshot(game.chess().pieces()));
+        }
+        boards
+    }
+
+    /// Ease the board towards the size that fits the window, and rebuild cached meshes
+    /// once the window has stopped changing size. With reduced motion, it just snaps to it.
+    ///
+    /// Until then, the old meshes are stretched to fit, which is a lot cheaper than
+    /// rebuilding them every frame of a drag.
+    fn step_resize(&mut self) {
+        let target = self.target_scale;
+        let remaining = target - self.transform.scale;
+        self.transform.scale = if self.settings.reduced_motion || remaining.abs() < 0.01 {
+            target
+        } else {
+            self.transform.scale + remaining * RESIZE_EASING
+        };
+
+        if let Some(resized_at) = self.resized_at
+            && resized_at.elapsed() >= RESIZE_SETTLE_DELAY
+            && self.transform.scale == target
+        {
+            self.resized_at = None;
+            self.invalidate_meshes();
+        }
+    }
+
+    /// Do what a repeating key does, then keep doing it for as long as it's held.
+    fn press_repeating_key(&mut self, key: RepeatingKey) {
+        self.fire_repeating_key(key);
+        self.held_key = Some((key, Instant::now() + KEY_REPEAT_DELAY));
+    }
+
+    fn fire_repeating_key(&mut self, key: RepeatingKey) {
+        match key {
+            RepeatingKey::RotateView => self.rotate_view(),
+            RepeatingKey::TurnPiece { clockwise, step } => self.turn_piece(clockwise, step),
+        }
+    }
+
+    /// Fire the held repeating key again, if it's time.
+    fn step_held_key(&mut self) {
+        if let Some((key, next_fire)) = self.held_key
+            && Instant::now() >= next_fire
+        {
+            self.fire_repeating_key(key);
+            self.held_key = Some((key, next_fire + KEY_REPEAT_INTERVAL));
+        }
+    }
+
+    /// The settings to draw with right now.
+    fn settings(&self) -> Settings {
+        if self.projector_mode {
+            self.settings.for_projector()
+        } else {
+            self.settings
+        }
+    }
+
+    fn toggle_projector_mode(&mut self) {
+        self.projector_mode = !self.projector_mode;
+        self.invalidate_meshes();
+    }
+
+    /// Turn the board view a quarter turn clockwise.
+    fn rotate_view(&mut self) {
+        self.transform.turn();
+        self.invalidate_meshes();
+    }
+
+    /// Look at the board from the other side. Black starts out looking from theirs.
+    fn turn_view_around(&mut self) {
+        self.transform.turn_around();
+        self.invalidate_meshes();
+    }
+
+    /// Zoom the board in or out around a point on screen, a notch of the scroll wheel at a
+    /// time.
+    fn zoom_view(&mut self, at: Vec2, notches: f32) {
+        self.transform.zoom_at(at, ZOOM_PER_SCROLL.powf(notches));
+        self.mouse_pos = self.transform.board_pos(at.x, at.y);
+        self.invalidate_meshes();
+    }
+
+    /// Move the camera to the place on the board under a point on the mini-map, if the
+    /// mini-map's up and the point is on it.
+    fn jump_camera(&mut self, point: Vec2) -> bool {
+        let Some((x, y)) = MiniMap::of(&self.transform).and_then(|map| map.board_pos(point)) else {
+            return false;
+        };
+        self.transform.center_on(x, y);
+        self.invalidate_meshes();
+        true
+    }
+
+    /// Put the board back where it was before any zooming or panning.
+    fn reset_camera(&mut self) {
+        self.transform.reset_camera();
+        self.invalidate_meshes();
+    }
+
+    /// While the board is zoomed or panned, keep what's drawn on it inside its frame, so it
+    /// doesn't spill over the scrubber. [`Canvas::set_default_scissor_rect`] lets go.
+    fn clip_to_board(&self, canvas: &mut Canvas) -> GameResult {
+        if self.transform.camera_moved() {
+            canvas.set_scissor_rect(self.transform.frame())?;
+        }
+        Ok(())
+    }
+}
+
+/// Netcode related stuff for our app.
+impl App {
+    /// Whether we get to act now. Always true in a local game, and never for spectators.
+    fn my_turn(&self) -> bool {
+        self.role == Role::Player && self.netcode.as_ref().is_none_or(NetcodeInterface::my_turn)
+    }
+
+    /// Send a turn to the opponent, if there is one. Spectators have nothing to say, other
+    /// than handing the turn back (see [`App::recv_spectated`]).
+    fn send_turn(&mut self, turn: &[u8; TURN_SIZE]) {
+        if self.role != Role::Player {
+            return;
+        }
+        if let Some(netcode) = &mut self.netcode {
+            netcode.send_turn(turn);
+            self.bandwidth.sent.record(TURN_SIZE, Instant::now());
+        }
+    }
+
+    /// Sends an event to our inner chess emulator, unless it is not our turn.
+    ///
+    /// If a thing happened under the hood, send it to the other player.
+    /// If we did an illegal turn phase action, revert it.
+    fn try_send_event(&mut self, e: Event) {
+        if !matches!(e, Event::MouseMotion { .. }) {
+            // whatever this is happened after the mouse got where it is.
+            self.flush_mouse_motion();
+        }
+        // even if nothing happens, the mouse might've moved onto something.
+        self.scene_version += 1;
+        if self.opponent_left
+            || self.quit_dialog_open
+            || self.takeback_dialog_open
+            || self.rematch_dialog_open
+            || self.rollback_offered.is_some()
+            || self.resyncing()
+            || self.pass_screen_up()
+        {
+            return;
+        }
+        let looking_back = matches!(
+            e,
+            Event::FirstTurn | Event::PrevTurn | Event::NextTurn | Event::LastTurn
+        );
+        if self.role != Role::Player {
+            // spectators can look back and forth on their own, but not play.
+            if looking_back {
+                self.apply(GameEvent::Input(e));
+                self.deselect();
+            }
+            return;
+        }
+        if !self.staged.is_empty() && looking_back {
+            // our staged actions aren't played yet, so there's nothing to go back through.
+            return;
+        }
+        if self.netcode.is_some() && looking_back {
+            // looking back is for us alone, whoever's turn it is. the opponent's board stays
+            // where it is, and going back for real takes a rollback they agree to.
+            self.apply(GameEvent::Input(e));
+            self.deselect();
+            return;
+        }
+        if !self.game.history().at_live()
+            && matches!(e, Event::ButtonDown { .. } | Event::ButtonUp { .. })
+        {
+            // acting from a past position would throw away everything after it.
+            // past positions are for looking at only.
+            return;
+        }
+        if self.game_over() && matches!(e, Event::ButtonDown { .. } | Event::ButtonUp { .. }) {
+            // the game is over, though it can still be looked back on.
+            return;
+        }
+        if matches!(e, Event::ButtonDown { .. }) {
+            self.pressed_board = notation::snapshot(self.game.chess().pieces());
+        }
+        if self.my_turn()
+            && let Some(thing_happened) = self.apply(GameEvent::Input(e))
+        {
+            let thing_happened = self.snap_rotation(thing_happened);
+            if let Err(violation) = self.turn.act(&thing_happened) {
+                self.notifications.push(violation.to_string());
+                self.phase_warning = Some(Fade::new(PHASE_WARNING_DURATION));
+                match thing_happened {
+                    ThingHappened::Move(_, x, y) => self.reject_click(x, y),
+                    ThingHappened::Rotate(piece_idx, _) => {
+                        if let Some(piece) = self.game.chess().pieces().get(piece_idx) {
+                            self.reject_click(piece.x(), piece.y());
+                        }
+                    }
+                    _ => (),
+                }
+                self.apply(GameEvent::TakeBack(1));
+                return;
+            }
+            if let Some(action) = Action::from_thing(&thing_happened) {
+                let before = std::mem::take(&mut self.pressed_board);
+                self.show_action(&before, action);
+                if let Action::Rotate(..) = action {
+                    // we dragged the rotation out by hand, so we watched it happen already.
+                    self.animations.pop();
+                }
+                if self.staged_turns && self.staged.is_empty() {
+                    self.staged_board = before;
+                }
+            }
+            // if we rotated, deselect the piece that we're rotating. and a selection made
+            // on another turn doesn't mean much on this one.
+            if !matches!(thing_happened, ThingHappened::Move(_, _, _)) {
+                self.deselect();
+            }
+            self.send_action(thing_happened);
+        }
+    }
+
+    /// If snapping is on, round off a rotation the emulator worked out from a drag, playing
+    /// it again at the snapped angle.
+    fn snap_rotation(&mut self, thing: ThingHappened) -> ThingHappened {
+        let ThingHappened::Rotate(piece_idx, r) = thing else {
+            return thing;
+        };
+        let Some(step) = self.snap_step else {
+            return thing;
+        };
+        let snapped = step.snap(r);
+        self.apply(GameEvent::TakeBack(1));
+        self.apply(GameEvent::Play(Action::Rotate(piece_idx, snapped)));
+        ThingHappened::Rotate(piece_idx, snapped)
+    }
+
+    /// Tell the opponent about something we did, or hold on to it if it's part of a staged
+    /// turn.
+    fn send_action(&mut self, thing: ThingHappened) {
+        if self.staged_turns && matches!(thing, ThingHappened::Move(..) | ThingHappened::Rotate(..))
+        {
+            self.staged.push(thing);
+        } else {
+            self.send_turn(&protocol::ser_thing(Some(&thing)));
+        }
+    }
+
+    /// Send the opponent our staged turn, if it's all there.
+    fn end_staged_turn(&mut self) {
+        if !self.turn.waiting() || !self.my_turn() || self.staged.is_empty() {
+            return;
+        }
+        let [
+            ThingHappened::Move(piece_idx, x, y),
+            ThingHappened::Rotate(_, r),
+        ] = self.staged[..]
+        else {
+            return;
+        };
+        // the whole turn in one message, so the opponent never sees half of it.
+        self.send_turn(&protocol::ser_message(&Message::Turn {
+            piece_idx,
+            x,
+            y,
+            r,
+        }));
+        self.staged.clear();
+        self.staged_board.clear();
+    }
+
+    /// Take back the staged actions. The opponent never heard about them, so there's
+    /// nobody to ask.
+    fn take_back_staged(&mut self) {
+        self.take_back(self.staged.len());
+        self.staged.clear();
+        self.staged_board.clear();
+        self.turn.restart_turn();
+    }
+
+    /// Pass the latest mouse motion on to the emulator, if it's news.
+    fn flush_mouse_motion(&mut self) {
+        if let Some((x, y)) = self.pending_motion.take()
+            && self.last_sent_motion != Some((x, y))
+        {
+            self.last_sent_motion = Some((x, y));
+            self.try_send_event(Event::MouseMotion { x, y });
+        }
+    }
+
+    /// If we're looking at the past when we should be acting, count down and then
+    /// bring us back to the live position.
+    ///
+    /// The countdown restarts whenever we look at a different turn, so browsing
+    /// around isn't interrupted.
+    fn check_snap_back(&mut self) {
+        let should_snap = self.my_turn()
+            && !self.turn.waiting()
+            && !self.game.history().at_live()
+            && !self.watching();
+        if !should_snap {
+            self.snap_back = None;
+            return;
+        }
+
+        let current = self.game.history().current();
+        match self.snap_back {
+            Some((turn, deadline)) if turn == current => {
+                if Instant::now() >= deadline {
+                    self.snap_back = None;
+                    self.try_jump_to_turn(self.game.history().len());
+                }
+            }
+            _ => self.snap_back = Some((current, Instant::now() + SNAP_BACK_DELAY)),
+        }
+    }
+
+    /// Jump straight to some turn in the history, unless something's in the way.
+    ///
+    /// Like the arrow keys, this only moves our own board. The opponent never hears about it.
+    fn try_jump_to_turn(&mut self, n: usize) {
+        if self.opponent_left
+            || self.quit_dialog_open
+            || self.takeback_dialog_open
+            || self.rematch_dialog_open
+            || self.rollback_offered.is_some()
+            || self.resyncing()
+            || !self.staged.is_empty()
+        {
+            return;
+        }
+        self.jump_to_turn(n);
+    }
+
+    /// Walk the emulator to the nth turn of its history.
+    ///
+    /// The emulator only knows how to step one turn at a time, so that's what we do.
+    fn jump_to_turn(&mut self, n: usize) {
+        let n = usize::min(n, self.game.history().len());
+        while self.game.history().current() > n {
+            if self.apply(GameEvent::Input(Event::PrevTurn)).is_none() {
+                break;
+            }
+        }
+        while self.game.history().current() < n {
+            if self.apply(GameEvent::Input(Event::NextTurn)).is_none() {
+                break;
+            }
+        }
+        self.deselect();
+    }
+
+    /// Play what the opponent played on their live board, which [`Netplay`] has checked:
+    /// a move, a rotation, or a whole turn sent in one go.
+    fn recv_actions(&mut self, actions: &[Action]) {
+        // they played on the live board, so that's where we follow along.
+        self.jump_to_turn(self.game.history().len());
+        self.opponent_away = false;
+        for &action in actions {
+            let before = notation::snapshot(self.game.chess().pieces());
+            self.apply(GameEvent::Play(action));
+            self.show_action(&before, action);
+        }
+        let turn_over = matches!(actions.last(), Some(Action::Rotate(..)));
+        if turn_over {
+            self.opponent_activity = Activity::Thinking;
+            self.activity_sent.0 = Activity::Thinking;
+            self.cues_due.push(Cue::Chime);
+        }
+        if std::mem::take(&mut self.takeback_wanted) {
+            // this is the first chance we've had to say anything since our turn.
+            self.takeback_asked = true;
+            self.send_turn(&protocol::ser_message(&Message::RequestTakeback));
+        } else if turn_over {
+            self.send_clock();
+        } else {
+            // it's still their turn, to rotate, so hand it back.
+            self.send_turn(&protocol::ser_thing(None));
+        }
+    }
+
+    /// Tell the opponent how much time we have left as our turn starts, so their copy of
+    /// our clock agrees with ours.
+    fn send_clock(&mut self) {
+        if self.netcode.is_some()
+            && let Some(clock) = &self.clock
+            && clock.flagged().is_none()
+        {
+            let remaining = clock.remaining(self.my_side, Instant::now());
+            self.send_turn(&protocol::ser_message(&Message::Clock(remaining)));
+        }
+    }
+
+    /// Play some side from the start of the game, turning the board to face us.
+    fn take_side(&mut self, side: Side) {
+        self.my_side = side;
+        let hands_over = self.netcode.is_some() || self.opponent_bot.is_some();
+        let goes_first = side == Side::White && self.role == Role::Player;
+        self.turn = TurnPhaseMachine::new(goes_first, hands_over);
+        self.transform.quarter_turns = match side {
+            Side::White => 0,
+            Side::Black => 2,
+        };
+        self.invalidate_meshes();
+    }
+
+    /// Set up a game by some rules, playing some side.
+    fn apply_rules(&mut self, rules: GameRules, side: Side) {
+        if self.role != Role::Player {
+            println!("Watching: {}.", rules.describe());
+        } else if self.netcode.is_some() {
+            println!(
+                "Playing {}: {}.",
+                notation::side_name(side),
+                rules.describe()
+            );
+        } else {
+            println!("Playing {}.", rules.describe());
+        }
+        self.rules = rules;
+        self.apply(GameEvent::Reset(starting_pieces(rules.chess960_ordering)));
+        self.chess960_ordering = rules.chess960_ordering;
+        self.chess_layout = match rules.chess960_ordering {
+            Some(_) => ChessLayout::Chess960,
+            None => ChessLayout::Standard,
+        };
+        self.clock = rules
+            .clock
+            .map(|(white, black)| ChessClock::new(white, black).with_increment(rules.increment));
+        self.take_side(side);
+    }
+
+    /// Run the clock of whoever's turn it is, once the first move is in, and check whether
+    /// they've run out of time.
+    fn step_clock(&mut self) {
+        let now = Instant::now();
+        let side = (!self.game.history().is_empty()).then(|| {
+            if !self.staged.is_empty() {
+                // our turn isn't over until the opponent hears about it.
+                self.my_side
+            } else if self.game.history().live_turns_played().is_multiple_of(2) {
+                Side::White
+            } else {
+                Side::Black
+            }
+        });
+        let Some(clock) = &mut self.clock else {
+            return;
+        };
+        if self.unfocused_since.is_some() {
+            // it picks up where it left off once we're back.
+            clock.run(None, now);
+            return;
+        }
+        clock.run(side, now);
+        if clock.flagged().is_none()
+            && let Some(side) = clock.check_flag(now)
+        {
+            println!("{} ran out of time.", notation::side_name(side));
+            self.deselect();
+        }
+    }
+
+    /// Handle whatever the opponent sent us, if anything.
+    fn poll_netcode(&mut self) {
+        let _span = profiling::span("poll_netcode");
+        // don't use turn phase for this check, the turn phase can be Wait even though netcode
+        // isn't done yet (ie when it's my turn)
+        if !self.my_turn()
+            && let Some(netcode) = &mut self.netcode
+            && let Ok(turn) = netcode.try_recv_turn()
+        {
+            self.bandwidth.received.record(TURN_SIZE, Instant::now());
+            // someone joined, if they hadn't already.
+            self.ticket = None;
+            self.scene_version += 1;
+            if self.role != Role::Player {
+                self.recv_spectated(&turn);
+                return;
+            }
+            match self.netplay.receive(&turn, &self.game, &mut self.turn) {
+                Received::Play(actions) => self.recv_actions(&actions),
+                Received::Owed => self.send_turn(&protocol::ser_thing(None)),
+                Received::Nothing => (),
+                Received::Resync { done } => self.start_resync(done),
+                Received::Replay { action, done } => self.recv_resync_action(action, done),
+                Received::Lost(why) => {
+                    println!("{why}");
+                    self.request_resync();
+                }
+                Received::GaveUp(why) => self.give_up_resync(&why),
+                Received::Other(message) => self.recv_message(message),
+            }
+        }
+    }
+
+    /// Handle something the opponent said that isn't about the board.
+    fn recv_message(&mut self, message: Message) {
+        match message {
+            // netplay takes care of everything about the board.
+            Message::Thing(_)
+            | Message::Turn { .. }
+            | Message::RequestResync
+            | Message::Resync { .. } => (),
+            Message::Quit => self.opponent_resigned(),
+            Message::Away => {
+                self.opponent_away = true;
+                self.send_turn(&protocol::ser_thing(None));
+            }
+            Message::Back => {
+                self.opponent_away = false;
+                self.send_turn(&protocol::ser_thing(None));
+            }
+            Message::Activity(activity) => {
+                self.opponent_activity = activity;
+                self.send_turn(&protocol::ser_thing(None));
+            }
+            Message::Clock(remaining) => {
+                let opponent = match self.my_side {
+                    Side::White => Side::Black,
+                    Side::Black => Side::White,
+                };
+                if let Some(clock) = &mut self.clock {
+                    clock.sync(opponent, remaining, Instant::now());
+                }
+                self.send_turn(&protocol::ser_thing(None));
+            }
+            Message::ProposeRollback { actions } => {
+                if self.rules.takebacks && actions < self.game.history().len() {
+                    self.rollback_offered = Some(actions);
+                    self.cues_due.push(Cue::Chime);
+                } else {
+                    // there's nothing there to roll back to, or we agreed not to.
+                    self.send_turn(&protocol::ser_message(&Message::RollbackReply {
+                        accepted: false,
+                    }));
+                }
+            }
+            Message::RollbackReply { accepted } => {
+                let actions = self.rollback_asked.take();
+                if accepted && let Some(actions) = actions {
+                    self.roll_back_to(actions);
+                    if self.turn.waiting() {
+                        // it's their turn from there.
+                        self.send_turn(&protocol::ser_thing(None));
+                    }
+                } else {
+                    println!("Opponent wants to keep playing from here.");
+                    self.ticker
+                        .announce("Opponent wants to keep playing from here".to_string());
+                    if self.turn.waiting() {
+                        // we still owe them this for their move.
+                        self.send_turn(&protocol::ser_thing(None));
+                    }
+                }
+            }
+            Message::Rules(rules) => {
+                self.apply_rules(rules, rules.client_side());
+                self.send_turn(&protocol::ser_message(&Message::RulesAgreed {
+                    checksum: rules.checksum(),
+                }));
+            }
+            Message::RulesAgreed { checksum } => {
+                if checksum != self.rules.checksum() {
+                    println!("Opponent understood different rules than ours. Ending the game.");
+                    self.send_turn(&protocol::ser_message(&Message::Quit));
+                    self.opponent_left = true;
+                } else if self.my_side == Side::Black {
+                    // white moves first.
+                    self.send_turn(&protocol::ser_thing(None));
+                }
+            }
+            Message::RequestTakeback => {
+                if self.takeback_owed().is_some() {
+                    self.takeback_dialog_open = true;
+                } else {
+                    // there's no turn of theirs and move of ours to take back.
+                    self.send_turn(&protocol::ser_message(&Message::TakebackReply {
+                        accepted: false,
+                    }));
+                }
+            }
+            Message::Rematch => {
+                if std::mem::take(&mut self.rematch_wanted) {
+                    // we were about to ask for the same thing.
+                    self.answer_rematch(true);
+                } else if self.game_over() && !self.opponent_left {
+                    self.rematch_dialog_open = true;
+                    self.cues_due.push(Cue::Chime);
+                } else {
+                    // as far as we can tell, this game isn't over yet.
+                    self.send_turn(&protocol::ser_message(&Message::RematchReply {
+                        accepted: false,
+                    }));
+                }
+            }
+            Message::RematchReply { accepted } => {
+                self.rematch_asked = false;
+                if accepted {
+                    self.rematch_swapping_sides();
+                    if self.my_side == Side::Black {
+                        // white moves first.
+                        self.send_turn(&protocol::ser_thing(None));
+                    }
+                } else {
+                    println!("Opponent doesn't want a rematch.");
+                    self.ticker
+                        .announce("Opponent doesn't want a rematch".to_string());
+                    self.game_over_passed_at = Instant::now();
+                }
+            }
+            Message::TakebackReply { accepted } => {
+                self.takeback_asked = false;
+                // we asked after their move, and they're still to rotate, or after their
+                // whole turn, and it's ours.
+                let after_move = self.turn.waiting();
+                if accepted {
+                    self.take_back(if after_move { 3 } else { 4 });
+                    self.turn.restart_turn();
+                } else {
+                    self.notifications
+                        .push("Opponent wants to keep playing from here.".to_string());
+                    if after_move {
+                        // we still owe them this for their move.
+                        self.send_turn(&protocol::ser_thing(None));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Ask to take back our last turn, e.g. after a misclick.
+    ///
+    /// In a local game there's nobody to ask, so the turn in progress (or if it's only
+    /// just started, the one before) is taken back right away. Otherwise, the opponent is
+    /// asked as soon as we can say anything. Asking again before then changes our mind.
+    fn request_takeback(&mut self) {
+        if self.watching() || self.role != Role::Player || self.opponent_left || self.resyncing() {
+            return;
+        }
+        if self.netcode.is_none() {
+            if !self.rules.takebacks {
+                self.notifications
+                    .push("Takebacks are off in this game.".to_string());
+                return;
+            }
+            let actions = self.local_takeback_size();
+            if self.game.history().len() >= actions {
+                self.take_back(actions);
+                self.turn.restart_turn();
+            }
+            return;
+        }
+        if !self.staged.is_empty() {
+            self.take_back_staged();
+            return;
+        }
+        if !self.turn.waiting() || self.game.history().is_empty() || self.takeback_asked {
+            return;
+        }
+        if !self.rules.takebacks {
+            self.notifications
+                .push("Takebacks are off in this game.".to_string());
+            return;
+        }
+        self.takeback_wanted = !self.takeback_wanted;
+        if self.takeback_wanted {
+            self.notifications
+                .push("Will ask to take back your last turn once the opponent moves.".to_string());
+        }
+    }
+
+    /// How many actions taking back the last turn of a local game undoes: our half turn if
+    /// we're in the middle of one, or the last whole turn before, and the computer's reply
+    /// to it.
+    fn local_takeback_size(&self) -> usize {
+        match self.turn.phase() {
+            TurnPhase::Rotate => 1,
+            // the bot's turn, and ours before it.
+            TurnPhase::Move if self.opponent_bot.is_some() => 4,
+            _ => 2,
+        }
+    }
+
+    /// Take back the last turn of a local game like a takeback, but keep it to redo.
+    ///
+    /// Against the computer, its reply goes too. If it's still thinking of one, it stops,
+    /// since it isn't its turn anymore.
+    fn undo(&mut self) {
+        if self.netcode.is_some() || self.watching() || self.opponent_left || !self.rules.takebacks
+        {
+            return;
+        }
+        let actions = self.local_takeback_size();
+        if self.game.history().len() < actions {
+            return;
+        }
+        self.apply(GameEvent::Undo(actions));
+        self.undone_phases.push(self.turn);
+        self.turn.restart_turn();
+        self.deselect();
+        self.clear_effects();
+        self.ticker.announce("Turn taken back".to_string());
+    }
+
+    /// Play the last undone turn again, picking up in the turn phase it was undone from.
+    fn redo(&mut self) {
+        if self.netcode.is_some() || self.watching() {
+            return;
+        }
+        let Some(turn) = self.undone_phases.pop() else {
+            return;
+        };
+        self.apply(GameEvent::Redo);
+        self.turn = turn;
+        self.deselect();
+        self.clear_effects();
+        self.ticker.announce("Turn redone".to_string());
+    }
+
+    /// How many actions giving the opponent the takeback they asked for undoes: their last
+    /// turn, and our move since, or our whole turn if we sent it in one go. `None` if
+    /// there's no such thing to take back.
+    fn takeback_owed(&self) -> Option<usize> {
+        let actions = match self.turn.phase() {
+            TurnPhase::Rotate => 3,
+            TurnPhase::Wait => 4,
+            TurnPhase::Move => return None,
+        };
+        (self.rules.takebacks && self.game.history().len() >= actions).then_some(actions)
+    }
+
+    /// Tell the opponent whether they can take back their last turn, and if so, do it.
+    fn answer_takeback(&mut self, accepted: bool) {
+        self.takeback_dialog_open = false;
+        if accepted && let Some(actions) = self.takeback_owed() {
+            self.take_back(actions);
+            self.turn.wait();
+        }
+        self.send_turn(&protocol::ser_message(&Message::TakebackReply { accepted }));
+    }
+
+    /// Ask the opponent to play on from the turn we're looking at, taking back everything
+    /// since. Looking back only ever moves our own board, so this is how both of us go back
+    /// for real.
+    ///
+    /// It goes back to the start of the turn we're looking at, and like anything else we
+    /// say, it waits on our turn to be said.
+    fn propose_rollback(&mut self) {
+        if self.netcode.is_none()
+            || self.role != Role::Player
+            || self.opponent_left
+            || self.resyncing()
+            || self.game_over()
+            || self.rollback_asked.is_some()
+            || !self.staged.is_empty()
+            || self.game.history().at_live()
+        {
+            return;
+        }
+        if !self.rules.takebacks {
+            self.notifications
+                .push("Takebacks are off in this game.".to_string());
+            return;
+        }
+        if !self.my_turn() {
+            self.notifications
+                .push("Wait for your turn to ask to play on from here.".to_string());
+            return;
+        }
+        // a move and its rotation make a turn.
+        let actions = self.game.history().current() / 2 * 2;
+        self.rollback_asked = Some(actions);
+        self.send_turn(&protocol::ser_message(&Message::ProposeRollback {
+            actions,
+        }));
+    }
+
+    /// Tell the opponent whether we'll play on from where they asked, and if so, go there.
+    fn answer_rollback(&mut self, accepted: bool) {
+        let Some(actions) = self.rollback_offered.take() else {
+            return;
+        };
+        if accepted {
+            self.roll_back_to(actions);
+        }
+        self.send_turn(&protocol::ser_message(&Message::RollbackReply { accepted }));
+    }
+
+    /// Take back everything after the first few actions of the game for good, picking the
+    /// turn up from there: ours if it's our side to move, or the opponent's to wait on.
+    fn roll_back_to(&mut self, actions: usize) {
+        self.take_back(self.game.history().len().saturating_sub(actions));
+        self.takeback_wanted = false;
+        if self.side_to_move() == self.my_side {
+            self.turn.restart_turn();
+        } else {
+            self.turn.wait();
+        }
+    }
+
+    /// Ask the opponent for a rematch once the game is over, as soon as it's our turn to
+    /// say anything. Asking again before then changes our mind.
+    fn request_rematch(&mut self) {
+        if self.role != Role::Player
+            || self.opponent_left
+            || self.resyncing()
+            || !self.game_over()
+            || self.rematch_asked
+        {
+            return;
+        }
+        self.rematch_wanted = !self.rematch_wanted;
+        if self.rematch_wanted {
+            println!("Will ask the opponent for a rematch.");
+        }
+    }
+
+    /// Once a networked game is over, nobody's moves hand the turn over anymore, so we hand
+    /// it back and forth every so often instead. That way, whichever of us wants a rematch
+    /// gets to ask, by sending a [`Message::Rematch`] on their turn.
+    fn step_rematch(&mut self) {
+        if self.netcode.is_none()
+            || self.role != Role::Player
+            || self.opponent_left
+            || self.resyncing()
+            || self.rematch_dialog_open
+            || !self.game_over()
+            || !self.my_turn()
+        {
+            return;
+        }
+        if std::mem::take(&mut self.rematch_wanted) {
+            self.rematch_asked = true;
+            self.send_turn(&protocol::ser_message(&Message::Rematch));
+        } else if self.game_over_passed_at.elapsed() >= GAME_OVER_PASS_INTERVAL {
+            self.game_over_passed_at = Instant::now();
+            self.send_turn(&protocol::ser_thing(None));
+        }
+    }
+
+    /// Tell the opponent whether we'll play them again, and if so, start over.
+    fn answer_rematch(&mut self, accepted: bool) {
+        self.rematch_dialog_open = false;
+        self.send_turn(&protocol::ser_message(&Message::RematchReply { accepted }));
+        if accepted {
+            self.rematch_swapping_sides();
+        } else {
+            self.game_over_passed_at = Instant::now();
+        }
+    }
+
+    /// Start a networked game over by the same rules, each of us playing the other color.
+    fn rematch_swapping_sides(&mut self) {
+        self.rules = self.rules.swapped();
+        self.my_side = match self.my_side {
+            Side::White => Side::Black,
+            Side::Black => Side::White,
+        };
+        println!(
+            "Rematch! Playing {}: {}.",
+            notation::side_name(self.my_side),
+            self.rules.describe()
+        );
+        self.ticker.announce(format!(
+            "Rematch! You play {}",
+            notation::side_name(self.my_side)
+        ));
+        self.rematch();
+    }
+
+    /// Undo the last few actions of the game for good.
+    fn take_back(&mut self, actions: usize) {
+        self.apply(GameEvent::TakeBack(actions));
+        self.deselect();
+        self.clear_effects();
+        self.ticker.announce("Turn taken back".to_string());
+    }
+
+    /// Note that the user did something, telling the opponent we're back if we went away.
+    fn note_input(&mut self) {
+        self.last_input = Instant::now();
+        if self.away_sent && self.my_turn() && !self.opponent_left {
+            self.send_turn(&protocol::ser_message(&Message::Back));
+            self.away_sent = false;
+        }
+    }
+
+    /// If we've been sitting on our turn for too long, let the opponent know.
+    fn check_idle(&mut self) {
+        if !self.away_sent
+            && !self.opponent_left
+            && self.my_turn()
+            && !self.turn.waiting()
+            && self.last_input.elapsed() > IDLE_TIMEOUT
+        {
+            self.send_turn(&protocol::ser_message(&Message::Away));
+            self.away_sent = true;
+        }
+    }
+
+    /// Tell the opponent if what we're up to on our turn changed, so they're not left
+    /// staring at a still board.
+    ///
+    /// Sending hands the turn over until they answer, so we only do it between clicks, and
+    /// not too often.
+    fn check_activity(&mut self, ctx: &Context) {
+        if self.netcode.is_none()
+            || self.opponent_left
+            || self.away_sent
+            || self.resyncing()
+            || !self.my_turn()
+            || self.turn.waiting()
+            || self.activity_sent.1.elapsed() < ACTIVITY_INTERVAL
+            || ctx
+                .mouse
+                .button_pressed(ggez::winit::event::MouseButton::Left)
+            || ctx
+                .mouse
+                .button_pressed(ggez::winit::event::MouseButton::Right)
+        {
+            return;
+        }
+        let activity = if self.comment_draft.is_some() {
+            Activity::Typing
+        } else if self.game.chess().selected().is_some() {
+            Activity::HoldingPiece
+        } else {
+            Activity::Thinking
+        };
+        if activity != self.activity_sent.0 {
+            self.send_turn(&protocol::ser_message(&Message::Activity(activity)));
+            self.activity_sent = (activity, Instant::now());
+        }
+    }
+
+    /// If the rules time out rotations, and we've left a moved piece unrotated (and left
+    /// the game alone) for that long, rotate it by 0° for us so the game keeps going.
+    fn check_auto_rotate(&mut self) {
+        let Some(timeout) = self.rules.auto_rotate else {
+            return;
+        };
+        if self.last_input.elapsed() >= timeout
+            && self.last_action_at.elapsed() >= timeout
+            && self.skip_rotation()
+        {
+            // whoever walked away isn't going to end their turn either.
+            self.end_staged_turn();
+            self.ticker
+                .announce("Rotation timed out, so the piece stayed put.".to_string());
+        }
+    }
+
+    /// Whether the board is hidden while a hot-seat game changes hands.
+    fn pass_screen_up(&self) -> bool {
+        self.pass_screen
+            .as_ref()
+            .is_some_and(|screen| screen.passing_to().is_some())
+    }
+
+    /// Hide the board once a hot-seat turn is done, until the next player is ready. A game
+    /// that's over has nothing left to hide.
+    fn step_pass_screen(&mut self) {
+        let turns = self.game.history().live_turns_played();
+        let next = if turns.is_multiple_of(2) {
+            Side::White
+        } else {
+            Side::Black
+        };
+        let game_over = self.game_over();
+        if let Some(screen) = &mut self.pass_screen {
+            screen.update(turns, next);
+            if game_over {
+                screen.ready();
+            }
+        }
+    }
+
+    /// Whether we're in our rotation phase, and free to finish it.
+    fn can_skip_rotation(&self) -> bool {
+        self.my_turn()
+            && self.turn.phase() == TurnPhase::Rotate
+            && self.game.history().at_live()
+            && !self.watching()
+            && !self.opponent_left
+            && !self.quit_dialog_open
+            && !self.takeback_dialog_open
+            && !self.rematch_dialog_open
+            && self.rollback_offered.is_none()
+            && !self.game_over()
+    }
+
+    /// Finish our rotation phase without rotating, by rotating the moved piece by 0°.
+    ///
+    /// Returns whether there was a rotation phase to finish.
+    fn skip_rotation(&mut self) -> bool {
+        let Some(piece_idx) = self.piece_to_rotate() else {
+            return false;
+        };
+        let r = self.game.chess().pieces()[piece_idx].angle();
+        self.finish_rotation(piece_idx, r);
+        true
+    }
+
+    /// The piece we moved this turn, if we're in our rotation phase and free to finish it.
+    fn piece_to_rotate(&self) -> Option<usize> {
+        if !self.can_skip_rotation() {
+            return None;
+        }
+        match self.game.history().actions().last() {
+            Some(&Action::Move(piece_idx, _, _)) => Some(piece_idx),
+            _ => None,
+        }
+    }
+
+    /// Finish our rotation phase by turning the moved piece to `r`.
+    fn finish_rotation(&mut self, piece_idx: usize, r: f32) {
+        // let go of any rotation that was dragged out halfway, or turned out with the keys.
+        self.deselect();
+        self.rotation_draft = None;
+        self.rotate_directly(piece_idx, r);
+        self.send_action(ThingHappened::Rotate(piece_idx, r));
+    }
+
+    /// Turn the moved piece a step on from wherever the keys and scroll wheel have it so far.
+    /// Nothing's played until [`App::confirm_rotation`], so it's only a preview until then.
+    fn turn_piece(&mut self, clockwise: bool, step: RotationStep) {
+        let Some(piece_idx) = self.piece_to_rotate() else {
+            return;
+        };
+        let degrees = match step {
+            RotationStep::Fine => self.settings.fine_rotation_step,
+            RotationStep::Normal => self.settings.rotation_step,
+            RotationStep::Coarse => self.settings.coarse_rotation_step,
+        };
+        let from = self
+            .rotation_draft
+            .unwrap_or_else(|| self.game.chess().pieces()[piece_idx].angle());
+        let by = if clockwise { degrees } else { -degrees }.to_radians();
+        self.rotation_draft = Some((from + by).rem_euclid(TAU));
+        self.scene_version += 1;
+    }
+
+    /// Play the rotation the keys and scroll wheel turned out, if there is one.
+    fn confirm_rotation(&mut self) {
+        if let Some(r) = self.rotation_draft
+            && let Some(piece_idx) = self.piece_to_rotate()
+        {
+            self.finish_rotation(piece_idx, r);
+        }
+    }
+
+    /// The cursor that best hints at what a click would do right now.
+    fn desired_cursor(&self) -> CursorIcon {
+        if self.quit_dialog_open
+            || self.takeback_dialog_open
+            || self.rematch_dialog_open
+            || self.rollback_offered.is_some()
+            || !self.my_turn()
+            || self.turn.waiting()
+        {
+            return CursorIcon::Default;
+        }
+        let Some((_, travelpoints)) = self.game.chess().selected() else {
+            return CursorIcon::Default;
+        };
+
+        let (x, y) = self.mouse_pos;
+        let hovering_travelpoint = travelpoints
+            .iter()
+            .any(|tp| tp.travelable && Piece::collidepoint_generic(x, y, tp.x, tp.y));
+        match self.turn.phase() {
+            TurnPhase::Move if hovering_travelpoint => CursorIcon::Pointer,
+            TurnPhase::Rotate if hovering_travelpoint => CursorIcon::NotAllowed,
+            TurnPhase::Rotate => CursorIcon::Grab,
+            _ => CursorIcon::Default,
+        }
+    }
+
+    fn update_cursor(&mut self, ctx: &mut Context) {
+        let cursor = self.desired_cursor();
+        if cursor != self.cursor {
+            mouse::set_cursor_type(ctx, cursor);
+            self.cursor = cursor;
+        }
+    }
+
+    /// Whether closing the window now would forfeit a game against someone.
+    fn game_in_progress(&self) -> bool {
+        self.netcode.is_some() && self.role == Role::Player && !self.opponent_left
+    }
+
+    /// Tell the opponent we're leaving, so they aren't left waiting on a dead connection.
+    ///
+    /// We can only talk on our turn, so if the opponent is the one thinking, they won't hear
+    /// about it until they try to hand the turn back.
+    fn send_quit(&mut self) {
+        if self.my_turn() && !self.opponent_left {
+            self.send_turn(&protocol::ser_message(&Message::Quit));
+        }
+    }
+}
+
+/// Spectating, from either end: relaying the game to a spectator while we host, and
+/// following along while we watch.
+impl App {
+    /// Bring our spectator, if we have one, up to date.
+    fn step_relay(&mut self) {
+        if let Some(relay) = &mut self.relay {
+            relay.step(self.game.history().actions(), self.opponent_left);
+        }
+    }
+
+    /// Follow along with a turn the host relayed, then hand the turn right back.
+    fn recv_spectated(&mut self, turn: &[u8; TURN_SIZE]) {
+        match protocol::de_message(turn) {
+            Ok(Message::Rules(rules)) => self.apply_rules(rules, Side::White),
+            Ok(Message::Resync { actions, .. }) => {
+                // the game changed under us, or we joined late. start over from the top.
+                self.apply(GameEvent::Reset(starting_pieces(self.chess960_ordering)));
+                self.action_times.clear();
+                self.thumbnails.clear();
+                self.clear_effects();
+                self.role = Role::Spectator {
+                    catching_up: actions,
+                };
+            }
+            Ok(Message::Thing(Some(thing))) => self.spectate_action(thing),
+            Ok(Message::Quit) => {
+                println!("The game is over.");
+                self.opponent_left = true;
+                return;
+            }
+            Ok(_) => (),
+            Err(e) => println!("{e}"),
+        }
+        if let Some(netcode) = &mut self.netcode {
+            netcode.send_turn(&protocol::ser_thing(None));
+            self.bandwidth.sent.record(TURN_SIZE, Instant::now());
+        }
+    }
+
+    /// Play out an action the host relayed, at the end of the game even if we're looking
+    /// back. Actions of a resync are played out quietly.
+    fn spectate_action(&mut self, thing: ThingHappened) {
+        let Some(action) = Action::from_thing(&thing) else {
+            return;
+        };
+        let (Action::Move(piece_idx, ..) | Action::Rotate(piece_idx, _)) = action;
+        if !self.game.is_piece_idx(piece_idx) {
+            println!("The host relayed an action for piece {piece_idx}, which we don't have.");
+            return;
+        }
+        let Role::Spectator { catching_up } = self.role else {
+            return;
+        };
+        self.jump_to_turn(self.game.history().len());
+        let before = notation::snapshot(self.game.chess().pieces());
+        self.apply(GameEvent::Play(action));
+        if catching_up > 0 {
+            self.role = Role::Spectator {
+                catching_up: catching_up - 1,
+            };
+        } else {
+            self.show_action(&before, action);
+        }
+    }
+}
+
+/// Getting our board back in line with the opponent's, after a turn we couldn't make sense
+/// of. [`Netplay`] keeps track of how it's going.
+impl App {
+    fn resyncing(&self) -> bool {
+        self.netplay.resyncing()
+    }
+
+    /// Ask the opponent for the copy of the game [`Netplay`] is waiting on. Only call this
+    /// while we have the turn.
+    fn request_resync(&mut self) {
+        println!("Asking the opponent for a fresh copy of the game.");
+        self.deselect();
+        self.send_turn(&protocol::ser_message(&Message::RequestResync));
+    }
+
+    /// Send the next queued resync turn, if we have the turn to send it with.
+    fn step_resync_send(&mut self) {
+        if self.my_turn()
+            && let Some(turn) = self.netplay.next_outgoing()
+        {
+            self.send_turn(&turn);
+        }
+    }
+
+    /// The opponent is about to send over their copy of the game. Start from a fresh board.
+    fn start_resync(&mut self, done: bool) {
+        self.apply(GameEvent::Reset(starting_pieces(self.chess960_ordering)));
+        self.action_times.clear();
+        self.thumbnails.clear();
+        self.clear_effects();
+        self.takeback_wanted = false;
+        self.takeback_asked = false;
+        self.rollback_asked = None;
+        self.rollback_offered = None;
+        self.send_turn(&protocol::ser_thing(None));
+        if done {
+            self.finish_resync();
+        }
+    }
+
+    /// Replay one action of the opponent's copy of the game.
+    fn recv_resync_action(&mut self, action: Action, done: bool) {
+        self.apply(GameEvent::Play(action));
+        self.send_turn(&protocol::ser_thing(None));
+        if done {
+            self.finish_resync();
+        }
+    }
+
+    /// If even a fresh copy doesn't make sense, there's no getting back in sync.
+    fn give_up_resync(&mut self, why: &str) {
+        println!("{why} Giving up.");
+        self.opponent_left = true;
+    }
+
+    fn finish_resync(&mut self) {
+        println!("Back in sync with the opponent.");
+        self.deselect();
+    }
+}
+
+/// The game library, for continuing saved games.
+impl App {
+    /// Open the library, unless we're in the middle of something a saved game can't
+    /// replace.
+    fn open_library(&mut self) {
+        if self.netcode.is_some() || self.watching() || self.bench.is_some() {
+            return;
+        }
+        let entries = library::scan(Path::new("."));
+        self.library = Some(LibraryView {
+            thumbnails: entries.iter().map(|_| None).collect(),
+            entries,
+            selected: 0,
+        });
+    }
+
+    /// Draw thumbnails for the library's games that don't have one yet, by playing each
+    /// out on a board of its own.
+    fn render_library_thumbnails(&mut self, ctx: &mut Context) -> GameResult {
+        let Some(view) = &self.library else {
+            return Ok(());
+        };
+        let missing: Vec<(usize, Game)> = view
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| view.thumbnails[*i].is_none())
+            .filter_map(|(i, entry)| {
+                play_out(entry.replay.chess960_ordering, &entry.replay.actions)
+                    .map(|game| (i, game))
+            })
+            .collect();
+        for (i, game) in missing {
+            let image = self.render_thumbnail(ctx, &game)?;
+            if let Some(view) = &mut self.library {
+                view.thumbnails[i] = Some(image);
+            }
+        }
+        Ok(())
+    }
+
+    /// Pick a saved game up where it left off, against whoever it was against, with
+    /// nothing left over from the game we were in. There's no picking a network game back
+    /// up with the same opponent, so those go on as hot-seat games, facing our side.
+    ///
+    /// If it was saved with its board, playing it out has to get there.
+    fn continue_game(&mut self, ctx: &Context, game: &SavedGame) -> GameResult {
+        let replay = &game.replay;
+        // play it out on the side first, so a bad save leaves the game alone.
+        let Some(played) = play_out(replay.chess960_ordering, &replay.actions) else {
+            return Err(GameError::CustomError(
+                "That game has pieces our board doesn't.".to_string(),
+            ));
+        };
+        game.check(&notation::snapshot(played.chess().pieces()))?;
+        let (side, personality) = match game.mode {
+            Mode::HotSeat => (Side::White, None),
+            Mode::Bot { side, personality } => (side, Some(personality)),
+            Mode::Network { side } => (side, None),
+        };
+        let mut turn = TurnPhaseMachine::new(side == Side::White, personality.is_some());
+        for action in &replay.actions {
+            let thing = ThingHappened::from(*action);
+            let checked = if turn.waiting() {
+                turn.receive(&thing)
+            } else {
+                turn.act(&thing)
+            };
+            if let Err(violation) = checked {
+                return Err(GameError::CustomError(format!(
+                    "That game wasn't played in turn: {violation}"
+                )));
+            }
+        }
+
+        self.opponent_bot = personality.map(|personality| new_opponent(ctx, personality, None));
+        self.start_over(
+            starting_pieces(replay.chess960_ordering),
+            replay.chess960_ordering,
+        );
+        for &action in &replay.actions {
+            self.apply(GameEvent::Play(action));
+        }
+        self.chess_layout = match replay.chess960_ordering {
+            Some(_) => ChessLayout::Chess960,
+            None => ChessLayout::Standard,
+        };
+        for (turn, annotation) in &replay.annotations {
+            self.annotations.toggle(*turn, *annotation);
+        }
+        for (turn, comment) in &replay.comments {
+            self.annotations.set_comment(*turn, comment.clone());
+        }
+        self.action_times = replay.times.clone();
+        // like a rematch, nothing about the last game carries over.
+        self.staged.clear();
+        self.staged_board.clear();
+        self.takeback_wanted = false;
+        self.takeback_asked = false;
+        self.rollback_asked = None;
+        self.rematch_wanted = false;
+        self.opponent_away = false;
+        self.opponent_left = false;
+        self.board_outcome = None;
+        self.outcome_checked = (0, None);
+        self.clock = self.rules.clock.map(|(white, black)| {
+            ChessClock::new(white, black).with_increment(self.rules.increment)
+        });
+        self.take_side(side);
+        self.turn = turn;
+        self.deselect();
+        if matches!(game.mode, Mode::Network { .. }) {
+            self.notifications.push(
+                "That was a network game, so it goes on here as a hot-seat game.".to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Handle a key while the library is open.
+    fn library_key(&mut self, ctx: &Context, key: &Key) {
+        let Some(view) = &mut self.library else {
+            return;
+        };
+        match key {
+            Key::Named(NamedKey::ArrowUp) => view.selected = view.selected.saturating_sub(1),
+            Key::Named(NamedKey::ArrowDown) => {
+                view.selected = usize::min(view.selected + 1, view.entries.len().saturating_sub(1))
+            }
+            Key::Named(NamedKey::Enter) => {
+                if let Some(view) = self.library.take()
+                    && let Some(entry) = view.entries.into_iter().nth(view.selected)
+                {
+                    let game = SavedGame {
+                        mode: entry.mode,
+                        board: entry.board,
+                        replay: entry.replay,
+                    };
+                    let result = self.continue_game(ctx, &game);
+                    self.report_failure("continue that game", result);
+                }
+            }
+            Key::Named(NamedKey::Escape) => self.library = None,
+            Key::Character(c) if c.as_str() == "c" => self.library = None,
+            Key::Character(c) if c.as_str() == "s" => {
+                let result = export_stats(&view.entries);
+                self.report_failure("export the stats", result);
+            }
+            _ => (),
+        }
+    }
+
+    /// Draw the library over everything: a row per saved game, with its thumbnail, name and
+    /// what's in it, and the picked one highlighted.
+    fn draw_library(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        /// Space around and between rows, in pixels.
+        const PADDING: f32 = 12.;
+        /// How big thumbnails are drawn in the list, in pixels.
+        const ROW_HEIGHT: f32 = 96.;
+
+        let Some(view) = &self.library else {
+            return Ok(());
+        };
+        let (width, height) = ctx.gfx.drawable_size();
+        self.draw_mesh(
+            canvas,
+            Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(0., 0., width, height),
+                self.theme.background,
+            )?,
+            DrawParam::new(),
+        );
+
+        let mut title = Text::new(
+            "Continue a game. Up/Down to pick, Enter to play, S for stats, Esc to close.",
+        );
+        title.set_scale(BANNER_TEXT_SIZE);
+        self.draw_on(
+            canvas,
+            &title,
+            DrawParam::new()
+                .dest(Vec2::new(PADDING, PADDING))
+                .color(self.theme.dialog_text),
+        );
+        let top = 2. * PADDING + title.measure(ctx)?.y;
+
+        if view.entries.is_empty() {
+            let mut text = Text::new("No saved games here yet. Press E during a game to save it.");
+            text.set_scale(TICKER_TEXT_SIZE);
+            self.draw_on(
+                canvas,
+                &text,
+                DrawParam::new()
+                    .dest(Vec2::new(PADDING, top))
+                    .color(self.theme.dialog_text),
+            );
+            return Ok(());
+        }
+
+        // scroll so the picked game is always on screen.
+        let row_step = ROW_HEIGHT + PADDING;
+        let rows_visible = (((height - top) / row_step).floor() as usize).max(1);
+        let first = (view.selected + 1).saturating_sub(rows_visible);
+        let now = SystemTime::now();
+        for (row, i) in (first..view.entries.len()).take(rows_visible).enumerate() {
+            let entry = &view.entries[i];
+            let y = top + row as f32 * row_step;
+            if i == view.selected {
+                self.draw_mesh(
+                    canvas,
+                    Mesh::new_rectangle(
+                        ctx,
+                        DrawMode::fill(),
+                        Rect::new(0., y - PADDING / 2., width, row_step),
+                        self.theme.selected,
+                    )?,
+                    DrawParam::new(),
+                );
+            }
+            if let Some(image) = &view.thumbnails[i] {
+                self.draw_on(
+                    canvas,
+                    image,
+                    DrawParam::new()
+                        .dest(Vec2::new(PADDING, y))
+                        .scale(Vec2::splat(ROW_HEIGHT / THUMBNAIL_SIZE as f32)),
+                );
+            }
+            let mut text = Text::new(format!("{}\n{}", entry.name(), entry.summary(now)));
+            text.set_scale(TICKER_TEXT_SIZE);
+            self.draw_on(
+                canvas,
+                &text,
+                DrawParam::new()
+                    .dest(Vec2::new(2. * PADDING + ROW_HEIGHT, y))
+                    .color(self.theme.dialog_text),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The blunder check after local games and games against the computer.
+impl App {
+    /// Look over the game so far for blunders and list them. Against the computer, only
+    /// ours are listed.
+    fn open_blunder_review(&mut self) {
+        if self.netcode.is_some() || self.watching() || self.bench.is_some() {
+            return;
+        }
+        let chess = RotchessEmulator::with(starting_pieces(self.chess960_ordering));
+        let mut blunders = blunders::find_blunders(chess, self.game.history().actions());
+        if self.opponent_bot.is_some() {
+            blunders.retain(|blunder| blunder.side == self.my_side);
+        }
+        self.blunder_review = Some(BlunderReview {
+            blunders,
+            selected: 0,
+        });
+    }
+
+    /// Once a local game is over, show its blunders.
+    fn check_post_game(&mut self) {
+        if !self.post_game_reviewed && self.netcode.is_none() && self.outcome().is_some() {
+            self.post_game_reviewed = true;
+            self.open_blunder_review();
+        }
+    }
+
+    fn blunder_review_key(&mut self, key: &Key) {
+        let Some(review) = &mut self.blunder_review else {
+            return;
+        };
+        match key {
+            Key::Named(NamedKey::ArrowUp) => review.selected = review.selected.saturating_sub(1),
+            Key::Named(NamedKey::ArrowDown) => {
+                review.selected =
+                    usize::min(review.selected + 1, review.blunders.len().saturating_sub(1))
+            }
+            Key::Named(NamedKey::Enter) => {
+                // show me: the board right before the blunder, to look at what else there was.
+                if let Some(review) = self.blunder_review.take()
+                    && let Some(blunder) = review.blunders.get(review.selected)
+                {
+                    self.jump_to_turn(blunder.turn);
+                }
+            }
+            Key::Named(NamedKey::Escape) => self.blunder_review = None,
+            _ => (),
+        }
+    }
+
+    /// Draw the blunder summary over everything: a line per blunder, with the picked one
+    /// highlighted.
+    fn draw_blunder_review(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        /// Space around and between rows, in pixels.
+        const PADDING: f32 = 12.;
+
+        let Some(review) = &self.blunder_review else {
+            return Ok(());
+        };
+        let (width, height) = ctx.gfx.drawable_size();
+        self.draw_mesh(
+            canvas,
+            Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(0., 0., width, height),
+                self.theme.background,
+            )?,
+            DrawParam::new(),
+        );
+
+        let mut title = Text::new("Blunders. Up/Down to pick, Enter to show me, Esc to close.");
+        title.set_scale(BANNER_TEXT_SIZE);
+        self.draw_on(
+            canvas,
+            &title,
+            DrawParam::new()
+                .dest(Vec2::new(PADDING, PADDING))
+                .color(self.theme.dialog_text),
+        );
+        let top = 2. * PADDING + title.measure(ctx)?.y;
+
+        if review.blunders.is_empty() {
+            let mut text = Text::new("Nothing gave away much this game. Well played!");
+            text.set_scale(TICKER_TEXT_SIZE);
+            self.draw_on(
+                canvas,
+                &text,
+                DrawParam::new()
+                    .dest(Vec2::new(PADDING, top))
+                    .color(self.theme.dialog_text),
+            );
+            return Ok(());
+        }
+
+        // scroll so the picked blunder is always on screen.
+        let row_step = TICKER_TEXT_SIZE + PADDING;
+        let rows_visible = (((height - top) / row_step).floor() as usize).max(1);
+        let first = (review.selected + 1).saturating_sub(rows_visible);
+        for (row, i) in (first..review.blunders.len())
+            .take(rows_visible)
+            .enumerate()
+        {
+            let blunder = &review.blunders[i];
+            let y = top + row as f32 * row_step;
+            if i == review.selected {
+                self.draw_mesh(
+                    canvas,
+                    Mesh::new_rectangle(
+                        ctx,
+                        DrawMode::fill(),
+                        Rect::new(0., y - PADDING / 2., width, row_step),
+                        self.theme.selected,
+                    )?,
+                    DrawParam::new(),
+                );
+            }
+            let mut text = Text::new(format!(
+                "Turn {}: {}, giving up about {:.0}",
+                blunder.turn / 2 + 1,
+                blunder.description,
+                blunder.swing
+            ));
+            text.set_scale(TICKER_TEXT_SIZE);
+            self.draw_on(
+                canvas,
+                &text,
+                DrawParam::new()
+                    .dest(Vec2::new(PADDING, y))
+                    .color(self.theme.dialog_text),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Analysis mode, where the engine suggests a turn from whatever board we're looking at.
+impl App {
+    /// Turn analysis mode on or off. It's off limits in networked games.
+    fn toggle_analysis(&mut self) {
+        if self.analysis.take().is_some() || self.netcode.is_some() {
+            return;
+        }
+        self.analysis = Some(Analysis {
+            actions: Vec::new(),
+            search: None,
+        });
+        self.restart_analysis();
+    }
+
+    /// Start searching from the board we're looking at.
+    fn restart_analysis(&mut self) {
+        if self.analysis.is_none() {
+            return;
+        }
+        let actions = self.game.history().actions()[..self.game.history().current()].to_vec();
+        // an odd number of actions is a move waiting for its rotation.
+        let search = if actions.len().is_multiple_of(2) {
+            play_out(self.chess960_ordering, &actions)
+                .map(|chess| Search::new(chess, self.side_to_move(), Personality::NEUTRAL))
+        } else {
+            None
+        };
+        self.analysis = Some(Analysis { actions, search });
+    }
+
+    /// Search a little deeper, or start over if the board we're looking at has changed.
+    fn step_analysis(&mut self) {
+        let Some(analysis) = &mut self.analysis else {
+            return;
+        };
+        if analysis.actions[..] != self.game.history().actions()[..self.game.history().current()] {
+            self.restart_analysis();
+            return;
+        }
+        if let Some(search) = &mut analysis.search {
+            search.step(ENGINE_TURNS_PER_FRAME);
+        }
+    }
+
+    /// The engine's best turn so far, as an arrow for the move and an arc around where it
+    /// lands for the rotation, sweeping from the piece's old angle to its new one.
+    fn draw_analysis(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        let Some(Suggestion {
+            from,
+            to,
+            from_angle,
+            angle,
+            score,
+            depth,
+            ..
+        }) = self
+            .analysis
+            .as_ref()
+            .and_then(|analysis| analysis.search.as_ref())
+            .and_then(Search::best)
+        else {
+            return Ok(());
+        };
+
+        self.draw_arrow((ctx, canvas), from, to, ENGINE_ARROW_COLOR)?;
+
+        // the shorter way around, like rotating by hand.
+        let sweep = (angle - from_angle + TAU / 2.).rem_euclid(TAU) - TAU / 2.;
+        let center = self.transform.world_pos(to.0, to.1);
+        let radius = self.transform.world_len(ENGINE_ARC_RADIUS);
+        let on_arc = |a: f32| {
+            let screen = self.transform.screen_angle(a);
+            center + radius * Vec2::new(screen.sin(), -screen.cos())
+        };
+        if sweep.abs() > 1e-3 {
+            let points: Vec<Vec2> = (0..=ENGINE_ARC_SEGMENTS)
+                .map(|i| on_arc(from_angle + sweep * i as f32 / ENGINE_ARC_SEGMENTS as f32))
+                .collect();
+            self.draw_mesh(
+                canvas,
+                Mesh::new_line(
+                    ctx,
+                    &points,
+                    self.transform.world_len(0.08),
+                    ENGINE_ARROW_COLOR,
+                )?,
+                DrawParam::new(),
+            );
+        }
+        self.draw_mesh(
+            canvas,
+            Mesh::new_circle(
+                ctx,
+                DrawMode::fill(),
+                Vec2::ZERO,
+                self.transform.world_len(0.08),
+                CIRC_TOLERANCE,
+                ENGINE_ARROW_COLOR,
+            )?,
+            on_arc(angle),
+        );
+
+        let searching = if depth < engine::MAX_DEPTH { "…" } else { "" };
+        let mut text = Text::new(format!("depth {depth}{searching}, {score:+.0}"));
+        text.set_scale(self.transform.world_len(0.18));
+        self.draw_on(
+            canvas,
+            &text,
+            DrawParam::new()
+                .dest(
+                    self.transform
+                        .world_pos(to.0 + ENGINE_ARC_RADIUS, to.1 + ENGINE_ARC_RADIUS),
+                )
+                .color(ENGINE_ARROW_COLOR),
+        );
+        Ok(())
+    }
+}
+
+/// Annotations drawn over the board.
+impl App {
+    /// Turn a finished right-drag into an annotation.
+    ///
+    /// A drag that barely went anywhere is a click: on a piece, that selects it like a right
+    /// click always has. Anywhere else, it circles the spot.
+    fn finish_annotation(&mut self, from: (f32, f32), to: (f32, f32)) {
+        let dragged = Vec2::from(from).distance(Vec2::from(to)) > ANNOTATION_DRAG_THRESHOLD;
+        let turn = self.game.history().current();
+        if dragged {
+            self.annotations
+                .toggle(turn, Annotation::Arrow { from, to });
+        } else if self.piece_at(from.0, from.1) {
+            let (x, y) = from;
+            let button = emulator::MouseButton::RIGHT;
+            self.try_send_event(Event::ButtonDown { x, y, button });
+            self.try_send_event(Event::ButtonUp { x, y, button });
+        } else {
+            self.annotations
+                .toggle(turn, Annotation::Circle { at: from });
+        }
+    }
+}
+
+/// The history scrubber under the board, and the move history panel beside it.
+impl App {
+    /// Where the scrubber is, in pixels.
+    fn scrubber_rect(&self) -> Rect {
+        let frame = self.transform.frame();
+        Rect::new(frame.x, frame.bottom(), frame.w, SCRUBBER_HEIGHT)
+    }
+
+    /// The turn that sits under some x position (in pixels) on the scrubber.
+    fn scrubber_turn_at(&self, x: f32) -> usize {
+        let fraction = (x / self.scrubber_rect().w).clamp(0., 1.);
+        (fraction * self.game.history().len() as f32).round() as usize
+    }
+
+    /// Jump to the turn the scrubber was dragged to.
+    fn step_scrub(&mut self) {
+        if let Some(target) = self.scrub_target.take()
+            && target != self.game.history().current()
+        {
+            self.try_jump_to_turn(target);
+        }
+    }
+
+    /// Open or close the move history panel, making room for it by shrinking the board.
+    fn toggle_history_panel(&mut self, ctx: &Context) {
+        self.history_panel.toggle();
+        let (width, height) = ctx.gfx.drawable_size();
+        self.target_scale =
+            BoardTransform::fitting_scale(self.history_panel.board_width(width), height);
+        self.resized_at = Some(Instant::now());
+    }
+
+    /// Keep the move history panel up to date with the game, and the turn we're looking at
+    /// in view.
+    fn step_history_panel(&mut self, ctx: &Context) {
+        if !self.history_panel.open() {
+            return;
+        }
+        if self.history_panel.is_stale(self.game.history().actions()) {
+            let boards = self.boards_through_game();
+            self.history_panel
+                .refresh(self.game.history().actions(), &boards);
+        }
+        let (_, height) = ctx.gfx.drawable_size();
+        self.history_panel
+            .follow(self.game.history().current(), height);
+    }
+}
+
+/// The scripted game played in benchmark mode.
+impl App {
+    /// Push the benchmark along by a frame, quitting once it's done.
+    fn step_bench(&mut self, ctx: &mut Context) {
+        let Some(bench) = &self.bench else {
+            return;
+        };
+        if bench.is_done() {
+            println!("{}", bench.report());
+            ctx.request_quit();
+            return;
+        }
+
+        // sweep the mouse around the board so hover highlights get drawn too.
+        let frame = bench.frames_done();
+        let t = frame as f32 / 30.;
+        let (x, y) = (4. + 3. * t.cos(), 4. + 3. * t.sin());
+        self.mouse_pos = (x, y);
+        self.try_send_event(Event::MouseMotion { x, y });
+
+        if frame % FRAMES_PER_SCRIPT_STEP == 0 {
+            self.bench_script_step(frame / FRAMES_PER_SCRIPT_STEP);
+        }
+    }
+
+    /// Click our way through a game: pick a piece, move it somewhere it can go, then
+    /// give it a quarter turn.
+    fn bench_script_step(&mut self, step: u32) {
+        match self.turn.phase() {
+            TurnPhase::Move => {
+                let destination = self.game.chess().selected().and_then(|(_, travelpoints)| {
+                    travelpoints
+                        .iter()
+                        .find(|tp| tp.travelable)
+                        .map(|tp| (tp.x, tp.y))
+                });
+                if let Some((x, y)) = destination {
+                    self.left_click(x, y);
+                } else {
+                    let pieces = self.game.chess().pieces();
+                    let piece = &pieces[step as usize % pieces.len()];
+                    let (x, y) = (piece.x(), piece.y());
+                    self.left_click(x, y);
+                }
+            }
+            TurnPhase::Rotate => {
+                let Some(&Action::Move(piece_idx, _, _)) = self.game.history().actions().last()
+                else {
+                    return;
+                };
+                let r = self.game.chess().pieces()[piece_idx].angle() + TAU / 4.;
+                self.rotate_directly(piece_idx, r);
+            }
+            TurnPhase::Wait => {}
+        }
+    }
+
+    /// Click somewhere on the board, in rotchess units, like the user would.
+    fn left_click(&mut self, x: f32, y: f32) {
+        let button = emulator::MouseButton::LEFT;
+        self.try_send_event(Event::ButtonDown { x, y, button });
+        self.try_send_event(Event::ButtonUp { x, y, button });
+    }
+
+    /// Rotate a piece and finish our turn.
+    ///
+    /// Dragging out a rotation by hand is a lot of ceremony for a script, so this rotates the
+    /// piece directly, like a received rotation.
+    fn rotate_directly(&mut self, piece_idx: usize, r: f32) {
+        let before = notation::snapshot(self.game.chess().pieces());
+        self.apply(GameEvent::Play(Action::Rotate(piece_idx, r)));
+        self.show_action(&before, Action::Rotate(piece_idx, r));
+        self.deselect();
+        self.turn.end_turn();
+    }
+}
+
+/// Bots playing each other in exhibition mode, and replays playing back.
+impl App {
+    /// Whether the game is playing itself, so the user is only watching.
+    fn watching(&self) -> bool {
+        self.exhibition.is_some() || self.playback.is_some()
+    }
+
+    /// Play the replay's next action, if it's time to.
+    fn step_playback(&mut self) {
+        // like exhibitions, someone looking back gets to look in peace.
+        if !self.game.history().at_live() || self.scrubbing {
+            return;
+        }
+        let Some(action) = self
+            .playback
+            .as_mut()
+            .and_then(|playback| playback.take_due_action(Instant::now()))
+        else {
+            return;
+        };
+        let (Action::Move(piece_idx, ..) | Action::Rotate(piece_idx, _)) = action;
+        if !self.game.is_piece_idx(piece_idx) {
+            println!("The replay moves piece {piece_idx}, which our board doesn't have.");
+            self.playback = None;
+            return;
+        }
+        let before = notation::snapshot(self.game.chess().pieces());
+        let Some(thing) = self.apply(GameEvent::Play(action)) else {
+            return;
+        };
+        if let Err(violation) = self.turn.act(&thing) {
+            println!("The replay wasn't played in turn: {violation}");
+        }
+        self.show_action(&before, action);
+    }
+
+    /// Have whichever bot's turn it is act, if it's time to.
+    fn step_exhibition(&mut self) {
+        // someone looking back through the game gets to look in peace.
+        if !self.game.history().at_live() || self.scrubbing {
+            return;
+        }
+        if !self
+            .exhibition
+            .as_mut()
+            .is_some_and(|exhibition| exhibition.take_due_action(Instant::now()))
+        {
+            return;
+        }
+
+        let side = self.side_to_move();
+        match self.turn.phase() {
+            TurnPhase::Move => {
+                let options = self.move_options();
+                let Some(exhibition) = &mut self.exhibition else {
+                    return;
+                };
+                match exhibition.bot(side).choose_move(&options) {
+                    Some(choice) => {
+                        let piece = &self.game.chess().pieces()[choice.piece_idx];
+                        let (x, y) = (piece.x(), piece.y());
+                        self.left_click(x, y);
+                        self.left_click(choice.x, choice.y);
+                    }
+                    None => {
+                        println!("The side to move has nowhere to go. Pausing the exhibition.");
+                        exhibition.pause();
+                    }
+                }
+            }
+            TurnPhase::Rotate => {
+                let Some(&Action::Move(piece_idx, _, _)) = self.game.history().actions().last()
+                else {
+                    return;
+                };
+                let angle = self.game.chess().pieces()[piece_idx].angle();
+                let Some(exhibition) = &mut self.exhibition else {
+                    return;
+                };
+                let r = exhibition.bot(side).choose_rotation(angle);
+                self.rotate_directly(piece_idx, r);
+            }
+            TurnPhase::Wait => {}
+        }
+    }
+
+    /// Have the bot we're playing against think about its turn, and take it once it's time
+    /// to. It plays through [`App::recv_actions`], just like an opponent over the network.
+    fn step_opponent_bot(&mut self) {
+        // like exhibitions, someone looking back gets to look in peace, and so does someone
+        // who's gone to another window.
+        if !self.game.history().at_live() || self.scrubbing || self.unfocused_since.is_some() {
+            return;
+        }
+        let its_turn = self.turn.waiting() && self.outcome().is_none();
+        if its_turn
+            && self
+                .opponent_bot
+                .as_ref()
+                .is_some_and(|opponent| !opponent.thinking())
+            && let Some(game) = play_out(self.chess960_ordering, self.game.history().actions())
+        {
+            let side = self.side_to_move();
+            if let Some(opponent) = &mut self.opponent_bot {
+                opponent.start_thinking(game.into_chess(), side);
+            }
+        }
+        let Some(opponent) = &mut self.opponent_bot else {
+            return;
+        };
+        if !opponent.take_due_turn(its_turn, Instant::now()) {
+            opponent.think(ENGINE_TURNS_PER_FRAME);
+            return;
+        }
+
+        let Some(choice) = opponent.choose_turn() else {
+            println!("The computer has nowhere to go, so it gives up.");
+            self.opponent_bot = None;
+            self.opponent_left = true;
+            return;
+        };
+        if opponent.resigns() {
+            self.opponent_resigned();
+            return;
+        }
+        let whole_turn = [
+            Action::Move(choice.piece_idx, choice.to.0, choice.to.1),
+            Action::Rotate(choice.piece_idx, choice.angle),
+        ];
+        for action in whole_turn {
+            let checked = self.turn.receive(&action.into());
+            debug_assert!(checked.is_ok(), "The computer only plays on its turn.");
+        }
+        self.recv_actions(&whole_turn);
+    }
+
+    /// The opponent gave up, by leaving the game or, for the computer, by resigning. It's
+    /// our win, and we stop talking to them.
+    fn opponent_resigned(&mut self) {
+        if self.opponent_bot.is_some() {
+            println!("The computer resigns.");
+        } else {
+            println!("Opponent left the game.");
+        }
+        self.opponent_left = true;
+    }
+
+    /// On a kiosk nobody has touched in a while, start a demo game.
+    fn check_kiosk_idle(&mut self) {
+        if self.kiosk && self.exhibition.is_none() && self.last_input.elapsed() > KIOSK_IDLE_TIMEOUT
+        {
+            self.reset_board();
+            self.turn = TurnPhaseMachine::new(true, false);
+            self.exhibition = Some(Exhibition::new(DEFAULT_ACTION_DELAY));
+        }
+    }
+
+    /// If a kiosk is playing a demo, stop it and set up a fresh game for whoever walked up.
+    ///
+    /// Returns whether it was, in which case the input that stopped it shouldn't do anything
+    /// else.
+    fn end_kiosk_demo(&mut self) -> bool {
+        if !self.kiosk || self.exhibition.is_none() {
+            return false;
+        }
+        self.exhibition = None;
+        self.reset_board();
+        self.turn = TurnPhaseMachine::new(true, false);
+        self.deselect();
+        true
+    }
+
+    /// The side whose turn it is on the board we're looking at. White goes first.
+    fn side_to_move(&self) -> Side {
+        if self.game.history().turns_played().is_multiple_of(2) {
+            Side::White
+        } else {
+            Side::Black
+        }
+    }
+
+    /// Everywhere the side to move could go.
+    fn move_options(&mut self) -> Vec<MoveOption> {
+        let side = self.side_to_move();
+        let options = self.game.move_options(side);
+        self.scene_version += 1;
+        options
+    }
+}
+
+/// Helper functions for drawing
+impl App {
+    /// Throw away every cached mesh, so they're rebuilt with the current sizes and
+    /// view on the next draw.
+    ///
+    /// Call this whenever something a cached mesh was built from changes.
+    fn invalidate_meshes(&mut self) {
+        self.board_mesh = None;
+        self.selection_meshes = None;
+    }
+
+    /// Stretch a cached mesh built at some [`BoardTransform::scale`] to fit the current one.
+    ///
+    /// Only works for meshes drawn at the origin, since it scales around it. Any other change
+    /// to the transform has to [rebuild](App::invalidate_meshes) them.
+    fn stretch_from(&self, built_at: f32) -> DrawParam {
+        DrawParam::new().scale(Vec2::splat(self.transform.scale / built_at))
+    }
+
+    /// The squares and the scrubber, with the last move and coordinates for a projector.
+    fn draw_board_layer(&mut self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        self.clip_to_board(canvas)?;
+        self.draw_board((ctx, canvas))?;
+        if self.projector_mode {
+            self.draw_last_move((ctx, canvas))?;
+            self.draw_coordinates(canvas);
+        }
+        canvas.set_default_scissor_rect();
+
+        self.draw_scrubber((ctx, canvas))?;
+        Ok(())
+    }
+
+    /// The pieces, between the selection's highlights, with trails and ghosts.
+    fn draw_piece_layer(&mut self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        self.update_selection_meshes(ctx)?;
+        if let Some(SelectionMeshes {
+            built_at,
+            under: Some(mesh),
+            ..
+        }) = &self.selection_meshes
+        {
+            self.draw_on(canvas, mesh, self.stretch_from(*built_at));
+        }
+
+        self.draw_move_trail((ctx, canvas))?;
+        self.draw_rotation_ghost(canvas);
+        self.draw_staged_ghosts(canvas);
+        self.draw_pieces(canvas, &self.game)?;
+        self.draw_rotation_draft(canvas);
+
+        if let Some(SelectionMeshes {
+            built_at,
+            over: Some(mesh),
+            ..
+        }) = &self.selection_meshes
+        {
+            self.draw_on(canvas, mesh, self.stretch_from(*built_at));
+        }
+        Ok(())
+    }
+
+    /// Everything marking up the board.
+    fn draw_indicator_layer(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        self.draw_hitcircles((ctx, canvas))?;
+        self.draw_rejected_click((ctx, canvas))?;
+        self.draw_annotations((ctx, canvas))?;
+        self.draw_inspected_piece((ctx, canvas))?;
+        self.draw_analysis((ctx, canvas))?;
+        self.draw_snap_ticks((ctx, canvas))?;
+        self.draw_angle_readout((ctx, canvas))?;
+        Ok(())
+    }
+
+    /// Label the selected piece with the angle it's at, or will be at once the rotation the
+    /// keys or scroll wheel turned out is played. Dragging a rotation turns the piece as it
+    /// goes, so this follows along.
+    fn draw_angle_readout(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        /// Space between the text and the edge of its background, in pixels.
+        const PADDING: f32 = 4.;
+
+        let Some((piece, _)) = self.game.chess().selected() else {
+            return Ok(());
+        };
+        let rotating = self
+            .piece_to_rotate()
+            .map(|piece_idx| &self.game.chess().pieces()[piece_idx]);
+        let angle = match (self.rotation_draft, rotating) {
+            // no two pieces stand on the same spot.
+            (Some(draft), Some(rotating))
+                if (rotating.x(), rotating.y()) == (piece.x(), piece.y()) =>
+            {
+                draft
+            }
+            _ => piece.angle(),
+        };
+        let mut text = Text::new(notation::angle_label(angle));
+        text.set_scale(self.transform.world_len(0.2));
+        let size = text.measure(ctx)?;
+        // off to the right on screen, whichever way the board's turned.
+        let at = self.transform.world_pos(piece.x(), piece.y())
+            + Vec2::new(self.transform.world_len(PIECE_RADIUS * 1.5), -size.y / 2.);
+        self.draw_mesh(
+            canvas,
+            Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(
+                    -PADDING,
+                    -PADDING,
+                    size.x + 2. * PADDING,
+                    size.y + 2. * PADDING,
+                ),
+                BANNER_COLOR,
+            )?,
+            at,
+        );
+        self.draw_on(
+            canvas,
+            &text,
+            DrawParam::new().dest(at).color(BANNER_TEXT_COLOR),
+        );
+        Ok(())
+    }
+
+    /// While snapping, tick marks around the piece being rotated at every angle it could snap
+    /// to, with the one it would snap to now picked out.
+    fn draw_snap_ticks(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        let (Some(step), Some(piece_idx)) = (self.snap_step, self.piece_to_rotate()) else {
+            return Ok(());
+        };
+        let piece = &self.game.chess().pieces()[piece_idx];
+        let center = self.transform.world_pos(piece.x(), piece.y());
+        let inner = self.transform.world_len(PIECE_RADIUS * 1.15);
+        let outer = self.transform.world_len(PIECE_RADIUS * 1.45);
+        let target = step.snap(piece.angle());
+        let mut mb = MeshBuilder::new();
+        for angle in step.angles() {
+            // pieces face up at 0, and turn clockwise on screen.
+            let direction = Vec2::from_angle(self.transform.screen_angle(angle) - TAU / 4.);
+            let (color, thickness) = if (angle - target).abs() < 1e-3 {
+                (
+                    ROTATE_AFFORDANCE_COLOR,
+                    3. * self.settings().outline_thickness,
+                )
+            } else {
+                (SNAP_TICK_COLOR, 2. * self.settings().outline_thickness)
+            };
+            mb.line(
+                &[center + inner * direction, center + outer * direction],
+                thickness,
+                color,
+            )?;
+        }
+        self.draw_mesh(canvas, Mesh::from_data(ctx, mb.build()), DrawParam::new());
+        Ok(())
+    }
+
+    /// The ticker, toasts, the clocks, and banners saying what's going on.
+    fn draw_hud_layer(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        self.history_panel.draw(
+            (ctx, canvas),
+            &self.theme,
+            &self.render_counters,
+            self.game.history().current(),
+        )?;
+        if let Some(minimap) = MiniMap::of(&self.transform) {
+            minimap.draw(
+                (ctx, canvas),
+                &self.transform,
+                self.game.chess().pieces(),
+                &self.theme,
+                &self.render_counters,
+            )?;
+        }
+        self.draw_ticker((ctx, canvas))?;
+        self.notifications.draw(
+            (ctx, canvas),
+            self.transform.frame(),
+            &self.render_counters,
+            self.settings.reduced_motion,
+        )?;
+
+        if self.opponent_left && self.role != Role::Player {
+            self.draw_banner((ctx, canvas), "The game is over.", BannerPosition::Top)?;
+        } else if self.opponent_left && self.opponent_bot.is_some() {
+            self.draw_banner(
+                (ctx, canvas),
+                "The computer resigns. Press R to rematch.",
+                BannerPosition::Top,
+            )?;
+        } else if self.opponent_left {
+            self.draw_banner(
+                (ctx, canvas),
+                "Opponent left the game.",
+                BannerPosition::Top,
+            )?;
+        } else if self.resyncing() {
+            self.draw_banner(
+                (ctx, canvas),
+                "Getting back in sync with opponent...",
+                BannerPosition::Top,
+            )?;
+        } else if self.opponent_away {
+            self.draw_banner((ctx, canvas), "Opponent is away.", BannerPosition::Top)?;
+        } else if let Some(ticket) = &self.ticket {
+            self.draw_banner(
+                (ctx, canvas),
+                &format!("Waiting for someone to join with this ticket:\n{ticket}"),
+                BannerPosition::Top,
+            )?;
+        } else if self.role != Role::Player {
+            self.draw_banner((ctx, canvas), "Spectating.", BannerPosition::Top)?;
+        } else if self.turn.waiting() {
+            self.draw_banner(
+                (ctx, canvas),
+                "Waiting for opponent...",
+                BannerPosition::Top,
+            )?;
+        }
+        self.draw_hud((ctx, canvas))?;
+
+        let mut bottom_lines = Vec::new();
+        if let Some(exhibition) = &self.exhibition
+            && !self.kiosk
+        {
+            bottom_lines.push(if exhibition.paused() {
+                "Exhibition paused. Space to resume.".to_string()
+            } else {
+                format!(
+                    "Exhibition, {}ms per action. Space to pause, +/- for speed.",
+                    exhibition.delay().as_millis()
+                )
+            });
+        }
+        if let Some(playback) = &self.playback {
+            let (played, total) = playback.progress();
+            bottom_lines.push(if playback.finished() {
+                format!("Replay over, {total} actions. The arrow keys look back through it.")
+            } else if playback.paused() {
+                format!("Replay paused at {played} of {total} actions. Space to resume.")
+            } else {
+                format!(
+                    "Replay at {}x speed, {played} of {total} actions. Space to pause, +/- for speed.",
+                    playback.speed()
+                )
+            });
+        }
+        if self.show_hitcircles {
+            let overlaps =
+                hitcircles::overlapping_pairs(&notation::snapshot(self.game.chess().pieces()))
+                    .len();
+            bottom_lines.push(match overlaps {
+                0 => "Showing hit circles. H to hide.".to_string(),
+                n => format!("Showing hit circles. {n} overlapping pair(s) of pieces! H to hide."),
+            });
+        }
+        if self.turn.waiting() && !self.my_turn() && !self.opponent_away {
+            match self.opponent_activity {
+                Activity::Thinking => (),
+                Activity::HoldingPiece => {
+                    bottom_lines.push("Opponent is holding a piece...".to_string())
+                }
+                Activity::Typing => bottom_lines.push("Opponent is typing...".to_string()),
+            }
+        }
+        if !self.staged.is_empty() && self.turn.waiting() {
+            bottom_lines
+                .push("Turn staged. Space to end your turn, T to take it back.".to_string());
+        }
+        if self.can_skip_rotation() {
+            bottom_lines.push("Drag to rotate, or S to leave the piece as it is.".to_string());
+        }
+        if self.takeback_wanted {
+            bottom_lines.push(
+                "Asking to take back your turn once the opponent moves. T to cancel.".to_string(),
+            );
+        } else if self.takeback_asked {
+            bottom_lines.push("Asked to take back your turn...".to_string());
+        }
+        if let Some(actions) = self.rollback_asked {
+            bottom_lines.push(format!("Asked to play on from turn {actions}..."));
+        }
+        if !self.game.history().at_live() {
+            // the turn counter up top says which turn this is.
+            let mut line = "Shift+Right to return to the live position.".to_string();
+            if self.netcode.is_some() && self.role == Role::Player && self.rules.takebacks {
+                line = format!("{line} Shift+T to ask to play on from here.");
+            }
+            if let Some((_, deadline)) = self.snap_back {
+                let secs_left = deadline
+                    .saturating_duration_since(Instant::now())
+                    .as_secs_f32()
+                    .ceil();
+                line = format!("{line} Back to live in {secs_left}s.");
+            }
+            bottom_lines.push(line);
+        }
+        if let Some(draft) = &self.comment_draft {
+            bottom_lines.push(format!(
+                "Comment on turn {}: {draft}_",
+                self.game.history().current()
+            ));
+            bottom_lines.push("(Enter to save, Esc to cancel)".to_string());
+        } else if let Some(comment) = self.annotations.comment(self.game.history().current()) {
+            bottom_lines.push(comment.to_string());
+        }
+        if !bottom_lines.is_empty() {
+            self.draw_banner(
+                (ctx, canvas),
+                &bottom_lines.join("\n"),
+                BannerPosition::Bottom,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Whatever covers the game, from the result to dialogs and the profiler.
+    fn draw_overlay_layer(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        self.draw_scrubber_thumbnail(canvas);
+        self.draw_result((ctx, canvas))?;
+        self.draw_library((ctx, canvas))?;
+        self.draw_blunder_review((ctx, canvas))?;
+
+        if self.quit_dialog_open {
+            self.draw_dialog((ctx, canvas), "Resign and quit?\n\n[Y]es / [N]o")?;
+        } else if self.takeback_dialog_open {
+            self.draw_dialog(
+                (ctx, canvas),
+                "Opponent asks to take back their last turn.\n\n[Y]es / [N]o",
+            )?;
+        } else if self.rematch_dialog_open {
+            self.draw_dialog(
+                (ctx, canvas),
+                "Opponent wants a rematch, with colors swapped.\n\n[Y]es / [N]o",
+            )?;
+        } else if let Some(actions) = self.rollback_offered {
+            self.draw_dialog(
+                (ctx, canvas),
+                &format!(
+                    "Opponent asks to play on from turn {actions}, taking back everything \
+                     since.\n\n[Y]es / [N]o"
+                ),
+            )?;
+        }
+
+        if self.kiosk && self.exhibition.is_some() {
+            self.draw_banner(
+                (ctx, canvas),
+                "Rotating Chess! Press any key to play.",
+                BannerPosition::Top,
+            )?;
+        }
+
+        if profiling::enabled() {
+            self.draw_profiler((ctx, canvas))?;
+        }
+        Ok(())
+    }
+
+    /// Draw something, keeping count for benchmarks.
+    fn draw_on(&self, canvas: &mut Canvas, drawable: &impl Drawable, param: impl Into<DrawParam>) {
+        self.render_counters.count_draw_call();
+        canvas.draw(drawable, param);
+    }
+
+    /// Draw a mesh that was built just for this, keeping count for benchmarks.
+    fn draw_mesh(&self, canvas: &mut Canvas, mesh: Mesh, param: impl Into<DrawParam>) {
+        self.render_counters.count_mesh_built();
+        self.draw_on(canvas, &mesh, param);
+    }
+
+    fn draw_board(&mut self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        let _span = profiling::span("draw_board");
+        if self.board_mesh.is_none() {
+            self.board_mesh = Some((self.transform.scale, self.build_board_mesh(ctx)?));
+        }
+        if let Some((built_at, board_mesh)) = &self.board_mesh {
+            self.draw_on(canvas, board_mesh, self.stretch_from(*built_at));
+        }
+        Ok(())
+    }
+
+    fn build_board_mesh(&self, ctx: &mut Context) -> GameResult<Mesh> {
+        let mut mb = MeshBuilder::new();
+        mb.rectangle(
+            DrawMode::fill(),
+            self.transform.board_rect(),
+            self.theme.light_tile,
+        )?;
+
+        let mut top = 0;
+        let mut left = 1;
+        let mut next_row_immediate_dark = true;
+
+        const NUM_TILES: u8 = 8 * 8;
+        const NUM_DARK_TILES: u8 = NUM_TILES / 2;
+
+        for _ in 0..NUM_DARK_TILES {
+            mb.rectangle(
+                DrawMode::fill(),
+                {
+                    // tiles are square, so turning the view only moves where they go.
+                    let center = self
+                        .transform
+                        .world_pos(left as f32 + 0.5, top as f32 + 0.5);
+                    let half = self.transform.world_len(0.5);
+                    Rect::new(center.x - half, center.y - half, 2. * half, 2. * half)
+                },
+                self.theme.dark_tile,
+            )?;
+
+            left += 2;
+            if left >= 8 {
+                left = if next_row_immediate_dark { 0 } else { 1 };
+                next_row_immediate_dark = !next_row_immediate_dark;
+                top += 1;
+            }
+        }
+
+        self.render_counters.count_mesh_built();
+        Ok(Mesh::from_data(ctx, mb.build()))
+    }
+
+    fn add_piece_outline(&self, mb: &mut MeshBuilder, x: f32, y: f32, color: Color) -> GameResult {
+        mb.circle(
+            DrawMode::stroke(self.settings().outline_thickness),
+            self.transform.world_pos(x, y),
+            self.transform.world_len(PIECE_RADIUS),
+            CIRC_TOLERANCE,
+            color,
+        )?;
+        Ok(())
+    }
+
+    fn add_piece_highlight(
+        &self,
+        mb: &mut MeshBuilder,
+        x: f32,
+        y: f32,
+        color: Color,
+    ) -> GameResult {
+        mb.circle(
+            DrawMode::fill(),
+            self.transform.world_pos(x, y),
+            self.transform.world_len(PIECE_RADIUS) + self.settings().highlight_tolerance,
+            CIRC_TOLERANCE,
+            color,
+        )?;
+        Ok(())
+    }
+
+    fn add_movablepoint_indicator(
+        &self,
+        mb: &mut MeshBuilder,
+        x: f32,
+        y: f32,
+        color: Color,
+    ) -> GameResult {
+        mb.circle(
+            DrawMode::fill(),
+            self.transform.world_pos(x, y),
+            self.transform.world_len(self.settings().indicator_size),
+            CIRC_TOLERANCE,
+            color,
+        )?;
+        Ok(())
+    }
+
+    fn add_capturablepoint_indicator(
+        &self,
+        mb: &mut MeshBuilder,
+        x: f32,
+        y: f32,
+        color: Color,
+    ) -> GameResult {
+        let Vec2 { x, y } = self.transform.world_pos(x, y);
+        let dist = self.transform.world_len(self.settings().indicator_size);
+
+        mb.triangles(
+            &[
+                Vec2::new(x, y - dist),
+                Vec2::new(x - dist / 2. * f32::sqrt(3.), y + dist / 2.),
+                Vec2::new(x + dist / 2. * f32::sqrt(3.), y + dist / 2.),
+            ],
+            color,
+        )?;
+        Ok(())
+    }
+
+    /// Add the dot (for a move) or triangle (for a capture) marking a travel point.
+    fn add_travelpoint_indicator(
+        &self,
+        mb: &mut MeshBuilder,
+        kind: TravelKind,
+        x: f32,
+        y: f32,
+        color: Color,
+    ) -> GameResult {
+        match kind {
+            TravelKind::Capture => self.add_capturablepoint_indicator(mb, x, y, color),
+            TravelKind::Move => self.add_movablepoint_indicator(mb, x, y, color),
+        }
+    }
+
+    /// Add the outline of a travel point: solid for a move, dashed for a capture.
+    ///
+    /// Along with the indicators, this means moves and captures can be told apart by shape
+    /// alone, whatever the colors are.
+    fn add_travelpoint_outline(
+        &self,
+        mb: &mut MeshBuilder,
+        kind: TravelKind,
+        x: f32,
+        y: f32,
+        color: Color,
+    ) -> GameResult {
+        const DASHES: usize = 12;
+        const POINTS_PER_DASH: usize = 4;
+
+        if kind == TravelKind::Move {
+            return self.add_piece_outline(mb, x, y, color);
+        }
+
+        let center = self.transform.world_pos(x, y);
+        let radius = self.transform.world_len(PIECE_RADIUS);
+        let dash_angle = TAU / DASHES as f32 / 2.;
+        for dash in 0..DASHES {
+            let start = dash as f32 * 2. * dash_angle;
+            let points: Vec<Vec2> = (0..=POINTS_PER_DASH)
+                .map(|i| {
+                    let angle = start + dash_angle * i as f32 / POINTS_PER_DASH as f32;
+                    center + radius * Vec2::from_angle(angle)
+                })
+                .collect();
+            mb.line(&points, self.settings().outline_thickness, color)?;
+        }
+        Ok(())
+    }
+
+    /// Add a ring around a piece, hinting that it's ready to be rotated.
+    fn add_rotation_affordance(&self, mb: &mut MeshBuilder, x: f32, y: f32) -> GameResult {
+        mb.circle(
+            DrawMode::stroke(3. * self.settings().outline_thickness),
+            self.transform.world_pos(x, y),
+            self.transform.world_len(PIECE_RADIUS * 1.3),
+            CIRC_TOLERANCE,
+            ROTATE_AFFORDANCE_COLOR,
+        )?;
+        Ok(())
+    }
+
+    /// Rebuild the selection meshes if anything they're built from changed since last time.
+    fn update_selection_meshes(&mut self, ctx: &mut Context) -> GameResult {
+        let stale = self
+            .selection_meshes
+            .as_ref()
+            .is_none_or(|meshes| meshes.version != self.scene_version);
+        if stale {
+            self.selection_meshes = Some(self.build_selection_meshes(ctx)?);
+        }
+        Ok(())
+    }
+
+    /// Build the highlights, hit circles, and travel points around the selected piece.
+    fn build_selection_meshes(&self, ctx: &mut Context) -> GameResult<SelectionMeshes> {
+        let _span = profiling::span("build_selection_meshes");
+        let version = self.scene_version;
+        let Some((piece, travelpoints)) = self.game.chess().selected() else {
+            return Ok(SelectionMeshes {
+                version,
+                built_at: self.transform.scale,
+                under: None,
+                over: None,
+            });
+        };
+
+        let mut under = MeshBuilder::new();
+        let mut over = MeshBuilder::new();
+
+        self.add_piece_highlight(&mut under, piece.x(), piece.y(), self.theme.selected)?;
+
+        for piece in self.game.chess().pieces() {
+            self.add_piece_outline(&mut over, piece.x(), piece.y(), HITCIRCLE_COLOR)?;
+        }
+
+        if self.turn.phase() == TurnPhase::Rotate {
+            self.add_rotation_affordance(&mut over, piece.x(), piece.y())?;
+        }
+
+        // you can't move in your rotation phase, so don't pretend you can.
+        let moves_disabled = self.turn.phase() == TurnPhase::Rotate;
+        for tp in travelpoints {
+            if tp.travelable {
+                let (x, y) = self.mouse_pos;
+                if moves_disabled {
+                    self.add_travelpoint_indicator(
+                        &mut over,
+                        tp.kind,
+                        tp.x,
+                        tp.y,
+                        DISABLED_HIGHLIGHT_COLOR,
+                    )?;
+                } else if Piece::collidepoint_generic(x, y, tp.x, tp.y) {
+                    self.add_piece_highlight(
+                        &mut over,
+                        tp.x,
+                        tp.y,
+                        match tp.kind {
+                            TravelKind::Capture => self.theme.capture_highlight,
+                            TravelKind::Move => self.theme.move_highlight,
+                        },
+                    )?;
+                    // keep the shape on top of the highlight, so it doesn't come down to color.
+                    self.add_travelpoint_indicator(
+                        &mut over,
+                        tp.kind,
+                        tp.x,
+                        tp.y,
+                        HOVERED_INDICATOR_COLOR,
+                    )?;
+                } else {
+                    self.add_travelpoint_indicator(
+                        &mut over,
+                        tp.kind,
+                        tp.x,
+                        tp.y,
+                        match tp.kind {
+                            TravelKind::Capture => self.theme.capture_highlight,
+                            TravelKind::Move => self.theme.move_highlight,
+                        },
+                    )?;
+                }
+            }
+            self.add_travelpoint_outline(
+                &mut over,
+                tp.kind,
+                tp.x,
+                tp.y,
+                match tp.kind {
+                    _ if moves_disabled => DISABLED_OUTLINE_COLOR,
+                    TravelKind::Capture => self.theme.capture_outline,
+                    TravelKind::Move => self.theme.move_outline,
+                },
+            )?;
+        }
+
+        self.render_counters.count_mesh_built();
+        self.render_counters.count_mesh_built();
+        Ok(SelectionMeshes {
+            version,
+            built_at: self.transform.scale,
+            under: Some(Mesh::from_data(ctx, under.build())),
+            over: Some(Mesh::from_data(ctx, over.build())),
+        })
+    }
+
+    /// Draw a strip of text across the top or bottom of the window, over everything else.
+    fn draw_banner(
+        &self,
+        (ctx, canvas): (&mut Context, &mut Canvas),
+        message: &str,
+        position: BannerPosition,
+    ) -> GameResult {
+        let (width, _) = ctx.gfx.drawable_size();
+        let mut text = Text::new(message);
+        text.set_scale(BANNER_TEXT_SIZE);
+        let text_size = text.measure(ctx)?;
+        let height = text_size.y * 2.;
+        let top = match position {
+            BannerPosition::Top => 0.,
+            BannerPosition::Bottom => self.transform.frame().bottom() - height,
+        };
+
+        self.draw_mesh(
+            canvas,
+            Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(0., top, width, height),
+                BANNER_COLOR,
+            )?,
+            DrawParam::new(),
+        );
+        self.draw_on(
+            canvas,
+            &text,
+            DrawParam::new()
+                .dest(Vec2::new(
+                    (width - text_size.x) / 2.,
+                    top + (height - text_size.y) / 2.,
+                ))
+                .color(BANNER_TEXT_COLOR),
+        );
+        Ok(())
+    }
+
+    /// Draw how the game ended in a see-through band across the middle of the board, once
+    /// it's over on the board or the clock and we're looking at the end of it.
+    fn draw_result(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        if !self.game_over() || !self.game.history().at_live() {
+            return Ok(());
+        }
+        let Some(outcome) = self.outcome() else {
+            return Ok(());
+        };
+        let how = match self.clock.as_ref().and_then(ChessClock::flagged) {
+            Some(side) => format!("{} ran out of time.", notation::side_name(side)),
+            None if outcome == Outcome::Draw => "Nobody can move.".to_string(),
+            None => "The king is taken.".to_string(),
+        };
+        let mut message = format!("{}\n{how}", outcome.describe());
+        if self.netcode.is_none() {
+            message.push_str("\nPress R to rematch.");
+        } else if self.rematch_wanted || self.rematch_asked {
+            message.push_str("\nAsking for a rematch...");
+        } else if self.role == Role::Player {
+            message.push_str("\nPress R to ask for a rematch.");
+        }
+
+        let (width, _) = ctx.gfx.drawable_size();
+        let mut text = Text::new(message);
+        text.set_scale(BANNER_TEXT_SIZE)
+            .set_layout(TextLayout::center());
+        let text_size = text.measure(ctx)?;
+        let height = text_size.y + BANNER_TEXT_SIZE * 2.;
+        let frame = self.transform.frame();
+        let middle = frame.y + frame.h / 2.;
+        self.draw_mesh(
+            canvas,
+            Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(0., middle - height / 2., width, height),
+                RESULT_BANNER_COLOR,
+            )?,
+            DrawParam::new(),
+        );
+        self.draw_on(
+            canvas,
+            &text,
+            DrawParam::new()
+                .dest(Vec2::new(width / 2., middle))
+                .color(BANNER_TEXT_COLOR),
+        );
+        Ok(())
+    }
+
+    /// Draw a box with some text in the middle of the window, dimming everything behind it.
+    fn draw_dialog(&self, (ctx, canvas): (&mut Context, &mut Canvas), message: &str) -> GameResult {
+        /// Space between the text and the edge of the box, in pixels.
+        const PADDING: f32 = 24.;
+
+        let (width, height) = ctx.gfx.drawable_size();
+        let mut text = Text::new(message);
+        text.set_scale(BANNER_TEXT_SIZE)
+            .set_layout(TextLayout::center());
+        let text_size = text.measure(ctx)?;
+
+        self.draw_mesh(
+            canvas,
+            Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(0., 0., width, height),
+                DIALOG_BACKDROP_COLOR,
+            )?,
+            DrawParam::new(),
+        );
+        self.draw_mesh(
+            canvas,
+            Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(
+                    (width - text_size.x) / 2. - PADDING,
+                    (height - text_size.y) / 2. - PADDING,
+                    text_size.x + 2. * PADDING,
+                    text_size.y + 2. * PADDING,
+                ),
+                self.theme.background,
+            )?,
+            DrawParam::new(),
+        );
+        self.draw_on(
+            canvas,
+            &text,
+            DrawParam::new()
+                .dest(Vec2::new(width / 2., height / 2.))
+                .color(self.theme.dialog_text),
+        );
+        Ok(())
+    }
+
+    /// Draw how long each profiled span took last frame, as a list of bars in the corner,
+    /// along with how much memory our images take.
+    fn draw_profiler(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        const PADDING: f32 = 8.;
+        const INDENT: f32 = 12.;
+        const LABEL_WIDTH: f32 = 220.;
+
+        let spans = profiling::last_frame();
+        let mut footer = vec![format!(
+            "{} images, ~{:.1} MB of GPU memory",
+            self.images.len(),
+            self.images.gpu_bytes_estimate() as f32 / 1_000_000.
+        )];
+        if self.netcode.is_some() {
+            let now = Instant::now();
+            footer.push(self.bandwidth.sent.describe("sent", now));
+            footer.push(self.bandwidth.received.describe("received", now));
+        }
+        let line_height = PROFILER_TEXT_SIZE * 1.4;
+        let longest = spans
+            .iter()
+            .map(|span| span.duration.as_secs_f32() * 1000. * PROFILER_PX_PER_MS)
+            .fold(0., f32::max);
+        self.draw_mesh(
+            canvas,
+            Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(
+                    0.,
+                    0.,
+                    2. * PADDING + LABEL_WIDTH + longest,
+                    2. * PADDING + line_height * (spans.len() + footer.len()) as f32,
+                ),
+                BANNER_COLOR,
+            )?,
+            DrawParam::new(),
+        );
+
+        for (i, span) in spans.iter().enumerate() {
+            let ms = span.duration.as_secs_f32() * 1000.;
+            let top = PADDING + line_height * i as f32;
+            let mut text = Text::new(format!("{} {ms:.2}ms", span.name));
+            text.set_scale(PROFILER_TEXT_SIZE);
+            self.draw_on(
+                canvas,
+                &text,
+                DrawParam::new()
+                    .dest(Vec2::new(PADDING + INDENT * span.depth as f32, top))
+                    .color(BANNER_TEXT_COLOR),
+            );
+            if ms > 0. {
+                self.draw_mesh(
+                    canvas,
+                    Mesh::new_rectangle(
+                        ctx,
+                        DrawMode::fill(),
+                        Rect::new(
+                            PADDING + LABEL_WIDTH,
+                            top,
+                            ms * PROFILER_PX_PER_MS,
+                            PROFILER_TEXT_SIZE,
+                        ),
+                        PROFILER_BAR_COLOR,
+                    )?,
+                    DrawParam::new(),
+                );
+            }
+        }
+
+        let mut text = Text::new(footer.join("\n"));
+        text.set_scale(PROFILER_TEXT_SIZE);
+        self.draw_on(
+            canvas,
+            &text,
+            DrawParam::new()
+                .dest(Vec2::new(
+                    PADDING,
+                    PADDING + line_height * spans.len() as f32,
+                ))
+                .color(BANNER_TEXT_COLOR),
+        );
+        Ok(())
+    }
+
+    /// Draw an arrow between two points, in rotchess units.
+    fn draw_arrow(
+        &self,
+        (ctx, canvas): (&mut Context, &mut Canvas),
+        from: (f32, f32),
+        to: (f32, f32),
+        color: Color,
+    ) -> GameResult {
+        let from = self.transform.world_pos(from.0, from.1);
+        let to = self.transform.world_pos(to.0, to.1);
+        let head_len = self.transform.world_len(0.35);
+        if from.distance(to) <= head_len {
+            return Ok(());
+        }
+
+        let dir = (to - from).normalize();
+        let head_base = to - dir * head_len;
+        let head_side = dir.perp() * head_len * 0.6;
+        self.draw_mesh(
+            canvas,
+            Mesh::new_line(
+                ctx,
+                &[from, head_base],
+                self.transform.world_len(0.12),
+                color,
+            )?,
+            DrawParam::new(),
+        );
+        self.draw_mesh(
+            canvas,
+            Mesh::from_triangles(
+                ctx,
+                &[to, head_base + head_side, head_base - head_side],
+                color,
+            )?,
+            DrawParam::new(),
+        );
+        Ok(())
+    }
+
+    fn draw_annotations(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        for annotation in self.annotations.get(self.game.history().current()) {
+            match *annotation {
+                Annotation::Arrow { from, to } => {
+                    self.draw_arrow((ctx, canvas), from, to, ANNOTATION_COLOR)?
+                }
+                Annotation::Circle { at: (x, y) } => self.draw_mesh(
+                    canvas,
+                    Mesh::new_circle(
+                        ctx,
+                        DrawMode::stroke(self.transform.world_len(0.06)),
+                        Vec2::ZERO,
+                        self.transform.world_len(PIECE_RADIUS),
+                        CIRC_TOLERANCE,
+                        ANNOTATION_COLOR,
+                    )?,
+                    self.transform.world_pos(x, y),
+                ),
+            }
+        }
+
+        if let Some(from) = self.annotation_drag {
+            self.draw_arrow((ctx, canvas), from, self.mouse_pos, ANNOTATION_COLOR)?;
+        }
+        Ok(())
+    }
+
+    /// Take a picture of the board as it is now for the current turn's thumbnail, unless
+    /// we already have one.
+    ///
+    /// Thumbnails of turns that have since been taken back are dropped first.
+    fn capture_thumbnail(&mut self, ctx: &mut Context) -> GameResult {
+        let _span = profiling::span("capture_thumbnail");
+        let len = self.game.history().len();
+        self.thumbnails.retain(|&turn, _| turn <= len);
+        let turn = self.game.history().current();
+        if self.thumbnails.contains_key(&turn) {
+            return Ok(());
+        }
+        let image = self.render_thumbnail(ctx, &self.game)?;
+        self.thumbnails.insert(turn, image);
+        Ok(())
+    }
+
+    /// Draw a game's board as it is now into a thumbnail-sized image.
+    fn render_thumbnail(&self, ctx: &mut Context, game: &Game) -> GameResult<Image> {
+        let image = Image::new_canvas_image(
+            ctx,
+            ctx.gfx.surface_format(),
+            THUMBNAIL_SIZE,
+            THUMBNAIL_SIZE,
+            1,
+        );
+        let mut canvas = Canvas::from_image(ctx, image.clone(), self.theme.background);
+        // draw the whole board as usual, squeezed into the thumbnail.
+        canvas.set_screen_coordinates(self.transform.frame());
+        self.draw_mesh(&mut canvas, self.build_board_mesh(ctx)?, DrawParam::new());
+        self.draw_pieces(&mut canvas, game)?;
+        canvas.finish(ctx)?;
+        Ok(image)
+    }
+
+    /// Draw the board, pieces and indicators of a known position into an image, for
+    /// [snapshot mode](crate::snapshots). Nothing about the window or the settings file
+    /// changes how it looks.
+    pub fn snapshot(ctx: &mut Context, scenario: Scenario) -> GameResult<Image> {
+        let mut s = Self::local(ctx);
+        s.settings = Settings::default();
+        s.transform = BoardTransform {
+            scale: SNAPSHOT_SIZE as f32 / 8.,
+            ..BoardTransform::default()
+        };
+        if scenario == Scenario::MidGame {
+            // a center pawn each, moved up two and turned a little.
+            for (side, file) in [(Side::White, 4.5), (Side::Black, 3.5)] {
+                let Some(pawn) = s.game.chess().pieces().iter().position(|piece| {
+                    piece.side() == side
+                        && piece.kind() == PieceKind::Pawn
+                        && (piece.x() - file).abs() < 0.1
+                }) else {
+                    continue;
+                };
+                let y = s.game.chess().pieces()[pawn].y();
+                let forward = if y > 4. { -2. } else { 2. };
+                for action in [
+                    Action::Move(pawn, file, y + forward),
+                    Action::Rotate(pawn, 0.3),
+                ] {
+                    if let Some(thing) = s.apply(GameEvent::Play(action)) {
+                        let _ = s.turn.act(&thing);
+                    }
+                }
+            }
+            let knight = s
+                .game
+                .chess()
+                .pieces()
+                .iter()
+                .find(|piece| piece.side() == Side::White && piece.kind() == PieceKind::Knight)
+                .map(|piece| (piece.x(), piece.y()));
+            if let Some((x, y)) = knight {
+                s.left_click(x, y);
+            }
+        }
+
+        let image = Image::new_canvas_image(
+            ctx,
+            ImageFormat::Rgba8UnormSrgb,
+            SNAPSHOT_SIZE,
+            SNAPSHOT_SIZE,
+            1,
+        );
+        let mut canvas = Canvas::from_image(ctx, image.clone(), s.theme.background);
+        canvas.set_screen_coordinates(s.transform.frame());
+        s.draw_board((ctx, &mut canvas))?;
+        s.draw_piece_layer((ctx, &mut canvas))?;
+        s.draw_indicator_layer((ctx, &mut canvas))?;
+        canvas.finish(ctx)?;
+        Ok(image)
+    }
+
+    /// Write a diagram of the turn we're looking at to a PNG in the user data directory:
+    /// the board with coordinates, the last move and any arrows and circles on it, and
+    /// nothing else.
+    fn export_diagram(&mut self, ctx: &mut Context) -> GameResult {
+        let image = Image::new_canvas_image(
+            ctx,
+            ImageFormat::Rgba8UnormSrgb,
+            DIAGRAM_SIZE,
+            DIAGRAM_SIZE,
+            1,
+        );
+        let mut canvas = Canvas::from_image(ctx, image.clone(), self.theme.background);
+        canvas.set_screen_coordinates(self.transform.frame());
+        self.draw_board((ctx, &mut canvas))?;
+        self.draw_last_move((ctx, &mut canvas))?;
+        self.draw_coordinates(&mut canvas);
+        if let Some((from, to)) = self.last_move() {
+            self.draw_mesh(
+                &mut canvas,
+                Mesh::new_line(
+                    ctx,
+                    &[
+                        self.transform.world_pos(from.0, from.1),
+                        self.transform.world_pos(to.0, to.1),
+                    ],
+                    MOVE_TRAIL_WIDTH,
+                    MOVE_TRAIL_COLOR,
+                )?,
+                DrawParam::new(),
+            );
+        }
+        self.draw_pieces(&mut canvas, &self.game)?;
+        self.draw_annotations((ctx, &mut canvas))?;
+        canvas.finish(ctx)?;
+
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let name = format!("diagram-{secs}.png");
+        image.encode(ctx, ImageEncodingFormat::Png, format!("/{name}"))?;
+        self.ticker.announce(format!(
+            "Diagram saved to {}",
+            ctx.fs.user_data_dir().join(name).display()
+        ));
+        Ok(())
+    }
+
+    /// Write the board we're looking at as text to the user data directory, and print it,
+    /// for pasting into chat.
+    fn export_text(&mut self, ctx: &Context) -> GameResult {
+        let text = notation::text_diagram(&notation::snapshot(self.game.chess().pieces()));
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let dir = ctx.fs.user_data_dir();
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("board-{secs}.txt"));
+        std::fs::write(&path, &text)?;
+        print!("{text}");
+        // the terminal isn't always in sight, so say where it went right on the board.
+        self.notifications
+            .push(format!("Board text saved to {}", path.display()));
+        Ok(())
+    }
+
+    /// Where the last move (as of the turn we're looking at) went from and to, in rotchess
+    /// units.
+    fn last_move(&self) -> Option<((f32, f32), (f32, f32))> {
+        let actions = &self.game.history().actions()[..self.game.history().current()];
+        let (i, piece_idx, to) =
+            actions
+                .iter()
+                .enumerate()
+                .rev()
+                .find_map(|(i, action)| match *action {
+                    Action::Move(piece_idx, x, y) => Some((i, piece_idx, (x, y))),
+                    Action::Rotate(..) => None,
+                })?;
+        let before = play_out(self.chess960_ordering, &actions[..i])?;
+        let piece = before.pieces().get(piece_idx)?;
+        Some(((piece.x(), piece.y()), to))
+    }
+
+    /// Draw the thumbnail of the turn under the mouse just above the scrubber, if we have one.
+    fn draw_scrubber_thumbnail(&self, canvas: &mut Canvas) {
+        let Some(x) = self.scrubber_hover else {
+            return;
+        };
+        let Some(image) = self.thumbnails.get(&self.scrubber_turn_at(x)) else {
+            return;
+        };
+        let size = THUMBNAIL_SIZE as f32;
+        let rect = self.scrubber_rect();
+        let left = (x - size / 2.).clamp(0., (rect.w - size).max(0.));
+        let top = rect.y - size - THUMBNAIL_MARGIN;
+        self.draw_on(canvas, image, Vec2::new(left, top));
+    }
+
+    fn draw_scrubber(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        let rect = self.scrubber_rect();
+        let mid_y = rect.y + rect.h / 2.;
+
+        self.draw_mesh(
+            canvas,
+            Mesh::new_line(
+                ctx,
+                &[Vec2::new(rect.x, mid_y), Vec2::new(rect.x + rect.w, mid_y)],
+                SCRUBBER_TRACK_WIDTH,
+                self.theme.scrubber_track,
+            )?,
+            DrawParam::new(),
+        );
+
+        let fraction = if self.game.history().is_empty() {
+            1.
+        } else {
+            self.game.history().current() as f32 / self.game.history().len() as f32
+        };
+        self.draw_mesh(
+            canvas,
+            Mesh::new_circle(
+                ctx,
+                DrawMode::fill(),
+                Vec2::ZERO,
+                rect.h / 3.,
+                CIRC_TOLERANCE,
+                self.theme.scrubber_knob,
+            )?,
+            Vec2::new(rect.x + fraction * rect.w, mid_y),
+        );
+        Ok(())
+    }
+
+    /// Label the files and ranks along the edges of the board.
+    fn draw_coordinates(&self, canvas: &mut Canvas) {
+        let size = self.transform.world_len(0.22);
+        let mut label = |text: String, x: f32, y: f32| {
+            let mut text = Text::new(text);
+            text.set_scale(size);
+            text.set_layout(TextLayout::center());
+            self.draw_on(
+                canvas,
+                &text,
+                DrawParam::new()
+                    .dest(self.transform.world_pos(x, y))
+                    .color(self.theme.coordinates),
+            );
+        };
+        for (i, file) in ('a'..='h').enumerate() {
+            label(file.to_string(), i as f32 + 0.85, 7.85);
+        }
+        for rank in 1..=8 {
+            label(rank.to_string(), 0.15, 8. - rank as f32 + 0.15);
+        }
+    }
+
+    /// Draw the HUD in the top right corner: the clocks, if the game has them, and under
+    /// them whose turn it is and which half of it they're on.
+    fn draw_hud(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        /// Space between a line's text and the edge of its background, in pixels.
+        const PADDING: f32 = 6.;
+
+        // clear of the move history panel, if it's open.
+        let width = self.history_panel.board_width(ctx.gfx.drawable_size().0);
+        let mut y = 0.;
+        for mut text in self
+            .clock_texts()
+            .into_iter()
+            .chain(self.phase_text())
+            .chain(self.turn_counter_text())
+        {
+            text.set_scale(CLOCK_TEXT_SIZE);
+            let text_size = text.measure(ctx)?;
+            let (w, h) = (text_size.x + 2. * PADDING, text_size.y + 2. * PADDING);
+            self.draw_mesh(
+                canvas,
+                Mesh::new_rectangle(ctx, DrawMode::fill(), Rect::new(0., 0., w, h), BANNER_COLOR)?,
+                Vec2::new(width - w, y),
+            );
+            self.draw_on(
+                canvas,
+                &text,
+                DrawParam::new().dest(Vec2::new(width - w + PADDING, y + PADDING)),
+            );
+            y += h;
+        }
+        Ok(())
+    }
+
+    /// A line for each clock, the opponent's on top, grayed out while it isn't running.
+    fn clock_texts(&self) -> Vec<Text> {
+        let Some(clock) = &self.clock else {
+            return Vec::new();
+        };
+        let now = Instant::now();
+        let their_side = match self.my_side {
+            Side::White => Side::Black,
+            Side::Black => Side::White,
+        };
+        [their_side, self.my_side]
+            .into_iter()
+            .map(|side| {
+                Text::new(
+                    TextFragment::new(format!(
+                        "{} {}",
+                        notation::side_name(side),
+                        format_clock(clock.remaining(side, now))
+                    ))
+                    .color(if clock.running() == Some(side) {
+                        BANNER_TEXT_COLOR
+                    } else {
+                        IDLE_CLOCK_TEXT_COLOR
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    /// Whose turn it is in the live game and which half of it, like "White to play: Move ·
+    /// Rotate" with the half they're on lit up. The lit half flashes red for a bit after a
+    /// click that broke the turn order. `None` once the game is over.
+    fn phase_text(&self) -> Option<Text> {
+        if self.game_over() {
+            return None;
+        }
+        let side = if self.game.history().live_turns_played().is_multiple_of(2) {
+            Side::White
+        } else {
+            Side::Black
+        };
+        let rotating = matches!(self.game.history().actions().last(), Some(Action::Move(..)));
+        let whose = if self.role == Role::Player && self.turn.waiting() {
+            format!("Waiting for {}: ", notation::side_name(side))
+        } else {
+            format!("{} to play: ", notation::side_name(side))
+        };
+        let warning = self.phase_warning.map_or(0., |fade| {
+            fade.visibility(Instant::now(), self.settings.reduced_motion)
+        });
+        let lit = effects::tint(BANNER_TEXT_COLOR, PHASE_WARNING_COLOR, warning);
+        let half = |name: &str, on: bool| {
+            TextFragment::new(name).color(if on { lit } else { IDLE_CLOCK_TEXT_COLOR })
+        };
+        let mut text = Text::new(TextFragment::new(whose).color(BANNER_TEXT_COLOR));
+        text.add(half("Move", !rotating))
+            .add(TextFragment::new(" \u{b7} ").color(IDLE_CLOCK_TEXT_COLOR))
+            .add(half("Rotate", rotating));
+        Some(text)
+    }
+
+    /// Which turn of how many we're looking at, once there are any. See
+    /// [`notation::turn_counter`].
+    fn turn_counter_text(&self) -> Option<Text> {
+        if self.game.history().is_empty() {
+            return None;
+        }
+        Some(Text::new(
+            TextFragment::new(notation::turn_counter(self.game.history())).color(BANNER_TEXT_COLOR),
+        ))
+    }
+
+    /// Draw the ticker's lines up from the bottom left corner of the board, newest lowest.
+    fn draw_ticker(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        /// Space between a line's text and the edge of its background, in pixels.
+        const PADDING: f32 = 4.;
+
+        let lines: Vec<_> = self
+            .ticker
+            .lines(Instant::now(), self.settings.reduced_motion)
+            .collect();
+        let mut y = self.transform.frame().bottom();
+        for (line, opacity) in lines.into_iter().rev() {
+            let mut text = Text::new(line);
+            text.set_scale(TICKER_TEXT_SIZE);
+            let text_size = text.measure(ctx)?;
+            y -= text_size.y + 2. * PADDING;
+
+            let mut background = BANNER_COLOR;
+            background.a *= opacity;
+            self.draw_mesh(
+                canvas,
+                Mesh::new_rectangle(
+                    ctx,
+                    DrawMode::fill(),
+                    Rect::new(
+                        0.,
+                        0.,
+                        text_size.x + 2. * PADDING,
+                        text_size.y + 2. * PADDING,
+                    ),
+                    background,
+                )?,
+                Vec2::new(0., y),
+            );
+            let mut color = BANNER_TEXT_COLOR;
+            color.a *= opacity;
+            self.draw_on(
+                canvas,
+                &text,
+                DrawParam::new()
+                    .dest(Vec2::new(PADDING, y + PADDING))
+                    .color(color),
+            );
+        }
+        Ok(())
+    }
+
+    /// Highlight where the last move (as of the turn we're looking at) went.
+    fn draw_last_move(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        let last_move = self.game.history().actions()[..self.game.history().current()]
+            .iter()
+            .rev()
+            .find_map(|action| match action {
+                Action::Move(_, x, y) => Some((*x, *y)),
+                Action::Rotate(_, _) => None,
+            });
+        if let Some((x, y)) = last_move {
+            self.draw_mesh(
+                canvas,
+                Mesh::new_circle(
+                    ctx,
+                    DrawMode::fill(),
+                    Vec2::ZERO,
+                    self.transform.world_len(PIECE_RADIUS) + self.settings().highlight_tolerance,
+                    CIRC_TOLERANCE,
+                    self.theme.last_move,
+                )?,
+                self.transform.world_pos(x, y),
+            );
+        }
+        Ok(())
+    }
+
+    /// Draw a game's pieces. Only the game being played animates, or dims while we wait;
+    /// any other is drawn as it stands.
+    fn draw_pieces(&self, canvas: &mut Canvas, game: &Game) -> GameResult {
+        let _span = profiling::span("draw_pieces");
+        let live = std::ptr::eq(game, &self.game);
+        // our pieces can't be touched while we wait, so make them look it.
+        let dim_mine = live && self.turn.waiting();
+        for piece in game.chess().pieces() {
+            // if (piece.angle() % PI).abs() > 0.001 {
+            //     // println!("{}", (piece.angle() % PI).abs());
+            //     println!("piece angle is not up or down: {}", piece.angle());
+            // }
+            let color = self.piece_tint(piece.side(), dim_mine);
+            let animation = self.animations.iter().filter(|_| live).find(|animation| {
+                animation.turn == game.history().current() && animation.is_at(piece.x(), piece.y())
+            });
+            match animation {
+                Some(animation) => {
+                    let (x, y, angle) = animation.pose();
+                    self.draw_snapshot(
+                        canvas,
+                        &PieceSnapshot {
+                            kind: piece.kind(),
+                            side: piece.side(),
+                            x,
+                            y,
+                            angle,
+                        },
+                        color,
+                    );
+                }
+                None => self.draw_piece(canvas, piece, piece.angle(), color),
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw a piece's image where the piece is, at some angle.
+    fn draw_piece(&self, canvas: &mut Canvas, piece: &Piece, angle: f32, color: Color) {
+        self.draw_snapshot(
+            canvas,
+            &PieceSnapshot {
+                kind: piece.kind(),
+                side: piece.side(),
+                x: piece.x(),
+                y: piece.y(),
+                angle,
+            },
+            color,
+        );
+    }
+
+    /// Draw a piece as it was some time.
+    fn draw_snapshot(&self, canvas: &mut Canvas, piece: &PieceSnapshot, color: Color) {
+        let tile_size_px = self.transform.world_len(1.); // I did the math.
+        let shrink = self.settings().piece_scale;
+        self.draw_on(
+            canvas,
+            self.images
+                .get(&format!(
+                    "piece_{}{}1",
+                    piece.kind.to_file_desc(),
+                    piece.side.to_file_desc()
+                ))
+                .expect("Pieces should have correctly mapped to the file descrs."),
+            DrawParam::new()
+                .dest_rect(Rect {
+                    x: self.transform.world_pos(piece.x, piece.y).x, // x
+                    y: self.transform.world_pos(piece.x, piece.y).y, // y
+                    w: tile_size_px / PIECE_PNG_SIZE_PX as f32 * shrink, // scale x multiplier
+                    h: tile_size_px / PIECE_PNG_SIZE_PX as f32 * shrink, // scale y multiplier
+                                                                     // again, I did the math.
+                })
+                .offset(Vec2::new(0.5, 0.5))
+                .rotation(self.transform.screen_angle(piece.angle))
+                .color(color),
+        );
+    }
+
+    /// Draw a line along the last move, fading out.
+    fn draw_move_trail(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        let Some(trail) = &self.move_trail else {
+            return Ok(());
+        };
+        if trail.turn != self.game.history().current() || trail.from == trail.to {
+            return Ok(());
+        }
+        let mut color = MOVE_TRAIL_COLOR;
+        color.a *= trail
+            .fade
+            .visibility(Instant::now(), self.settings.reduced_motion);
+        self.draw_mesh(
+            canvas,
+            Mesh::new_line(
+                ctx,
+                &[
+                    self.transform.world_pos(trail.from.0, trail.from.1),
+                    self.transform.world_pos(trail.to.0, trail.to.1),
+                ],
+                MOVE_TRAIL_WIDTH,
+                color,
+            )?,
+            DrawParam::new(),
+        );
+        Ok(())
+    }
+
+    /// Draw where the inspected piece went over the game: a line through every spot it
+    /// stood on, each labeled with the turns it got there or turned there, and its angle.
+    fn draw_inspected_piece(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        if self.game.history().at_live() || self.inspected_piece.is_empty() {
+            return Ok(());
+        }
+
+        // consecutive stops on the same spot share a dot and a label.
+        let mut spots: Vec<((f32, f32), Vec<String>)> = Vec::new();
+        for stop in &self.inspected_piece {
+            let actions = &self.game.history().actions()[..stop.turn];
+            let label = match actions.split_last() {
+                None => "start".to_string(),
+                Some((_, before)) => {
+                    let turn = 1 + before
+                        .iter()
+                        .filter(|action| matches!(action, Action::Rotate(..)))
+                        .count();
+                    format!("{turn}.")
+                }
+            };
+            let label = format!("{label} {}", notation::angle_label(stop.piece.angle));
+            let spot = (stop.piece.x, stop.piece.y);
+            match spots.last_mut() {
+                Some((last, labels)) if *last == spot => labels.push(label),
+                _ => spots.push((spot, vec![label])),
+            }
+        }
+
+        if spots.len() > 1 {
+            let points: Vec<Vec2> = spots
+                .iter()
+                .map(|&((x, y), _)| self.transform.world_pos(x, y))
+                .collect();
+            self.draw_mesh(
+                canvas,
+                Mesh::new_line(ctx, &points, MOVE_TRAIL_WIDTH, PIECE_HISTORY_COLOR)?,
+                DrawParam::new(),
+            );
+        }
+        for ((x, y), labels) in &spots {
+            self.draw_mesh(
+                canvas,
+                Mesh::new_circle(
+                    ctx,
+                    DrawMode::fill(),
+                    Vec2::ZERO,
+                    self.transform.world_len(0.08),
+                    CIRC_TOLERANCE,
+                    PIECE_HISTORY_COLOR,
+                )?,
+                self.transform.world_pos(*x, *y),
+            );
+            let mut text = Text::new(labels.join("\n"));
+            text.set_scale(self.transform.world_len(0.18));
+            self.draw_on(
+                canvas,
+                &text,
+                DrawParam::new()
+                    .dest(self.transform.world_pos(x + 0.12, y + 0.12))
+                    .color(PIECE_HISTORY_COLOR),
+            );
+        }
+        Ok(())
+    }
+
+    /// Draw every piece's hit circle and center, the reach of the selected piece's travel
+    /// points, and red rings and lines between pieces that overlap.
+    fn draw_hitcircles(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        if !self.show_hitcircles {
+            return Ok(());
+        }
+        let mut mb = MeshBuilder::new();
+        let pieces = notation::snapshot(self.game.chess().pieces());
+        for piece in &pieces {
+            self.add_piece_outline(&mut mb, piece.x, piece.y, HITCIRCLE_COLOR)?;
+            // a move has to land its center inside the circle to capture.
+            mb.circle(
+                DrawMode::fill(),
+                self.transform.world_pos(piece.x, piece.y),
+                self.transform.world_len(0.04),
+                CIRC_TOLERANCE,
+                HITCIRCLE_COLOR,
+            )?;
+        }
+        if let Some((_, travelpoints)) = self.game.chess().selected() {
+            for tp in travelpoints {
+                mb.circle(
+                    DrawMode::fill(),
+                    self.transform.world_pos(tp.x, tp.y),
+                    self.transform.world_len(PIECE_RADIUS),
+                    CIRC_TOLERANCE,
+                    CAPTURE_REACH_COLOR,
+                )?;
+            }
+        }
+        for (i, j) in hitcircles::overlapping_pairs(&pieces) {
+            let (a, b) = (pieces[i], pieces[j]);
+            self.add_piece_outline(&mut mb, a.x, a.y, OVERLAP_WARNING_COLOR)?;
+            self.add_piece_outline(&mut mb, b.x, b.y, OVERLAP_WARNING_COLOR)?;
+            if (a.x, a.y) != (b.x, b.y) {
+                mb.line(
+                    &[
+                        self.transform.world_pos(a.x, a.y),
+                        self.transform.world_pos(b.x, b.y),
+                    ],
+                    self.settings().outline_thickness,
+                    OVERLAP_WARNING_COLOR,
+                )?;
+            }
+        }
+        self.draw_mesh(canvas, Mesh::from_data(ctx, mb.build()), DrawParam::new());
+        Ok(())
+    }
+
+    /// Draw a red ring where a click wasn't allowed, shaking and fading out (unless motion is
+    /// reduced).
+    fn draw_rejected_click(&self, (ctx, canvas): (&mut Context, &mut Canvas)) -> GameResult {
+        let Some(click) = &self.rejected_click else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        let mut color = REJECTED_CLICK_COLOR;
+        color.a *= click.fade.visibility(now, self.settings.reduced_motion);
+        let shake = if self.settings.reduced_motion {
+            Vec2::ZERO
+        } else {
+            Vec2::new(
+                self.transform
+                    .world_len(REJECTED_CLICK_SHAKE * click.shake(now)),
+                0.,
+            )
+        };
+        self.draw_mesh(
+            canvas,
+            Mesh::new_circle(
+                ctx,
+                DrawMode::stroke(self.transform.world_len(0.06)),
+                Vec2::ZERO,
+                self.transform.world_len(PIECE_RADIUS),
+                CIRC_TOLERANCE,
+                color,
+            )?,
+            self.transform.world_pos(click.at.0, click.at.1) + shake,
+        );
+        Ok(())
+    }
+
+    /// Draw the last rotated piece at its old angle, fading out.
+    fn draw_rotation_ghost(&self, canvas: &mut Canvas) {
+        let Some(ghost) = &self.rotation_ghost else {
+            return;
+        };
+        let Some(piece) = self.game.chess().pieces().get(ghost.piece_idx) else {
+            return;
+        };
+        if ghost.turn != self.game.history().current() {
+            return;
+        }
+        let mut color = self.piece_tint(piece.side(), false);
+        color.a *= ROTATION_GHOST_OPACITY
+            * ghost
+                .fade
+                .visibility(Instant::now(), self.settings.reduced_motion);
+        self.draw_piece(canvas, piece, ghost.angle, color);
+    }
+
+    /// Draw the moved piece faintly over itself at the angle the keys or scroll wheel have
+    /// turned it to, until the rotation's confirmed.
+    fn draw_rotation_draft(&self, canvas: &mut Canvas) {
+        let (Some(angle), Some(piece_idx)) = (self.rotation_draft, self.piece_to_rotate()) else {
+            return;
+        };
+        let piece = &self.game.chess().pieces()[piece_idx];
+        let mut color = self.piece_tint(piece.side(), false);
+        color.a *= ROTATION_DRAFT_OPACITY;
+        self.draw_piece(canvas, piece, angle, color);
+    }
+
+    /// Draw the pieces our staged actions moved or turned, faintly, as they were before.
+    fn draw_staged_ghosts(&self, canvas: &mut Canvas) {
+        let Some(idx) = self.staged.iter().find_map(|thing| match thing {
+            ThingHappened::Move(piece_idx, ..) | ThingHappened::Rotate(piece_idx, _) => {
+                Some(*piece_idx)
+            }
+            _ => None,
+        }) else {
+            return;
+        };
+        let Some(piece) = self.staged_board.get(idx) else {
+            return;
+        };
+        let mut color = self.piece_tint(piece.side, false);
+        color.a *= ROTATION_GHOST_OPACITY;
+        self.draw_snapshot(canvas, piece, color);
+    }
+
+    /// The color to multiply into a piece's image: its side's tint, washed out if it's
+    /// ours and we're waiting.
+    fn piece_tint(&self, side: Side, dim_mine: bool) -> Color {
+        let tint = match side {
+            Side::White => self.settings().white_tint,
+            Side::Black => self.settings().black_tint,
+        };
+        if dim_mine && side == self.my_side {
+            Color::new(
+                tint.r * WAITING_PIECE_TINT.r,
+                tint.g * WAITING_PIECE_TINT.g,
+                tint.b * WAITING_PIECE_TINT.b,
+                tint.a * WAITING_PIECE_TINT.a,
+            )
+        } else {
+            tint
+        }
+    }
+}
+
+/// On update events, forward events to the chess emulator. Then, draw.
+impl EventHandler for App {
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        input: ggez::input::keyboard::KeyInput,
+        repeated: bool,
+    ) -> GameResult {
+        self.note_input();
+        if self.end_kiosk_demo() {
+            return Ok(());
+        }
+
+        if self.quit_dialog_open {
+            match input.event.key_without_modifiers() {
+                Key::Named(NamedKey::Enter) => {
+                    self.quit_confirmed = true;
+                    ctx.request_quit();
+                }
+                Key::Named(NamedKey::Escape) => self.quit_dialog_open = false,
+                Key::Character(c) => match c.as_str() {
+                    "y" => {
+                        self.quit_confirmed = true;
+                        ctx.request_quit();
+                    }
+                    "n" => self.quit_dialog_open = false,
+                    _ => (),
+                },
+                _ => (),
+            }
+            return Ok(());
+        }
+
+        if self.pass_screen_up() {
+            if let Key::Named(NamedKey::Enter | NamedKey::Space) =
+                input.event.key_without_modifiers()
+                && let Some(screen) = &mut self.pass_screen
+            {
+                screen.ready();
+            }
+            return Ok(());
+        }
+
+        if self.library.is_some() {
+            self.library_key(ctx, &input.event.key_without_modifiers());
+            return Ok(());
+        }
+
+        if self.blunder_review.is_some() {
+            self.blunder_review_key(&input.event.key_without_modifiers());
+            return Ok(());
+        }
+
+        if self.takeback_dialog_open {
+            match input.event.key_without_modifiers() {
+                Key::Named(NamedKey::Enter) => self.answer_takeback(true),
+                Key::Named(NamedKey::Escape) => self.answer_takeback(false),
+                Key::Character(c) => match c.as_str() {
+                    "y" => self.answer_takeback(true),
+                    "n" => self.answer_takeback(false),
+                    _ => (),
+                },
+                _ => (),
+            }
+            return Ok(());
+        }
+
+        if self.rematch_dialog_open {
+            match input.event.key_without_modifiers() {
+                Key::Named(NamedKey::Enter) => self.answer_rematch(true),
+                Key::Named(NamedKey::Escape) => self.answer_rematch(false),
+                Key::Character(c) => match c.as_str() {
+                    "y" => self.answer_rematch(true),
+                    "n" => self.answer_rematch(false),
+                    _ => (),
+                },
+                _ => (),
+            }
+            return Ok(());
+        }
+
+        if self.rollback_offered.is_some() {
+            match input.event.key_without_modifiers() {
+                Key::Named(NamedKey::Enter) => self.answer_rollback(true),
+                Key::Named(NamedKey::Escape) => self.answer_rollback(false),
+                Key::Character(c) => match c.as_str() {
+                    "y" => self.answer_rollback(true),
+                    "n" => self.answer_rollback(false),
+                    _ => (),
+                },
+                _ => (),
+            }
+            return Ok(());
+        }
+
+        if let Some(draft) = &mut self.comment_draft {
+            // typed characters come in through text_input_event. here we only edit.
+            match input.event.key_without_modifiers() {
+                Key::Named(NamedKey::Enter) => {
+                    let comment = draft.trim().to_string();
+                    self.annotations
+                        .set_comment(self.game.history().current(), comment);
+                    self.comment_draft = None;
+                }
+                Key::Named(NamedKey::Escape) => self.comment_draft = None,
+                Key::Named(NamedKey::Backspace) => {
+                    draft.pop();
+                }
+                _ => (),
+            }
+            return Ok(());
+        }
+
+        let key = input.event.key_without_modifiers();
+        let command = self.keymap.command(&key, input.mods);
+        if let Some(repeating_key) =
+            command.and_then(|command| RepeatingKey::from_command(command, input.mods))
+        {
+            // we repeat these ourselves, in step_held_key.
+            if !repeated {
+                self.press_repeating_key(repeating_key);
+            }
+            return Ok(());
+        }
+
+        if let Some(playback) = &mut self.playback {
+            match &key {
+                Key::Named(NamedKey::Space) => {
+                    playback.toggle_pause();
+                    return Ok(());
+                }
+                Key::Character(c) if c.as_str() == "+" || c.as_str() == "=" => {
+                    playback.speed_up();
+                    return Ok(());
+                }
+                Key::Character(c) if c.as_str() == "-" => {
+                    playback.slow_down();
+                    return Ok(());
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(exhibition) = &mut self.exhibition {
+            match &key {
+                Key::Named(NamedKey::Space) => {
+                    exhibition.toggle_pause();
+                    return Ok(());
+                }
+                Key::Character(c) if c.as_str() == "+" || c.as_str() == "=" => {
+                    exhibition.speed_up();
+                    return Ok(());
+                }
+                Key::Character(c) if c.as_str() == "-" => {
+                    exhibition.slow_down();
+                    return Ok(());
+                }
+                _ => (),
+            }
+        }
+
+        let Some(command) = command else {
+            return Ok(());
+        };
+        match command {
+            Command::Comment if self.rotation_draft.is_some() => self.confirm_rotation(),
+            Command::Comment => {
+                // start commenting on the current turn, picking up any comment already there.
+                self.comment_draft = Some(
+                    self.annotations
+                        .comment(self.game.history().current())
+                        .unwrap_or_default()
+                        .to_string(),
+                );
+            }
+            Command::EndTurn => self.end_staged_turn(),
+            Command::PrevTurn => self.try_send_event(Event::PrevTurn),
+            Command::NextTurn => self.try_send_event(Event::NextTurn),
+            Command::FirstTurn => self.try_send_event(Event::FirstTurn),
+            Command::LastTurn => self.try_send_event(Event::LastTurn),
+            Command::HistoryPanel => self.toggle_history_panel(ctx),
+            // the layout is part of the rules in networked games.
+            Command::Chess960Layout if self.netcode.is_none() => {
+                self.chess_layout = ChessLayout::Chess960;
+                self.reset_board();
+            }
+            Command::StandardLayout if self.netcode.is_none() => {
+                self.chess_layout = ChessLayout::Standard;
+                self.reset_board();
+            }
+            Command::Chess960Layout | Command::StandardLayout => (),
+            Command::Save => {
+                let saved = self.save_game();
+                self.report_failure("save the game", saved);
+            }
+            Command::Library => self.open_library(),
+            Command::Reset if self.netcode.is_none() && self.outcome().is_some() => self.rematch(),
+            Command::Reset if self.netcode.is_some() => self.request_rematch(),
+            Command::Reset => self.reset_board(),
+            Command::ExportReplay => {
+                let exported = self.export_replay();
+                self.report_failure("export the replay", exported);
+            }
+            Command::ExportDiagram => {
+                let exported = self.export_diagram(ctx);
+                self.report_failure("export the diagram", exported);
+            }
+            Command::ExportText => {
+                let exported = self.export_text(ctx);
+                self.report_failure("export the board text", exported);
+            }
+            Command::ProjectorMode => self.toggle_projector_mode(),
+            Command::Hitcircles => self.show_hitcircles = !self.show_hitcircles,
+            Command::NextSoundSet => self.next_sound_set(ctx),
+            Command::NextTheme => self.next_theme(ctx),
+            Command::Takeback => self.request_takeback(),
+            Command::ProposeRollback => self.propose_rollback(),
+            Command::Undo => self.undo(),
+            Command::Redo => self.redo(),
+            Command::BlunderCheck => self.open_blunder_review(),
+            Command::Analysis => self.toggle_analysis(),
+            Command::SkipRotation => {
+                self.skip_rotation();
+            }
+            // handled above, since they repeat.
+            Command::RotateView
+            | Command::TurnPieceCounterclockwise
+            | Command::TurnPieceClockwise => (),
+            Command::TurnAround => self.turn_view_around(),
+            Command::ResetCamera => self.reset_camera(),
+            #[cfg(debug_assertions)]
+            Command::Profiler => profiling::toggle(),
+            #[cfg(not(debug_assertions))]
+            Command::Profiler => (),
+            Command::ToggleLayer(layer) => self.layers.toggle(layer),
+        }
+
+        Ok(())
+    }
+
+    fn key_up_event(
         &mut self,
         _ctx: &mut Context,
+        input: ggez::input::keyboard::KeyInput,
+    ) -> GameResult {
+        let released = self
+            .keymap
+            .command(&input.event.key_without_modifiers(), input.mods)
+            .and_then(|command| RepeatingKey::from_command(command, input.mods));
+        if let Some((held, _)) = self.held_key
+            && released.is_some_and(|released| released.same_key(held))
+        {
+            self.held_key = None;
+        }
+        Ok(())
+    }
+
+    fn focus_event(&mut self, _ctx: &mut Context, gained: bool) -> GameResult {
+        if !gained {
+            // we won't hear about keys being let go while we're not focused.
+            self.held_key = None;
+            // over the network, the opponent's clock keeps going, so ours has to as well.
+            if self.settings.pause_when_unfocused && self.netcode.is_none() {
+                self.unfocused_since.get_or_insert_with(Instant::now);
+            }
+        } else if let Some(since) = self.unfocused_since.take()
+            && let Some(opponent) = &mut self.opponent_bot
+        {
+            opponent.delay(since.elapsed());
+        }
+        Ok(())
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) -> GameResult {
+        if let Some(draft) = &mut self.comment_draft
+            && !character.is_control()
+        {
+            draft.push(character);
+        }
+        Ok(())
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
         button: ggez::winit::event::MouseButton,
         x: f32,
         y: f32,
     ) -> GameResult {
+        self.note_input();
+        if let Some(screen) = &mut self.pass_screen
+            && screen.passing_to().is_some()
+        {
+            let (width, height) = ctx.gfx.drawable_size();
+            if button == ggez::winit::event::MouseButton::Left
+                && PassScreen::button(width, height).contains(Vec2::new(x, y))
+            {
+                screen.ready();
+            }
+            return Ok(());
+        }
+        if self.end_kiosk_demo() || self.library.is_some() || self.blunder_review.is_some() {
+            return Ok(());
+        }
+        if button == ggez::winit::event::MouseButton::Middle {
+            self.panning = true;
+            return Ok(());
+        }
+        if button == ggez::winit::event::MouseButton::Left && self.jump_camera(Vec2::new(x, y)) {
+            self.minimap_dragging = true;
+            return Ok(());
+        }
+        let (width, height) = ctx.gfx.drawable_size();
+        if self.history_panel.open() && HistoryPanel::rect(width, height).contains(Vec2::new(x, y))
+        {
+            if button == ggez::winit::event::MouseButton::Left
+                && let Some(turn) = self.history_panel.turn_at(Vec2::new(x, y), width, height)
+            {
+                self.try_jump_to_turn(turn);
+            }
+            return Ok(());
+        }
+        if button == ggez::winit::event::MouseButton::Left
+            && self.scrubber_rect().contains(Vec2::new(x, y))
+        {
+            self.scrubbing = true;
+            self.scrub_target = Some(self.scrubber_turn_at(x));
+            return Ok(());
+        }
+        if button == ggez::winit::event::MouseButton::Left && !self.game.history().at_live() {
+            let (x, y) = self.transform.board_pos(x, y);
+            self.inspect_piece_at(x, y);
+        }
+        match button {
+            // right dragging with nothing selected draws annotations, like on an analysis board.
+            ggez::winit::event::MouseButton::Right if self.game.chess().selected().is_none() => {
+                self.annotation_drag = Some(self.transform.board_pos(x, y));
+                return Ok(());
+            }
+            ggez::winit::event::MouseButton::Left => {
+                self.annotations.clear(self.game.history().current());
+            }
+            _ => (),
+        }
+        if self.watching() {
+            // the bots (or the replay) are playing. we're just watching.
+            return Ok(());
+        }
+        let (x, y) = self.transform.board_pos(x, y);
+        if button == ggez::winit::event::MouseButton::Left
+            && !self.quit_dialog_open
+            && !self.takeback_dialog_open
+            && !self.rematch_dialog_open
+            && self.rollback_offered.is_none()
+            && self.click_rejected(x, y)
+        {
+            self.reject_click(x, y);
+        }
         if let Some(button) = match button {
             ggez::winit::event::MouseButton::Left => Some(emulator::MouseButton::LEFT),
             ggez::winit::event::MouseButton::Right => Some(emulator::MouseButton::RIGHT),
             _ => None,
         } {
-            let (x, y) = (self.cnv_w(x), self.cnv_w(y));
             self.try_send_event(Event::ButtonDown { x, y, button });
         }
         Ok(())
@@ -641,12 +5462,35 @@ impl EventHandler for App {
         x: f32,
         y: f32,
     ) -> GameResult {
+        self.note_input();
+        if button == ggez::winit::event::MouseButton::Middle {
+            self.panning = false;
+            return Ok(());
+        }
+        if self.scrubbing && button == ggez::winit::event::MouseButton::Left {
+            self.scrubbing = false;
+            return Ok(());
+        }
+        if self.minimap_dragging && button == ggez::winit::event::MouseButton::Left {
+            self.minimap_dragging = false;
+            return Ok(());
+        }
+        if button == ggez::winit::event::MouseButton::Right
+            && let Some(from) = self.annotation_drag.take()
+        {
+            self.finish_annotation(from, self.transform.board_pos(x, y));
+            return Ok(());
+        }
+        if self.watching() {
+            // the bots (or the replay) are playing. we're just watching.
+            return Ok(());
+        }
         if let Some(button) = match button {
             ggez::winit::event::MouseButton::Left => Some(emulator::MouseButton::LEFT),
             ggez::winit::event::MouseButton::Right => Some(emulator::MouseButton::RIGHT),
             _ => None,
         } {
-            let (x, y) = (self.cnv_w(x), self.cnv_w(y));
+            let (x, y) = self.transform.board_pos(x, y);
             self.try_send_event(Event::ButtonUp { x, y, button });
         }
         Ok(())
@@ -657,104 +5501,160 @@ impl EventHandler for App {
         _ctx: &mut Context,
         x: f32,
         y: f32,
-        _dx: f32,
-        _dy: f32,
+        dx: f32,
+        dy: f32,
     ) -> GameResult {
-        let (x, y) = (self.cnv_w(x), self.cnv_w(y));
-        self.mouse_pos = (x, y);
-        self.try_send_event(Event::MouseMotion { x, y });
+        self.note_input();
+        if self.panning {
+            self.transform.pan_by(Vec2::new(dx, dy));
+            self.invalidate_meshes();
+        }
+        if self.minimap_dragging {
+            self.jump_camera(Vec2::new(x, y));
+            return Ok(());
+        }
+        self.scrubber_hover =
+            (self.scrubbing || self.scrubber_rect().contains(Vec2::new(x, y))).then_some(x);
+        if self.scrubbing {
+            self.scrub_target = Some(self.scrubber_turn_at(x));
+            return Ok(());
+        }
+        self.mouse_pos = self.transform.board_pos(x, y);
+        self.pending_motion = Some(self.mouse_pos);
         Ok(())
     }
 
-    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
-        self.update_runit_to_world_multiplier(width, height);
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: f32, y: f32) -> GameResult {
+        self.note_input();
+        if self.library.is_some() || self.blunder_review.is_some() {
+            return Ok(());
+        }
+        let (mouse_x, mouse_y) = self.mouse_pos;
+        if y != 0.
+            && let Some(piece_idx) = self.piece_to_rotate()
+            && let Some(piece) = self.game.chess().pieces().get(piece_idx)
+            && Piece::collidepoint_generic(mouse_x, mouse_y, piece.x(), piece.y())
+        {
+            // scrolling over the moved piece turns it, like the arrow keys.
+            let step = RotationStep::from_mods(ctx.keyboard.active_mods());
+            self.turn_piece(y < 0., step);
+            return Ok(());
+        }
+        let at = ctx.mouse.position();
+        let (width, height) = ctx.gfx.drawable_size();
+        if self.history_panel.open() && HistoryPanel::rect(width, height).contains(at) {
+            // scrolling down the list goes later in the game.
+            self.history_panel.scroll_by(-y.signum() as i32, height);
+            return Ok(());
+        }
+        if !self.scrubber_rect().contains(at) {
+            self.zoom_view(Vec2::new(at.x, at.y), y);
+        }
         Ok(())
     }
 
-    fn update(&mut self, _ctx: &mut Context) -> GameResult {
-        // don't use turn phase for this check, the turn phase can be Wait even though netcode
-        // isn't done yet (ie when it's my turn)
-        if !self.netcode.my_turn()
-            && let Ok(turn) = self.netcode.try_recv_turn()
-        {
-            match Self::de_thing(&turn) {
-                Some(ThingHappened::FirstTurn) => self.chess.handle_event(Event::FirstTurn),
-                Some(ThingHappened::PrevTurn) => self.chess.handle_event(Event::PrevTurn),
-                Some(ThingHappened::NextTurn) => self.chess.handle_event(Event::NextTurn),
-                Some(ThingHappened::LastTurn) => self.chess.handle_event(Event::LastTurn),
-                Some(ThingHappened::Rotate(piece_idx, r)) => {
-                    assert!(self.turn_phase == TurnPhase::Wait);
-                    self.turn_phase = TurnPhase::Move;
-                    self.chess
-                        .handle_event(Event::RotateUnchecked(piece_idx, r))
-                }
-                Some(ThingHappened::Move(piece_idx, x, y)) => {
-                    assert!(self.turn_phase == TurnPhase::Wait);
-                    self.chess
-                        .handle_event(Event::MoveUnchecked(piece_idx, x, y));
-                    self.netcode.send_turn(&Self::ser_thing(None));
-                    None
-                }
-                None => None,
-            };
+    fn quit_event(&mut self, _ctx: &mut Context) -> GameResult<bool> {
+        if self.game_in_progress() && !self.quit_confirmed {
+            // a stray Alt+F4 shouldn't forfeit the game. ask first.
+            self.quit_dialog_open = true;
+            return Ok(true);
         }
-        Ok(())
+        self.send_quit();
+        Ok(false)
     }
 
-    fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        let mut canvas = Canvas::from_frame(ctx, BACKGROUND_COLOR);
+    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        self.target_scale =
+            BoardTransform::fitting_scale(self.history_panel.board_width(width), height);
+        self.resized_at = Some(Instant::now());
+        Ok(())
+    }
 
-        self.draw_board((ctx, &mut canvas))?;
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        profiling::finish_frame();
+        let _span = profiling::span("update");
 
-        let selected = self.chess.selected();
+        #[cfg(feature = "hot-reload")]
+        self.hot_reload_images(ctx);
 
-        if let Some((piece, _)) = selected {
-            self.draw_piece_highlight(
-                (ctx, &mut canvas),
-                piece.x(),
-                piece.y(),
-                SELECTED_PIECE_COLOR,
-            )?;
+        self.poll_netcode();
+        self.step_resync_send();
+        self.step_relay();
+        self.flush_mouse_motion();
+        self.step_resize();
+        self.step_held_key();
+        self.snap_step = SnapStep::from_mods(ctx.keyboard.active_mods());
+        self.step_bench(ctx);
+        self.step_exhibition();
+        self.step_opponent_bot();
+        self.step_analysis();
+        self.step_playback();
+        self.step_clock();
+        self.check_outcome();
+        self.step_pass_screen();
+        self.step_rematch();
+        self.check_kiosk_idle();
+        self.step_effects();
+        self.step_animations(ctx.time.delta());
+        self.step_scrub();
+        self.step_history_panel(ctx);
+        self.check_snap_back();
+        self.check_idle();
+        self.check_auto_rotate();
+        self.check_activity(ctx);
+        self.check_post_game();
+        self.sounds.set_ducked(
+            self.quit_dialog_open
+                || self.takeback_dialog_open
+                || self.rematch_dialog_open
+                || self.rollback_offered.is_some()
+                || self.library.is_some()
+                || self.blunder_review.is_some(),
+        );
+        for cue in std::mem::take(&mut self.cues_due) {
+            self.sounds.play(ctx, cue);
         }
+        self.update_cursor(ctx);
 
-        self.draw_pieces((ctx, &mut canvas), selected.is_some())?;
+        Ok(())
+    }
 
-        if let Some((_, travelpoints)) = selected {
-            for tp in travelpoints {
-                if tp.travelable {
-                    let (xpix, ypix) = self.mouse_pos;
-                    if Piece::collidepoint_generic(self.cnv_w(xpix), self.cnv_w(ypix), tp.x, tp.y) {
-                        self.draw_piece_highlight(
-                            (ctx, &mut canvas),
-                            tp.x,
-                            tp.y,
-                            match tp.kind {
-                                TravelKind::Capture => CAPTURE_HIGHLIGHT_COLOR,
-                                TravelKind::Move => MOVE_HIGHLIGHT_COLOR,
-                            },
-                        )?;
-                    } else {
-                        match tp.kind {
-                            TravelKind::Capture => {
-                                self.draw_capturablepoint_indicator((ctx, &mut canvas), tp.x, tp.y)?
-                            }
-                            TravelKind::Move => {
-                                self.draw_movablepoint_indicator((ctx, &mut canvas), tp.x, tp.y)?
-                            }
-                        }
-                    }
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let draw_started = Instant::now();
+        let _span = profiling::span("draw");
+        self.capture_thumbnail(ctx)?;
+        self.render_library_thumbnails(ctx)?;
+        let mut canvas = Canvas::from_frame(ctx, self.theme.background);
+
+        for layer in self.layers.visible() {
+            let _span = profiling::span(layer.name());
+            match layer {
+                Layer::Board => self.draw_board_layer((ctx, &mut canvas))?,
+                Layer::Pieces => {
+                    self.clip_to_board(&mut canvas)?;
+                    self.draw_piece_layer((ctx, &mut canvas))?;
+                    canvas.set_default_scissor_rect();
                 }
-                self.draw_piece_outline(
-                    (ctx, &mut canvas),
-                    tp.x,
-                    tp.y,
-                    match tp.kind {
-                        TravelKind::Capture => CAPTURE_OUTLINE_COLOR,
-                        TravelKind::Move => MOVE_OUTLINE_COLOR,
-                    },
-                )?;
+                Layer::Indicators => {
+                    self.clip_to_board(&mut canvas)?;
+                    self.draw_indicator_layer((ctx, &mut canvas))?;
+                    canvas.set_default_scissor_rect();
+                }
+                Layer::Hud => self.draw_hud_layer((ctx, &mut canvas))?,
+                Layer::Overlay => self.draw_overlay_layer((ctx, &mut canvas))?,
             }
         }
-        canvas.finish(ctx)
+        if let Some(screen) = &self.pass_screen {
+            // over everything, whatever layers are hidden, so there's no peeking.
+            screen.draw((ctx, &mut canvas), &self.theme, &self.render_counters)?;
+        }
+
+        canvas.finish(ctx)?;
+
+        let counts = self.render_counters.take();
+        if let Some(bench) = &mut self.bench {
+            bench.record_frame(draw_started.elapsed(), counts);
+        }
+        Ok(())
     }
 }
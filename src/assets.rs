@@ -0,0 +1,197 @@
+//! Keeps track of the images we have loaded, so we only hold on to the ones in use, and
+//! finds the piece sets and sound sets there are to pick from.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use ggez::{Context, GameError, GameResult, graphics::Image};
+
+use crate::audio::CUES;
+
+/// The ID for an image is the file stem from its file path.
+///
+/// See [`PieceImages::load`], where they are canonically generated.
+pub type ImageID = String;
+
+/// The ggez resource directory of the piece set we ship with.
+pub const DEFAULT_PIECE_SET: &str = "/pieces_png";
+
+/// Every file a piece set directory must have.
+const PIECE_IMAGE_FILES: [&str; 12] = [
+    "piece_bishopB1.png",
+    "piece_bishopW1.png",
+    "piece_kingB1.png",
+    "piece_kingW1.png",
+    "piece_knightB1.png",
+    "piece_knightW1.png",
+    "piece_pawnB1.png",
+    "piece_pawnW1.png",
+    "piece_queenB1.png",
+    "piece_queenW1.png",
+    "piece_rookB1.png",
+    "piece_rookW1.png",
+];
+
+/// The images of the piece set in use. Only one set is loaded at a time.
+pub struct PieceImages {
+    /// The ggez resource directory the set was loaded from.
+    dir: PathBuf,
+    images: HashMap<ImageID, Image>,
+}
+
+impl PieceImages {
+    pub fn load(ctx: &mut Context, dir: &Path) -> GameResult<Self> {
+        let mut images = HashMap::new();
+        for file in PIECE_IMAGE_FILES {
+            let (id, image) = load_image(ctx, &dir.join(file))?;
+            images.insert(id, image);
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            images,
+        })
+    }
+
+    /// Switch to the piece set in another directory, letting go of the current one.
+    ///
+    /// The new set is loaded in full before the old one is dropped, so if it's broken we
+    /// keep drawing with what we had.
+    pub fn switch_set(&mut self, ctx: &mut Context, dir: &Path) -> GameResult {
+        if dir != self.dir {
+            *self = Self::load(ctx, dir)?;
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Image> {
+        self.images.get(id)
+    }
+
+    /// Swap in a fresh copy of the image at some path, if it's part of the set in use.
+    ///
+    /// Returns whether it was.
+    pub fn reload(&mut self, ctx: &mut Context, path: &Path) -> GameResult<bool> {
+        if path.parent() != Some(self.dir.as_path()) {
+            return Ok(false);
+        }
+        let (id, image) = load_image(ctx, path)?;
+        if !self.images.contains_key(&id) {
+            return Ok(false);
+        }
+        self.images.insert(id, image);
+        Ok(true)
+    }
+
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Roughly how much GPU memory the images take up, in bytes.
+    ///
+    /// Assumes uncompressed RGBA8 textures without mipmaps, which is what ggez makes for PNGs.
+    pub fn gpu_bytes_estimate(&self) -> u64 {
+        self.images
+            .values()
+            .map(|image| image.width() as u64 * image.height() as u64 * 4)
+            .sum()
+    }
+}
+
+/// Load a single image from a ggez resource path, keyed by its [`ImageID`].
+fn load_image(ctx: &mut Context, path: &Path) -> GameResult<(ImageID, Image)> {
+    let id = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| GameError::ResourceLoadError(format!("Bad image path {path:?}")))?
+        .to_string();
+    Ok((id, Image::from_path(ctx, path)?))
+}
+
+/// The directories on disk ggez looks for resources in: the one next to the executable,
+/// and the crate's own when run with cargo (see `main.rs`).
+pub fn resource_dirs(ctx: &Context) -> Vec<PathBuf> {
+    let mut dirs = vec![ctx.fs.resources_dir().to_path_buf()];
+    if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
+        dirs.push(PathBuf::from(manifest_dir).join("resources"));
+    }
+    dirs
+}
+
+/// The piece sets and sound sets in a resource directory, as ggez resource paths.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Discovered {
+    pub piece_sets: Vec<PathBuf>,
+    pub sound_sets: Vec<PathBuf>,
+}
+
+/// Find the sets right under a resource directory on disk.
+///
+/// A directory with every piece image is a piece set, and one with a sound for any
+/// [`Cue`](crate::audio::Cue) is a sound set. A resource pack can be both, but its sounds
+/// and images are picked separately.
+pub fn discover(resources: &Path) -> Discovered {
+    let mut discovered = Discovered::default();
+    let Ok(dirs) = std::fs::read_dir(resources) else {
+        return discovered;
+    };
+    let mut dirs: Vec<PathBuf> = dirs
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    for dir in dirs {
+        let Some(name) = dir.file_name() else {
+            continue;
+        };
+        let resource_path = Path::new("/").join(name);
+        if PIECE_IMAGE_FILES
+            .iter()
+            .all(|file| dir.join(file).is_file())
+        {
+            discovered.piece_sets.push(resource_path.clone());
+        }
+        if CUES.iter().any(|cue| dir.join(cue.file()).is_file()) {
+            discovered.sound_sets.push(resource_path);
+        }
+    }
+    discovered
+}
+
+#[cfg(test)]
+mod test_discover {
+    use super::*;
+
+    #[test]
+    fn packs_can_have_images_sounds_or_both() {
+        let root = std::env::temp_dir().join(format!("rotchess-assets-{}", std::process::id()));
+        for dir in ["both", "images", "sounds", "neither"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for dir in ["both", "images"] {
+            for file in PIECE_IMAGE_FILES {
+                std::fs::write(root.join(dir).join(file), "").unwrap();
+            }
+        }
+        for dir in ["both", "sounds"] {
+            std::fs::write(root.join(dir).join("capture.wav"), "").unwrap();
+        }
+        std::fs::write(root.join("neither").join("move.png"), "").unwrap();
+
+        let discovered = discover(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            discovered,
+            Discovered {
+                piece_sets: vec![PathBuf::from("/both"), PathBuf::from("/images")],
+                sound_sets: vec![PathBuf::from("/both"), PathBuf::from("/sounds")],
+            }
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Sounds the game plays: a chime for when it becomes your turn, so a player off in another
+//! window doesn't leave their opponent waiting, and the sound set's sounds for moves,
+//! captures and rotations.
+//!
+//! A sound set is a ggez resource directory with any of `move.wav`, `capture.wav`,
+//! `rotate.wav` and `chime.wav` in it. Cues it has no file for stay quiet, except the chime,
+//! which falls back to the one picked in the settings. Sets are found like piece sets (see
+//! [`crate::assets::discover`]), and can be in the same directory as one, but are picked on
+//! their own.
+//!
+//! Everything plays through [`Sounds`], which mixes it down while a dialog or menu is up
+//! (see [`Sounds::set_ducked`]).
+
+use std::path::{Path, PathBuf};
+
+use ggez::{
+    Context,
+    audio::{SoundSource, Source},
+};
+
+use crate::settings::Settings;
+
+/// The ggez resource directory of the sound set we ship with.
+pub const DEFAULT_SOUND_SET: &str = "/sounds";
+
+/// How loud sounds play while they're ducked, compared to usual.
+const DUCKED_VOLUME: f32 = 0.3;
+
+/// Something that happened that a sound set has a sound for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cue {
+    Move,
+    Capture,
+    Rotate,
+    /// It became our turn.
+    Chime,
+}
+
+pub const CUES: [Cue; 4] = [Cue::Move, Cue::Capture, Cue::Rotate, Cue::Chime];
+
+impl Cue {
+    /// The file in a sound set with this cue's sound.
+    pub fn file(self) -> &'static str {
+        match self {
+            Cue::Move => "move.wav",
+            Cue::Capture => "capture.wav",
+            Cue::Rotate => "rotate.wav",
+            Cue::Chime => "chime.wav",
+        }
+    }
+
+    fn index(self) -> usize {
+        CUES.iter()
+            .position(|&cue| cue == self)
+            .expect("Every cue is in CUES.")
+    }
+}
+
+/// The chimes we ship with, picked with `turn_chime` in the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnChime {
+    Bell,
+    Blip,
+    Off,
+}
+
+impl TurnChime {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bell" => Some(Self::Bell),
+            "blip" => Some(Self::Blip),
+            "off" => Some(Self::Off),
+            _ => None,
+        }
+    }
+
+    /// The ggez resource path of the chime's sound, if it makes one.
+    fn path(self) -> Option<&'static str> {
+        match self {
+            Self::Bell => Some("/sounds/bell.wav"),
+            Self::Blip => Some("/sounds/blip.wav"),
+            Self::Off => None,
+        }
+    }
+}
+
+/// The sounds in use, loaded, and how loud to play them.
+#[derive(Default)]
+pub struct Sounds {
+    /// The ggez resource directory the sound set was loaded from.
+    set: PathBuf,
+    /// The set's sound for each of [`CUES`], if it has one.
+    cues: [Option<Source>; CUES.len()],
+    /// The chime picked in the settings, for sets without one.
+    turn_chime: Option<Source>,
+    chime_off: bool,
+    turn_chime_volume: f32,
+    effects_volume: f32,
+    /// Whether something's up that sounds shouldn't talk over.
+    ducked: bool,
+}
+
+impl Sounds {
+    /// Load the sound set in a directory, and the chime the settings ask for.
+    ///
+    /// Sound is a nicety, so any that won't load (say, for lack of an audio device) are
+    /// reported and gone without.
+    pub fn load(ctx: &mut Context, settings: &Settings, set: &Path) -> Self {
+        let turn_chime = settings
+            .turn_chime
+            .path()
+            .and_then(|path| load_source(ctx, Path::new(path)));
+        Self {
+            set: set.to_path_buf(),
+            cues: CUES.map(|cue| {
+                let path = set.join(cue.file());
+                // a set doesn't have to have every sound.
+                ctx.fs
+                    .exists(&path)
+                    .then(|| load_source(ctx, &path))
+                    .flatten()
+            }),
+            turn_chime,
+            chime_off: settings.turn_chime == TurnChime::Off,
+            turn_chime_volume: settings.turn_chime_volume,
+            effects_volume: settings.effects_volume,
+            ducked: false,
+        }
+    }
+
+    /// The ggez resource directory of the sound set in use.
+    pub fn set(&self) -> &Path {
+        &self.set
+    }
+
+    /// Play everything quieter from now on (say, because a dialog opened), or back at the
+    /// usual volume.
+    pub fn set_ducked(&mut self, ducked: bool) {
+        self.ducked = ducked;
+    }
+
+    pub fn play(&mut self, ctx: &Context, cue: Cue) {
+        let (source, volume) = match cue {
+            Cue::Chime if self.chime_off => return,
+            Cue::Chime => (
+                match &mut self.cues[cue.index()] {
+                    Some(source) => Some(source),
+                    None => self.turn_chime.as_mut(),
+                },
+                self.turn_chime_volume,
+            ),
+            _ => (self.cues[cue.index()].as_mut(), self.effects_volume),
+        };
+        if let Some(source) = source {
+            source.set_volume(mix(volume, self.ducked));
+            if let Err(e) = source.play_detached(ctx) {
+                println!("Couldn't play the {cue:?} sound: {e}");
+            }
+        }
+    }
+}
+
+fn load_source(ctx: &mut Context, path: &Path) -> Option<Source> {
+    match Source::new(ctx, path) {
+        Ok(source) => Some(source),
+        Err(e) => {
+            println!("Couldn't load {path:?}, so it won't play: {e}");
+            None
+        }
+    }
+}
+
+/// How loud a sound plays, given how loud it's set to be.
+fn mix(volume: f32, ducked: bool) -> f32 {
+    if ducked {
+        volume * DUCKED_VOLUME
+    } else {
+        volume
+    }
+}
+
+#[cfg(test)]
+mod test_mixer {
+    use super::*;
+
+    #[test]
+    fn ducking_lowers_and_restores() {
+        assert_eq!(mix(0.5, false), 0.5);
+        assert!(mix(0.5, true) < 0.5);
+        assert_eq!(mix(0., true), 0.);
+    }
+
+    #[test]
+    fn cues_index_into_cues() {
+        for (i, cue) in CUES.into_iter().enumerate() {
+            assert_eq!(cue.index(), i);
+        }
+    }
+}
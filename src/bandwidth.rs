@@ -0,0 +1,99 @@
+//! How much we're sending and receiving over the network, shown with the profiler (F3).
+//!
+//! Every message is [`TURN_SIZE`](crate::constants::TURN_SIZE) bytes, so this is mostly
+//! counting messages. It's here to check that chattier features don't flood a slow link.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// How far back the per-second rates look.
+const WINDOW: Duration = Duration::from_secs(5);
+
+/// Traffic in one direction.
+#[derive(Debug, Default)]
+pub struct Traffic {
+    /// When each message in the window went by, and how big it was.
+    recent: VecDeque<(Instant, usize)>,
+    pub total_bytes: usize,
+    pub total_messages: usize,
+}
+
+impl Traffic {
+    pub fn record(&mut self, bytes: usize, now: Instant) {
+        self.recent.push_back((now, bytes));
+        self.total_bytes += bytes;
+        self.total_messages += 1;
+        self.forget_before(now);
+    }
+
+    /// Bytes per second, then messages per second, averaged over the last few seconds.
+    pub fn rates(&self, now: Instant) -> (f32, f32) {
+        let in_window: Vec<usize> = self
+            .recent
+            .iter()
+            .filter(|(at, _)| now.saturating_duration_since(*at) <= WINDOW)
+            .map(|(_, bytes)| *bytes)
+            .collect();
+        let secs = WINDOW.as_secs_f32();
+        (
+            in_window.iter().sum::<usize>() as f32 / secs,
+            in_window.len() as f32 / secs,
+        )
+    }
+
+    fn forget_before(&mut self, now: Instant) {
+        while let Some(&(at, _)) = self.recent.front()
+            && now.saturating_duration_since(at) > WINDOW
+        {
+            self.recent.pop_front();
+        }
+    }
+
+    /// A line like "sent 1.2 msg/s, 18 B/s (40 msgs, 600 B total)".
+    pub fn describe(&self, direction: &str, now: Instant) -> String {
+        let (bytes_per_sec, messages_per_sec) = self.rates(now);
+        format!(
+            "{direction} {messages_per_sec:.1} msg/s, {bytes_per_sec:.0} B/s \
+             ({} msgs, {} B total)",
+            self.total_messages, self.total_bytes
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Bandwidth {
+    pub sent: Traffic,
+    pub received: Traffic,
+}
+
+#[cfg(test)]
+mod test_bandwidth {
+    use super::*;
+
+    #[test]
+    fn rates_only_count_the_window() {
+        let start = Instant::now();
+        let mut traffic = Traffic::default();
+        traffic.record(15, start);
+        traffic.record(15, start + Duration::from_secs(1));
+        assert_eq!(traffic.rates(start + Duration::from_secs(2)), (6., 0.4));
+
+        // the first message has aged out, but still counts towards the totals.
+        assert_eq!(traffic.rates(start + Duration::from_secs(6)), (3., 0.2));
+        assert_eq!(traffic.total_bytes, 30);
+        assert_eq!(traffic.total_messages, 2);
+    }
+
+    #[test]
+    fn quiet_links_read_zero() {
+        let start = Instant::now();
+        let mut traffic = Traffic::default();
+        traffic.record(15, start);
+        assert_eq!(
+            traffic.describe("sent", start + Duration::from_secs(60)),
+            "sent 0.0 msg/s, 0 B/s (1 msgs, 15 B total)"
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Benchmark mode: play a scripted game for a set number of frames and report how long
+//! rendering took, so rendering optimizations can be measured instead of guessed at.
+//!
+//! Run with `cargo run --release -- --bench [frames]`.
+
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+/// How many frames to run for if `--bench` isn't given a number.
+pub const DEFAULT_BENCH_FRAMES: u32 = 600;
+
+/// How many frames the script waits between clicks, so each position gets drawn a few times.
+pub const FRAMES_PER_SCRIPT_STEP: u32 = 10;
+
+/// Parse `--bench [frames]` out of the command line arguments.
+///
+/// Returns `None` if we shouldn't benchmark.
+pub fn bench_frames_from_args() -> Option<u32> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--bench");
+    args.next()?;
+    Some(
+        args.next()
+            .and_then(|frames| frames.parse().ok())
+            .unwrap_or(DEFAULT_BENCH_FRAMES),
+    )
+}
+
+/// Counts of the work done drawing a frame.
+///
+/// Drawing only borrows the app immutably, hence the cells.
+#[derive(Default)]
+pub struct RenderCounters {
+    draw_calls: Cell<u32>,
+    meshes_built: Cell<u32>,
+}
+
+impl RenderCounters {
+    pub fn count_draw_call(&self) {
+        self.draw_calls.set(self.draw_calls.get() + 1);
+    }
+
+    pub fn count_mesh_built(&self) {
+        self.meshes_built.set(self.meshes_built.get() + 1);
+    }
+
+    /// The draw calls and meshes built since the last time this was called.
+    pub fn take(&self) -> (u32, u32) {
+        (self.draw_calls.take(), self.meshes_built.take())
+    }
+}
+
+/// A running benchmark.
+pub struct Bench {
+    frames: u32,
+    frames_done: u32,
+    started: Option<Instant>,
+    time_drawing: Duration,
+    draw_calls: u64,
+    meshes_built: u64,
+}
+
+impl Bench {
+    pub fn new(frames: u32) -> Self {
+        Self {
+            frames,
+            frames_done: 0,
+            started: None,
+            time_drawing: Duration::ZERO,
+            draw_calls: 0,
+            meshes_built: 0,
+        }
+    }
+
+    /// How many frames have been drawn so far.
+    pub fn frames_done(&self) -> u32 {
+        self.frames_done
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.frames_done >= self.frames
+    }
+
+    /// Note that a frame was drawn, along with what it took to draw it.
+    pub fn record_frame(&mut self, time_drawing: Duration, (draw_calls, meshes_built): (u32, u32)) {
+        self.started.get_or_insert_with(Instant::now);
+        self.frames_done += 1;
+        self.time_drawing += time_drawing;
+        self.draw_calls += draw_calls as u64;
+        self.meshes_built += meshes_built as u64;
+    }
+
+    pub fn report(&self) -> String {
+        let frames = self.frames_done.max(1);
+        let elapsed = self
+            .started
+            .map_or(Duration::ZERO, |started| started.elapsed());
+        format!(
+            "Benchmarked {} frames in {:.2?}.\n\
+             average frame time: {:.2?}\n\
+             average time drawing: {:.2?}\n\
+             average draw calls: {:.1}\n\
+             average meshes built: {:.1}",
+            self.frames_done,
+            elapsed,
+            elapsed / frames,
+            self.time_drawing / frames,
+            self.draw_calls as f64 / frames as f64,
+            self.meshes_built as f64 / frames as f64,
+        )
+    }
+}
@@ -0,0 +1,147 @@
+//! Looking back over a finished game for turns that gave away material, for the summary
+//! after local games and games against the computer (`b`).
+//!
+//! The "engine" is as simple as the bot: a position is worth the material a side has over
+//! the other, less the most the other side could take on its next move. A turn that makes
+//! that a lot worse for whoever played it is a blunder.
+
+use rotchess_core::{
+    RotchessEmulator,
+    emulator::Event,
+    piece::{PieceKind, Side},
+};
+
+use crate::{
+    bot,
+    history::Action,
+    notation::{self, PieceSnapshot},
+};
+
+/// How much worse, in pawns, a turn has to leave things to count as a blunder.
+pub const BLUNDER_THRESHOLD: f32 = 2.;
+
+/// A turn that made things a lot worse for whoever played it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blunder {
+    /// How many actions were played before the blundering move, i.e. where to look back to
+    /// see it coming.
+    pub turn: usize,
+    pub side: Side,
+    /// What the move was, like "White knight moves".
+    pub description: String,
+    /// How much worse things got, in pawns.
+    pub swing: f32,
+}
+
+/// Roughly what a piece is worth, in pawns. Losing the king loses the game, so it's worth
+/// more than everything else put together.
+pub fn piece_value(kind: PieceKind) -> f32 {
+    match kind {
+        PieceKind::Pawn => 1.,
+        PieceKind::Knight | PieceKind::Bishop => 3.,
+        PieceKind::Rook => 5.,
+        PieceKind::Queen => 9.,
+        PieceKind::King => 100.,
+    }
+}
+
+/// How much more material `side` has than the other side, in pawns.
+pub fn material(board: &[PieceSnapshot], side: Side) -> f32 {
+    board
+        .iter()
+        .map(|piece| {
+            let value = piece_value(piece.kind);
+            if piece.side == side { value } else { -value }
+        })
+        .sum()
+}
+
+/// The most `side` could take with its next move, in pawns.
+fn best_capture(chess: &mut RotchessEmulator, side: Side) -> f32 {
+    let board = notation::snapshot(chess.pieces());
+    bot::move_options(chess, side)
+        .iter()
+        .filter_map(|option| notation::captured_by(&board, option.piece_idx, option.x, option.y))
+        .map(|captured| piece_value(captured.kind))
+        .fold(0., f32::max)
+}
+
+/// Go through a game from its first board, checking each whole turn (a move and the
+/// rotation after it) for blunders. A turn still missing its rotation isn't checked.
+pub fn find_blunders(mut chess: RotchessEmulator, actions: &[Action]) -> Vec<Blunder> {
+    let mut blunders = Vec::new();
+    let mut side = Side::White;
+    let mut i = 0;
+    while let [Action::Move(piece_idx, x, y), Action::Rotate(..), ..] = actions[i..] {
+        let before = notation::snapshot(chess.pieces());
+        let description = notation::describe(&before, &Action::Move(piece_idx, x, y));
+        for action in &actions[i..i + 2] {
+            let event = match *action {
+                Action::Move(piece_idx, x, y) if piece_idx < chess.pieces().len() => {
+                    Event::MoveUnchecked(piece_idx, x, y)
+                }
+                Action::Rotate(piece_idx, r) if piece_idx < chess.pieces().len() => {
+                    Event::RotateUnchecked(piece_idx, r)
+                }
+                _ => return blunders,
+            };
+            chess.handle_event(event);
+        }
+        let other = match side {
+            Side::White => Side::Black,
+            Side::Black => Side::White,
+        };
+        let after =
+            material(&notation::snapshot(chess.pieces()), side) - best_capture(&mut chess, other);
+        let swing = material(&before, side) - after;
+        if swing >= BLUNDER_THRESHOLD
+            && let Some(description) = description
+        {
+            blunders.push(Blunder {
+                turn: i,
+                side,
+                description,
+                swing,
+            });
+        }
+        side = other;
+        i += 2;
+    }
+    blunders
+}
+
+#[cfg(test)]
+mod test_blunders {
+    use super::*;
+
+    fn piece(kind: PieceKind, side: Side) -> PieceSnapshot {
+        PieceSnapshot {
+            kind,
+            side,
+            x: 0.5,
+            y: 0.5,
+            angle: 0.,
+        }
+    }
+
+    #[test]
+    fn material_counts_for_one_side_against_the_other() {
+        let board = [
+            piece(PieceKind::Queen, Side::White),
+            piece(PieceKind::Rook, Side::Black),
+            piece(PieceKind::Pawn, Side::Black),
+        ];
+        assert_eq!(material(&board, Side::White), 3.);
+        assert_eq!(material(&board, Side::Black), -3.);
+    }
+
+    #[test]
+    fn kings_outweigh_everything_else() {
+        let everything_else = 8. * piece_value(PieceKind::Pawn)
+            + 2. * piece_value(PieceKind::Rook)
+            + 2. * piece_value(PieceKind::Knight)
+            + 2. * piece_value(PieceKind::Bishop)
+            + piece_value(PieceKind::Queen);
+        assert!(piece_value(PieceKind::King) > everything_else);
+    }
+}
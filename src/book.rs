@@ -0,0 +1,244 @@
+//! An opening book for the computer opponent, so it starts games with moves people play
+//! instead of whatever odd rotations its shallow search settles on. Read from
+//! `resources/book.txt`:
+//!
+//! ```text
+//! # the open game.
+//! e2e4 0 e7e5 0 g1f3 0 b8c6 0
+//! ```
+//!
+//! Each line is a line of play from the standard board, a turn at a time: the squares a
+//! piece moves from and to, then how far it turns after, in degrees. Every position along a
+//! line is in the book, with the turn the line plays from there, so lines that share a
+//! start make that start likelier. Lines starting with `#` are comments.
+//!
+//! Positions are matched on the whole board, so the book knows a position however it was
+//! reached, and never one from a Chess960 layout.
+
+use std::{f32::consts::TAU, io::Read};
+
+use ggez::{Context, GameError, GameResult};
+use rotchess_core::{
+    RotchessEmulator,
+    emulator::Event,
+    piece::{PIECE_RADIUS, Pieces, Side},
+};
+
+use crate::{
+    bot,
+    notation::{self, PieceSnapshot},
+};
+
+/// The ggez resource path of the book.
+pub const BOOK_PATH: &str = "/book.txt";
+
+/// How far apart, in runits, pieces can be and still count as on the same spot.
+const POSITION_TOLERANCE: f32 = 0.01;
+/// How far apart, in radians, angles can be and still count as the same.
+const ANGLE_TOLERANCE: f32 = 0.01;
+
+/// A turn the book plays: a piece moved, then rotated to `angle`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookTurn {
+    pub piece_idx: usize,
+    pub to: (f32, f32),
+    pub angle: f32,
+}
+
+/// A position in the book, and what one of its lines plays from there.
+struct Entry {
+    side: Side,
+    board: Vec<PieceSnapshot>,
+    turn: BookTurn,
+}
+
+pub struct Book {
+    entries: Vec<Entry>,
+}
+
+impl Book {
+    /// Load the book from ggez's resource directories.
+    pub fn load(ctx: &Context) -> GameResult<Self> {
+        let mut text = String::new();
+        ctx.fs.open(BOOK_PATH)?.read_to_string(&mut text)?;
+        Self::from_text(&text)
+    }
+
+    /// Read a book, playing each of its lines out to check every turn can be played.
+    pub fn from_text(text: &str) -> GameResult<Self> {
+        let mut entries = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut chess = RotchessEmulator::with(Pieces::standard_board());
+            let mut side = Side::White;
+            let words: Vec<&str> = line.split_whitespace().collect();
+            for turn in words.chunks(2) {
+                let &[squares, degrees] = turn else {
+                    return Err(malformed(line_no, "expected a move and a rotation"));
+                };
+                let turn = parse_turn(&mut chess, side, squares, degrees).ok_or_else(|| {
+                    malformed(
+                        line_no,
+                        &format!(
+                            "{} can't play {squares} {degrees} there",
+                            notation::side_name(side)
+                        ),
+                    )
+                })?;
+                entries.push(Entry {
+                    side,
+                    board: notation::snapshot(chess.pieces()),
+                    turn,
+                });
+                chess.handle_event(Event::MoveUnchecked(turn.piece_idx, turn.to.0, turn.to.1));
+                chess.handle_event(Event::RotateUnchecked(turn.piece_idx, turn.angle));
+                side = match side {
+                    Side::White => Side::Black,
+                    Side::Black => Side::White,
+                };
+            }
+        }
+        Ok(Self { entries })
+    }
+
+    /// Every turn the book has for `side` on `board`, once for each line that plays it.
+    /// Empty once the game is out of the book.
+    pub fn turns(&self, board: &[PieceSnapshot], side: Side) -> Vec<BookTurn> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.side == side && same_board(&entry.board, board))
+            .map(|entry| entry.turn)
+            .collect()
+    }
+}
+
+fn same_board(a: &[PieceSnapshot], b: &[PieceSnapshot]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(a, b)| {
+            let turned = (a.angle - b.angle).rem_euclid(TAU);
+            a.kind == b.kind
+                && a.side == b.side
+                && (a.x - b.x).hypot(a.y - b.y) < POSITION_TOLERANCE
+                && turned.min(TAU - turned) < ANGLE_TOLERANCE
+        })
+}
+
+/// The middle of a square like `e2`, in runits.
+fn square(name: &str) -> Option<(f32, f32)> {
+    let &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] = name.as_bytes() else {
+        return None;
+    };
+    let row = 8 - (rank - b'0');
+    Some((f32::from(file - b'a') + 0.5, f32::from(row) + 0.5))
+}
+
+/// The turn `side` plays on `chess` moving from one square to another, like `e2e4`, and
+/// turning the piece by some degrees, if it can.
+fn parse_turn(
+    chess: &mut RotchessEmulator,
+    side: Side,
+    squares: &str,
+    degrees: &str,
+) -> Option<BookTurn> {
+    let from = square(squares.get(..2)?)?;
+    let to = square(squares.get(2..)?)?;
+    let degrees: f32 = degrees.parse().ok()?;
+    let board = notation::snapshot(chess.pieces());
+    let piece_idx = board.iter().position(|piece| {
+        piece.side == side && (piece.x - from.0).hypot(piece.y - from.1) < PIECE_RADIUS
+    })?;
+    // where the emulator puts it, which might be a hair off the middle of the square.
+    let option = bot::move_options(chess, side).into_iter().find(|option| {
+        option.piece_idx == piece_idx && (option.x - to.0).hypot(option.y - to.1) < PIECE_RADIUS
+    })?;
+    Some(BookTurn {
+        piece_idx,
+        to: (option.x, option.y),
+        angle: board[piece_idx].angle + degrees.to_radians(),
+    })
+}
+
+fn malformed(line_no: usize, why: &str) -> GameError {
+    GameError::CustomError(format!("Malformed opening book at line {line_no}: {why}."))
+}
+
+#[cfg(test)]
+mod test_book {
+    use super::*;
+
+    fn start() -> Vec<PieceSnapshot> {
+        notation::snapshot(RotchessEmulator::with(Pieces::standard_board()).pieces())
+    }
+
+    #[test]
+    fn squares_are_named_like_chess() {
+        assert_eq!(square("a8"), Some((0.5, 0.5)));
+        assert_eq!(square("e2"), Some((4.5, 6.5)));
+        assert_eq!(square("h1"), Some((7.5, 7.5)));
+        assert_eq!(square("i1"), None);
+        assert_eq!(square("e9"), None);
+        assert_eq!(square("e"), None);
+    }
+
+    #[test]
+    fn shared_starts_are_likelier() {
+        let book =
+            Book::from_text("# two lines.\ne2e4 0 e7e5 0\n\ne2e4 0 c7c5 0\nd2d4 0\n").unwrap();
+        let turns = book.turns(&start(), Side::White);
+        assert_eq!(turns.len(), 3);
+        assert_eq!(turns[0], turns[1]);
+        assert_ne!(turns[0], turns[2]);
+        let (x, y) = square("e4").unwrap();
+        assert!((turns[0].to.0 - x).hypot(turns[0].to.1 - y) < POSITION_TOLERANCE);
+        // it's not black's turn at the start.
+        assert!(book.turns(&start(), Side::Black).is_empty());
+    }
+
+    #[test]
+    fn positions_are_followed_along_a_line() {
+        let book = Book::from_text("e2e4 0 e7e5 45").unwrap();
+        let first = book.turns(&start(), Side::White)[0];
+        let mut chess = RotchessEmulator::with(Pieces::standard_board());
+        chess.handle_event(Event::MoveUnchecked(
+            first.piece_idx,
+            first.to.0,
+            first.to.1,
+        ));
+        chess.handle_event(Event::RotateUnchecked(first.piece_idx, first.angle));
+
+        let board = notation::snapshot(chess.pieces());
+        let [reply] = book.turns(&board, Side::Black)[..] else {
+            panic!("the book has one reply to e4");
+        };
+        let turned = notation::rotation_degrees(board[reply.piece_idx].angle, reply.angle);
+        assert!((turned - 45.).abs() < 0.01);
+
+        // turned differently, it's not the book's position anymore.
+        chess.handle_event(Event::PrevTurn);
+        chess.handle_event(Event::RotateUnchecked(first.piece_idx, first.angle + 1.));
+        assert!(
+            book.turns(&notation::snapshot(chess.pieces()), Side::Black)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn bad_lines_are_errors() {
+        assert!(Book::from_text("e2e4").is_err());
+        assert!(Book::from_text("e2e4 lots").is_err());
+        // nothing of white's on e3.
+        assert!(Book::from_text("e3e4 0").is_err());
+        // a knight can't get there.
+        assert!(Book::from_text("g1g3 0").is_err());
+    }
+
+    #[test]
+    fn the_shipped_book_reads() {
+        let book = Book::from_text(include_str!("../resources/book.txt")).unwrap();
+        assert!(!book.turns(&start(), Side::White).is_empty());
+    }
+}
@@ -0,0 +1,432 @@
+//! Computer players.
+//!
+//! The [`Bot`] isn't clever: it plays a random move, taking something if it can, then gives
+//! the piece it moved a random turn. That's plenty to exercise the emulator and to have
+//! something moving on screen.
+//!
+//! The [`Opponent`] you play against with `--vs-bot` (or from the menu) thinks a little
+//! harder, with the [`crate::engine`], and has a [`Personality`] of its own. It opens from
+//! the [`crate::book`] while the game is still in it.
+
+use std::{
+    f32::consts::TAU,
+    time::{Duration, Instant},
+};
+
+use ggez::{GameError, GameResult};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use rotchess_core::{
+    RotchessEmulator,
+    emulator::{self, Event, TravelKind},
+    piece::Side,
+};
+
+use crate::{
+    book::Book,
+    engine::{Personality, Search, Suggestion},
+    notation,
+};
+
+/// Somewhere a piece could move this turn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveOption {
+    pub piece_idx: usize,
+    pub x: f32,
+    pub y: f32,
+    pub capture: bool,
+}
+
+/// Everywhere a side could move, found by selecting each of its pieces in turn and asking
+/// the emulator where it can travel. Nothing is left selected afterwards.
+pub fn move_options(chess: &mut RotchessEmulator, side: Side) -> Vec<MoveOption> {
+    let button = emulator::MouseButton::LEFT;
+    // right clicking far off the board can only deselect. see App::deselect.
+    let deselect = |chess: &mut RotchessEmulator| {
+        chess.handle_event(Event::ButtonDown {
+            x: -1000.,
+            y: -1000.,
+            button: emulator::MouseButton::RIGHT,
+        });
+    };
+    let mut options = Vec::new();
+    for piece_idx in 0..chess.pieces().len() {
+        let piece = &chess.pieces()[piece_idx];
+        if piece.side() != side {
+            continue;
+        }
+        let (x, y) = (piece.x(), piece.y());
+        // with something selected, the click could land on one of its travel points.
+        deselect(chess);
+        chess.handle_event(Event::ButtonDown { x, y, button });
+        chess.handle_event(Event::ButtonUp { x, y, button });
+        let Some((selected, travelpoints)) = chess.selected() else {
+            continue;
+        };
+        if (selected.x(), selected.y()) != (x, y) {
+            // some other piece sits on top of this one.
+            continue;
+        }
+        options.extend(
+            travelpoints
+                .iter()
+                .filter(|tp| tp.travelable)
+                .map(|tp| MoveOption {
+                    piece_idx,
+                    x: tp.x,
+                    y: tp.y,
+                    capture: tp.kind == TravelKind::Capture,
+                }),
+        );
+    }
+    deselect(chess);
+    options
+}
+
+pub struct Bot {
+    rng: StdRng,
+}
+
+impl Bot {
+    /// A bot that plays the same game every time for the same seed (and the same board).
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Pick a move out of everything our side could do, or `None` if there's nothing.
+    pub fn choose_move(&mut self, options: &[MoveOption]) -> Option<MoveOption> {
+        let captures: Vec<_> = options.iter().filter(|o| o.capture).copied().collect();
+        if captures.is_empty() {
+            options.choose(&mut self.rng).copied()
+        } else {
+            captures.choose(&mut self.rng).copied()
+        }
+    }
+
+    /// Pick a new angle for the piece we just moved, some eighths of a turn away from `angle`.
+    pub fn choose_rotation(&mut self, angle: f32) -> f32 {
+        let eighths = self.rng.random_range(1..8);
+        angle + eighths as f32 * TAU / 8.
+    }
+
+    /// Pick one of the turns a search scored: the best, with no randomness, or with some,
+    /// any of them, the better ones likelier. `temperature` is in pawns, roughly how much
+    /// worse a turn can be and still get played now and then.
+    pub fn choose_turn(&mut self, turns: &[Suggestion], temperature: f32) -> Option<Suggestion> {
+        let best = turns
+            .iter()
+            .map(|turn| turn.score)
+            .fold(f32::NEG_INFINITY, f32::max);
+        if temperature <= 0. {
+            return turns.iter().find(|turn| turn.score == best).copied();
+        }
+        let weights: Vec<f32> = turns
+            .iter()
+            .map(|turn| ((turn.score - best) / temperature).exp())
+            .collect();
+        let mut pick = self.rng.random_range(0. ..weights.iter().sum::<f32>());
+        for (turn, weight) in turns.iter().zip(weights) {
+            if pick < weight {
+                return Some(*turn);
+            }
+            pick -= weight;
+        }
+        turns.last().copied()
+    }
+}
+
+/// How long a bot opponent waits once it's its turn, so there's time to see what it does.
+pub const THINKING_TIME: Duration = Duration::from_millis(800);
+/// How much worse, in pawns, a turn can be than its favorite for an opponent with all the
+/// randomness there is to still play it now and then.
+pub const MAX_TEMPERATURE: f32 = 3.;
+/// How many turns in a row an opponent has to think it's lost before it resigns.
+pub const RESIGN_TURNS: usize = 3;
+
+/// How far behind, in pawns, the opponent can fall before it resigns, from
+/// `--resign-at=9`. Without it, the opponent plays on however things go.
+pub fn resign_threshold_from_args() -> GameResult<Option<f32>> {
+    for arg in std::env::args() {
+        if let Some(("--resign-at", pawns)) = arg.split_once("=") {
+            return match pawns.parse::<f32>() {
+                Ok(pawns) if pawns > 0. && pawns.is_finite() => Ok(Some(pawns)),
+                _ => Err(GameError::CustomError(format!(
+                    "Bad resign threshold {pawns:?}. Try --resign-at=9, in pawns."
+                ))),
+            };
+        }
+    }
+    Ok(None)
+}
+
+/// A bot playing against a person. It answers their turns the way an opponent over the
+/// network would, with a whole turn at once.
+pub struct Opponent {
+    pub bot: Bot,
+    pub personality: Personality,
+    /// The openings it knows, if any.
+    pub book: Option<Book>,
+    /// How far behind, in pawns, it can think it is for [`RESIGN_TURNS`] turns before it
+    /// resigns, if it ever does.
+    pub resign_below: Option<f32>,
+    /// How many turns in a row it's thought it was further behind than that.
+    losing_turns: usize,
+    /// When it plays, once it's its turn.
+    due: Option<Instant>,
+    /// Its search for a turn, while it's its turn.
+    search: Option<Search>,
+    /// The turn it picked from the book, if the game's still in it. It doesn't search then.
+    book_turn: Option<Suggestion>,
+}
+
+impl Opponent {
+    pub fn new(seed: u64, personality: Personality) -> Self {
+        Self {
+            bot: Bot::new(seed),
+            personality,
+            book: None,
+            resign_below: None,
+            losing_turns: 0,
+            due: None,
+            search: None,
+            book_turn: None,
+        }
+    }
+
+    /// Whether it's started looking for a turn.
+    pub fn thinking(&self) -> bool {
+        self.search.is_some() || self.book_turn.is_some()
+    }
+
+    /// Start looking for a turn for `side` on `chess`, in the book first.
+    pub fn start_thinking(&mut self, chess: RotchessEmulator, side: Side) {
+        let board = notation::snapshot(chess.pieces());
+        let book_turns = self
+            .book
+            .as_ref()
+            .map(|book| book.turns(&board, side))
+            .unwrap_or_default();
+        if let Some(turn) = book_turns.choose(&mut self.bot.rng) {
+            let piece = &board[turn.piece_idx];
+            self.book_turn = Some(Suggestion {
+                piece_idx: turn.piece_idx,
+                from: (piece.x, piece.y),
+                to: turn.to,
+                from_angle: piece.angle,
+                angle: turn.angle,
+                score: 0.,
+                depth: 0,
+            });
+            return;
+        }
+        self.search = Some(Search::new(chess, side, self.personality));
+    }
+
+    /// Look at a few more turns, while the thinking time runs down.
+    pub fn think(&mut self, turns: usize) {
+        if let Some(search) = &mut self.search {
+            search.step(turns);
+        }
+    }
+
+    /// Finish thinking and pick a turn, or `None` if there's nothing to play.
+    pub fn choose_turn(&mut self) -> Option<Suggestion> {
+        if let Some(turn) = self.book_turn.take() {
+            return Some(turn);
+        }
+        let mut search = self.search.take()?;
+        while !search.done() {
+            search.step(usize::MAX);
+        }
+        let losing = search
+            .best()
+            .zip(self.resign_below)
+            .is_some_and(|(best, threshold)| best.score < -threshold);
+        self.losing_turns = if losing { self.losing_turns + 1 } else { 0 };
+        let temperature = self.personality.temperature * MAX_TEMPERATURE;
+        self.bot.choose_turn(search.scored(), temperature)
+    }
+
+    /// Whether it's thought it was too far behind for long enough to give up.
+    pub fn resigns(&self) -> bool {
+        self.losing_turns >= RESIGN_TURNS
+    }
+
+    /// Forget the game so far, for a rematch.
+    pub fn new_game(&mut self) {
+        self.losing_turns = 0;
+        self.due = None;
+        self.search = None;
+        self.book_turn = None;
+    }
+
+    /// Whether the bot should play its turn now, [`THINKING_TIME`] after it became its turn.
+    pub fn take_due_turn(&mut self, its_turn: bool, now: Instant) -> bool {
+        if !its_turn {
+            self.due = None;
+            self.search = None;
+            self.book_turn = None;
+            return false;
+        }
+        let due = *self.due.get_or_insert(now + THINKING_TIME);
+        if now < due {
+            return false;
+        }
+        self.due = None;
+        true
+    }
+
+    /// Put off playing its turn by however long the game was paused for.
+    pub fn delay(&mut self, by: Duration) {
+        if let Some(due) = &mut self.due {
+            *due += by;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_bot {
+    use rotchess_core::piece::{PieceKind, Pieces};
+
+    use super::*;
+
+    fn option(piece_idx: usize, capture: bool) -> MoveOption {
+        MoveOption {
+            piece_idx,
+            x: piece_idx as f32,
+            y: 0.,
+            capture,
+        }
+    }
+
+    #[test]
+    fn takes_when_it_can() {
+        let options = [option(0, false), option(1, true), option(2, false)];
+        for seed in 0..20 {
+            assert_eq!(Bot::new(seed).choose_move(&options), Some(options[1]));
+        }
+    }
+
+    #[test]
+    fn no_options_no_move() {
+        assert_eq!(Bot::new(0).choose_move(&[]), None);
+    }
+
+    #[test]
+    fn opponents_think_before_playing() {
+        let mut opponent = Opponent::new(0, Personality::default());
+        let start = Instant::now();
+        assert!(!opponent.take_due_turn(false, start + THINKING_TIME));
+        assert!(!opponent.take_due_turn(true, start));
+        assert!(opponent.take_due_turn(true, start + THINKING_TIME));
+        // and again for its next turn.
+        assert!(!opponent.take_due_turn(true, start + THINKING_TIME));
+    }
+
+    #[test]
+    fn pauses_put_off_the_turn() {
+        let mut opponent = Opponent::new(0, Personality::default());
+        let start = Instant::now();
+        let pause = Duration::from_secs(60);
+        assert!(!opponent.take_due_turn(true, start));
+        opponent.delay(pause);
+        assert!(!opponent.take_due_turn(true, start + THINKING_TIME));
+        assert!(opponent.take_due_turn(true, start + THINKING_TIME + pause));
+    }
+
+    #[test]
+    fn opponents_open_from_the_book() {
+        let mut opponent = Opponent::new(0, Personality::default());
+        opponent.book = Some(Book::from_text("d2d4 0").unwrap());
+        let chess = RotchessEmulator::with(Pieces::standard_board());
+        opponent.start_thinking(chess, Side::White);
+        assert!(opponent.thinking());
+        let turn = opponent.choose_turn().unwrap();
+        assert_eq!((turn.from, turn.depth), ((3.5, 6.5), 0));
+        assert!((turn.to.0 - 3.5).hypot(turn.to.1 - 4.5) < 0.01);
+    }
+
+    /// The standard board with white's queen and rooks taken.
+    fn white_losing_badly() -> RotchessEmulator {
+        let mut chess = RotchessEmulator::with(Pieces::standard_board());
+        let black_queen = |chess: &RotchessEmulator| {
+            chess
+                .pieces()
+                .iter()
+                .position(|piece| piece.side() == Side::Black && piece.kind() == PieceKind::Queen)
+                .unwrap()
+        };
+        let home = {
+            let queen = &chess.pieces()[black_queen(&chess)];
+            (queen.x(), queen.y())
+        };
+        for (x, y) in [(3.5, 7.5), (0.5, 7.5), (7.5, 7.5)] {
+            chess.handle_event(Event::MoveUnchecked(black_queen(&chess), x, y));
+            chess.handle_event(Event::MoveUnchecked(black_queen(&chess), home.0, home.1));
+        }
+        chess
+    }
+
+    #[test]
+    fn opponents_resign_after_a_few_lost_turns() {
+        let mut opponent = Opponent::new(0, Personality::NEUTRAL);
+        opponent.resign_below = Some(9.);
+        for _ in 0..RESIGN_TURNS {
+            assert!(!opponent.resigns());
+            opponent.start_thinking(white_losing_badly(), Side::White);
+            opponent.choose_turn().unwrap();
+        }
+        assert!(opponent.resigns());
+
+        // but not if they never give up.
+        opponent.new_game();
+        opponent.resign_below = None;
+        opponent.start_thinking(white_losing_badly(), Side::White);
+        opponent.choose_turn().unwrap();
+        assert!(!opponent.resigns());
+    }
+
+    #[test]
+    fn rotations_always_turn_the_piece() {
+        let mut bot = Bot::new(7);
+        for _ in 0..50 {
+            let eighths = bot.choose_rotation(1.) - 1.;
+            let eighths = eighths / (TAU / 8.);
+            assert!((1. ..8.).contains(&eighths.round()));
+            assert!((eighths - eighths.round()).abs() < 1e-4);
+        }
+    }
+
+    fn turn(score: f32) -> Suggestion {
+        Suggestion {
+            piece_idx: 0,
+            from: (0.5, 0.5),
+            to: (0.5, 1.5),
+            from_angle: 0.,
+            angle: 0.,
+            score,
+            depth: 2,
+        }
+    }
+
+    #[test]
+    fn no_randomness_plays_the_best_turn() {
+        let turns = [turn(0.), turn(3.), turn(1.)];
+        for seed in 0..20 {
+            assert_eq!(Bot::new(seed).choose_turn(&turns, 0.), Some(turns[1]));
+        }
+        assert_eq!(Bot::new(0).choose_turn(&[], 0.), None);
+    }
+
+    #[test]
+    fn randomness_sometimes_plays_worse_turns() {
+        let turns = [turn(0.), turn(1.)];
+        let mut bot = Bot::new(3);
+        let picks: Vec<_> = (0..200)
+            .map(|_| bot.choose_turn(&turns, 1.).unwrap().score)
+            .collect();
+        let worse = picks.iter().filter(|&&score| score == 0.).count();
+        assert!(worse > 0 && worse < 100);
+    }
+}
@@ -0,0 +1,284 @@
+//! Chess clocks. Each side can start with a different amount of time, so a stronger player
+//! can give a weaker one odds.
+//!
+//! The host sets them up with `--time=10` (ten minutes each) or `--time=10/3` (ten minutes
+//! for the host, three for their opponent), and the opponent hears about it with the
+//! rest of the [rules](crate::rules). An increment goes on the end, like `--time=5+3` for
+//! five minutes each and three more seconds every turn. Nobody's clock runs until the
+//! first move is made.
+//!
+//! Each player's own clock is the one that counts for them. At the start of every turn,
+//! whoever's turn it is tells the other how much time they have left, so the two
+//! computers never drift apart by more than the time a turn takes to cross the network.
+
+use std::time::{Duration, Instant};
+
+use ggez::{GameError, GameResult};
+use rotchess_core::piece::Side;
+
+pub struct ChessClock {
+    white: Duration,
+    black: Duration,
+    /// What a side gets back every time it finishes a turn.
+    increment: Duration,
+    /// Whose clock is running, and since when it was last brought up to date.
+    running: Option<(Side, Instant)>,
+    /// Who ran out of time, if anyone has. The clock stops for good once someone does.
+    flagged: Option<Side>,
+}
+
+impl ChessClock {
+    pub fn new(white: Duration, black: Duration) -> Self {
+        Self {
+            white,
+            black,
+            increment: Duration::ZERO,
+            running: None,
+            flagged: None,
+        }
+    }
+
+    /// The same clock, giving back `increment` every turn.
+    pub fn with_increment(self, increment: Duration) -> Self {
+        Self { increment, ..self }
+    }
+
+    /// The time a side has left, as of `now`.
+    pub fn remaining(&self, side: Side, now: Instant) -> Duration {
+        let banked = match side {
+            Side::White => self.white,
+            Side::Black => self.black,
+        };
+        match self.running {
+            Some((running, since)) if running == side => {
+                banked.saturating_sub(now.saturating_duration_since(since))
+            }
+            _ => banked,
+        }
+    }
+
+    /// Whose clock is running, if anyone's.
+    pub fn running(&self) -> Option<Side> {
+        self.running.map(|(side, _)| side)
+    }
+
+    pub fn flagged(&self) -> Option<Side> {
+        self.flagged
+    }
+
+    /// Run some side's clock from `now`, or nobody's, stopping whichever was running.
+    pub fn run(&mut self, side: Option<Side>, now: Instant) {
+        if self.flagged.is_some() || self.running() == side {
+            return;
+        }
+        if let Some((running, _)) = self.running {
+            let mut left = self.remaining(running, now);
+            if side.is_some() {
+                // their turn's over, and someone else's is starting.
+                left += self.increment;
+            }
+            *self.bank(running) = left;
+        }
+        self.running = side.map(|side| (side, now));
+    }
+
+    /// Take the other computer's word for how much time a side has left as of `now`.
+    pub fn sync(&mut self, side: Side, remaining: Duration, now: Instant) {
+        if self.flagged.is_some() {
+            return;
+        }
+        *self.bank(side) = remaining;
+        if let Some((running, since)) = &mut self.running
+            && *running == side
+        {
+            *since = now;
+        }
+    }
+
+    fn bank(&mut self, side: Side) -> &mut Duration {
+        match side {
+            Side::White => &mut self.white,
+            Side::Black => &mut self.black,
+        }
+    }
+
+    /// Check whether the running side is out of time. If so, they lose on time, and the
+    /// clock stops.
+    pub fn check_flag(&mut self, now: Instant) -> Option<Side> {
+        if let Some(side) = self.running()
+            && self.remaining(side, now).is_zero()
+        {
+            self.run(None, now);
+            self.flagged = Some(side);
+        }
+        self.flagged
+    }
+}
+
+/// Show a time left as minutes and seconds, rounding up so nobody sees 0:00 with time left.
+pub fn format_clock(time: Duration) -> String {
+    let secs = time.as_secs() + u64::from(time.subsec_nanos() > 0);
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Parse a time control like `10` (minutes each) or `10/3` (the host's minutes, then
+/// their opponent's) into the host's time and their opponent's, rounded to the second.
+pub fn parse_time_control(text: &str) -> Option<(Duration, Duration)> {
+    let minutes = |text: &str| {
+        let minutes: f64 = text.trim().parse().ok()?;
+        let secs = (minutes * 60.).round();
+        (secs.is_finite() && secs >= 1. && secs <= u32::MAX as f64)
+            .then(|| Duration::from_secs(secs as u64))
+    };
+    match text.split_once('/') {
+        Some((mine, theirs)) => Some((minutes(mine)?, minutes(theirs)?)),
+        None => minutes(text).map(|time| (time, time)),
+    }
+}
+
+/// Split the increment, in whole seconds, off the end of a time control like `5+3`. With no
+/// `+`, there's no increment.
+pub fn parse_increment(text: &str) -> Option<(&str, Duration)> {
+    match text.split_once('+') {
+        Some((base, increment)) => {
+            let secs: u16 = increment.trim().parse().ok()?;
+            Some((base, Duration::from_secs(secs.into())))
+        }
+        None => Some((text, Duration::ZERO)),
+    }
+}
+
+/// The time control from `--time=...` (or `--time ...`), if there is one: the host's time,
+/// their opponent's, and the increment. See [`parse_time_control`] and [`parse_increment`].
+pub fn time_control_from_args() -> GameResult<Option<(Duration, Duration, Duration)>> {
+    let args: Vec<String> = std::env::args().collect();
+    let time = args
+        .iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg.split_once("=") {
+            Some(("--time", time)) => Some(time),
+            _ if arg == "--time" => args.get(i + 1).map(String::as_str),
+            _ => None,
+        });
+    let Some(time) = time else {
+        return Ok(None);
+    };
+    parse_increment(time)
+        .and_then(|(base, increment)| {
+            let (mine, theirs) = parse_time_control(base)?;
+            Some((mine, theirs, increment))
+        })
+        .map(Some)
+        .ok_or_else(|| {
+            GameError::CustomError(format!(
+                "Bad time control {time:?}. Try --time=10, --time=10/3 for odds, or \
+                --time=5+3 for three seconds more each turn."
+            ))
+        })
+}
+
+#[cfg(test)]
+mod test_clock {
+    use super::*;
+    use parameterized::parameterized;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn only_the_running_side_loses_time() {
+        let start = Instant::now();
+        let mut clock = ChessClock::new(10 * MINUTE, 3 * MINUTE);
+        clock.run(Some(Side::White), start);
+        assert_eq!(clock.remaining(Side::White, start + MINUTE), 9 * MINUTE);
+        assert_eq!(clock.remaining(Side::Black, start + MINUTE), 3 * MINUTE);
+
+        clock.run(Some(Side::Black), start + MINUTE);
+        assert_eq!(clock.remaining(Side::White, start + 2 * MINUTE), 9 * MINUTE);
+        assert_eq!(clock.remaining(Side::Black, start + 2 * MINUTE), 2 * MINUTE);
+    }
+
+    #[test]
+    fn running_out_is_final() {
+        let start = Instant::now();
+        let mut clock = ChessClock::new(10 * MINUTE, 3 * MINUTE);
+        clock.run(Some(Side::Black), start);
+        assert_eq!(clock.check_flag(start + MINUTE), None);
+        assert_eq!(clock.check_flag(start + 3 * MINUTE), Some(Side::Black));
+
+        clock.run(Some(Side::White), start + 3 * MINUTE);
+        assert_eq!(clock.running(), None);
+        assert_eq!(
+            clock.remaining(Side::White, start + 4 * MINUTE),
+            10 * MINUTE
+        );
+    }
+
+    #[parameterized(text = { "10", "10/3", " 5 / 0.5 " }, expected = {
+        (600, 600), (600, 180), (300, 30)
+    })]
+    fn time_controls_parse(text: &str, expected: (u64, u64)) {
+        let (mine, theirs) = expected;
+        assert_eq!(
+            parse_time_control(text),
+            Some((Duration::from_secs(mine), Duration::from_secs(theirs)))
+        );
+    }
+
+    #[parameterized(text = { "", "ten", "10/", "0", "-1", "10/3/1", "inf" })]
+    fn bad_time_controls_dont_parse(text: &str) {
+        assert_eq!(parse_time_control(text), None);
+    }
+
+    #[test]
+    fn finishing_a_turn_earns_the_increment() {
+        let start = Instant::now();
+        let mut clock = ChessClock::new(5 * MINUTE, 5 * MINUTE).with_increment(SECOND * 3);
+        clock.run(Some(Side::White), start);
+        clock.run(Some(Side::Black), start + MINUTE);
+        assert_eq!(
+            clock.remaining(Side::White, start + MINUTE),
+            4 * MINUTE + 3 * SECOND
+        );
+        // stopping the clock isn't finishing a turn.
+        clock.run(None, start + 2 * MINUTE);
+        assert_eq!(clock.remaining(Side::Black, start + 2 * MINUTE), 4 * MINUTE);
+    }
+
+    #[test]
+    fn syncing_takes_the_other_clocks_word() {
+        let start = Instant::now();
+        let mut clock = ChessClock::new(5 * MINUTE, 5 * MINUTE);
+        clock.run(Some(Side::Black), start);
+        clock.sync(Side::Black, 4 * MINUTE, start + SECOND);
+        assert_eq!(
+            clock.remaining(Side::Black, start + MINUTE),
+            3 * MINUTE + SECOND
+        );
+        clock.sync(Side::White, MINUTE, start + MINUTE);
+        assert_eq!(clock.remaining(Side::White, start + 2 * MINUTE), MINUTE);
+    }
+
+    #[parameterized(text = { "5", "5+3", "10/3+0", " 5 + 10 " }, expected = {
+        ("5", 0), ("5", 3), ("10/3", 0), (" 5 ", 10)
+    })]
+    fn increments_split_off(text: &str, expected: (&str, u64)) {
+        let (base, secs) = expected;
+        assert_eq!(
+            parse_increment(text),
+            Some((base, Duration::from_secs(secs)))
+        );
+    }
+
+    #[parameterized(text = { "5+", "5+x", "5+-1", "5+1.5" })]
+    fn bad_increments_dont_parse(text: &str) {
+        assert_eq!(parse_increment(text), None);
+    }
+
+    #[test]
+    fn clocks_round_up() {
+        assert_eq!(format_clock(Duration::from_millis(59_001)), "1:00");
+        assert_eq!(format_clock(Duration::from_secs(600)), "10:00");
+        assert_eq!(format_clock(Duration::ZERO), "0:00");
+    }
+}
@@ -0,0 +1,318 @@
+//! Pitting two bot personalities against each other over many headless games, to see which
+//! plays better when tuning the engine.
+//!
+//! Run with `cargo run -- compare [games] [--a=...] [--b=...] [--turns=n] [--seed=n]
+//! [--chess960]`. A personality is given as percentages for aggressiveness, rotation
+//! happiness and temperature, like `--a=80,50,0`, and defaults to [`Personality::default`].
+//! The two take turns playing white, each pair of games starting from the same board.
+//!
+//! Games end the way [simulated](crate::simulate) ones do, with a side that has nowhere to
+//! go counted as a draw. A's score is its wins plus half its draws, over every game, given
+//! with a 95% confidence interval so it's clear when a difference is only luck.
+
+use ggez::{GameError, GameResult};
+use rotchess_core::{
+    RotchessEmulator,
+    emulator::Event,
+    piece::{PieceKind, Side},
+};
+
+use crate::{
+    bot::{Bot, MAX_TEMPERATURE},
+    engine::{Personality, Search},
+    notation,
+    simulate::{self, DEFAULT_MAX_TURNS, Ending},
+};
+
+/// How many games to play if `compare` isn't told.
+pub const DEFAULT_GAMES: usize = 20;
+
+/// How many standard errors a 95% confidence interval reaches either side of a score.
+const Z_95: f64 = 1.96;
+
+/// A match between two personalities, A and B.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub games: usize,
+    pub max_turns: usize,
+    /// The seed for the first pair of games. Each pair after it gets the next one.
+    pub seed: u64,
+    pub chess960: bool,
+    pub a: Personality,
+    pub b: Personality,
+}
+
+/// How a match has gone so far, from A's side.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+}
+
+impl Tally {
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+
+    /// A's wins plus half its draws, as a fraction of the games played.
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.) / self.games() as f64
+    }
+
+    /// The 95% confidence interval of A's score, by the Wilson score interval, which
+    /// behaves itself even for scores near 0 or 1.
+    pub fn interval(&self) -> (f64, f64) {
+        let n = self.games() as f64;
+        if n == 0. {
+            return (0., 1.);
+        }
+        let p = self.score();
+        let z2 = Z_95 * Z_95;
+        let center = (p + z2 / (2. * n)) / (1. + z2 / n);
+        let half = Z_95 / (1. + z2 / n) * (p * (1. - p) / n + z2 / (4. * n * n)).sqrt();
+        ((center - half).max(0.), (center + half).min(1.))
+    }
+}
+
+impl Comparison {
+    /// The comparison asked for on the command line, or `None` if we shouldn't compare.
+    pub fn from_args() -> Option<GameResult<Self>> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    fn parse(args: impl Iterator<Item = String>) -> Option<GameResult<Self>> {
+        let mut args = args.skip_while(|arg| arg != "compare");
+        args.next()?;
+        let mut comparison = Self {
+            games: DEFAULT_GAMES,
+            max_turns: DEFAULT_MAX_TURNS,
+            seed: rand::random(),
+            chess960: false,
+            a: Personality::default(),
+            b: Personality::default(),
+        };
+        let bad = |arg: &str| {
+            Some(Err(GameError::CustomError(format!(
+                "Bad compare argument {arg:?}. Try compare 100 --a=80,50,0 --b=20,50,0 \
+                 --turns=50 --seed=7 --chess960."
+            ))))
+        };
+        for arg in args {
+            let parsed = match arg.split_once('=') {
+                Some(("--a", knobs)) => parse_personality(knobs).map(|p| comparison.a = p),
+                Some(("--b", knobs)) => parse_personality(knobs).map(|p| comparison.b = p),
+                Some(("--turns", n)) => n.parse().map(|n| comparison.max_turns = n).ok(),
+                Some(("--seed", n)) => n.parse().map(|n| comparison.seed = n).ok(),
+                None if arg == "--chess960" => {
+                    comparison.chess960 = true;
+                    Some(())
+                }
+                None => arg.parse().map(|n| comparison.games = n).ok(),
+                Some(_) => None,
+            };
+            if parsed.is_none() {
+                return bad(&arg);
+            }
+        }
+        Some(Ok(comparison))
+    }
+
+    /// Play every game, then say how A did against B.
+    pub fn run(&self) -> GameResult {
+        let mut tally = Tally::default();
+        for i in 0..self.games {
+            // each pair of games starts from the same board, with the colors swapped.
+            let board_seed = self.seed.wrapping_add((i / 2) as u64);
+            let a_side = if i.is_multiple_of(2) {
+                Side::White
+            } else {
+                Side::Black
+            };
+            let personalities = match a_side {
+                Side::White => [self.a, self.b],
+                Side::Black => [self.b, self.a],
+            };
+            let (pieces, _) = simulate::starting_board(board_seed, self.chess960);
+            let ending = play_game(
+                RotchessEmulator::with(pieces),
+                self.seed.wrapping_add(i as u64),
+                self.max_turns,
+                personalities,
+            );
+            println!(
+                "game {i} (seed {board_seed}, a plays {}): {}",
+                notation::side_name(a_side),
+                simulate::describe_ending(ending)
+            );
+            match ending {
+                Ending::KingTaken { winner } if winner == a_side => tally.wins += 1,
+                Ending::KingTaken { .. } => tally.losses += 1,
+                Ending::Stuck(_) | Ending::TurnLimit => tally.draws += 1,
+            }
+        }
+        let (low, high) = tally.interval();
+        println!(
+            "a won {}, b won {}, {} drawn. a scored {:.0}% (95% confidence: {:.0}% to {:.0}%)",
+            tally.wins,
+            tally.losses,
+            tally.draws,
+            tally.score() * 100.,
+            low * 100.,
+            high * 100.
+        );
+        Ok(())
+    }
+}
+
+/// A personality from its knobs as percentages, like `80,50,0`.
+pub fn parse_personality(text: &str) -> Option<Personality> {
+    let knobs: Vec<f32> = text
+        .split(',')
+        .map(|knob| {
+            let percent: f32 = knob.trim().parse().ok()?;
+            (0. ..=100.).contains(&percent).then_some(percent / 100.)
+        })
+        .collect::<Option<_>>()?;
+    let [aggressiveness, rotation_happiness, temperature] = knobs[..] else {
+        return None;
+    };
+    Some(Personality {
+        aggressiveness,
+        rotation_happiness,
+        temperature,
+    })
+}
+
+/// Play a game on `chess` between white and black playing as `personalities`, the same way
+/// every time for the same seed.
+pub fn play_game(
+    mut chess: RotchessEmulator,
+    seed: u64,
+    max_turns: usize,
+    personalities: [Personality; 2],
+) -> Ending {
+    let mut bots = [Bot::new(seed), Bot::new(seed.wrapping_add(1))];
+    for turn in 0..max_turns {
+        let side = if turn.is_multiple_of(2) {
+            Side::White
+        } else {
+            Side::Black
+        };
+        let personality = personalities[turn % 2];
+
+        let mut search = Search::new(chess, side, personality);
+        while !search.done() {
+            search.step(usize::MAX);
+        }
+        let choice =
+            bots[turn % 2].choose_turn(search.scored(), personality.temperature * MAX_TEMPERATURE);
+        chess = search.into_chess();
+        let Some(choice) = choice else {
+            return Ending::Stuck(side);
+        };
+
+        let before = notation::snapshot(chess.pieces());
+        let (x, y) = choice.to;
+        chess.handle_event(Event::MoveUnchecked(choice.piece_idx, x, y));
+        if notation::captured_by(&before, choice.piece_idx, x, y)
+            .is_some_and(|captured| captured.kind == PieceKind::King)
+        {
+            return Ending::KingTaken { winner: side };
+        }
+        chess.handle_event(Event::RotateUnchecked(choice.piece_idx, choice.angle));
+    }
+    Ending::TurnLimit
+}
+
+#[cfg(test)]
+mod test_compare {
+    use rotchess_core::piece::Pieces;
+
+    use super::*;
+
+    fn parse(args: &str) -> Option<GameResult<Comparison>> {
+        Comparison::parse(args.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn only_compares_when_asked() {
+        assert!(parse("simulate").is_none());
+        assert!(parse("").is_none());
+    }
+
+    #[test]
+    fn everything_can_be_set() {
+        let comparison =
+            parse("compare 50 --a=100,0,0 --b=0,100,50 --turns=20 --seed=9 --chess960")
+                .unwrap()
+                .unwrap();
+        assert_eq!(
+            comparison,
+            Comparison {
+                games: 50,
+                max_turns: 20,
+                seed: 9,
+                chess960: true,
+                a: Personality {
+                    aggressiveness: 1.,
+                    rotation_happiness: 0.,
+                    temperature: 0.,
+                },
+                b: Personality {
+                    aggressiveness: 0.,
+                    rotation_happiness: 1.,
+                    temperature: 0.5,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn bad_arguments_are_errors() {
+        assert!(parse("compare lots").unwrap().is_err());
+        assert!(parse("compare --a=50,50").unwrap().is_err());
+        assert!(parse("compare --b=50,50,150").unwrap().is_err());
+        assert!(parse("compare --c=50,50,50").unwrap().is_err());
+    }
+
+    #[test]
+    fn intervals_hold_the_score_and_narrow_with_more_games() {
+        let few = Tally {
+            wins: 6,
+            losses: 3,
+            draws: 1,
+        };
+        let many = Tally {
+            wins: 600,
+            losses: 300,
+            draws: 100,
+        };
+        assert_eq!(few.score(), many.score());
+        let (low, high) = few.interval();
+        assert!(low < few.score() && few.score() < high);
+        let (many_low, many_high) = many.interval();
+        assert!(low < many_low && many_high < high);
+    }
+
+    #[test]
+    fn intervals_stay_between_nothing_and_everything() {
+        let sweep = Tally {
+            wins: 10,
+            ..Tally::default()
+        };
+        let (low, high) = sweep.interval();
+        assert!(low > 0.5 && high <= 1.);
+        assert_eq!(Tally::default().interval(), (0., 1.));
+    }
+
+    #[test]
+    fn games_stop_at_the_turn_limit() {
+        let chess = RotchessEmulator::with(Pieces::standard_board());
+        let ending = play_game(chess, 0, 2, [Personality::NEUTRAL; 2]);
+        assert_eq!(ending, Ending::TurnLimit);
+    }
+}
@@ -1,22 +1,122 @@
+use std::time::Duration;
+
 use ggez::graphics::Color;
 
 /// Generically acceptable tolerance for e.g. [`ggez::graphics::Mesh::new_circle`].
 pub const CIRC_TOLERANCE: f32 = 0.1;
 
-pub const DARK_TILE_COLOR: Color = Color::new(0.70980, 0.53333, 0.38824, 1.00000);
-pub const LIGHT_TILE_COLOR: Color = Color::new(0.94118, 0.85098, 0.70980, 1.00000);
-pub const BACKGROUND_COLOR: Color = Color::new(0.90196, 0.90196, 0.90196, 1.00000);
-
-/// yellowish
-pub const SELECTED_PIECE_COLOR: Color = Color::new(1.00000, 1.00000, 0.60000, 0.78431);
-/// cyanish
-pub const MOVE_OUTLINE_COLOR: Color = Color::new(0.67843, 1.00000, 0.95686, 1.00000);
-pub const MOVE_HIGHLIGHT_COLOR: Color = Color::new(0.67843, 1.00000, 0.95686, 0.78431);
-/// red
-pub const CAPTURE_OUTLINE_COLOR: Color = Color::new(1.00000, 0.00000, 0.00000, 1.00000);
-pub const CAPTURE_HIGHLIGHT_COLOR: Color = Color::new(1.00000, 0.00000, 0.00000, 0.78431);
+// the board's own colors (tiles, background, highlights, dialogs) come from the theme. see
+// `crate::theme`.
+
+/// translucent white, for the travel point shape drawn over a hovered travel point
+pub const HOVERED_INDICATOR_COLOR: Color = Color::new(1.00000, 1.00000, 1.00000, 0.78431);
+/// grey, for travel points you can't use right now
+pub const DISABLED_OUTLINE_COLOR: Color = Color::new(0.50196, 0.50196, 0.50196, 1.00000);
+pub const DISABLED_HIGHLIGHT_COLOR: Color = Color::new(0.50196, 0.50196, 0.50196, 0.78431);
+/// orangeish, ringed around a piece waiting to be rotated
+pub const ROTATE_AFFORDANCE_COLOR: Color = Color::new(1.00000, 0.64706, 0.00000, 0.78431);
+/// faint white, for the angles a snapped rotation could land on
+pub const SNAP_TICK_COLOR: Color = Color::new(1.00000, 1.00000, 1.00000, 0.47059);
+/// washed out, for our pieces while it's not our turn
+pub const WAITING_PIECE_TINT: Color = Color::new(0.70000, 0.70000, 0.70000, 0.60000);
 /// springgreen
 pub const HITCIRCLE_COLOR: Color = Color::new(0.00000, 1.00000, 0.49804, 1.00000);
+/// translucent springgreen, for where a move from a travel point would capture, in the hit
+/// circle layer
+pub const CAPTURE_REACH_COLOR: Color = Color::new(0.00000, 1.00000, 0.49804, 0.39216);
+/// red, ringed around pieces whose hit circles overlap
+pub const OVERLAP_WARNING_COLOR: Color = Color::new(1.00000, 0.00000, 0.00000, 1.00000);
+
+/// translucent black, for banners drawn over the board
+pub const BANNER_COLOR: Color = Color::new(0.00000, 0.00000, 0.00000, 0.62745);
+pub const BANNER_TEXT_COLOR: Color = Color::WHITE;
+/// Font size in pixels
+pub const BANNER_TEXT_SIZE: f32 = 24.;
+/// Font size in pixels of the chess clocks, which sit on banner colored boxes.
+pub const CLOCK_TEXT_SIZE: f32 = 20.;
+/// gray, for the clock that isn't running
+pub const IDLE_CLOCK_TEXT_COLOR: Color = Color::new(0.62745, 0.62745, 0.62745, 1.00000);
+/// Font size in pixels of the lines on the ticker, which share the banners' colors.
+pub const TICKER_TEXT_SIZE: f32 = 16.;
+/// Font size in pixels of toasts, which share the banners' colors too.
+pub const NOTIFICATION_TEXT_SIZE: f32 = 20.;
+
+/// translucent black, dims the board behind a dialog
+pub const DIALOG_BACKDROP_COLOR: Color = Color::new(0.00000, 0.00000, 0.00000, 0.39216);
+/// translucent black, behind how a game ended, so the final position shows through
+pub const RESULT_BANNER_COLOR: Color = Color::new(0.00000, 0.00000, 0.00000, 0.47059);
+
+/// dark green, for arrows and circles drawn over the board
+pub const ANNOTATION_COLOR: Color = Color::new(0.08235, 0.47059, 0.10588, 0.78431);
+/// How long the ghost of a rotated piece at its old angle takes to fade away.
+pub const ROTATION_GHOST_DURATION: Duration = Duration::from_millis(1500);
+/// How opaque a rotation ghost is when it appears.
+pub const ROTATION_GHOST_OPACITY: f32 = 0.5;
+/// How opaque the preview of a rotation turned out with the keys or scroll wheel is.
+pub const ROTATION_DRAFT_OPACITY: f32 = 0.6;
+
+/// dark blue, for the path of a piece looked back on
+pub const PIECE_HISTORY_COLOR: Color = Color::new(0.09804, 0.18824, 0.58824, 0.86275);
+
+/// purple, for the turn the engine suggests in analysis mode
+pub const ENGINE_ARROW_COLOR: Color = Color::new(0.50196, 0.00000, 0.50196, 0.78431);
+/// How far from its center, in rotchess units, the arc showing a suggested rotation is.
+pub const ENGINE_ARC_RADIUS: f32 = 0.45;
+/// How many straight lines the arc of a suggested rotation is drawn with.
+pub const ENGINE_ARC_SEGMENTS: usize = 16;
+/// How many turns the engine looks at each frame in analysis mode. More finds the best
+/// turn sooner, at the cost of frame rate.
+pub const ENGINE_TURNS_PER_FRAME: usize = 16;
+
+/// How long the line from where a piece moved from takes to fade away.
+pub const MOVE_TRAIL_DURATION: Duration = Duration::from_secs(3);
+/// Width of a move trail, in pixels.
+pub const MOVE_TRAIL_WIDTH: f32 = 4.;
+/// yellowish, the color of a move trail when it appears
+pub const MOVE_TRAIL_COLOR: Color = Color::new(1.00000, 0.84314, 0.00000, 0.70588);
+
+/// How far out the board can be zoomed, to see where pieces reach off of it.
+pub const MIN_ZOOM: f32 = 0.25;
+/// How far in the board can be zoomed.
+pub const MAX_ZOOM: f32 = 4.;
+/// How much one notch of the scroll wheel zooms in.
+pub const ZOOM_PER_SCROLL: f32 = 1.15;
+
+/// How long a piece takes to slide or turn into place after a move or rotation.
+pub const PIECE_ANIMATION_DURATION: Duration = Duration::from_millis(200);
+
+/// How long the red flash where a click wasn't allowed takes to fade away.
+pub const REJECTED_CLICK_DURATION: Duration = Duration::from_millis(400);
+/// How far a rejected click's flash shakes to either side, in rotchess units.
+pub const REJECTED_CLICK_SHAKE: f32 = 0.08;
+/// red, the color of a rejected click's flash when it appears
+pub const REJECTED_CLICK_COLOR: Color = Color::new(0.86275, 0.07843, 0.07843, 0.78431);
+/// How long the HUD's turn phase flashes after something is done out of turn.
+pub const PHASE_WARNING_DURATION: Duration = Duration::from_millis(800);
+/// red, what the HUD's turn phase flashes to after something is done out of turn
+pub const PHASE_WARNING_COLOR: Color = Color::new(1.00000, 0.23529, 0.23529, 1.00000);
+
+/// How far, in rotchess units, a right-drag has to go before it draws an arrow
+/// instead of a circle.
+pub const ANNOTATION_DRAG_THRESHOLD: f32 = 0.2;
+
+/// Height of the history scrubber under the board, in pixels.
+pub const SCRUBBER_HEIGHT: f32 = 30.;
+pub const SCRUBBER_TRACK_WIDTH: f32 = 4.;
+/// Width and height, in pixels, of the board thumbnails shown over the scrubber.
+pub const THUMBNAIL_SIZE: u32 = 160;
+/// Gap, in pixels, between a thumbnail and the scrubber under it.
+pub const THUMBNAIL_MARGIN: f32 = 8.;
+/// Width and height, in pixels, of exported diagrams. Big enough to read, small enough
+/// for a forum post.
+pub const DIAGRAM_SIZE: u32 = 800;
+
+/// orange, for the bars of the profiler overlay
+pub const PROFILER_BAR_COLOR: Color = Color::new(1.00000, 0.54902, 0.00000, 0.78431);
+/// Font size in pixels
+pub const PROFILER_TEXT_SIZE: f32 = 14.;
+/// How long a profiler bar is per millisecond, in pixels.
+pub const PROFILER_PX_PER_MS: f32 = 40.;
 
 /// Size of window in pixels
 pub const STARTING_WINDOW_SIZE: f32 = 800.;
@@ -26,7 +126,40 @@ pub const STARTING_WINDOW_SIZE: f32 = 800.;
 /// Yes, it's square.
 pub const PIECE_PNG_SIZE_PX: u32 = 200;
 
-/// Bytes that can represent a chess turn or ThingHappened.
+/// Bytes that can represent a whole chess turn (a move and its rotation) or ThingHappened.
 ///
-/// Defined exactly as `1 + size_of::<u8>() + size_of::<f32>() + size_of::<f32>()`.
-pub const TURN_SIZE: usize = 1 + size_of::<u8>() + size_of::<f32>() + size_of::<f32>();
+/// Defined exactly as `1 + size_of::<u16>() + 3 * size_of::<f32>()`: a tag, a piece, where
+/// it moved, and its new angle.
+pub const TURN_SIZE: usize = 1 + size_of::<u16>() + 3 * size_of::<f32>();
+
+/// How much of the way to its new size the board grows (or shrinks) each frame while the
+/// window is being resized.
+pub const RESIZE_EASING: f32 = 0.3;
+
+/// How long the window has to keep the same size before we rebuild cached meshes for it.
+pub const RESIZE_SETTLE_DELAY: Duration = Duration::from_millis(150);
+
+/// How long a repeating key (like V, to turn the view) has to be held before it starts
+/// repeating.
+pub const KEY_REPEAT_DELAY: Duration = Duration::from_millis(400);
+/// How often a held repeating key fires once it's repeating.
+pub const KEY_REPEAT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long a player can sit on their turn without touching anything before
+/// we tell their opponent they're away.
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(3 * 60);
+
+/// How often at most we tell the opponent what we're up to on our turn. Each update
+/// costs a round trip, during which our clicks don't count.
+pub const ACTIVITY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often we hand the turn over once a networked game is over, so either player can ask
+/// for a rematch. Like any turn, each one is a round trip.
+pub const GAME_OVER_PASS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a kiosk sits untouched before it starts playing a demo game.
+pub const KIOSK_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long we let a player look at a past position on their turn before
+/// bringing them back to the live one.
+pub const SNAP_BACK_DELAY: Duration = Duration::from_secs(10);
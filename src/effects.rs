@@ -0,0 +1,254 @@
+//! Short-lived things drawn over the board after something happens, fading away so
+//! nobody misses what just changed.
+
+use std::{
+    f32::consts::{PI, TAU},
+    time::{Duration, Instant},
+};
+
+use ggez::graphics::Color;
+
+/// Something that fades out over a while after it starts.
+#[derive(Debug, Clone, Copy)]
+pub struct Fade {
+    started: Instant,
+    duration: Duration,
+}
+
+impl Fade {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            started: Instant::now(),
+            duration,
+        }
+    }
+
+    /// How visible it is at some point in time, from 1 when it starts to 0 when it's done.
+    pub fn opacity(&self, now: Instant) -> f32 {
+        let elapsed = now.saturating_duration_since(self.started);
+        1. - (elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.)
+    }
+
+    pub fn is_done(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.started) >= self.duration
+    }
+
+    /// Like [`Fade::opacity`], unless motion is reduced (see
+    /// [`Settings::reduced_motion`](crate::settings::Settings::reduced_motion)): then it stays
+    /// fully visible, and is gone all at once when it's done.
+    pub fn visibility(&self, now: Instant, reduced_motion: bool) -> f32 {
+        match (reduced_motion, self.is_done(now)) {
+            (false, _) => self.opacity(now),
+            (true, false) => 1.,
+            (true, true) => 0.,
+        }
+    }
+}
+
+/// A piece as it was before a rotation, so the size of the rotation is easy to see.
+#[derive(Debug, Clone, Copy)]
+pub struct RotationGhost {
+    pub piece_idx: usize,
+    /// The angle the piece had before it was rotated.
+    pub angle: f32,
+    /// The [`History::current`](crate::history::History::current) right after the
+    /// rotation. The ghost only makes sense on that board.
+    pub turn: usize,
+    pub fade: Fade,
+}
+
+/// A line from where a piece was to where it moved, so moves don't look like teleports.
+#[derive(Debug, Clone, Copy)]
+pub struct MoveTrail {
+    /// Where the piece moved from, in rotchess units.
+    pub from: (f32, f32),
+    /// Where the piece moved to, in rotchess units.
+    pub to: (f32, f32),
+    /// The [`History::current`](crate::history::History::current) right after the move.
+    pub turn: usize,
+    pub fade: Fade,
+}
+
+/// A piece sliding and turning from how it was to how it is, so moves and rotations don't
+/// happen all at once.
+///
+/// Unlike the fading effects, animations run on frame time (see [`PieceAnimation::step`]),
+/// so a frame that takes long doesn't skip most of one.
+#[derive(Debug, Clone, Copy)]
+pub struct PieceAnimation {
+    /// Where the piece was, in rotchess units, and its angle.
+    pub from: (f32, f32, f32),
+    /// Where the piece is now, in rotchess units, and its angle.
+    ///
+    /// The piece is found by where it is rather than by index, since a capture shuffles the
+    /// indices of the pieces after it.
+    pub to: (f32, f32, f32),
+    /// The [`History::current`](crate::history::History::current) right after the action.
+    pub turn: usize,
+    elapsed: Duration,
+    duration: Duration,
+}
+
+impl PieceAnimation {
+    pub fn new(
+        from: (f32, f32, f32),
+        to: (f32, f32, f32),
+        turn: usize,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            from,
+            to,
+            turn,
+            elapsed: Duration::ZERO,
+            duration,
+        }
+    }
+
+    /// Move the animation along by a frame's worth of time.
+    pub fn step(&mut self, delta: Duration) {
+        self.elapsed += delta;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Whether it's animating the piece that's now at some point.
+    pub fn is_at(&self, x: f32, y: f32) -> bool {
+        (self.to.0 - x).hypot(self.to.1 - y) < 1e-3
+    }
+
+    /// Where to draw the piece right now, and at what angle. Rotations go the short way
+    /// around.
+    pub fn pose(&self) -> (f32, f32, f32) {
+        let t = (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.);
+        // ease out, so the piece settles into place rather than stopping dead.
+        let t = 1. - (1. - t).powi(3);
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        let turned = (self.to.2 - self.from.2 + PI).rem_euclid(TAU) - PI;
+        (
+            lerp(self.from.0, self.to.0),
+            lerp(self.from.1, self.to.1),
+            self.from.2 + turned * t,
+        )
+    }
+}
+
+/// A red flash where a click didn't do anything because it wasn't allowed, so it doesn't
+/// look like the click got lost.
+#[derive(Debug, Clone, Copy)]
+pub struct RejectedClick {
+    /// Where the click was, in rotchess units.
+    pub at: (f32, f32),
+    pub fade: Fade,
+}
+
+impl RejectedClick {
+    /// How many times the flash shakes side to side before it's gone.
+    const SHAKES: f32 = 3.;
+
+    /// How far to the side to draw the flash, from -1 to 1 of its widest shake. The shake
+    /// dies down as the flash fades, and ends where it started.
+    pub fn shake(&self, now: Instant) -> f32 {
+        let left = self.fade.opacity(now);
+        left * (left * Self::SHAKES * TAU).sin()
+    }
+}
+
+/// Some of the way from one color to another, from 0 for all of `from` to 1 for all of `to`,
+/// say to flash something that's fading back to normal.
+pub fn tint(from: Color, to: Color, amount: f32) -> Color {
+    let lerp = |a: f32, b: f32| a + (b - a) * amount;
+    Color::new(
+        lerp(from.r, to.r),
+        lerp(from.g, to.g),
+        lerp(from.b, to.b),
+        lerp(from.a, to.a),
+    )
+}
+
+#[cfg(test)]
+mod test_fade {
+    use super::*;
+
+    #[test]
+    fn fades_out_over_its_duration() {
+        let fade = Fade::new(Duration::from_secs(2));
+        let start = fade.started;
+        assert_eq!(fade.opacity(start), 1.);
+        assert_eq!(fade.opacity(start + Duration::from_secs(1)), 0.5);
+        assert!(!fade.is_done(start + Duration::from_secs(1)));
+        assert_eq!(fade.opacity(start + Duration::from_secs(3)), 0.);
+        assert!(fade.is_done(start + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn reduced_motion_doesnt_fade() {
+        let fade = Fade::new(Duration::from_secs(2));
+        let start = fade.started;
+        assert_eq!(fade.visibility(start + Duration::from_secs(1), false), 0.5);
+        assert_eq!(fade.visibility(start + Duration::from_secs(1), true), 1.);
+        assert_eq!(fade.visibility(start + Duration::from_secs(2), true), 0.);
+    }
+
+    #[test]
+    fn animations_end_where_the_piece_is() {
+        let mut animation = PieceAnimation::new(
+            (0.5, 0.5, 0.),
+            (2.5, 4.5, 1.),
+            1,
+            Duration::from_millis(200),
+        );
+        assert_eq!(animation.pose(), (0.5, 0.5, 0.));
+        animation.step(Duration::from_millis(100));
+        let (x, y, _) = animation.pose();
+        assert!(0.5 < x && x < 2.5 && 0.5 < y && y < 4.5);
+        assert!(!animation.is_done());
+        animation.step(Duration::from_millis(150));
+        assert!(animation.is_done());
+        let (x, y, angle) = animation.pose();
+        assert_eq!((x, y), (2.5, 4.5));
+        assert!((angle - 1.).abs() < 1e-5);
+        assert!(animation.is_at(2.5, 4.5));
+        assert!(!animation.is_at(0.5, 0.5));
+    }
+
+    #[test]
+    fn rotations_animate_the_short_way_around() {
+        let mut animation = PieceAnimation::new(
+            (0., 0., 0.1),
+            (0., 0., TAU - 0.1),
+            1,
+            Duration::from_millis(200),
+        );
+        animation.step(Duration::from_millis(100));
+        let (_, _, angle) = animation.pose();
+        // from 0.1 to -0.1, rather than most of the way around.
+        assert!((-0.1..0.1).contains(&angle));
+    }
+
+    #[test]
+    fn rejected_clicks_shake_in_place() {
+        let click = RejectedClick {
+            at: (0., 0.),
+            fade: Fade::new(Duration::from_secs(1)),
+        };
+        let start = click.fade.started;
+        assert!(click.shake(start).abs() < 1e-4);
+        assert_eq!(click.shake(start + Duration::from_secs(1)), 0.);
+        for ms in 0..1000 {
+            assert!(click.shake(start + Duration::from_millis(ms)).abs() <= 1.);
+        }
+    }
+
+    #[test]
+    fn tints_go_all_the_way_at_either_end() {
+        assert_eq!(tint(Color::WHITE, Color::RED, 0.), Color::WHITE);
+        assert_eq!(tint(Color::WHITE, Color::RED, 1.), Color::RED);
+        assert_eq!(
+            tint(Color::BLACK, Color::WHITE, 0.5),
+            Color::new(0.5, 0.5, 0.5, 1.)
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! A small search for a good turn, for the suggestion arrows of analysis mode (`n`).
+//!
+//! It looks deeper the longer it's left running:
+//!
+//! 1. Every move is scored by what it takes, keeping the piece's angle.
+//! 2. Every move with every eighth of a turn after it is scored by what it takes, less the
+//!    most the other side could take back right away. This takes a while, so it's done a
+//!    few turns at a time, between frames.
+//!
+//! Scores are material, like [`crate::blunders`], weighed by a [`Personality`] for bots
+//! that play with some character. The best turn so far is always there to show, and only
+//! changes once a whole depth has been looked through.
+
+use std::f32::consts::TAU;
+
+use rotchess_core::{RotchessEmulator, emulator::Event, piece::Side};
+
+use crate::{
+    blunders::{material, piece_value},
+    bot::{self, MoveOption},
+    notation::{self, PieceSnapshot},
+};
+
+/// The deepest the search goes. See the module docs for what each depth looks at.
+pub const MAX_DEPTH: u8 = 2;
+/// How much, in pawns, a turn all the way around is worth to a bot that loves rotating, or
+/// costs one that hates it.
+pub const ROTATION_WEIGHT: f32 = 1.;
+
+/// How a bot likes to play, as knobs from 0 to 1, where the engine's own plain scoring is
+/// all of them at a half, and no randomness.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Personality {
+    /// How much taking things matters against keeping our own pieces safe.
+    pub aggressiveness: f32,
+    /// How much the bot likes to give its pieces a good turn.
+    pub rotation_happiness: f32,
+    /// How often the bot plays a turn other than its favorite, with better ones likelier.
+    pub temperature: f32,
+}
+
+impl Personality {
+    /// Scores turns by material alone, and always plays the best. Used for analysis.
+    pub const NEUTRAL: Self = Self {
+        aggressiveness: 0.5,
+        rotation_happiness: 0.5,
+        temperature: 0.,
+    };
+
+    /// How good a turn is that gains some material, risks losing some right back, and turns
+    /// the piece some fraction of the way around.
+    pub fn score(&self, gain: f32, risk: f32, turned: f32) -> f32 {
+        gain * (0.5 + self.aggressiveness) - risk * (1.5 - self.aggressiveness)
+            + (self.rotation_happiness - 0.5) * 2. * ROTATION_WEIGHT * turned
+    }
+}
+
+impl Default for Personality {
+    /// A bot with a bit of unpredictability.
+    fn default() -> Self {
+        Self {
+            temperature: 0.3,
+            ..Self::NEUTRAL
+        }
+    }
+}
+
+/// A turn the search thinks is good.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Suggestion {
+    pub piece_idx: usize,
+    pub from: (f32, f32),
+    pub to: (f32, f32),
+    /// The piece's angle before the turn, and after it.
+    pub from_angle: f32,
+    pub angle: f32,
+    /// How good the turn is, in pawns: how much material we'd be up afterwards, as the
+    /// personality sees it.
+    pub score: f32,
+    /// How deep the search had gone when it found this, or 0 for a turn from the
+    /// [opening book](crate::book).
+    pub depth: u8,
+}
+
+/// A search from one position, for the side to move there.
+pub struct Search {
+    /// A board of the search's own to try turns out on. Each try is taken back after.
+    chess: RotchessEmulator,
+    side: Side,
+    personality: Personality,
+    board: Vec<PieceSnapshot>,
+    /// The turns left to look at for this depth.
+    turns: Vec<(MoveOption, f32)>,
+    /// The depth being searched, or the last one searched once we're done.
+    depth: u8,
+    done: bool,
+    /// Every turn scored at this depth so far, which become `scored` once the depth is done.
+    scored_this_depth: Vec<Suggestion>,
+    scored: Vec<Suggestion>,
+}
+
+impl Search {
+    /// Start looking for a turn for `side` on `chess`, which the search takes over, as a
+    /// bot with some personality would see it.
+    pub fn new(mut chess: RotchessEmulator, side: Side, personality: Personality) -> Self {
+        let board = notation::snapshot(chess.pieces());
+        let options = bot::move_options(&mut chess, side);
+        let mut search = Self {
+            chess,
+            side,
+            personality,
+            board,
+            turns: Vec::new(),
+            depth: 1,
+            done: false,
+            scored_this_depth: Vec::new(),
+            scored: Vec::new(),
+        };
+
+        // depth 1 is quick enough to do all at once.
+        for option in &options {
+            let angle = search.board[option.piece_idx].angle;
+            let gain = notation::captured_by(&search.board, option.piece_idx, option.x, option.y)
+                .map_or(0., |captured| piece_value(captured.kind));
+            let score = material(&search.board, side) + personality.score(gain, 0., 0.);
+            search.consider(*option, angle, score);
+        }
+        search.finish_depth();
+
+        search.depth = 2;
+        search.turns = options
+            .iter()
+            .flat_map(|&option| {
+                let angle = search.board[option.piece_idx].angle;
+                (0..8).map(move |eighths| (option, angle + eighths as f32 * TAU / 8.))
+            })
+            .rev()
+            .collect();
+        search
+    }
+
+    /// The best turn found so far, if there's any turn at all.
+    pub fn best(&self) -> Option<Suggestion> {
+        self.scored
+            .iter()
+            .copied()
+            .reduce(|best, turn| if turn.score > best.score { turn } else { best })
+    }
+
+    /// Every turn the deepest finished depth scored.
+    pub fn scored(&self) -> &[Suggestion] {
+        &self.scored
+    }
+
+    /// Whether the search has gone as deep as it goes.
+    pub fn done(&self) -> bool {
+        self.done
+    }
+
+    /// The board the search was started on, just as it was.
+    pub fn into_chess(self) -> RotchessEmulator {
+        self.chess
+    }
+
+    /// Look at a few more turns.
+    pub fn step(&mut self, turns: usize) {
+        if self.done {
+            return;
+        }
+        for _ in 0..turns {
+            let Some((option, angle)) = self.turns.pop() else {
+                self.finish_depth();
+                self.done = true;
+                return;
+            };
+            let score = self.try_turn(option, angle);
+            self.consider(option, angle, score);
+        }
+    }
+
+    /// Play a turn out on our board, score it, and take it back.
+    fn try_turn(&mut self, option: MoveOption, angle: f32) -> f32 {
+        let other = match self.side {
+            Side::White => Side::Black,
+            Side::Black => Side::White,
+        };
+        self.chess
+            .handle_event(Event::MoveUnchecked(option.piece_idx, option.x, option.y));
+        self.chess
+            .handle_event(Event::RotateUnchecked(option.piece_idx, angle));
+        let after = notation::snapshot(self.chess.pieces());
+        let comeback = bot::move_options(&mut self.chess, other)
+            .iter()
+            .filter_map(|reply| notation::captured_by(&after, reply.piece_idx, reply.x, reply.y))
+            .map(|captured| piece_value(captured.kind))
+            .fold(0., f32::max);
+        self.chess.handle_event(Event::PrevTurn);
+        self.chess.handle_event(Event::PrevTurn);
+
+        let before = material(&self.board, self.side);
+        let gain = material(&after, self.side) - before;
+        // the shorter way around, as a fraction of half a turn.
+        let from_angle = self.board[option.piece_idx].angle;
+        let turned =
+            ((angle - from_angle + TAU / 2.).rem_euclid(TAU) - TAU / 2.).abs() / (TAU / 2.);
+        before + self.personality.score(gain, comeback, turned)
+    }
+
+    fn consider(&mut self, option: MoveOption, angle: f32, score: f32) {
+        let piece = &self.board[option.piece_idx];
+        self.scored_this_depth.push(Suggestion {
+            piece_idx: option.piece_idx,
+            from: (piece.x, piece.y),
+            to: (option.x, option.y),
+            from_angle: piece.angle,
+            angle,
+            score,
+            depth: self.depth,
+        });
+    }
+
+    fn finish_depth(&mut self) {
+        if !self.scored_this_depth.is_empty() {
+            self.scored = std::mem::take(&mut self.scored_this_depth);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_engine {
+    use rotchess_core::piece::Pieces;
+
+    use super::*;
+
+    #[test]
+    fn searches_deeper_and_leaves_the_board_alone() {
+        let mut search = Search::new(
+            RotchessEmulator::with(Pieces::standard_board()),
+            Side::White,
+            Personality::NEUTRAL,
+        );
+        let first = search.best().expect("there are moves from the start");
+        assert_eq!((first.depth, first.score), (1, 0.));
+
+        while !search.done() {
+            search.step(50);
+        }
+        let best = search.best().unwrap();
+        // nothing to take from the start, and nothing we have to give up either.
+        assert_eq!((best.depth, best.score), (MAX_DEPTH, 0.));
+        assert_eq!(notation::snapshot(search.chess.pieces()), search.board);
+    }
+
+    #[test]
+    fn neutral_personalities_score_by_material() {
+        assert_eq!(Personality::NEUTRAL.score(3., 1., 0.5), 2.);
+    }
+
+    #[test]
+    fn personalities_weigh_turns_their_own_way() {
+        let reckless = Personality {
+            aggressiveness: 1.,
+            ..Personality::NEUTRAL
+        };
+        let careful = Personality {
+            aggressiveness: 0.,
+            ..Personality::NEUTRAL
+        };
+        // taking a knight for a rook.
+        assert!(reckless.score(3., 5., 0.) > careful.score(3., 5., 0.));
+        assert!(careful.score(3., 5., 0.) < 0.);
+
+        let spinner = Personality {
+            rotation_happiness: 1.,
+            ..Personality::NEUTRAL
+        };
+        assert!(spinner.score(0., 0., 1.) > spinner.score(0., 0., 0.25));
+    }
+}
@@ -0,0 +1,146 @@
+//! Exhibition mode: two [`Bot`]s play each other while a person watches.
+//!
+//! Good for testing the emulator, making replays to look at, and leaving running on a
+//! kiosk. The watcher can pause (space), speed up or slow down (`+`/`-`), and look back
+//! through the game with the arrow keys or the scrubber. The bots only play on from the
+//! live position.
+//!
+//! Run with `cargo run -- --exhibition [milliseconds per action]`.
+//!
+//! Kiosk mode (`--kiosk`) plays one of these as a demo whenever nobody has touched the
+//! game in a while, until someone presses a key to play.
+
+use std::time::{Duration, Instant};
+
+use rotchess_core::piece::Side;
+
+use crate::bot::Bot;
+
+/// How long the bots wait before each move and rotation if `--exhibition` isn't given one.
+pub const DEFAULT_ACTION_DELAY: Duration = Duration::from_millis(600);
+
+/// The fastest the bots are allowed to go, so there's still something to watch.
+pub const MIN_ACTION_DELAY: Duration = Duration::from_millis(50);
+
+/// The slowest the bots are allowed to go.
+pub const MAX_ACTION_DELAY: Duration = Duration::from_secs(10);
+
+/// Parse `--exhibition [milliseconds]` out of the command line arguments.
+///
+/// Returns `None` if we shouldn't play an exhibition.
+pub fn exhibition_delay_from_args() -> Option<Duration> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--exhibition");
+    args.next()?;
+    Some(
+        args.next()
+            .and_then(|ms| ms.parse().ok())
+            .map_or(DEFAULT_ACTION_DELAY, Duration::from_millis)
+            .clamp(MIN_ACTION_DELAY, MAX_ACTION_DELAY),
+    )
+}
+
+/// Whether `--kiosk` was passed on the command line.
+pub fn kiosk_from_args() -> bool {
+    std::env::args().any(|arg| arg == "--kiosk")
+}
+
+/// A running exhibition game.
+pub struct Exhibition {
+    white: Bot,
+    black: Bot,
+    delay: Duration,
+    paused: bool,
+    /// When the next action is due.
+    next_action_at: Instant,
+}
+
+impl Exhibition {
+    pub fn new(delay: Duration) -> Self {
+        let seed = rand::random();
+        Self {
+            white: Bot::new(seed),
+            black: Bot::new(seed.wrapping_add(1)),
+            delay,
+            paused: false,
+            next_action_at: Instant::now() + delay,
+        }
+    }
+
+    pub fn bot(&mut self, side: Side) -> &mut Bot {
+        match side {
+            Side::White => &mut self.white,
+            Side::Black => &mut self.black,
+        }
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.next_action_at = Instant::now() + self.delay;
+    }
+
+    /// Stop until the watcher unpauses, e.g. because the game can't go on.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn speed_up(&mut self) {
+        self.delay = (self.delay / 2).max(MIN_ACTION_DELAY);
+    }
+
+    pub fn slow_down(&mut self) {
+        self.delay = (self.delay * 2).min(MAX_ACTION_DELAY);
+    }
+
+    /// Whether a bot should act now. If so, the one after it is scheduled.
+    pub fn take_due_action(&mut self, now: Instant) -> bool {
+        if self.paused || now < self.next_action_at {
+            return false;
+        }
+        self.next_action_at = now + self.delay;
+        true
+    }
+}
+
+#[cfg(test)]
+mod test_exhibition {
+    use super::*;
+
+    #[test]
+    fn actions_are_spaced_out() {
+        let mut exhibition = Exhibition::new(Duration::from_millis(100));
+        let start = Instant::now();
+        assert!(!exhibition.take_due_action(start));
+        let later = start + Duration::from_millis(200);
+        assert!(exhibition.take_due_action(later));
+        assert!(!exhibition.take_due_action(later));
+        assert!(exhibition.take_due_action(later + Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn paused_games_wait() {
+        let mut exhibition = Exhibition::new(MIN_ACTION_DELAY);
+        exhibition.toggle_pause();
+        assert!(!exhibition.take_due_action(Instant::now() + MAX_ACTION_DELAY));
+    }
+
+    #[test]
+    fn speed_stays_in_bounds() {
+        let mut exhibition = Exhibition::new(DEFAULT_ACTION_DELAY);
+        for _ in 0..20 {
+            exhibition.speed_up();
+        }
+        assert_eq!(exhibition.delay(), MIN_ACTION_DELAY);
+        for _ in 0..20 {
+            exhibition.slow_down();
+        }
+        assert_eq!(exhibition.delay(), MAX_ACTION_DELAY);
+    }
+}
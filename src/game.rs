@@ -0,0 +1,351 @@
+//! The game itself: the emulator's board and our record of its history, which only ever
+//! change through [`Game::apply`].
+//!
+//! Clicks, the opponent's turns, bots, replays and resyncs all come down to the same few
+//! [`GameEvent`]s, so the game takes them the same way whoever sent them, and playing a
+//! game's actions back onto a fresh board gets to the same board it did.
+
+use rotchess_core::{
+    RotchessEmulator,
+    emulator::{Event, ThingHappened},
+    piece::{Pieces, Side},
+};
+
+use crate::{
+    bot::{self, MoveOption},
+    history::{Action, History},
+};
+
+/// Something that changes the game.
+pub enum GameEvent {
+    /// Something the user did on the board, like a click or a drag, or looking at another
+    /// turn. The emulator decides whether anything comes of it.
+    Input(Event),
+    /// A move or rotation played straight onto the board, by the opponent, a bot, a replay
+    /// or a resync. Nothing happens if the piece isn't on our board.
+    Play(Action),
+    /// Take back the last few actions, as if they never happened.
+    TakeBack(usize),
+    /// Take back the last few actions, but keep them to [redo](GameEvent::Redo).
+    Undo(usize),
+    /// Play the last undone actions again, from the live position. Anything else that
+    /// plays or takes back an action forgets the undone ones.
+    Redo,
+    /// Start over from a fresh board.
+    Reset(Pieces),
+}
+
+pub struct Game {
+    chess: RotchessEmulator,
+    /// Mirrors the turn history inside `chess`. [`Game::apply`] keeps the two in step.
+    history: History,
+    /// The actions of each undo, newest last, to redo.
+    undone: Vec<Vec<Action>>,
+}
+
+impl Game {
+    pub fn new(pieces: Pieces) -> Self {
+        Self {
+            chess: RotchessEmulator::with(pieces),
+            history: History::default(),
+            undone: Vec::new(),
+        }
+    }
+
+    /// A fresh game with some actions played on it, or `None` if an action is about a piece
+    /// the board doesn't have.
+    pub fn played_out(pieces: Pieces, actions: &[Action]) -> Option<Self> {
+        let mut game = Self::new(pieces);
+        for &action in actions {
+            game.apply(GameEvent::Play(action))?;
+        }
+        Some(game)
+    }
+
+    pub fn chess(&self) -> &RotchessEmulator {
+        &self.chess
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// The board on its own, for something that wants to search through it, like a bot.
+    pub fn into_chess(self) -> RotchessEmulator {
+        self.chess
+    }
+
+    /// How many undos there are to redo.
+    pub fn undone_turns(&self) -> usize {
+        self.undone.len()
+    }
+
+    /// Whether a piece index, say from the opponent, points at a piece on our board.
+    pub fn is_piece_idx(&self, piece_idx: usize) -> bool {
+        piece_idx < self.chess.pieces().len()
+    }
+
+    /// Everywhere `side` could move on the board we're looking at. Finding out clicks
+    /// around, so whatever was selected isn't anymore.
+    pub fn move_options(&mut self, side: Side) -> Vec<MoveOption> {
+        bot::move_options(&mut self.chess, side)
+    }
+
+    /// Change the game, returning what the emulator says happened, if anything. Take backs
+    /// and resets don't say.
+    pub fn apply(&mut self, event: GameEvent) -> Option<ThingHappened> {
+        match event {
+            GameEvent::Input(Event::NextTurn | Event::LastTurn) if self.history.at_live() => {
+                // the emulator still remembers anything taken back, and would step into it.
+                None
+            }
+            GameEvent::Input(e) => {
+                let thing = self.chess.handle_event(e);
+                if let Some(thing) = &thing {
+                    if Action::from_thing(thing).is_some() {
+                        // there's no redoing onto a different game.
+                        self.undone.clear();
+                    }
+                    self.history.record(thing);
+                }
+                thing
+            }
+            GameEvent::Play(action) => {
+                self.undone.clear();
+                self.play(action)
+            }
+            GameEvent::TakeBack(actions) => {
+                self.undone.clear();
+                self.take_back(actions);
+                None
+            }
+            GameEvent::Undo(actions) => {
+                let mut undone = self.history.actions().to_vec();
+                self.take_back(actions);
+                undone.drain(..self.history.len());
+                self.undone.push(undone);
+                None
+            }
+            GameEvent::Redo => {
+                let actions = self.undone.pop()?;
+                // back to the live position first, taking nothing back.
+                self.take_back(0);
+                actions
+                    .into_iter()
+                    .fold(None, |_, action| self.play(action))
+            }
+            GameEvent::Reset(pieces) => {
+                *self = Self::new(pieces);
+                None
+            }
+        }
+    }
+
+    /// Play an action straight onto the board, unless the piece isn't on it.
+    fn play(&mut self, action: Action) -> Option<ThingHappened> {
+        let (Action::Move(piece_idx, ..) | Action::Rotate(piece_idx, _)) = action;
+        if !self.is_piece_idx(piece_idx) {
+            // the unchecked events would index the emulator with this.
+            return None;
+        }
+        self.chess.handle_event(match action {
+            Action::Move(piece_idx, x, y) => Event::MoveUnchecked(piece_idx, x, y),
+            Action::Rotate(piece_idx, r) => Event::RotateUnchecked(piece_idx, r),
+        });
+        let thing = ThingHappened::from(action);
+        self.history.record(&thing);
+        Some(thing)
+    }
+
+    /// Take back the last few actions for good, from the live position.
+    fn take_back(&mut self, actions: usize) {
+        // the emulator only steps one turn at a time, so walk to the end, then back.
+        while !self.history.at_live() {
+            if self.apply(GameEvent::Input(Event::NextTurn)).is_none() {
+                break;
+            }
+        }
+        for _ in 0..actions {
+            if self.apply(GameEvent::Input(Event::PrevTurn)).is_none() {
+                break;
+            }
+        }
+        self.history.discard_future();
+    }
+}
+
+#[cfg(test)]
+mod test_game {
+    use rotchess_core::piece::PieceKind;
+
+    use crate::notation;
+
+    use super::*;
+
+    /// A white pawn's move and a rotation of it, then a black pawn's move. Plays skip the
+    /// rules, so where they go doesn't matter.
+    fn opening(game: &Game) -> Vec<Action> {
+        let pawn = |side| {
+            game.chess()
+                .pieces()
+                .iter()
+                .position(|piece| piece.side() == side && piece.kind() == PieceKind::Pawn)
+                .unwrap()
+        };
+        let (white, black) = (pawn(Side::White), pawn(Side::Black));
+        vec![
+            Action::Move(white, 3.5, 4.5),
+            Action::Rotate(white, 0.5),
+            Action::Move(black, 4.5, 3.5),
+        ]
+    }
+
+    #[test]
+    fn playing_out_matches_applying_one_at_a_time() {
+        let mut game = Game::new(Pieces::standard_board());
+        let actions = opening(&game);
+        for &action in &actions {
+            assert!(game.apply(GameEvent::Play(action)).is_some());
+        }
+        assert_eq!(game.history().actions(), &actions[..]);
+        let played = Game::played_out(Pieces::standard_board(), &actions).unwrap();
+        assert_eq!(played.history().actions(), &actions[..]);
+        assert_eq!(
+            notation::snapshot(played.chess().pieces()),
+            notation::snapshot(game.chess().pieces())
+        );
+    }
+
+    #[test]
+    fn plays_on_missing_pieces_change_nothing() {
+        let mut game = Game::new(Pieces::standard_board());
+        let before = notation::snapshot(game.chess().pieces());
+        assert!(
+            game.apply(GameEvent::Play(Action::Rotate(1000, 1.)))
+                .is_none()
+        );
+        assert!(game.history().is_empty());
+        assert_eq!(notation::snapshot(game.chess().pieces()), before);
+        assert!(
+            Game::played_out(Pieces::standard_board(), &[Action::Move(1000, 0., 0.)]).is_none()
+        );
+    }
+
+    #[test]
+    fn taking_back_forgets_actions_even_from_the_past() {
+        let mut game = Game::new(Pieces::standard_board());
+        let start = notation::snapshot(game.chess().pieces());
+        let actions = opening(&game);
+        for &action in &actions {
+            game.apply(GameEvent::Play(action));
+        }
+        game.apply(GameEvent::Input(Event::FirstTurn));
+        game.apply(GameEvent::TakeBack(1));
+        assert!(game.history().at_live());
+        assert_eq!(game.history().actions(), &actions[..2]);
+        game.apply(GameEvent::TakeBack(2));
+        assert!(game.history().is_empty());
+        assert_eq!(notation::snapshot(game.chess().pieces()), start);
+    }
+
+    #[test]
+    fn taken_back_actions_cant_be_stepped_into() {
+        let mut game = Game::new(Pieces::standard_board());
+        let actions = opening(&game);
+        for &action in &actions {
+            game.apply(GameEvent::Play(action));
+        }
+        game.apply(GameEvent::TakeBack(2));
+        let board = notation::snapshot(game.chess().pieces());
+        assert!(game.apply(GameEvent::Input(Event::NextTurn)).is_none());
+        assert!(game.apply(GameEvent::Input(Event::LastTurn)).is_none());
+        assert_eq!(game.history().actions(), &actions[..1]);
+        assert_eq!(notation::snapshot(game.chess().pieces()), board);
+    }
+
+    #[test]
+    fn undoing_then_redoing_gets_the_turn_back() {
+        let mut game = Game::new(Pieces::standard_board());
+        let actions = opening(&game);
+        for &action in &actions {
+            game.apply(GameEvent::Play(action));
+        }
+        let board = notation::snapshot(game.chess().pieces());
+        game.apply(GameEvent::Undo(2));
+        game.apply(GameEvent::Undo(1));
+        assert!(game.history().is_empty());
+        assert_eq!(game.undone_turns(), 2);
+        // looking around doesn't change the game.
+        game.apply(GameEvent::Input(Event::FirstTurn));
+        assert_eq!(game.undone_turns(), 2);
+        game.apply(GameEvent::Redo);
+        assert!(game.apply(GameEvent::Redo).is_some());
+        assert!(game.apply(GameEvent::Redo).is_none());
+        assert_eq!(game.history().actions(), &actions[..]);
+        assert_eq!(notation::snapshot(game.chess().pieces()), board);
+    }
+
+    #[test]
+    fn playing_on_after_an_undo_forgets_the_redo() {
+        let mut game = Game::new(Pieces::standard_board());
+        let actions = opening(&game);
+        for &action in &actions {
+            game.apply(GameEvent::Play(action));
+        }
+        game.apply(GameEvent::Undo(1));
+        // a different turn.
+        let Action::Move(black, ..) = actions[2] else {
+            unreachable!("the opening ends with a move");
+        };
+        game.apply(GameEvent::Play(Action::Move(black, 4.5, 2.5)));
+        let board = notation::snapshot(game.chess().pieces());
+        assert_eq!(game.undone_turns(), 0);
+        assert!(game.apply(GameEvent::Redo).is_none());
+        assert_eq!(game.history().len(), 3);
+        assert_eq!(notation::snapshot(game.chess().pieces()), board);
+
+        // and so does taking back or starting over.
+        game.apply(GameEvent::Undo(1));
+        game.apply(GameEvent::TakeBack(1));
+        assert_eq!(game.undone_turns(), 0);
+        game.apply(GameEvent::Undo(1));
+        game.apply(GameEvent::Reset(Pieces::standard_board()));
+        assert_eq!(game.undone_turns(), 0);
+    }
+
+    #[test]
+    fn loading_a_game_over_another_is_the_same_as_playing_it_out() {
+        let mut game = Game::new(Pieces::standard_board());
+        let actions = opening(&game);
+        for &action in &actions {
+            game.apply(GameEvent::Play(action));
+        }
+        game.apply(GameEvent::Undo(1));
+        // what continuing a saved game does.
+        game.apply(GameEvent::Reset(Pieces::standard_board()));
+        for &action in &actions[..2] {
+            game.apply(GameEvent::Play(action));
+        }
+        let played = Game::played_out(Pieces::standard_board(), &actions[..2]).unwrap();
+        assert_eq!(game.history().actions(), played.history().actions());
+        assert_eq!(
+            notation::snapshot(game.chess().pieces()),
+            notation::snapshot(played.chess().pieces())
+        );
+        assert!(game.apply(GameEvent::Redo).is_none());
+    }
+
+    #[test]
+    fn resetting_starts_over() {
+        let mut game = Game::new(Pieces::standard_board());
+        for action in opening(&game) {
+            game.apply(GameEvent::Play(action));
+        }
+        game.apply(GameEvent::Reset(Pieces::standard_board()));
+        assert!(game.history().is_empty());
+        assert_eq!(
+            notation::snapshot(game.chess().pieces()),
+            notation::snapshot(Game::new(Pieces::standard_board()).chess().pieces())
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Our own record of the turns the emulator has seen.
+//!
+//! [`rotchess_core`] keeps its history to itself, so we mirror it from the
+//! [`ThingHappened`]s it hands back. Anything that wants to know where in the
+//! game we're looking (or what happened so far) asks this instead.
+
+use rotchess_core::emulator::ThingHappened;
+
+/// Something that changed the board, i.e. a turn in the history.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Move(usize, f32, f32),
+    Rotate(usize, f32),
+}
+
+impl Action {
+    /// The action a thing is, if it's one that changes the board.
+    pub fn from_thing(thing: &ThingHappened) -> Option<Self> {
+        match *thing {
+            ThingHappened::Move(piece_idx, x, y) => Some(Action::Move(piece_idx, x, y)),
+            ThingHappened::Rotate(piece_idx, r) => Some(Action::Rotate(piece_idx, r)),
+            _ => None,
+        }
+    }
+}
+
+impl From<Action> for ThingHappened {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::Move(piece_idx, x, y) => ThingHappened::Move(piece_idx, x, y),
+            Action::Rotate(piece_idx, r) => ThingHappened::Rotate(piece_idx, r),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    actions: Vec<Action>,
+    /// How many of `actions` are applied to the board we're looking at.
+    ///
+    /// 0 is the starting position, `actions.len()` is the live position.
+    current: usize,
+}
+
+impl History {
+    /// Update our mirror of the emulator's history after it tells us something happened.
+    pub fn record(&mut self, thing: &ThingHappened) {
+        match thing {
+            ThingHappened::FirstTurn => self.current = 0,
+            ThingHappened::PrevTurn => self.current = self.current.saturating_sub(1),
+            ThingHappened::NextTurn => self.current = usize::min(self.current + 1, self.len()),
+            ThingHappened::LastTurn => self.current = self.len(),
+            ThingHappened::Move(piece_idx, x, y) => self.push(Action::Move(*piece_idx, *x, *y)),
+            ThingHappened::Rotate(piece_idx, r) => self.push(Action::Rotate(*piece_idx, *r)),
+        }
+    }
+
+    /// Doing something from a past position throws away whatever came after it.
+    fn push(&mut self, action: Action) {
+        self.actions.truncate(self.current);
+        self.actions.push(action);
+        self.current = self.actions.len();
+    }
+
+    /// Forget everything after the current turn, as if it never happened.
+    ///
+    /// For when we take back an action with a `PrevTurn` and don't want it to count as history.
+    pub fn discard_future(&mut self) {
+        self.actions.truncate(self.current);
+    }
+
+    /// How many actions have been applied to the board we're looking at.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// How many actions there are in total.
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// How many whole turns, a move and a rotation each, led to the board we're looking at.
+    pub fn turns_played(&self) -> usize {
+        self.actions[..self.current]
+            .iter()
+            .filter(|action| matches!(action, Action::Rotate(..)))
+            .count()
+    }
+
+    /// Like [`History::turns_played`], but for the live position.
+    pub fn live_turns_played(&self) -> usize {
+        self.actions
+            .iter()
+            .filter(|action| matches!(action, Action::Rotate(..)))
+            .count()
+    }
+
+    /// Whether we're looking at the latest position, rather than one in the past.
+    pub fn at_live(&self) -> bool {
+        self.current == self.len()
+    }
+
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+}
+
+#[cfg(test)]
+mod test_history {
+    use super::*;
+
+    fn history_with_moves(n: usize) -> History {
+        let mut history = History::default();
+        for i in 0..n {
+            history.record(&ThingHappened::Move(i, 0., 0.));
+        }
+        history
+    }
+
+    #[test]
+    fn navigation_stays_in_bounds() {
+        let mut history = history_with_moves(3);
+        history.record(&ThingHappened::NextTurn);
+        assert_eq!(history.current(), 3);
+        for _ in 0..5 {
+            history.record(&ThingHappened::PrevTurn);
+        }
+        assert_eq!(history.current(), 0);
+        history.record(&ThingHappened::LastTurn);
+        assert!(history.at_live());
+    }
+
+    #[test]
+    fn acting_in_the_past_truncates() {
+        let mut history = history_with_moves(3);
+        history.record(&ThingHappened::FirstTurn);
+        history.record(&ThingHappened::NextTurn);
+        history.record(&ThingHappened::Rotate(7, 1.));
+        assert_eq!(history.len(), 2);
+        assert!(history.at_live());
+        assert_eq!(history.actions()[1], Action::Rotate(7, 1.));
+    }
+
+    #[test]
+    fn turns_end_with_rotations() {
+        let mut history = history_with_moves(1);
+        assert_eq!(history.turns_played(), 0);
+        history.record(&ThingHappened::Rotate(0, 1.));
+        history.record(&ThingHappened::Move(1, 0., 0.));
+        assert_eq!(history.turns_played(), 1);
+        history.record(&ThingHappened::FirstTurn);
+        assert_eq!(history.turns_played(), 0);
+    }
+}
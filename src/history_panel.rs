@@ -0,0 +1,289 @@
+//! The move history panel: every action of the game so far in short notation, down the
+//! right of the window, with the board shrunk to make room. Clicking an action shows the
+//! board right after it.
+//!
+//! It's closed unless opened with `m`.
+
+use ggez::{
+    Context, GameResult,
+    glam::Vec2,
+    graphics::{Canvas, Color, DrawMode, DrawParam, Mesh, Rect, Text},
+};
+
+use crate::{
+    bench::RenderCounters,
+    constants::{BANNER_COLOR, BANNER_TEXT_COLOR},
+    history::Action,
+    notation::{self, PieceSnapshot},
+    theme::Theme,
+};
+
+/// How wide the panel is, in pixels.
+pub const PANEL_WIDTH: f32 = 220.;
+/// How tall each row is, in pixels.
+const ROW_HEIGHT: f32 = 22.;
+/// Font size in pixels
+const TEXT_SIZE: f32 = 16.;
+/// Space around the rows, in pixels.
+const PADDING: f32 = 8.;
+
+#[derive(Debug, Default)]
+pub struct HistoryPanel {
+    open: bool,
+    /// What each row says, one per action.
+    rows: Vec<String>,
+    /// The actions `rows` were worked out from, to tell when they're out of date.
+    rows_for: Vec<Action>,
+    /// The first row showing at the top of the panel.
+    scroll: usize,
+    /// The turn we last scrolled to keep in view.
+    followed: usize,
+}
+
+impl HistoryPanel {
+    pub fn open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// How much of a window this wide is left for the board.
+    pub fn board_width(&self, window_width: f32) -> f32 {
+        if self.open {
+            (window_width - PANEL_WIDTH).max(0.)
+        } else {
+            window_width
+        }
+    }
+
+    /// Where the panel is, on a window this big.
+    pub fn rect(width: f32, height: f32) -> Rect {
+        Rect::new(width - PANEL_WIDTH, 0., PANEL_WIDTH, height)
+    }
+
+    /// How many rows fit on a window this tall.
+    fn rows_shown(height: f32) -> usize {
+        (((height - 2. * PADDING) / ROW_HEIGHT).floor() as usize).max(1)
+    }
+
+    /// Whether the rows don't say what these actions are anymore.
+    pub fn is_stale(&self, actions: &[Action]) -> bool {
+        self.rows_for != actions
+    }
+
+    /// Write out a row for each action, with `boards` the board before each of them.
+    ///
+    /// Each turn's move is numbered, like in chess: "3." for white and "3..." for black. Its
+    /// rotation goes under it.
+    pub fn refresh(&mut self, actions: &[Action], boards: &[Vec<PieceSnapshot>]) {
+        self.rows = actions
+            .iter()
+            .zip(boards)
+            .enumerate()
+            .map(|(i, (action, before))| {
+                let notation = notation::short(before, action).unwrap_or_else(|| "?".to_string());
+                let turn = i / 2;
+                match (action, turn % 2) {
+                    (Action::Move(..), 0) => format!("{}. {notation}", turn / 2 + 1),
+                    (Action::Move(..), _) => format!("{}... {notation}", turn / 2 + 1),
+                    (Action::Rotate(..), _) => format!("    {notation}"),
+                }
+            })
+            .collect();
+        self.rows_for = actions.to_vec();
+        self.scroll = self.scroll.min(self.rows.len().saturating_sub(1));
+    }
+
+    /// Scroll the row for the `current` turn into view, if we've moved to another turn since
+    /// we last did. Scrolling away from it by hand lasts until then.
+    pub fn follow(&mut self, current: usize, height: f32) {
+        if current == self.followed {
+            return;
+        }
+        self.followed = current;
+        let Some(row) = current.checked_sub(1) else {
+            self.scroll = 0;
+            return;
+        };
+        let shown = Self::rows_shown(height);
+        if row < self.scroll {
+            self.scroll = row;
+        } else if row >= self.scroll + shown {
+            self.scroll = row + 1 - shown;
+        }
+    }
+
+    /// Scroll down some rows, or up for negative `rows`.
+    pub fn scroll_by(&mut self, rows: i32, height: f32) {
+        let last = self.rows.len().saturating_sub(Self::rows_shown(height));
+        self.scroll = self.scroll.saturating_add_signed(rows as isize).min(last);
+    }
+
+    /// The turn showing the board right after the row at some point, if there's a row
+    /// there.
+    pub fn turn_at(&self, point: Vec2, width: f32, height: f32) -> Option<usize> {
+        let rect = Self::rect(width, height);
+        if !self.open || !rect.contains(point) || point.y < PADDING {
+            return None;
+        }
+        let row = self.scroll + ((point.y - PADDING) / ROW_HEIGHT) as usize;
+        (row < self.rows.len()).then_some(row + 1)
+    }
+
+    /// Draw the panel, if it's open, with the row for the `current` turn lit up.
+    pub fn draw(
+        &self,
+        (ctx, canvas): (&mut Context, &mut Canvas),
+        theme: &Theme,
+        counters: &RenderCounters,
+        current: usize,
+    ) -> GameResult {
+        if !self.open {
+            return Ok(());
+        }
+        let (width, height) = ctx.gfx.drawable_size();
+        let rect = Self::rect(width, height);
+        let background = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, BANNER_COLOR)?;
+        counters.count_mesh_built();
+        counters.count_draw_call();
+        canvas.draw(&background, DrawParam::new());
+
+        let shown = self.rows.iter().enumerate().skip(self.scroll);
+        for (y, (i, row)) in (0..Self::rows_shown(height)).zip(shown) {
+            let top = rect.y + PADDING + y as f32 * ROW_HEIGHT;
+            if i + 1 == current {
+                let highlight = Rect::new(rect.x, top, rect.w, ROW_HEIGHT);
+                let highlight = Mesh::new_rectangle(
+                    ctx,
+                    DrawMode::fill(),
+                    highlight,
+                    Color {
+                        a: 0.5,
+                        ..theme.selected
+                    },
+                )?;
+                counters.count_mesh_built();
+                counters.count_draw_call();
+                canvas.draw(&highlight, DrawParam::new());
+            }
+            let mut text = Text::new(row.as_str());
+            text.set_scale(TEXT_SIZE);
+            counters.count_draw_call();
+            canvas.draw(
+                &text,
+                DrawParam::new()
+                    .dest(Vec2::new(
+                        rect.x + PADDING,
+                        top + (ROW_HEIGHT - TEXT_SIZE) / 2.,
+                    ))
+                    .color(BANNER_TEXT_COLOR),
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_history_panel {
+    use rotchess_core::piece::{PieceKind, Side};
+
+    use super::*;
+
+    fn opening() -> (Vec<Action>, Vec<Vec<PieceSnapshot>>) {
+        let knight = |x, y| PieceSnapshot {
+            kind: PieceKind::Knight,
+            side: Side::White,
+            x,
+            y,
+            angle: 0.,
+        };
+        let pawn = PieceSnapshot {
+            kind: PieceKind::Pawn,
+            side: Side::Black,
+            x: 4.5,
+            y: 1.5,
+            angle: 0.,
+        };
+        let actions = vec![
+            Action::Move(0, 2.5, 5.5),
+            Action::Rotate(0, 0.),
+            Action::Move(1, 4.5, 3.5),
+            Action::Rotate(1, 0.),
+        ];
+        let boards = vec![
+            vec![knight(1.5, 7.5), pawn],
+            vec![knight(2.5, 5.5), pawn],
+            vec![knight(2.5, 5.5), pawn],
+            vec![knight(2.5, 5.5), PieceSnapshot { y: 3.5, ..pawn }],
+        ];
+        (actions, boards)
+    }
+
+    #[test]
+    fn rows_are_numbered_like_chess() {
+        let (actions, boards) = opening();
+        let mut panel = HistoryPanel::default();
+        assert!(panel.is_stale(&actions));
+        panel.refresh(&actions, &boards);
+        assert!(!panel.is_stale(&actions));
+        assert_eq!(
+            panel.rows,
+            [
+                "1. Nb1 → (2.5, 5.5)",
+                "    Nc3 ↻ 0°",
+                "1... e7 → (4.5, 3.5)",
+                "    e5 ↻ 0°",
+            ]
+        );
+        assert!(panel.is_stale(&actions[..2]));
+    }
+
+    #[test]
+    fn clicking_a_row_shows_the_board_after_it() {
+        let (actions, boards) = opening();
+        let mut panel = HistoryPanel::default();
+        panel.refresh(&actions, &boards);
+        let x = 800. - PANEL_WIDTH / 2.;
+        let row_y = |row: f32| PADDING + (row + 0.5) * ROW_HEIGHT;
+        // closed panels can't be clicked.
+        assert_eq!(panel.turn_at(Vec2::new(x, row_y(0.)), 800., 600.), None);
+        panel.toggle();
+        assert_eq!(panel.turn_at(Vec2::new(x, row_y(0.)), 800., 600.), Some(1));
+        assert_eq!(panel.turn_at(Vec2::new(x, row_y(3.)), 800., 600.), Some(4));
+        assert_eq!(panel.turn_at(Vec2::new(x, row_y(4.)), 800., 600.), None);
+        assert_eq!(panel.turn_at(Vec2::new(100., row_y(0.)), 800., 600.), None);
+    }
+
+    #[test]
+    fn following_keeps_the_current_row_in_view() {
+        let mut panel = HistoryPanel {
+            rows: vec![String::new(); 100],
+            ..HistoryPanel::default()
+        };
+        // room for 10 rows.
+        let height = 2. * PADDING + 10. * ROW_HEIGHT;
+        panel.follow(30, height);
+        assert_eq!(panel.scroll, 20);
+        panel.follow(5, height);
+        assert_eq!(panel.scroll, 4);
+        // scrolling away by hand sticks until the turn changes.
+        panel.scroll_by(50, height);
+        panel.follow(5, height);
+        assert_eq!(panel.scroll, 54);
+        panel.scroll_by(1000, height);
+        assert_eq!(panel.scroll, 90);
+        panel.scroll_by(-1000, height);
+        assert_eq!(panel.scroll, 0);
+    }
+
+    #[test]
+    fn an_open_panel_takes_room_from_the_board() {
+        let mut panel = HistoryPanel::default();
+        assert_eq!(panel.board_width(800.), 800.);
+        panel.toggle();
+        assert_eq!(panel.board_width(800.), 800. - PANEL_WIDTH);
+        assert_eq!(panel.board_width(100.), 0.);
+    }
+}
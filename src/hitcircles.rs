@@ -0,0 +1,64 @@
+//! The hit circle debug layer (`h` to toggle): every piece's hit circle, where a move has to
+//! land to capture it, and warnings for pieces sitting on top of each other. For figuring
+//! out "why couldn't I capture that" reports.
+//!
+//! A click picks a piece if it's within [`PIECE_RADIUS`] of its center, and a move captures
+//! a piece if it lands within [`PIECE_RADIUS`] of its center. Two pieces closer than
+//! twice that overlap, which the rules should never allow, and which makes clicking on
+//! either of them a gamble.
+
+use rotchess_core::piece::PIECE_RADIUS;
+
+use crate::notation::PieceSnapshot;
+
+/// Whether two pieces, by their centers, are close enough that their hit circles overlap.
+pub fn overlap(a: (f32, f32), b: (f32, f32)) -> bool {
+    (a.0 - b.0).hypot(a.1 - b.1) < 2. * PIECE_RADIUS
+}
+
+/// Every pair of pieces whose hit circles overlap, as indexes into `pieces`, the lower
+/// first.
+pub fn overlapping_pairs(pieces: &[PieceSnapshot]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for (i, a) in pieces.iter().enumerate() {
+        for (j, b) in pieces.iter().enumerate().skip(i + 1) {
+            if overlap((a.x, a.y), (b.x, b.y)) {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod test_hitcircles {
+    use super::*;
+    use rotchess_core::piece::{PieceKind, Side};
+
+    fn piece(x: f32, y: f32) -> PieceSnapshot {
+        PieceSnapshot {
+            kind: PieceKind::Pawn,
+            side: Side::White,
+            x,
+            y,
+            angle: 0.,
+        }
+    }
+
+    #[test]
+    fn neighbouring_squares_dont_overlap() {
+        let pieces = [piece(0.5, 0.5), piece(1.5, 0.5), piece(1.5, 1.5)];
+        assert!(overlapping_pairs(&pieces).is_empty());
+    }
+
+    #[test]
+    fn close_pieces_overlap() {
+        let pieces = [
+            piece(0.5, 0.5),
+            piece(4.5, 4.5),
+            piece(0.5 + PIECE_RADIUS, 0.5),
+            piece(4.5, 4.5 + 1.9 * PIECE_RADIUS),
+        ];
+        assert_eq!(overlapping_pairs(&pieces), [(0, 2), (1, 3)]);
+    }
+}
@@ -0,0 +1,81 @@
+//! Dev-only watcher over the `resources/` directory, so piece art can be iterated on
+//! without restarting (and reconnecting) a game.
+//!
+//! Only compiled with the `hot-reload` feature, e.g. `cargo run --features hot-reload server`.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How often we stat the resource files. Polling is plenty for a dev tool.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct ResourceWatcher {
+    root: PathBuf,
+    mtimes: HashMap<PathBuf, SystemTime>,
+    last_poll: Instant,
+}
+
+impl ResourceWatcher {
+    /// Watch the `resources/` directory next to our manifest.
+    ///
+    /// Returns `None` if we weren't launched through cargo, since we then have
+    /// no idea where the resources live on disk.
+    pub fn new() -> Option<Self> {
+        let root = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").ok()?).join("resources");
+        let mut s = Self {
+            root,
+            mtimes: HashMap::new(),
+            last_poll: Instant::now(),
+        };
+        // prime the modification times so we don't report everything as changed.
+        s.scan();
+        Some(s)
+    }
+
+    /// Returns the files that changed since the last poll, as ggez resource paths
+    /// (i.e. relative to the resources dir, with a leading `/`).
+    ///
+    /// Cheap to call every frame; we only actually hit the disk every [`POLL_INTERVAL`].
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.last_poll = Instant::now();
+        self.scan()
+    }
+
+    fn scan(&mut self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        Self::collect_files(&self.root, &mut files);
+
+        let mut changed = Vec::new();
+        for file in files {
+            let Ok(mtime) = std::fs::metadata(&file).and_then(|m| m.modified()) else {
+                continue;
+            };
+            if self.mtimes.insert(file.clone(), mtime) != Some(mtime)
+                && let Ok(rel) = file.strip_prefix(&self.root)
+            {
+                changed.push(Path::new("/").join(rel));
+            }
+        }
+        changed
+    }
+
+    fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                Self::collect_files(&path, out);
+            } else {
+                out.push(path);
+            }
+        }
+    }
+}
@@ -0,0 +1,607 @@
+//! Which keys do what in a game, read from [`KEYMAP_FILE`] in ggez's user data directory.
+//!
+//! The file is a small TOML table of command names to keys, written out with the defaults
+//! the first time the game runs:
+//!
+//! ```toml
+//! reset = "r"
+//! first_turn = "shift+left"
+//! library = ["c", "ctrl+o"]
+//! ```
+//!
+//! A key is a character or one of the names in [`named_key`], after any of `ctrl+`, `shift+`
+//! and `alt+`. A command can have a list of keys, or `[]` for none. Commands left out keep
+//! their default keys. Lines starting with `#` are comments.
+
+use std::path::Path;
+
+use ggez::{
+    GameError, GameResult,
+    winit::keyboard::{Key, ModifiersState, NamedKey},
+};
+
+use crate::layers::Layer;
+
+/// Where the keymap lives, relative to ggez's user data directory.
+pub const KEYMAP_FILE: &str = "keys.toml";
+
+/// Something a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Comment,
+    EndTurn,
+    PrevTurn,
+    NextTurn,
+    FirstTurn,
+    LastTurn,
+    HistoryPanel,
+    Chess960Layout,
+    StandardLayout,
+    Save,
+    Library,
+    Reset,
+    ExportReplay,
+    ExportDiagram,
+    ExportText,
+    ProjectorMode,
+    Hitcircles,
+    NextSoundSet,
+    NextTheme,
+    Takeback,
+    ProposeRollback,
+    Undo,
+    Redo,
+    BlunderCheck,
+    Analysis,
+    SkipRotation,
+    TurnPieceCounterclockwise,
+    TurnPieceClockwise,
+    RotateView,
+    TurnAround,
+    ResetCamera,
+    Profiler,
+    ToggleLayer(Layer),
+}
+
+impl Command {
+    /// Whether holding ctrl or shift changes how far this goes, rather than which command
+    /// it is.
+    fn takes_step(self) -> bool {
+        matches!(
+            self,
+            Command::TurnPieceCounterclockwise | Command::TurnPieceClockwise
+        )
+    }
+}
+
+/// Every command, with its name in the keymap file, its default keys and what it does.
+const COMMANDS: [(Command, &str, &[&str], &str); 37] = [
+    (
+        Command::Comment,
+        "comment",
+        &["enter"],
+        "comment on the current turn",
+    ),
+    (
+        Command::EndTurn,
+        "end_turn",
+        &["space"],
+        "end a staged turn",
+    ),
+    (
+        Command::PrevTurn,
+        "prev_turn",
+        &["left"],
+        "look back a turn",
+    ),
+    (
+        Command::NextTurn,
+        "next_turn",
+        &["right"],
+        "look forward a turn",
+    ),
+    (
+        Command::FirstTurn,
+        "first_turn",
+        &["shift+left"],
+        "look back to the start",
+    ),
+    (
+        Command::LastTurn,
+        "last_turn",
+        &["shift+right"],
+        "look forward to the latest turn",
+    ),
+    (
+        Command::HistoryPanel,
+        "history",
+        &["m"],
+        "show or hide the list of moves so far",
+    ),
+    (
+        Command::Chess960Layout,
+        "chess960",
+        &["9"],
+        "restart in Chess960 (local games only)",
+    ),
+    (
+        Command::StandardLayout,
+        "standard",
+        &["0"],
+        "restart in the standard layout (local games only)",
+    ),
+    (
+        Command::Save,
+        "save",
+        &["ctrl+s"],
+        "save the game and board",
+    ),
+    (
+        Command::Library,
+        "library",
+        &["c", "ctrl+o"],
+        "list saved games and replays",
+    ),
+    (Command::Reset, "reset", &["r"], "reset the board"),
+    (
+        Command::ExportReplay,
+        "export_replay",
+        &["e"],
+        "export a replay",
+    ),
+    (
+        Command::ExportDiagram,
+        "export_diagram",
+        &["d"],
+        "export a diagram of the board",
+    ),
+    (
+        Command::ExportText,
+        "export_text",
+        &["x"],
+        "export the board as text for chat",
+    ),
+    (
+        Command::ProjectorMode,
+        "projector_mode",
+        &["p"],
+        "toggle projector mode",
+    ),
+    (
+        Command::Hitcircles,
+        "hitcircles",
+        &["h"],
+        "toggle hitcircles",
+    ),
+    (
+        Command::NextSoundSet,
+        "sound_set",
+        &["a"],
+        "switch to the next sound set",
+    ),
+    (
+        Command::NextTheme,
+        "theme",
+        &["g"],
+        "switch to the next theme",
+    ),
+    (Command::Takeback, "takeback", &["t"], "ask for a takeback"),
+    (
+        Command::ProposeRollback,
+        "rollback",
+        &["shift+t"],
+        "ask to play on from the turn you're looking at (networked games only)",
+    ),
+    (
+        Command::Undo,
+        "undo",
+        &["ctrl+z"],
+        "undo the last turn, and the computer's reply (local games only)",
+    ),
+    (
+        Command::Redo,
+        "redo",
+        &["ctrl+shift+z", "ctrl+y"],
+        "redo an undone turn (local games only)",
+    ),
+    (
+        Command::BlunderCheck,
+        "blunders",
+        &["b"],
+        "list the blunders so far (local games only)",
+    ),
+    (
+        Command::Analysis,
+        "analysis",
+        &["n"],
+        "toggle the engine's suggested turn (local games only)",
+    ),
+    (
+        Command::SkipRotation,
+        "skip_rotation",
+        &["s"],
+        "skip the rotation phase",
+    ),
+    (
+        Command::TurnPieceCounterclockwise,
+        "turn_piece_counterclockwise",
+        &["up"],
+        "turn the moved piece counterclockwise (ctrl for less, shift for more, enter to confirm)",
+    ),
+    (
+        Command::TurnPieceClockwise,
+        "turn_piece_clockwise",
+        &["down"],
+        "turn the moved piece clockwise (ctrl for less, shift for more, enter to confirm)",
+    ),
+    (
+        Command::RotateView,
+        "rotate_view",
+        &["v"],
+        "turn the board a quarter (hold to keep going)",
+    ),
+    (
+        Command::TurnAround,
+        "turn_around",
+        &["f"],
+        "look at the board from the other side",
+    ),
+    (
+        Command::ResetCamera,
+        "reset_camera",
+        &["home"],
+        "undo zooming and panning the board",
+    ),
+    (
+        Command::Profiler,
+        "profiler",
+        &["f3"],
+        "toggle the profiler (debug builds only)",
+    ),
+    (
+        Command::ToggleLayer(Layer::Board),
+        "board_layer",
+        &["f5"],
+        "show or hide the board",
+    ),
+    (
+        Command::ToggleLayer(Layer::Pieces),
+        "piece_layer",
+        &["f6"],
+        "show or hide the pieces",
+    ),
+    (
+        Command::ToggleLayer(Layer::Indicators),
+        "indicator_layer",
+        &["f7"],
+        "show or hide hit circles, annotations and other indicators",
+    ),
+    (
+        Command::ToggleLayer(Layer::Hud),
+        "hud_layer",
+        &["f8"],
+        "show or hide the clocks and banners",
+    ),
+    (
+        Command::ToggleLayer(Layer::Overlay),
+        "overlay_layer",
+        &["f9"],
+        "show or hide results, dialogs and the library",
+    ),
+];
+
+/// A key, with the modifiers that have to be held along with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Chord {
+    key: ChordKey,
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ChordKey {
+    Character(String),
+    Named(NamedKey),
+}
+
+impl Chord {
+    fn parse(text: &str) -> Option<Self> {
+        let mut chord = Self {
+            key: ChordKey::Named(NamedKey::Space),
+            ctrl: false,
+            shift: false,
+            alt: false,
+        };
+        let mut rest = text.trim();
+        loop {
+            if let Some(r) = rest.strip_prefix("ctrl+") {
+                chord.ctrl = true;
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("shift+") {
+                chord.shift = true;
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("alt+") {
+                chord.alt = true;
+                rest = r;
+            } else {
+                break;
+            }
+        }
+        chord.key = match named_key(rest) {
+            Some(named) => ChordKey::Named(named),
+            // the keys come in without modifiers, so shift+a is "a", not "A".
+            None if rest.chars().count() == 1 => ChordKey::Character(rest.to_lowercase()),
+            None => return None,
+        };
+        Some(chord)
+    }
+
+    fn matches(&self, key: &Key, mods: ModifiersState) -> bool {
+        let key_matches = match (&self.key, key) {
+            (ChordKey::Character(ours), Key::Character(theirs)) => ours == theirs.as_str(),
+            (ChordKey::Named(ours), Key::Named(theirs)) => ours == theirs,
+            _ => false,
+        };
+        key_matches
+            && self.ctrl == mods.control_key()
+            && self.shift == mods.shift_key()
+            && self.alt == mods.alt_key()
+            && !mods.super_key()
+    }
+
+    fn modifier_count(&self) -> usize {
+        [self.ctrl, self.shift, self.alt]
+            .into_iter()
+            .filter(|&held| held)
+            .count()
+    }
+}
+
+/// The keys with names, as they're written in the keymap file.
+fn named_key(name: &str) -> Option<NamedKey> {
+    Some(match name {
+        "space" => NamedKey::Space,
+        "enter" => NamedKey::Enter,
+        "tab" => NamedKey::Tab,
+        "backspace" => NamedKey::Backspace,
+        "delete" => NamedKey::Delete,
+        "left" => NamedKey::ArrowLeft,
+        "right" => NamedKey::ArrowRight,
+        "up" => NamedKey::ArrowUp,
+        "down" => NamedKey::ArrowDown,
+        "home" => NamedKey::Home,
+        "end" => NamedKey::End,
+        "pageup" => NamedKey::PageUp,
+        "pagedown" => NamedKey::PageDown,
+        "f1" => NamedKey::F1,
+        "f2" => NamedKey::F2,
+        "f3" => NamedKey::F3,
+        "f4" => NamedKey::F4,
+        "f5" => NamedKey::F5,
+        "f6" => NamedKey::F6,
+        "f7" => NamedKey::F7,
+        "f8" => NamedKey::F8,
+        "f9" => NamedKey::F9,
+        "f10" => NamedKey::F10,
+        "f11" => NamedKey::F11,
+        "f12" => NamedKey::F12,
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: Vec<(Chord, Command)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = COMMANDS
+            .iter()
+            .flat_map(|&(command, _, keys, _)| {
+                keys.iter().map(move |key| {
+                    let chord = Chord::parse(key).expect("Default keys should parse.");
+                    (chord, command)
+                })
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Load the keymap from ggez's user data directory, writing the default one there first
+    /// if there isn't one yet.
+    pub fn load(user_data_dir: &Path) -> GameResult<Self> {
+        let path = user_data_dir.join(KEYMAP_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::from_text(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                std::fs::create_dir_all(user_data_dir)?;
+                std::fs::write(&path, Self::default_text())?;
+                Ok(Self::default())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// What pressing `key` with `mods` held does, if anything.
+    ///
+    /// The modifiers have to be just the ones bound, so ctrl+r doesn't reset the board,
+    /// except that ctrl and shift pick the step of commands that [take one](Command::takes_step).
+    /// When more than one binding fits, the one asking for the most modifiers wins.
+    pub fn command(&self, key: &Key, mods: ModifiersState) -> Option<Command> {
+        let stepless = mods.difference(ModifiersState::CONTROL | ModifiersState::SHIFT);
+        self.bindings
+            .iter()
+            .filter(|(chord, command)| {
+                chord.matches(key, mods) || (command.takes_step() && chord.matches(key, stepless))
+            })
+            .max_by_key(|(chord, _)| chord.modifier_count())
+            .map(|&(_, command)| command)
+    }
+
+    /// The keymap file we write on first run: every command with its default keys.
+    pub fn default_text() -> String {
+        let mut text = String::from(
+            "# which keys do what in rotchess. delete a line to get its default back.\n\n",
+        );
+        for (_, name, keys, what) in COMMANDS {
+            let keys = match keys {
+                [key] => format!("\"{key}\""),
+                keys => {
+                    let keys: Vec<_> = keys.iter().map(|key| format!("\"{key}\"")).collect();
+                    format!("[{}]", keys.join(", "))
+                }
+            };
+            text.push_str(&format!("# {what}\n{name} = {keys}\n"));
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> GameResult<Self> {
+        let mut keymap = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| malformed(line_no, "expected a command = key"))?;
+            let &(command, ..) = COMMANDS
+                .iter()
+                .find(|(_, n, ..)| *n == name.trim())
+                .ok_or_else(|| malformed(line_no, "unknown command"))?;
+            let chords = parse_keys(line_no, value.trim())?;
+            keymap.bindings.retain(|&(_, c)| c != command);
+            keymap
+                .bindings
+                .extend(chords.into_iter().map(|chord| (chord, command)));
+        }
+        Ok(keymap)
+    }
+}
+
+/// Parse a `"key"` or a `["key", ...]` list of them.
+fn parse_keys(line_no: usize, value: &str) -> GameResult<Vec<Chord>> {
+    let list = match value
+        .strip_prefix('[')
+        .and_then(|list| list.strip_suffix(']'))
+    {
+        Some(list) => list
+            .split(',')
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .collect(),
+        None => vec![value],
+    };
+    list.into_iter()
+        .map(|key| {
+            let key = key
+                .strip_prefix('"')
+                .and_then(|key| key.strip_suffix('"'))
+                .ok_or_else(|| malformed(line_no, "expected a quoted key like \"r\""))?;
+            Chord::parse(key).ok_or_else(|| malformed(line_no, "unknown key"))
+        })
+        .collect()
+}
+
+fn malformed(line_no: usize, why: &str) -> GameError {
+    GameError::CustomError(format!("Malformed keymap at line {line_no}: {why}."))
+}
+
+#[cfg(test)]
+mod test_keymap {
+    use super::*;
+
+    fn press(keymap: &Keymap, key: Key, mods: ModifiersState) -> Option<Command> {
+        keymap.command(&key, mods)
+    }
+
+    #[test]
+    fn default_text_is_the_default_keymap() {
+        assert_eq!(
+            Keymap::from_text(&Keymap::default_text()).unwrap(),
+            Keymap::default()
+        );
+    }
+
+    #[test]
+    fn most_modifiers_win() {
+        let keymap = Keymap::default();
+        let s = || Key::Character("s".into());
+        assert_eq!(
+            press(&keymap, s(), ModifiersState::empty()),
+            Some(Command::SkipRotation)
+        );
+        assert_eq!(
+            press(&keymap, s(), ModifiersState::CONTROL),
+            Some(Command::Save)
+        );
+        assert_eq!(
+            press(
+                &keymap,
+                Key::Named(NamedKey::ArrowLeft),
+                ModifiersState::SHIFT
+            ),
+            Some(Command::FirstTurn)
+        );
+        assert_eq!(
+            press(
+                &keymap,
+                Key::Named(NamedKey::ArrowLeft),
+                ModifiersState::empty()
+            ),
+            Some(Command::PrevTurn)
+        );
+    }
+
+    #[test]
+    fn modifiers_have_to_match_exactly() {
+        let keymap = Keymap::default();
+        let r = || Key::Character("r".into());
+        assert_eq!(
+            press(&keymap, r(), ModifiersState::empty()),
+            Some(Command::Reset)
+        );
+        assert_eq!(press(&keymap, r(), ModifiersState::CONTROL), None);
+        assert_eq!(press(&keymap, r(), ModifiersState::ALT), None);
+        assert_eq!(press(&keymap, r(), ModifiersState::SUPER), None);
+        // ctrl and shift only pick how far to turn a piece.
+        for mods in [ModifiersState::CONTROL, ModifiersState::SHIFT] {
+            assert_eq!(
+                press(&keymap, Key::Named(NamedKey::ArrowDown), mods),
+                Some(Command::TurnPieceClockwise)
+            );
+        }
+    }
+
+    #[test]
+    fn rebinding_replaces_the_default() {
+        let keymap = Keymap::from_text("reset = \"shift+f5\"\nlibrary = []").unwrap();
+        assert_eq!(
+            press(&keymap, Key::Character("r".into()), ModifiersState::empty()),
+            None
+        );
+        assert_eq!(
+            press(&keymap, Key::Named(NamedKey::F5), ModifiersState::SHIFT),
+            Some(Command::Reset)
+        );
+        assert_eq!(
+            press(&keymap, Key::Character("c".into()), ModifiersState::empty()),
+            None
+        );
+    }
+
+    #[test]
+    fn garbage_is_an_error() {
+        assert!(Keymap::from_text("reset").is_err());
+        assert!(Keymap::from_text("reset = r").is_err());
+        assert!(Keymap::from_text("reset = \"rr\"").is_err());
+        assert!(Keymap::from_text("explode = \"x\"").is_err());
+        assert!(Keymap::from_text("reset = [\"r\", x]").is_err());
+    }
+}
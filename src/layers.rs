@@ -0,0 +1,98 @@
+//! The layers a game is drawn in, from the bottom up. Everything on screen belongs to one,
+//! and draws with the rest of its layer, so a new overlay only has to pick a layer instead of
+//! finding the right spot among every other draw call.
+//!
+//! Any layer can be hidden (F5 through F9 by default), say to see the bare board, or what an
+//! overlay is covering. Hidden layers still take input; they're just not drawn.
+
+/// A layer of the screen. Later layers draw over earlier ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    /// The squares and the scrubber, and in projector mode the last move and coordinates.
+    Board,
+    /// The pieces, with the selection, move trails and ghosts of turns in progress.
+    Pieces,
+    /// Things pointing at the board: hit circles, rejected clicks, annotations, the engine's
+    /// suggestion and the piece being inspected.
+    Indicators,
+    /// The ticker, toasts, clocks and banners about how the game is going.
+    Hud,
+    /// Whatever covers the game: the result, the library, blunder reviews, dialogs and the
+    /// profiler.
+    Overlay,
+}
+
+impl Layer {
+    /// Every layer, bottom first.
+    pub const ALL: [Layer; 5] = [
+        Layer::Board,
+        Layer::Pieces,
+        Layer::Indicators,
+        Layer::Hud,
+        Layer::Overlay,
+    ];
+
+    /// What the layer is called in the profiler.
+    pub fn name(self) -> &'static str {
+        match self {
+            Layer::Board => "board layer",
+            Layer::Pieces => "piece layer",
+            Layer::Indicators => "indicator layer",
+            Layer::Hud => "hud layer",
+            Layer::Overlay => "overlay layer",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Which layers are hidden. Everything is shown to start with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Layers {
+    hidden: [bool; Layer::ALL.len()],
+}
+
+impl Layers {
+    pub fn shown(&self, layer: Layer) -> bool {
+        !self.hidden[layer.index()]
+    }
+
+    pub fn toggle(&mut self, layer: Layer) {
+        self.hidden[layer.index()] = !self.hidden[layer.index()];
+    }
+
+    /// The layers to draw, bottom first.
+    pub fn visible(&self) -> impl Iterator<Item = Layer> + use<> {
+        let layers = *self;
+        Layer::ALL
+            .into_iter()
+            .filter(move |&layer| layers.shown(layer))
+    }
+}
+
+#[cfg(test)]
+mod test_layers {
+    use super::*;
+
+    #[test]
+    fn everything_starts_shown_in_order() {
+        assert!(Layers::default().visible().eq(Layer::ALL));
+    }
+
+    #[test]
+    fn toggling_hides_then_shows_a_layer() {
+        let mut layers = Layers::default();
+        layers.toggle(Layer::Hud);
+        assert!(!layers.shown(Layer::Hud));
+        assert!(layers.visible().eq([
+            Layer::Board,
+            Layer::Pieces,
+            Layer::Indicators,
+            Layer::Overlay
+        ]));
+        layers.toggle(Layer::Hud);
+        assert_eq!(layers, Layers::default());
+    }
+}
@@ -1,2 +1,48 @@
+pub mod annotation;
 pub mod app;
+pub mod assets;
+pub mod audio;
+pub mod bandwidth;
+pub mod bench;
+pub mod blunders;
+pub mod book;
+pub mod bot;
+pub mod clock;
+pub mod compare;
 pub mod constants;
+pub mod effects;
+pub mod engine;
+pub mod exhibition;
+pub mod game;
+pub mod history;
+pub mod history_panel;
+pub mod hitcircles;
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
+pub mod keymap;
+pub mod layers;
+pub mod library;
+pub mod minimap;
+pub mod netplay;
+pub mod notation;
+pub mod notifications;
+pub mod outcome;
+pub mod pass_screen;
+pub mod persistence;
+pub mod piece_history;
+pub mod playback;
+pub mod profiling;
+pub mod protocol;
+pub mod replay;
+pub mod rules;
+pub mod scenes;
+pub mod settings;
+pub mod simulate;
+pub mod snapping;
+pub mod snapshots;
+pub mod spectate;
+pub mod stats;
+pub mod theme;
+pub mod ticker;
+pub mod transform;
+pub mod turn_phase;
@@ -0,0 +1,165 @@
+//! The game library: saved games in the working directory, listed so they can be picked up
+//! again without going through a file browser (`c` to open, Enter to continue one).
+//!
+//! Saved games (`Ctrl+S`, see [`crate::persistence`]) and exported replays (`e`) both show
+//! up here, newest first.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    history::Action,
+    notation::PieceSnapshot,
+    persistence::{Mode, SavedGame},
+    replay::Replay,
+};
+
+/// The extension saved games have.
+pub const SAVE_EXTENSION: &str = "rotchess";
+
+/// A saved game, and what we know about it.
+pub struct LibraryEntry {
+    pub path: PathBuf,
+    /// When the game was saved, if the filesystem knows.
+    pub saved: Option<SystemTime>,
+    pub replay: Replay,
+    /// The board the game was saved on, if it was saved rather than exported.
+    pub board: Option<Vec<PieceSnapshot>>,
+    /// Who the game was against.
+    pub mode: Mode,
+}
+
+impl LibraryEntry {
+    /// The name to list the game by.
+    pub fn name(&self) -> String {
+        self.path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned())
+    }
+
+    /// A line about the game, like "Chess960, 12 turns, saved 3 hours ago".
+    pub fn summary(&self, now: SystemTime) -> String {
+        let variant = match self.replay.chess960_ordering {
+            Some(_) => "Chess960",
+            None => "Standard",
+        };
+        let turns = turns_played(&self.replay.actions);
+        let mut summary = format!(
+            "{variant}, {turns} turn{}",
+            if turns == 1 { "" } else { "s" }
+        );
+        if matches!(self.replay.actions.last(), Some(Action::Move(..))) {
+            summary.push_str(" and a move");
+        }
+        if let Some(saved) = self.saved {
+            summary.push_str(&format!(", saved {}", describe_age(saved, now)));
+        }
+        summary
+    }
+}
+
+/// How many whole turns (moves and their rotations) some actions make up.
+fn turns_played(actions: &[Action]) -> usize {
+    actions
+        .iter()
+        .filter(|action| matches!(action, Action::Rotate(..)))
+        .count()
+}
+
+/// How long ago something happened, roughly, in words.
+pub fn describe_age(then: SystemTime, now: SystemTime) -> String {
+    let secs = now.duration_since(then).unwrap_or(Duration::ZERO).as_secs();
+    let (amount, unit) = match secs {
+        0..60 => return "just now".to_string(),
+        60..3600 => (secs / 60, "minute"),
+        3600..86400 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+    format!("{amount} {unit}{} ago", if amount == 1 { "" } else { "s" })
+}
+
+/// Every saved game in a directory, newest first.
+///
+/// Files that can't be read as a game are skipped, with a note on stdout.
+pub fn scan(dir: &Path) -> Vec<LibraryEntry> {
+    let Ok(files) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<LibraryEntry> = files
+        .flatten()
+        .map(|file| file.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == SAVE_EXTENSION))
+        .filter_map(|path| match SavedGame::load(&path) {
+            Ok(game) => Some(LibraryEntry {
+                saved: std::fs::metadata(&path)
+                    .and_then(|meta| meta.modified())
+                    .ok(),
+                path,
+                replay: game.replay,
+                board: game.board,
+                mode: game.mode,
+            }),
+            Err(e) => {
+                println!("skipping {} in the library: {e}", path.display());
+                None
+            }
+        })
+        .collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.saved));
+    entries
+}
+
+#[cfg(test)]
+mod test_library {
+    use super::*;
+    use parameterized::parameterized;
+
+    #[parameterized(secs = { 5, 60, 150, 3600, 7200, 86400 * 3 }, expected = {
+        "just now", "1 minute ago", "2 minutes ago", "1 hour ago", "2 hours ago", "3 days ago"
+    })]
+    fn ages_read_naturally(secs: u64, expected: &str) {
+        let then = SystemTime::UNIX_EPOCH;
+        assert_eq!(
+            describe_age(then, then + Duration::from_secs(secs)),
+            expected
+        );
+    }
+
+    #[test]
+    fn summaries_count_whole_turns() {
+        let entry = LibraryEntry {
+            path: PathBuf::from("replay-1.rotchess"),
+            saved: None,
+            replay: Replay {
+                actions: vec![
+                    Action::Move(0, 0.5, 2.5),
+                    Action::Rotate(0, 1.),
+                    Action::Move(9, 0.5, 5.5),
+                ],
+                ..Replay::default()
+            },
+            board: None,
+            mode: Mode::HotSeat,
+        };
+        assert_eq!(entry.name(), "replay-1");
+        assert_eq!(
+            entry.summary(SystemTime::now()),
+            "Standard, 1 turn and a move"
+        );
+    }
+
+    #[test]
+    fn scans_only_readable_saves() {
+        let dir = std::env::temp_dir().join(format!("rotchess-library-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Replay::default().save(&dir.join("good.rotchess")).unwrap();
+        std::fs::write(dir.join("bad.rotchess"), "not a replay").unwrap();
+        std::fs::write(dir.join("notes.txt"), "hi").unwrap();
+
+        let names: Vec<String> = scan(&dir).iter().map(LibraryEntry::name).collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(names, ["good"]);
+    }
+}
@@ -5,10 +5,34 @@ use ggez::{
     conf::{WindowMode, WindowSetup},
     event,
 };
-use rotchess_ggez::{app::App, constants::STARTING_WINDOW_SIZE};
+use rotchess_ggez::{
+    app::{
+        App, client_needs_ticket, hot_seat_from_args, load_path_from_args, netcode_from_args,
+        roll_chess960_ordering, spectate_from_args, vs_bot_from_args,
+    },
+    bench::bench_frames_from_args,
+    bot::resign_threshold_from_args,
+    compare::Comparison,
+    constants::STARTING_WINDOW_SIZE,
+    engine::Personality,
+    exhibition::{exhibition_delay_from_args, kiosk_from_args},
+    playback::replay_path_from_args,
+    rules::GameRules,
+    scenes::Scenes,
+    simulate::Simulation,
+    snapshots::{self, snapshot_mode_from_args},
+};
 
 #[tokio::main]
 pub async fn main() -> GameResult {
+    if let Some(simulation) = Simulation::from_args() {
+        // no window needed for this.
+        return simulation?.run();
+    }
+    if let Some(comparison) = Comparison::from_args() {
+        return comparison?.run();
+    }
+
     let mut cb = ggez::ContextBuilder::new("super_simple", "ggez")
         .window_mode(
             WindowMode::default()
@@ -27,7 +51,44 @@ pub async fn main() -> GameResult {
 
     let (mut ctx, event_loop) = cb.build()?;
 
-    let state = App::new(&mut ctx).await?;
+    if let Some(mode) = snapshot_mode_from_args() {
+        // everything's drawn offscreen, so there's no event loop to run.
+        return snapshots::run(&mut ctx, mode);
+    }
+
+    let state = if let Some(frames) = bench_frames_from_args() {
+        Scenes::playing(App::bench(&mut ctx, frames))
+    } else if let Some(delay) = exhibition_delay_from_args() {
+        Scenes::playing(App::exhibition(&mut ctx, delay))
+    } else if kiosk_from_args() {
+        Scenes::playing(App::kiosk(&mut ctx))
+    } else if vs_bot_from_args() {
+        let rules = GameRules::from_args(roll_chess960_ordering)?;
+        Scenes::playing(App::vs_bot(
+            &mut ctx,
+            rules,
+            Personality::default(),
+            resign_threshold_from_args()?,
+        ))
+    } else if hot_seat_from_args() {
+        let rules = GameRules::from_args(roll_chess960_ordering)?;
+        Scenes::playing(App::hot_seat(&mut ctx, rules))
+    } else if let Some(path) = replay_path_from_args() {
+        Scenes::playing(App::playback(&mut ctx, &path)?)
+    } else if let Some(path) = load_path_from_args() {
+        Scenes::playing(App::loaded(&mut ctx, &path)?)
+    } else if let Some(ticket) = spectate_from_args()? {
+        Scenes::playing(App::spectating(&mut ctx, ticket))
+    } else if client_needs_ticket() {
+        Scenes::joining()
+    } else if netcode_from_args() {
+        match App::new(&mut ctx).await? {
+            Some(app) => Scenes::playing(app),
+            None => Scenes::offline(),
+        }
+    } else {
+        Scenes::menu()
+    };
 
     event::run(ctx, event_loop, state)
 }
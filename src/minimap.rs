@@ -0,0 +1,178 @@
+//! The mini-map: a small copy of the whole board in the corner while it's zoomed in, with a
+//! dot for each piece and a box around the part that's on screen, so nobody gets lost
+//! looking at a rotation up close. Clicking (or dragging) on it moves the camera there.
+
+use ggez::{
+    Context, GameResult,
+    glam::Vec2,
+    graphics::{Canvas, Color, DrawMode, DrawParam, Mesh, MeshBuilder, Rect},
+};
+use rotchess_core::piece::{Piece, Side};
+
+use crate::{
+    bench::RenderCounters, constants::CIRC_TOLERANCE, theme::Theme, transform::BoardTransform,
+};
+
+/// How big the mini-map is along each side, in pixels.
+pub const MINIMAP_SIZE: f32 = 160.;
+/// Space between the mini-map and the corner of the board's frame, in pixels.
+const MARGIN: f32 = 12.;
+/// Radius of a piece's dot, in pixels.
+const DOT_RADIUS: f32 = 3.;
+/// Width of the mini-map's outline and viewport box, in pixels.
+const LINE_WIDTH: f32 = 2.;
+
+pub struct MiniMap {
+    /// Where the mini-map's board is on screen, turned and flipped like the real one.
+    map: BoardTransform,
+}
+
+impl MiniMap {
+    /// The mini-map for a board shown like this, in the top left of its frame, if it's
+    /// zoomed in enough to need one.
+    pub fn of(board: &BoardTransform) -> Option<Self> {
+        if board.zoom <= 1. {
+            return None;
+        }
+        let frame = board.frame();
+        Some(Self {
+            map: BoardTransform {
+                scale: MINIMAP_SIZE / 8.,
+                offset: Vec2::new(frame.x + MARGIN, frame.y + MARGIN),
+                flipped: board.flipped,
+                quarter_turns: board.quarter_turns,
+                ..BoardTransform::default()
+            },
+        })
+    }
+
+    /// Where the mini-map is on screen.
+    pub fn rect(&self) -> Rect {
+        self.map.frame()
+    }
+
+    /// The part of the board that's on screen, on the mini-map. Only what's inside the
+    /// board's frame is drawn, so that's what counts.
+    pub fn viewport(&self, board: &BoardTransform) -> Rect {
+        let frame = board.frame();
+        let [a, b] = [(frame.x, frame.y), (frame.right(), frame.bottom())].map(|(x, y)| {
+            let (x, y) = board.board_pos(x, y);
+            self.map.world_pos(x, y)
+        });
+        let rect = self.rect();
+        let top_left = a.min(b).max(Vec2::new(rect.x, rect.y));
+        let bottom_right = a.max(b).min(Vec2::new(rect.right(), rect.bottom()));
+        let size = (bottom_right - top_left).max(Vec2::ZERO);
+        Rect::new(top_left.x, top_left.y, size.x, size.y)
+    }
+
+    /// The place on the board, in runits, under a point on the mini-map, if it's on it.
+    pub fn board_pos(&self, point: Vec2) -> Option<(f32, f32)> {
+        self.rect()
+            .contains(point)
+            .then(|| self.map.board_pos(point.x, point.y))
+    }
+
+    /// Draw the mini-map of a board shown like this, with these pieces on it.
+    pub fn draw(
+        &self,
+        (ctx, canvas): (&mut Context, &mut Canvas),
+        board: &BoardTransform,
+        pieces: &[Piece],
+        theme: &Theme,
+        counters: &RenderCounters,
+    ) -> GameResult {
+        let rect = self.rect();
+        let mut mb = MeshBuilder::new();
+        mb.rectangle(DrawMode::fill(), rect, theme.light_tile)?;
+        mb.rectangle(DrawMode::stroke(LINE_WIDTH), rect, theme.dark_tile)?;
+        for piece in pieces {
+            let (fill, outline) = match piece.side() {
+                Side::White => (Color::WHITE, Color::BLACK),
+                Side::Black => (Color::BLACK, Color::WHITE),
+            };
+            let at = self.map.world_pos(piece.x(), piece.y());
+            if !rect.contains(at) {
+                // pieces can sit off the board a little, but not off the mini-map.
+                continue;
+            }
+            mb.circle(DrawMode::fill(), at, DOT_RADIUS, CIRC_TOLERANCE, fill)?;
+            mb.circle(
+                DrawMode::stroke(1.),
+                at,
+                DOT_RADIUS,
+                CIRC_TOLERANCE,
+                outline,
+            )?;
+        }
+        let viewport = self.viewport(board);
+        if viewport.w > 0. && viewport.h > 0. {
+            mb.rectangle(DrawMode::stroke(LINE_WIDTH), viewport, theme.selected)?;
+        }
+        counters.count_mesh_built();
+        counters.count_draw_call();
+        canvas.draw(&Mesh::from_data(ctx, mb.build()), DrawParam::new());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_minimap {
+    use super::*;
+
+    fn zoomed(zoom: f32) -> BoardTransform {
+        BoardTransform {
+            scale: 50.,
+            zoom,
+            ..BoardTransform::default()
+        }
+    }
+
+    #[test]
+    fn only_zoomed_in_boards_have_one() {
+        assert!(MiniMap::of(&zoomed(1.)).is_none());
+        assert!(MiniMap::of(&zoomed(0.5)).is_none());
+        assert!(MiniMap::of(&zoomed(2.)).is_some());
+    }
+
+    #[test]
+    fn the_viewport_is_what_fits_in_the_frame() {
+        let mut board = zoomed(2.);
+        let minimap = MiniMap::of(&board).unwrap();
+        let quarter = MINIMAP_SIZE / 4.;
+        // twice as big shows the middle half of the board.
+        assert_eq!(
+            minimap.viewport(&board),
+            Rect::new(
+                MARGIN + quarter,
+                MARGIN + quarter,
+                2. * quarter,
+                2. * quarter
+            )
+        );
+        // centered on the top left corner, only that quarter of the board is left.
+        board.center_on(0., 0.);
+        assert_eq!(
+            minimap.viewport(&board),
+            Rect::new(MARGIN, MARGIN, quarter, quarter)
+        );
+    }
+
+    #[test]
+    fn clicking_it_jumps_the_camera_there() {
+        let mut board = zoomed(3.);
+        board.turn();
+        let minimap = MiniMap::of(&board).unwrap();
+        assert_eq!(minimap.board_pos(Vec2::new(1000., 1000.)), None);
+        let (x, y) = minimap
+            .board_pos(Vec2::new(MARGIN + 1., MARGIN + 1.))
+            .unwrap();
+        // turned a quarter clockwise, the top left of the screen is the bottom left of the
+        // board.
+        assert!(x < 0.1 && y > 7.9, "({x}, {y})");
+        board.center_on(x, y);
+        let frame = board.frame();
+        let middle = board.world_pos(x, y);
+        assert!((middle - Vec2::new(frame.w / 2., frame.h / 2.)).length() < 1e-3);
+    }
+}
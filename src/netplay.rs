@@ -0,0 +1,507 @@
+//! Following the opponent's half of a networked game: checking the turns they send against
+//! whose turn it is, and when one doesn't make sense, getting their whole copy of the game
+//! to replay instead.
+//!
+//! Whoever got the bad turn asks for a [`Message::Resync`], and the other player sends over
+//! their whole history for them to replay from the starting position.
+//!
+//! [`Netplay`] only works out what a turn means. Playing it on the board, and whatever else
+//! comes of it, is up to the app, or the loopback tests below, which go through the same
+//! checks and bookkeeping as the app does.
+
+use std::collections::VecDeque;
+
+use rotchess_core::emulator::ThingHappened;
+
+use crate::{
+    constants::TURN_SIZE,
+    game::Game,
+    history::Action,
+    protocol::{self, Message},
+    turn_phase::TurnPhaseMachine,
+};
+
+/// What a turn from the opponent comes to.
+pub enum Received {
+    /// The opponent played these on their live board, and so should we: a move, a
+    /// rotation, or a whole turn. The turn phase already knows, so if they rotated, it's
+    /// our turn.
+    Play(Vec<Action>),
+    /// Nothing for the board, but we owe the opponent a turn back.
+    Owed,
+    /// Nothing to do, e.g. the opponent handing the turn back. If they asked for our copy
+    /// of the game, it's queued for [`Netplay::next_outgoing`].
+    Nothing,
+    /// The opponent is sending over their copy of the game, so start from a fresh board.
+    /// `done` if there's nothing in it, so we're already caught up.
+    Resync { done: bool },
+    /// Play an action of the opponent's copy of the game onto the fresh board, quietly.
+    /// `done` if it was the last one, and the turn phase is whatever they said it was.
+    Replay { action: Action, done: bool },
+    /// The turn made no sense, so our boards must've drifted apart (or the turn got
+    /// garbled). We're waiting on the opponent's copy of the game, which the caller should
+    /// ask for with a [`Message::RequestResync`].
+    Lost(String),
+    /// Even the opponent's copy of the game didn't make sense, so there's no getting back
+    /// in sync.
+    GaveUp(String),
+    /// Something that isn't about the board, for the caller to deal with.
+    Other(Message),
+}
+
+#[derive(Debug, Default)]
+pub struct Netplay {
+    /// Turns queued up to send the opponent a whole copy of the game, sent one per turn we
+    /// get.
+    outgoing: VecDeque<[u8; TURN_SIZE]>,
+    /// While the opponent sends us a copy of the game: how many actions are left, and
+    /// whether it'll be our turn once they're in.
+    incoming: Option<(usize, bool)>,
+    /// Whether we asked for a copy of the game and it hasn't started coming yet.
+    requested: bool,
+}
+
+impl Netplay {
+    /// Whether we're getting back in sync, either way, so nobody should play until then.
+    pub fn resyncing(&self) -> bool {
+        self.requested || self.incoming.is_some() || !self.outgoing.is_empty()
+    }
+
+    /// Wait for the opponent's copy of the game. The caller should ask for it, with a
+    /// [`Message::RequestResync`] on our turn.
+    pub fn request(&mut self, phase: &mut TurnPhaseMachine) {
+        self.requested = true;
+        phase.wait();
+    }
+
+    /// The next turn of our copy of the game, for an opponent who asked for it. Only send
+    /// it while we have the turn.
+    pub fn next_outgoing(&mut self) -> Option<[u8; TURN_SIZE]> {
+        self.outgoing.pop_front()
+    }
+
+    /// Work out what a turn from the opponent comes to, given our game and our turn phase,
+    /// which moves along with it.
+    pub fn receive(
+        &mut self,
+        turn: &[u8; TURN_SIZE],
+        game: &Game,
+        phase: &mut TurnPhaseMachine,
+    ) -> Received {
+        let message = match protocol::de_message(turn) {
+            Ok(message) => message,
+            Err(e) if self.incoming.is_some() => return self.give_up(e.to_string()),
+            Err(e) => return self.lose(phase, e.to_string()),
+        };
+        match message {
+            Message::Thing(thing) if self.incoming.is_some() => self.replay(thing, game, phase),
+            Message::Thing(None) => Received::Nothing,
+            Message::Thing(Some(thing)) => match Action::from_thing(&thing) {
+                Some(action) => self.play(vec![action], game, phase),
+                // looking back is each player's own business, so our board stays put.
+                None => Received::Owed,
+            },
+            Message::Turn { piece_idx, x, y, r } => self.play(
+                vec![Action::Move(piece_idx, x, y), Action::Rotate(piece_idx, r)],
+                game,
+                phase,
+            ),
+            Message::RequestResync => {
+                self.outgoing.extend(protocol::ser_resync(
+                    game.history().actions(),
+                    phase.waiting(),
+                ));
+                Received::Nothing
+            }
+            Message::Resync { actions, your_turn } => {
+                self.requested = false;
+                phase.wait();
+                self.incoming = Some((actions, your_turn));
+                let done = actions == 0;
+                if done {
+                    self.finish(phase);
+                }
+                Received::Resync { done }
+            }
+            other => Received::Other(other),
+        }
+    }
+
+    /// Check the opponent's actions against the board and the turn phase.
+    fn play(
+        &mut self,
+        actions: Vec<Action>,
+        game: &Game,
+        phase: &mut TurnPhaseMachine,
+    ) -> Received {
+        for action in &actions {
+            let (Action::Move(piece_idx, ..) | Action::Rotate(piece_idx, _)) = *action;
+            if !game.is_piece_idx(piece_idx) {
+                return self.lose(
+                    phase,
+                    format!(
+                        "Opponent sent a turn for piece {piece_idx}, but there are only {} pieces.",
+                        game.chess().pieces().len()
+                    ),
+                );
+            }
+        }
+        if actions
+            .iter()
+            .any(|&action| phase.receive(&action.into()).is_err())
+        {
+            return self.lose(phase, "Opponent played on our turn.".to_string());
+        }
+        Received::Play(actions)
+    }
+
+    /// Check an action of the opponent's copy of the game against the fresh board.
+    fn replay(
+        &mut self,
+        thing: Option<ThingHappened>,
+        game: &Game,
+        phase: &mut TurnPhaseMachine,
+    ) -> Received {
+        let Some((remaining, your_turn)) = self.incoming else {
+            return Received::Nothing;
+        };
+        let action = thing
+            .as_ref()
+            .and_then(Action::from_thing)
+            .filter(|action| {
+                let (Action::Move(piece_idx, ..) | Action::Rotate(piece_idx, _)) = *action;
+                game.is_piece_idx(piece_idx)
+            });
+        let Some(action) = action else {
+            return self.give_up("Opponent's copy of the game doesn't fit our board.".to_string());
+        };
+        self.incoming = Some((remaining - 1, your_turn));
+        let done = remaining == 1;
+        if done {
+            self.finish(phase);
+        }
+        Received::Replay { action, done }
+    }
+
+    fn lose(&mut self, phase: &mut TurnPhaseMachine, why: String) -> Received {
+        self.request(phase);
+        Received::Lost(why)
+    }
+
+    fn give_up(&mut self, why: String) -> Received {
+        self.incoming = None;
+        Received::GaveUp(why)
+    }
+
+    /// We've caught up on the opponent's copy of the game.
+    fn finish(&mut self, phase: &mut TurnPhaseMachine) {
+        if let Some((_, your_turn)) = self.incoming.take() {
+            phase.finish_resync(your_turn);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_netplay {
+    use std::collections::VecDeque;
+
+    use rotchess_core::piece::{PieceKind, Pieces, Side};
+
+    use crate::{
+        game::GameEvent,
+        notation::{self, PieceSnapshot},
+        turn_phase::{PhaseViolation, TurnPhase},
+    };
+
+    use super::*;
+
+    /// Turns on their way from one player to the other. Like the netcode, whatever's sent
+    /// arrives, and in order.
+    type Wire = VecDeque<[u8; TURN_SIZE]>;
+
+    /// One player of a networked game: their board and turn phase, taking the opponent's
+    /// turns through a [`Netplay`] like the app does, minus the window.
+    struct Peer {
+        game: Game,
+        turn: TurnPhaseMachine,
+        netplay: Netplay,
+    }
+
+    impl Peer {
+        fn new(goes_first: bool) -> Self {
+            Self {
+                game: Game::new(Pieces::standard_board()),
+                turn: TurnPhaseMachine::new(goes_first, true),
+                netplay: Netplay::default(),
+            }
+        }
+
+        /// Do something on our board, and send it if it was in turn. If it wasn't, it's
+        /// taken back and nothing is sent.
+        fn act(&mut self, action: Action, wire: &mut Wire) -> Result<(), PhaseViolation> {
+            let thing = self
+                .game
+                .apply(GameEvent::Play(action))
+                .expect("tests only play pieces on the board");
+            if let Err(violation) = self.turn.act(&thing) {
+                self.game.apply(GameEvent::TakeBack(1));
+                return Err(violation);
+            }
+            wire.push_back(protocol::ser_thing(Some(&thing)));
+            Ok(())
+        }
+
+        /// Ask the opponent for their copy of the game, say after reconnecting.
+        fn ask_for_resync(&mut self, wire: &mut Wire) {
+            self.netplay.request(&mut self.turn);
+            wire.push_back(protocol::ser_message(&Message::RequestResync));
+        }
+
+        /// Send over whatever of our copy of the game the opponent asked for.
+        fn send_resync(&mut self, wire: &mut Wire) {
+            while let Some(turn) = self.netplay.next_outgoing() {
+                wire.push_back(turn);
+            }
+        }
+
+        /// Handle the next turn on the wire.
+        fn receive(&mut self, wire: &mut Wire) {
+            let turn = wire.pop_front().expect("a turn on the wire");
+            match self.netplay.receive(&turn, &self.game, &mut self.turn) {
+                Received::Play(actions) => {
+                    for action in actions {
+                        self.game.apply(GameEvent::Play(action));
+                    }
+                }
+                Received::Resync { .. } => {
+                    self.game.apply(GameEvent::Reset(Pieces::standard_board()));
+                }
+                Received::Replay { action, .. } => {
+                    self.game.apply(GameEvent::Play(action));
+                }
+                Received::Lost(_) => {
+                    wire.push_back(protocol::ser_message(&Message::RequestResync));
+                }
+                Received::Owed | Received::Nothing => (),
+                Received::GaveUp(why) => panic!("gave up: {why}"),
+                Received::Other(_) => panic!("unexpected turn"),
+            }
+        }
+
+        fn board(&self) -> Vec<PieceSnapshot> {
+            notation::snapshot(self.game.chess().pieces())
+        }
+
+        /// The first pawn of a side, moved a square toward the middle, and a rotation of it.
+        fn pawn_turn(&self, side: Side) -> (Action, Action) {
+            let pawn = self
+                .game
+                .chess()
+                .pieces()
+                .iter()
+                .position(|piece| piece.side() == side && piece.kind() == PieceKind::Pawn)
+                .unwrap();
+            let piece = &self.game.chess().pieces()[pawn];
+            let forward = if piece.y() > 4. { -1. } else { 1. };
+            (
+                Action::Move(pawn, piece.x(), piece.y() + forward),
+                Action::Rotate(pawn, 0.5),
+            )
+        }
+    }
+
+    /// White and black, with nothing played yet.
+    fn players() -> (Peer, Peer, Wire) {
+        (Peer::new(true), Peer::new(false), Wire::new())
+    }
+
+    #[test]
+    fn turns_cross_the_wire_and_hand_over() {
+        let (mut white, mut black, mut wire) = players();
+        let (move_, rotate) = white.pawn_turn(Side::White);
+        white.act(move_, &mut wire).unwrap();
+        black.receive(&mut wire);
+        assert!(black.turn.waiting(), "half a turn doesn't hand it over");
+        white.act(rotate, &mut wire).unwrap();
+        black.receive(&mut wire);
+        assert!(white.turn.waiting());
+        assert_eq!(black.turn.phase(), TurnPhase::Move);
+        assert_eq!(white.board(), black.board());
+
+        let (move_, rotate) = black.pawn_turn(Side::Black);
+        black.act(move_, &mut wire).unwrap();
+        black.act(rotate, &mut wire).unwrap();
+        white.receive(&mut wire);
+        white.receive(&mut wire);
+        assert!(wire.is_empty());
+        assert_eq!(white.turn.phase(), TurnPhase::Move);
+        assert_eq!(white.board(), black.board());
+        assert_eq!(
+            white.game.history().actions(),
+            black.game.history().actions()
+        );
+    }
+
+    #[test]
+    fn a_whole_turn_in_one_message_hands_over() {
+        let (mut white, mut black, mut wire) = players();
+        let (move_, rotate) = white.pawn_turn(Side::White);
+        white.act(move_, &mut wire).unwrap();
+        white.act(rotate, &mut wire).unwrap();
+        // staged, the turn goes out as one message instead.
+        wire.clear();
+        let (Action::Move(piece_idx, x, y), Action::Rotate(_, r)) = (move_, rotate) else {
+            unreachable!("pawn turns are a move and a rotation");
+        };
+        wire.push_back(protocol::ser_message(&Message::Turn { piece_idx, x, y, r }));
+        black.receive(&mut wire);
+        assert_eq!(black.turn.phase(), TurnPhase::Move);
+        assert_eq!(white.board(), black.board());
+    }
+
+    #[test]
+    fn rotating_before_moving_is_taken_back_unsent() {
+        let (mut white, _, mut wire) = players();
+        let before = white.board();
+        let (_, rotate) = white.pawn_turn(Side::White);
+        assert_eq!(
+            white.act(rotate, &mut wire),
+            Err(PhaseViolation::RotateInMovePhase)
+        );
+        assert!(wire.is_empty());
+        assert_eq!(white.board(), before);
+        assert!(white.game.history().is_empty());
+        assert_eq!(white.turn.phase(), TurnPhase::Move);
+    }
+
+    #[test]
+    fn a_second_move_is_taken_back_unsent() {
+        let (mut white, _, mut wire) = players();
+        let (move_, _) = white.pawn_turn(Side::White);
+        white.act(move_, &mut wire).unwrap();
+        let after_first = white.board();
+        let (second, _) = white.pawn_turn(Side::White);
+        assert_eq!(
+            white.act(second, &mut wire),
+            Err(PhaseViolation::MoveInRotatePhase)
+        );
+        assert_eq!(wire.len(), 1);
+        assert_eq!(white.board(), after_first);
+        assert_eq!(white.game.history().len(), 1);
+        assert_eq!(white.turn.phase(), TurnPhase::Rotate);
+    }
+
+    #[test]
+    fn acting_while_waiting_is_taken_back_unsent() {
+        let (_, mut black, mut wire) = players();
+        let before = black.board();
+        let (move_, _) = black.pawn_turn(Side::Black);
+        assert_eq!(
+            black.act(move_, &mut wire),
+            Err(PhaseViolation::NotYourTurn)
+        );
+        assert!(wire.is_empty());
+        assert_eq!(black.board(), before);
+        assert!(black.turn.waiting());
+    }
+
+    #[test]
+    fn turns_for_missing_pieces_are_fixed_by_a_resync() {
+        let (mut white, mut black, mut wire) = players();
+        wire.push_back(protocol::ser_thing(Some(&ThingHappened::Move(
+            1000, 1., 1.,
+        ))));
+        black.receive(&mut wire);
+        assert!(black.netplay.resyncing());
+        assert!(black.game.history().is_empty());
+
+        white.receive(&mut wire);
+        white.send_resync(&mut wire);
+        while !wire.is_empty() {
+            black.receive(&mut wire);
+        }
+        assert!(!black.netplay.resyncing());
+        assert!(black.turn.waiting());
+    }
+
+    #[test]
+    fn turns_that_arrive_out_of_order_are_fixed_by_a_resync() {
+        let (mut white, mut black, mut wire) = players();
+        let (move_, rotate) = white.pawn_turn(Side::White);
+        white.act(move_, &mut wire).unwrap();
+        white.act(rotate, &mut wire).unwrap();
+        // the rotation overtakes the move, which the netcode never lets happen, but a
+        // garbled turn could look just like it.
+        wire.swap(0, 1);
+        black.receive(&mut wire);
+        black.receive(&mut wire);
+        assert!(black.netplay.resyncing());
+        assert_ne!(white.board(), black.board());
+
+        // black asks, and white answers with their whole game.
+        white.receive(&mut wire);
+        white.send_resync(&mut wire);
+        while !wire.is_empty() {
+            black.receive(&mut wire);
+        }
+        assert!(!black.netplay.resyncing());
+        assert_eq!(white.board(), black.board());
+        assert_eq!(black.turn.phase(), TurnPhase::Move);
+    }
+
+    #[test]
+    fn garbled_turns_are_fixed_by_a_resync_but_a_garbled_resync_is_hopeless() {
+        let (mut white, mut black, mut wire) = players();
+        let (move_, rotate) = white.pawn_turn(Side::White);
+        white.act(move_, &mut wire).unwrap();
+        white.act(rotate, &mut wire).unwrap();
+        black.receive(&mut wire);
+        black.receive(&mut wire);
+        wire.push_back([255; TURN_SIZE]);
+        black.receive(&mut wire);
+        assert!(black.netplay.resyncing());
+        assert!(black.turn.waiting());
+
+        white.receive(&mut wire);
+        white.send_resync(&mut wire);
+        // the announcement gets through, but the first action doesn't.
+        black.receive(&mut wire);
+        wire[0] = [255; TURN_SIZE];
+        let turn = wire.pop_front().unwrap();
+        assert!(matches!(
+            black.netplay.receive(&turn, &black.game, &mut black.turn),
+            Received::GaveUp(_)
+        ));
+    }
+
+    #[test]
+    fn a_reconnected_player_picks_up_where_the_game_is() {
+        let (mut white, mut black, mut wire) = players();
+        let (move_, rotate) = white.pawn_turn(Side::White);
+        white.act(move_, &mut wire).unwrap();
+        white.act(rotate, &mut wire).unwrap();
+        black.receive(&mut wire);
+        black.receive(&mut wire);
+        let (move_, rotate) = black.pawn_turn(Side::Black);
+        black.act(move_, &mut wire).unwrap();
+        black.act(rotate, &mut wire).unwrap();
+        white.receive(&mut wire);
+        white.receive(&mut wire);
+
+        // black comes back with nothing, and white, whose turn it is, fills them in.
+        let mut black = Peer::new(false);
+        black.ask_for_resync(&mut wire);
+        white.receive(&mut wire);
+        white.send_resync(&mut wire);
+        while !wire.is_empty() {
+            black.receive(&mut wire);
+        }
+        assert_eq!(white.board(), black.board());
+        assert!(black.turn.waiting());
+        assert_eq!(white.turn.phase(), TurnPhase::Move);
+        assert_eq!(
+            black.game.history().actions(),
+            white.game.history().actions()
+        );
+    }
+}
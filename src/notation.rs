@@ -0,0 +1,392 @@
+//! Words for what happens in a game, like "Black rook captures white bishop".
+
+use std::f32::consts::{PI, TAU};
+
+use rotchess_core::piece::{PIECE_RADIUS, Piece, PieceKind, Side};
+
+use crate::history::{Action, History};
+
+/// What a piece was, where, and how it was turned, at some moment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PieceSnapshot {
+    pub kind: PieceKind,
+    pub side: Side,
+    pub x: f32,
+    pub y: f32,
+    pub angle: f32,
+}
+
+/// Every piece on a board, in the same order (so with the same indexes).
+pub fn snapshot(pieces: &[Piece]) -> Vec<PieceSnapshot> {
+    pieces
+        .iter()
+        .map(|piece| PieceSnapshot {
+            kind: piece.kind(),
+            side: piece.side(),
+            x: piece.x(),
+            y: piece.y(),
+            angle: piece.angle(),
+        })
+        .collect()
+}
+
+pub fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::White => "White",
+        Side::Black => "Black",
+    }
+}
+
+pub fn kind_name(kind: PieceKind) -> &'static str {
+    match kind {
+        PieceKind::Pawn => "pawn",
+        PieceKind::Rook => "rook",
+        PieceKind::Knight => "knight",
+        PieceKind::Bishop => "bishop",
+        PieceKind::Queen => "queen",
+        PieceKind::King => "king",
+    }
+}
+
+/// A piece's chess symbol, like ♘ for a white knight.
+pub fn piece_symbol(side: Side, kind: PieceKind) -> char {
+    match (side, kind) {
+        (Side::White, PieceKind::Pawn) => '♙',
+        (Side::White, PieceKind::Rook) => '♖',
+        (Side::White, PieceKind::Knight) => '♘',
+        (Side::White, PieceKind::Bishop) => '♗',
+        (Side::White, PieceKind::Queen) => '♕',
+        (Side::White, PieceKind::King) => '♔',
+        (Side::Black, PieceKind::Pawn) => '♟',
+        (Side::Black, PieceKind::Rook) => '♜',
+        (Side::Black, PieceKind::Knight) => '♞',
+        (Side::Black, PieceKind::Bishop) => '♝',
+        (Side::Black, PieceKind::Queen) => '♛',
+        (Side::Black, PieceKind::King) => '♚',
+    }
+}
+
+/// A piece's letter in notation, like N for a knight. Pawns go without, as in chess.
+pub fn kind_letter(kind: PieceKind) -> &'static str {
+    match kind {
+        PieceKind::Pawn => "",
+        PieceKind::Rook => "R",
+        PieceKind::Knight => "N",
+        PieceKind::Bishop => "B",
+        PieceKind::Queen => "Q",
+        PieceKind::King => "K",
+    }
+}
+
+/// The square a place on the board is in, like e4. Places off the board get the nearest
+/// square.
+pub fn square_name(x: f32, y: f32) -> String {
+    let file = x.floor().clamp(0., 7.) as u8;
+    let row = y.floor().clamp(0., 7.) as u8;
+    format!("{}{}", (b'a' + file) as char, 8 - row)
+}
+
+/// The board as a grid of piece symbols in a code block, for pasting into chat.
+///
+/// Each piece goes on the square its center is in, and any piece not facing straight up or
+/// down is listed under the grid with its angle, since the grid can't show that.
+pub fn text_diagram(board: &[PieceSnapshot]) -> String {
+    let mut grid = [['·'; 8]; 8];
+    let mut turned = Vec::new();
+    for piece in board {
+        let (file, row) = (piece.x.floor(), piece.y.floor());
+        if !(0. ..8.).contains(&file) || !(0. ..8.).contains(&row) {
+            continue;
+        }
+        let symbol = piece_symbol(piece.side, piece.kind);
+        let square = &mut grid[row as usize][file as usize];
+        if *square == '·' {
+            *square = symbol;
+        }
+        let degrees = piece.angle.to_degrees().rem_euclid(360.);
+        if f32::min(degrees % 180., 180. - degrees % 180.) > 0.5 {
+            turned.push(format!(
+                "{symbol}{} {degrees:.0}°",
+                square_name(piece.x, piece.y)
+            ));
+        }
+    }
+
+    let mut text = String::from("```\n");
+    for (row, squares) in grid.iter().enumerate() {
+        let squares: Vec<String> = squares.iter().map(char::to_string).collect();
+        text.push_str(&format!("{} {}\n", 8 - row, squares.join(" ")));
+    }
+    text.push_str("  a b c d e f g h\n");
+    if !turned.is_empty() {
+        text.push_str(&format!("turned: {}\n", turned.join(", ")));
+    }
+    text.push_str("```\n");
+    text
+}
+
+/// The piece of the other side sitting where a piece moved to on the board before the move,
+/// if there was one.
+pub fn captured_by(
+    before: &[PieceSnapshot],
+    piece_idx: usize,
+    x: f32,
+    y: f32,
+) -> Option<PieceSnapshot> {
+    let mover = before.get(piece_idx)?;
+    before
+        .iter()
+        .find(|piece| piece.side != mover.side && (piece.x - x).hypot(piece.y - y) < PIECE_RADIUS)
+        .copied()
+}
+
+/// An angle the way it's shown next to a piece: whole degrees from 0 up to (but not
+/// including) 360.
+pub fn angle_label(angle: f32) -> String {
+    format!("{:.0}°", angle.to_degrees().round().rem_euclid(360.))
+}
+
+/// How far a piece was rotated, in degrees, the short way around.
+pub fn rotation_degrees(from: f32, to: f32) -> f32 {
+    let turned = (to - from).rem_euclid(TAU);
+    let turned = if turned > PI { TAU - turned } else { turned };
+    turned.to_degrees()
+}
+
+/// Describe an action, given the board right before it.
+///
+/// Returns `None` if the board doesn't have the piece the action is about.
+pub fn describe(before: &[PieceSnapshot], action: &Action) -> Option<String> {
+    match *action {
+        Action::Move(piece_idx, x, y) => {
+            let mover = before.get(piece_idx)?;
+            let who = format!("{} {}", side_name(mover.side), kind_name(mover.kind));
+            Some(match captured_by(before, piece_idx, x, y) {
+                Some(captured) => format!(
+                    "{who} captures {} {}",
+                    side_name(captured.side).to_lowercase(),
+                    kind_name(captured.kind)
+                ),
+                None => format!("{who} moves"),
+            })
+        }
+        Action::Rotate(piece_idx, r) => {
+            let piece = before.get(piece_idx)?;
+            Some(format!(
+                "{} rotates {} {:.0}°",
+                side_name(piece.side),
+                kind_name(piece.kind),
+                rotation_degrees(piece.angle, r)
+            ))
+        }
+    }
+}
+
+/// An action in short notation, given the board right before it: the piece and its
+/// square, then where it went, like "Nb1 → (3.4, 2.1)" (× instead of → for a capture), or
+/// which way and how far it turned, like "Qd1 ↻ 37°".
+///
+/// Returns `None` if the board doesn't have the piece the action is about.
+pub fn short(before: &[PieceSnapshot], action: &Action) -> Option<String> {
+    let (Action::Move(piece_idx, ..) | Action::Rotate(piece_idx, _)) = *action;
+    let piece = before.get(piece_idx)?;
+    let who = format!(
+        "{}{}",
+        kind_letter(piece.kind),
+        square_name(piece.x, piece.y)
+    );
+    Some(match *action {
+        Action::Move(_, x, y) => {
+            let arrow = match captured_by(before, piece_idx, x, y) {
+                Some(_) => '×',
+                None => '→',
+            };
+            format!("{who} {arrow} ({x:.1}, {y:.1})")
+        }
+        Action::Rotate(_, r) => {
+            // positive angles turn clockwise.
+            let clockwise = (r - piece.angle).rem_euclid(TAU) <= PI;
+            let arrow = if clockwise { '↻' } else { '↺' };
+            format!("{who} {arrow} {:.0}°", rotation_degrees(piece.angle, r))
+        }
+    })
+}
+
+/// Where in the game the board we're looking at is, like "Move 7 · Turn 14 / 30": the
+/// chess move number, like in the move list, then how many of the history's turns are on
+/// the board out of how many there are. Looking back says so.
+pub fn turn_counter(history: &History) -> String {
+    let counter = format!(
+        "Move {} \u{b7} Turn {} / {}",
+        history.turns_played() / 2 + 1,
+        history.current(),
+        history.len()
+    );
+    if history.at_live() {
+        counter
+    } else {
+        format!("{counter} \u{2014} viewing past position")
+    }
+}
+
+#[cfg(test)]
+mod test_notation {
+    use rotchess_core::emulator::ThingHappened;
+
+    use super::*;
+
+    fn piece(kind: PieceKind, side: Side, x: f32, y: f32) -> PieceSnapshot {
+        PieceSnapshot {
+            kind,
+            side,
+            x,
+            y,
+            angle: 0.,
+        }
+    }
+
+    #[test]
+    fn captures_name_both_pieces() {
+        let before = [
+            piece(PieceKind::Rook, Side::Black, 0.5, 0.5),
+            piece(PieceKind::Bishop, Side::White, 0.5, 4.5),
+        ];
+        assert_eq!(
+            describe(&before, &Action::Move(0, 0.5, 4.5)).unwrap(),
+            "Black rook captures white bishop"
+        );
+        assert_eq!(
+            describe(&before, &Action::Move(0, 0.5, 3.5)).unwrap(),
+            "Black rook moves"
+        );
+    }
+
+    #[test]
+    fn no_capturing_your_own() {
+        let before = [
+            piece(PieceKind::Rook, Side::White, 0.5, 0.5),
+            piece(PieceKind::Bishop, Side::White, 0.5, 4.5),
+        ];
+        assert_eq!(captured_by(&before, 0, 0.5, 4.5), None);
+    }
+
+    #[test]
+    fn rotations_go_the_short_way() {
+        assert!((rotation_degrees(0., 48f32.to_radians()) - 48.).abs() < 1e-3);
+        assert!((rotation_degrees(0., -90f32.to_radians()) - 90.).abs() < 1e-3);
+        assert!((rotation_degrees(0.1, 0.1 + TAU + PI / 2.) - 90.).abs() < 1e-3);
+
+        let before = [piece(PieceKind::Knight, Side::White, 0.5, 0.5)];
+        assert_eq!(
+            describe(&before, &Action::Rotate(0, 48f32.to_radians())).unwrap(),
+            "White rotates knight 48°"
+        );
+    }
+
+    #[test]
+    fn text_diagrams_note_turned_pieces() {
+        let board = [
+            piece(PieceKind::Rook, Side::Black, 0.5, 0.5),
+            PieceSnapshot {
+                angle: 45f32.to_radians(),
+                ..piece(PieceKind::Knight, Side::White, 1.3, 7.9)
+            },
+            PieceSnapshot {
+                angle: PI,
+                ..piece(PieceKind::Pawn, Side::Black, 4.5, 1.5)
+            },
+        ];
+        assert_eq!(
+            text_diagram(&board),
+            "```\n\
+             8 ♜ · · · · · · ·\n\
+             7 · · · · ♟ · · ·\n\
+             6 · · · · · · · ·\n\
+             5 · · · · · · · ·\n\
+             4 · · · · · · · ·\n\
+             3 · · · · · · · ·\n\
+             2 · · · · · · · ·\n\
+             1 · ♘ · · · · · ·\n\
+             \x20 a b c d e f g h\n\
+             turned: ♘b1 45°\n\
+             ```\n"
+        );
+    }
+
+    #[test]
+    fn angle_labels_wrap_around() {
+        assert_eq!(angle_label(45f32.to_radians()), "45°");
+        assert_eq!(angle_label(-90f32.to_radians()), "270°");
+        // would round up to 360 otherwise.
+        assert_eq!(angle_label(359.8f32.to_radians()), "0°");
+    }
+
+    #[test]
+    fn missing_pieces_have_no_description() {
+        assert_eq!(describe(&[], &Action::Rotate(3, 1.)), None);
+        assert_eq!(short(&[], &Action::Move(3, 1., 1.)), None);
+    }
+
+    #[test]
+    fn short_notation_names_the_piece_and_its_square() {
+        let before = [
+            piece(PieceKind::Knight, Side::White, 1.5, 7.5),
+            piece(PieceKind::Pawn, Side::Black, 3.4, 2.1),
+            piece(PieceKind::Queen, Side::White, 3.5, 7.5),
+        ];
+        assert_eq!(
+            short(&before, &Action::Move(0, 2.5, 5.5)).unwrap(),
+            "Nb1 → (2.5, 5.5)"
+        );
+        assert_eq!(
+            short(&before, &Action::Move(0, 3.4, 2.1)).unwrap(),
+            "Nb1 × (3.4, 2.1)"
+        );
+        assert_eq!(
+            short(&before, &Action::Move(1, 3.5, 3.1)).unwrap(),
+            "d6 → (3.5, 3.1)"
+        );
+    }
+
+    #[test]
+    fn short_notation_says_which_way_a_piece_turned() {
+        let before = [piece(PieceKind::Queen, Side::White, 3.5, 7.5)];
+        assert_eq!(
+            short(&before, &Action::Rotate(0, 37f32.to_radians())).unwrap(),
+            "Qd1 ↻ 37°"
+        );
+        assert_eq!(
+            short(&before, &Action::Rotate(0, -37f32.to_radians())).unwrap(),
+            "Qd1 ↺ 37°"
+        );
+    }
+
+    #[test]
+    fn turn_counters_follow_the_board_being_looked_at() {
+        let mut history = History::default();
+        assert_eq!(turn_counter(&history), "Move 1 \u{b7} Turn 0 / 0");
+        for _ in 0..3 {
+            history.record(&ThingHappened::Move(0, 1., 1.));
+            history.record(&ThingHappened::Rotate(0, 0.));
+        }
+        assert_eq!(turn_counter(&history), "Move 2 \u{b7} Turn 6 / 6");
+        history.record(&ThingHappened::PrevTurn);
+        history.record(&ThingHappened::PrevTurn);
+        assert_eq!(
+            turn_counter(&history),
+            "Move 2 \u{b7} Turn 4 / 6 \u{2014} viewing past position"
+        );
+        history.record(&ThingHappened::FirstTurn);
+        assert_eq!(
+            turn_counter(&history),
+            "Move 1 \u{b7} Turn 0 / 6 \u{2014} viewing past position"
+        );
+    }
+
+    #[test]
+    fn squares_stay_on_the_board() {
+        assert_eq!(square_name(0.5, 0.5), "a8");
+        assert_eq!(square_name(7.9, 7.9), "h1");
+        assert_eq!(square_name(-0.2, 8.3), "a1");
+    }
+}
@@ -0,0 +1,136 @@
+//! Toasts: short messages across the top of the board for things the user should know
+//! about right away, like trying to rotate before moving, that fade out after a few seconds.
+//!
+//! Unlike the [ticker](crate::ticker), which tells the story of the game, these are about
+//! something the user just tried.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use ggez::{
+    Context, GameResult,
+    glam::Vec2,
+    graphics::{Canvas, DrawMode, DrawParam, Mesh, Rect, Text},
+};
+
+use crate::{
+    bench::RenderCounters,
+    constants::{BANNER_COLOR, BANNER_TEXT_COLOR, NOTIFICATION_TEXT_SIZE},
+    effects::Fade,
+};
+
+/// How long a toast stays up.
+pub const NOTIFICATION_DURATION: Duration = Duration::from_secs(3);
+
+/// How many toasts are up at once.
+pub const NOTIFICATIONS: usize = 3;
+
+#[derive(Default)]
+pub struct Notifications {
+    toasts: VecDeque<(String, Fade)>,
+}
+
+impl Notifications {
+    /// Put up a toast. The same message as the newest one doesn't stack up, it just stays
+    /// up for longer.
+    pub fn push(&mut self, message: String) {
+        if self
+            .toasts
+            .back()
+            .is_some_and(|(newest, _)| *newest == message)
+        {
+            self.toasts.pop_back();
+        }
+        self.toasts
+            .push_back((message, Fade::new(NOTIFICATION_DURATION)));
+        if self.toasts.len() > NOTIFICATIONS {
+            self.toasts.pop_front();
+        }
+    }
+
+    /// Let go of toasts that have faded away.
+    pub fn update(&mut self, now: Instant) {
+        self.toasts.retain(|(_, fade)| !fade.is_done(now));
+    }
+
+    /// Draw the toasts down from the top of `frame`, centered, oldest first.
+    pub fn draw(
+        &self,
+        (ctx, canvas): (&mut Context, &mut Canvas),
+        frame: Rect,
+        counters: &RenderCounters,
+        reduced_motion: bool,
+    ) -> GameResult {
+        /// Space between a toast's text and the edge of its background, in pixels.
+        const PADDING: f32 = 8.;
+        /// Space between toasts, and between the first one and the top of the board.
+        const GAP: f32 = 12.;
+
+        let now = Instant::now();
+        let mut y = frame.y + frame.h / 8.;
+        for (message, fade) in &self.toasts {
+            let opacity = fade.visibility(now, reduced_motion);
+            let mut text = Text::new(message.as_str());
+            text.set_scale(NOTIFICATION_TEXT_SIZE);
+            let text_size = text.measure(ctx)?;
+            let (w, h) = (text_size.x + 2. * PADDING, text_size.y + 2. * PADDING);
+            let x = frame.x + (frame.w - w) / 2.;
+
+            let mut background = BANNER_COLOR;
+            background.a *= opacity;
+            let mesh =
+                Mesh::new_rectangle(ctx, DrawMode::fill(), Rect::new(0., 0., w, h), background)?;
+            counters.count_mesh_built();
+            counters.count_draw_call();
+            canvas.draw(&mesh, Vec2::new(x, y));
+            let mut color = BANNER_TEXT_COLOR;
+            color.a *= opacity;
+            counters.count_draw_call();
+            canvas.draw(
+                &text,
+                DrawParam::new()
+                    .dest(Vec2::new(x + PADDING, y + PADDING))
+                    .color(color),
+            );
+            y += h + GAP;
+        }
+        Ok(())
+    }
+
+    /// The messages up right now, oldest first.
+    pub fn messages(&self) -> impl Iterator<Item = &str> {
+        self.toasts.iter().map(|(message, _)| message.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test_notifications {
+    use super::*;
+
+    #[test]
+    fn keeps_only_the_newest_toasts() {
+        let mut notifications = Notifications::default();
+        for i in 0..NOTIFICATIONS + 2 {
+            notifications.push(i.to_string());
+        }
+        assert!(notifications.messages().eq(["2", "3", "4"]));
+    }
+
+    #[test]
+    fn repeats_dont_stack_up() {
+        let mut notifications = Notifications::default();
+        notifications.push("Move first!".to_string());
+        notifications.push("Move first!".to_string());
+        assert!(notifications.messages().eq(["Move first!"]));
+    }
+
+    #[test]
+    fn toasts_fade_away() {
+        let mut notifications = Notifications::default();
+        notifications.push("It's not your turn!".to_string());
+        notifications.update(Instant::now() + NOTIFICATION_DURATION);
+        assert_eq!(notifications.messages().count(), 0);
+    }
+}
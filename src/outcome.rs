@@ -0,0 +1,92 @@
+//! Telling when a game is over on the board: when a king is taken, which wins the game for
+//! whoever took it, or when the side to move has nowhere to go, which is a draw.
+//!
+//! Running out of time and leaving end games too, but those are up to the
+//! [clock](crate::clock) and the netcode.
+
+use rotchess_core::{
+    RotchessEmulator,
+    piece::{PieceKind, Side},
+};
+
+use crate::{bot, notation};
+
+/// How a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win(Side),
+    Draw,
+}
+
+impl Outcome {
+    /// Like "White wins".
+    pub fn describe(&self) -> String {
+        match self {
+            Outcome::Win(side) => format!("{} wins", notation::side_name(*side)),
+            Outcome::Draw => "Draw".to_string(),
+        }
+    }
+}
+
+/// Whether the game on `chess` is over. `to_move` is the side whose move it is, if one is
+/// due, rather than a rotation.
+pub fn board_outcome(chess: &mut RotchessEmulator, to_move: Option<Side>) -> Option<Outcome> {
+    let has_king = |side: Side| {
+        chess
+            .pieces()
+            .iter()
+            .any(|piece| piece.side() == side && piece.kind() == PieceKind::King)
+    };
+    match (has_king(Side::White), has_king(Side::Black)) {
+        (true, false) => return Some(Outcome::Win(Side::White)),
+        (false, true) => return Some(Outcome::Win(Side::Black)),
+        _ => (),
+    }
+    let to_move = to_move?;
+    bot::move_options(chess, to_move)
+        .is_empty()
+        .then_some(Outcome::Draw)
+}
+
+#[cfg(test)]
+mod test_outcome {
+    use rotchess_core::{emulator::Event, piece::Pieces};
+
+    use super::*;
+
+    #[test]
+    fn games_start_undecided() {
+        let mut chess = RotchessEmulator::with(Pieces::standard_board());
+        assert_eq!(board_outcome(&mut chess, Some(Side::White)), None);
+        assert_eq!(board_outcome(&mut chess, None), None);
+    }
+
+    #[test]
+    fn taking_the_king_wins() {
+        let mut chess = RotchessEmulator::with(Pieces::standard_board());
+        let king = |chess: &RotchessEmulator, side| {
+            chess
+                .pieces()
+                .iter()
+                .position(|piece| piece.side() == side && piece.kind() == PieceKind::King)
+                .unwrap()
+        };
+        let (black_king, white_king) = (king(&chess, Side::Black), king(&chess, Side::White));
+        let (x, y) = (
+            chess.pieces()[black_king].x(),
+            chess.pieces()[black_king].y(),
+        );
+        // not a move anyone could make, but the emulator takes the king all the same.
+        chess.handle_event(Event::MoveUnchecked(white_king, x, y));
+        assert_eq!(
+            board_outcome(&mut chess, None),
+            Some(Outcome::Win(Side::White))
+        );
+    }
+
+    #[test]
+    fn outcomes_read_like_results() {
+        assert_eq!(Outcome::Win(Side::Black).describe(), "Black wins");
+        assert_eq!(Outcome::Draw.describe(), "Draw");
+    }
+}
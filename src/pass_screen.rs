@@ -0,0 +1,159 @@
+//! The pass-and-play screen: in a hot-seat game, the board is hidden between turns until
+//! whoever's playing next says they're ready, so nothing is seen that shouldn't be while the
+//! computer changes hands.
+//!
+//! It's off unless asked for with `--pass-screen`.
+
+use ggez::{
+    Context, GameResult,
+    glam::Vec2,
+    graphics::{Canvas, DrawMode, DrawParam, Mesh, Rect, Text, TextLayout},
+};
+use rotchess_core::piece::Side;
+
+use crate::{
+    bench::RenderCounters,
+    constants::{BANNER_COLOR, BANNER_TEXT_COLOR, BANNER_TEXT_SIZE},
+    notation,
+    theme::Theme,
+};
+
+/// How big the ready button is, in pixels.
+const BUTTON_SIZE: Vec2 = Vec2::new(160., 56.);
+
+/// Whether to hide the board between hot-seat turns, from `--pass-screen`.
+pub fn pass_screen_from_args() -> bool {
+    std::env::args().any(|arg| arg == "--pass-screen")
+}
+
+#[derive(Debug, Default)]
+pub struct PassScreen {
+    /// Who the computer is being passed to, while the screen is up.
+    passing_to: Option<Side>,
+    /// How many turns had been played the last time we looked.
+    turns_seen: usize,
+}
+
+impl PassScreen {
+    /// Put the screen up if a turn was finished since we last looked, for `next` to take
+    /// over. Turns taken back or a fresh board don't need passing anything.
+    pub fn update(&mut self, turns_played: usize, next: Side) {
+        if turns_played > self.turns_seen {
+            self.passing_to = Some(next);
+        }
+        self.turns_seen = turns_played;
+    }
+
+    /// Who the computer is being passed to, if the screen is up.
+    pub fn passing_to(&self) -> Option<Side> {
+        self.passing_to
+    }
+
+    /// Whoever we were passing to has it, so show the board again.
+    pub fn ready(&mut self) {
+        self.passing_to = None;
+    }
+
+    /// Where the ready button is, on a window this big.
+    pub fn button(width: f32, height: f32) -> Rect {
+        Rect::new(
+            (width - BUTTON_SIZE.x) / 2.,
+            height / 2. + BUTTON_SIZE.y,
+            BUTTON_SIZE.x,
+            BUTTON_SIZE.y,
+        )
+    }
+
+    /// Cover the whole window, if the screen is up, saying who to pass to.
+    pub fn draw(
+        &self,
+        (ctx, canvas): (&mut Context, &mut Canvas),
+        theme: &Theme,
+        counters: &RenderCounters,
+    ) -> GameResult {
+        let Some(side) = self.passing_to else {
+            return Ok(());
+        };
+        let (width, height) = ctx.gfx.drawable_size();
+        let cover = Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(0., 0., width, height),
+            theme.background,
+        )?;
+        counters.count_mesh_built();
+        counters.count_draw_call();
+        canvas.draw(&cover, DrawParam::new());
+
+        let mut message = Text::new(format!(
+            "Pass the computer to {}.",
+            notation::side_name(side)
+        ));
+        message
+            .set_scale(BANNER_TEXT_SIZE)
+            .set_layout(TextLayout::center());
+        counters.count_draw_call();
+        canvas.draw(
+            &message,
+            DrawParam::new()
+                .dest(Vec2::new(width / 2., height / 2.))
+                .color(theme.dialog_text),
+        );
+
+        let button = Self::button(width, height);
+        let background = Mesh::new_rectangle(ctx, DrawMode::fill(), button, BANNER_COLOR)?;
+        counters.count_mesh_built();
+        counters.count_draw_call();
+        canvas.draw(&background, DrawParam::new());
+        let mut label = Text::new("Ready");
+        label
+            .set_scale(BANNER_TEXT_SIZE)
+            .set_layout(TextLayout::center());
+        counters.count_draw_call();
+        canvas.draw(
+            &label,
+            DrawParam::new()
+                .dest(button.center())
+                .color(BANNER_TEXT_COLOR),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_pass_screen {
+    use super::*;
+
+    #[test]
+    fn goes_up_after_each_turn() {
+        let mut screen = PassScreen::default();
+        screen.update(0, Side::White);
+        assert_eq!(screen.passing_to(), None);
+        screen.update(1, Side::Black);
+        assert_eq!(screen.passing_to(), Some(Side::Black));
+        screen.ready();
+        screen.update(1, Side::Black);
+        assert_eq!(screen.passing_to(), None);
+        screen.update(2, Side::White);
+        assert_eq!(screen.passing_to(), Some(Side::White));
+    }
+
+    #[test]
+    fn taking_back_passes_nothing() {
+        let mut screen = PassScreen::default();
+        screen.update(3, Side::Black);
+        screen.ready();
+        screen.update(2, Side::White);
+        assert_eq!(screen.passing_to(), None);
+        // but the turn after that is passed on again.
+        screen.update(3, Side::Black);
+        assert_eq!(screen.passing_to(), Some(Side::Black));
+    }
+
+    #[test]
+    fn the_button_is_below_the_middle() {
+        let button = PassScreen::button(800., 600.);
+        assert!(button.top() > 300.);
+        assert_eq!(button.center().x, 400.);
+    }
+}
@@ -0,0 +1,311 @@
+//! Saved games (`Ctrl+S`): a replay of the game so far, after the board it ended on.
+//!
+//! ```text
+//! rotchess-save 1
+//! mode bot black 0.5 0.5 0.3
+//! piece white rook 0.5 0.5 0
+//! piece white knight 1.5 0.5 0
+//! ...
+//! rotchess-replay 1
+//! move 12 3.5 4.5
+//! ...
+//! ```
+//!
+//! The mode says who the game was against, so it picks up that way again: `mode hot-seat`,
+//! `mode bot <our side> <aggressiveness> <rotation happiness> <temperature>` or
+//! `mode network <our side>`. Saves from before there was a mode line are hot-seat games.
+//!
+//! The board is one `piece <side> <kind> <x> <y> <angle>` line per piece, in the emulator's
+//! order. The replay alone is enough to pick the game up again, so the board is there to
+//! check it against: if playing the replay back doesn't land on it, say because the rules
+//! changed since, the save won't load (see [`SavedGame::check`]).
+//!
+//! Plain replay files load too, as hot-seat games without a board to check.
+
+use std::{fmt::Write, path::Path};
+
+use ggez::{GameError, GameResult};
+use rotchess_core::piece::{PieceKind, Side};
+
+use crate::{
+    engine::Personality,
+    notation::{PieceSnapshot, kind_name},
+    replay::{self, Replay},
+};
+
+const HEADER: &str = "rotchess-save 1";
+
+/// Every kind of piece, to look them up by name.
+const KINDS: [PieceKind; 6] = [
+    PieceKind::Pawn,
+    PieceKind::Rook,
+    PieceKind::Knight,
+    PieceKind::Bishop,
+    PieceKind::Queen,
+    PieceKind::King,
+];
+
+/// Who a saved game was against.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Mode {
+    /// Both sides played at this computer.
+    #[default]
+    HotSeat,
+    /// We played `side` against the computer.
+    Bot {
+        side: Side,
+        personality: Personality,
+    },
+    /// We played `side` against someone over the network.
+    Network { side: Side },
+}
+
+pub struct SavedGame {
+    pub mode: Mode,
+    /// The board the game was saved on, if we know it.
+    pub board: Option<Vec<PieceSnapshot>>,
+    pub replay: Replay,
+}
+
+impl SavedGame {
+    pub fn save(&self, path: &Path) -> GameResult {
+        Ok(std::fs::write(path, self.to_text())?)
+    }
+
+    pub fn load(path: &Path) -> GameResult<Self> {
+        Self::from_text(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn to_text(&self) -> String {
+        let Some(board) = &self.board else {
+            return self.replay.to_text();
+        };
+        let mut text = format!("{HEADER}\n");
+        // writing to a String can't fail.
+        match self.mode {
+            Mode::HotSeat => writeln!(text, "mode hot-seat"),
+            Mode::Bot { side, personality } => writeln!(
+                text,
+                "mode bot {} {} {} {}",
+                side_word(side),
+                personality.aggressiveness,
+                personality.rotation_happiness,
+                personality.temperature
+            ),
+            Mode::Network { side } => writeln!(text, "mode network {}", side_word(side)),
+        }
+        .unwrap();
+        for piece in board {
+            writeln!(
+                text,
+                "piece {} {} {} {} {}",
+                side_word(piece.side),
+                kind_name(piece.kind),
+                piece.x,
+                piece.y,
+                piece.angle
+            )
+            .unwrap();
+        }
+        text.push_str(&self.replay.to_text());
+        text
+    }
+
+    pub fn from_text(text: &str) -> GameResult<Self> {
+        if text.lines().next().map(str::trim) == Some(replay::HEADER) {
+            return Ok(Self {
+                mode: Mode::HotSeat,
+                board: None,
+                replay: Replay::from_text(text)?,
+            });
+        }
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+            return Err(malformed(1, "missing header"));
+        }
+
+        let mut mode = Mode::HotSeat;
+        let mut board = Vec::new();
+        for (i, line) in lines {
+            let line_no = i + 1;
+            if line.trim() == replay::HEADER {
+                // the replay is the rest of the file, header and all.
+                let replay: Vec<&str> = text.lines().skip(i).collect();
+                return Ok(Self {
+                    mode,
+                    board: Some(board),
+                    replay: Replay::from_text(&replay.join("\n"))?,
+                });
+            }
+            let number = |word: &str| {
+                word.parse::<f32>()
+                    .map_err(|_| malformed(line_no, "expected a number"))
+            };
+            let side = |word: &str| match word {
+                "white" => Ok(Side::White),
+                "black" => Ok(Side::Black),
+                _ => Err(malformed(line_no, "expected white or black")),
+            };
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                [] => (),
+                ["mode", "hot-seat"] => mode = Mode::HotSeat,
+                [
+                    "mode",
+                    "bot",
+                    ours,
+                    aggressiveness,
+                    rotation_happiness,
+                    temperature,
+                ] => {
+                    mode = Mode::Bot {
+                        side: side(ours)?,
+                        personality: Personality {
+                            aggressiveness: number(aggressiveness)?,
+                            rotation_happiness: number(rotation_happiness)?,
+                            temperature: number(temperature)?,
+                        },
+                    }
+                }
+                ["mode", "network", ours] => mode = Mode::Network { side: side(ours)? },
+                ["mode", ..] => return Err(malformed(line_no, "unknown mode")),
+                ["piece", color, kind, x, y, angle] => {
+                    board.push(PieceSnapshot {
+                        side: side(color)?,
+                        kind: KINDS
+                            .into_iter()
+                            .find(|&k| kind_name(k) == kind)
+                            .ok_or_else(|| malformed(line_no, "unknown kind of piece"))?,
+                        x: number(x)?,
+                        y: number(y)?,
+                        angle: number(angle)?,
+                    });
+                }
+                _ => return Err(malformed(line_no, "expected a piece")),
+            }
+        }
+        Err(malformed(text.lines().count(), "missing replay"))
+    }
+
+    /// Check that a board the replay was played out on is the one it was saved with.
+    pub fn check(&self, played_out: &[PieceSnapshot]) -> GameResult {
+        match &self.board {
+            Some(board) if board != played_out => Err(GameError::CustomError(
+                "That game's moves don't lead to the board it was saved with. \
+                 Was it saved by another version?"
+                    .to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn side_word(side: Side) -> &'static str {
+    match side {
+        Side::White => "white",
+        Side::Black => "black",
+    }
+}
+
+fn malformed(line_no: usize, why: &str) -> GameError {
+    GameError::CustomError(format!("Malformed save at line {line_no}: {why}."))
+}
+
+#[cfg(test)]
+mod test_save_format {
+    use super::*;
+    use crate::history::Action;
+    use parameterized::parameterized;
+    use proptest::prelude::*;
+
+    fn arb_piece() -> impl Strategy<Value = PieceSnapshot> {
+        (
+            any::<bool>(),
+            0..KINDS.len(),
+            -8f32..16.,
+            -8f32..16.,
+            -10f32..10.,
+        )
+            .prop_map(|(white, kind, x, y, angle)| PieceSnapshot {
+                kind: KINDS[kind],
+                side: if white { Side::White } else { Side::Black },
+                x,
+                y,
+                angle,
+            })
+    }
+
+    fn saved_game(board: Option<Vec<PieceSnapshot>>) -> SavedGame {
+        SavedGame {
+            mode: Mode::HotSeat,
+            board,
+            replay: Replay {
+                actions: vec![Action::Move(12, 3.5, 4.25), Action::Rotate(12, 1.5707964)],
+                comments: vec![(1, "rotchess-replay 1".to_string())],
+                ..Replay::default()
+            },
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn any_board_round_trips(board in proptest::collection::vec(arb_piece(), 0..40)) {
+            let saved = SavedGame::from_text(&saved_game(Some(board.clone())).to_text()).unwrap();
+            prop_assert_eq!(saved.board, Some(board));
+            prop_assert_eq!(saved.replay.to_text(), saved_game(None).replay.to_text());
+        }
+    }
+
+    #[test]
+    fn plain_replays_load_without_a_board() {
+        let saved = SavedGame::from_text(&saved_game(None).to_text()).unwrap();
+        assert_eq!(saved.board, None);
+        assert_eq!(saved.replay.actions.len(), 2);
+    }
+
+    #[parameterized(mode = {
+        Mode::HotSeat,
+        Mode::Bot { side: Side::Black, personality: Personality::default() },
+        Mode::Network { side: Side::White },
+    })]
+    fn modes_round_trip(mode: Mode) {
+        let game = SavedGame {
+            mode,
+            ..saved_game(Some(Vec::new()))
+        };
+        assert_eq!(SavedGame::from_text(&game.to_text()).unwrap().mode, mode);
+    }
+
+    #[test]
+    fn saves_from_before_modes_are_hot_seat() {
+        let saved = SavedGame::from_text(&format!("{HEADER}\n{}", saved_game(None).to_text()));
+        assert_eq!(saved.unwrap().mode, Mode::HotSeat);
+    }
+
+    #[test]
+    fn garbage_is_an_error() {
+        assert!(SavedGame::from_text("not a save").is_err());
+        assert!(SavedGame::from_text(&format!("{HEADER}\n")).is_err());
+        assert!(SavedGame::from_text(&format!("{HEADER}\npiece white rook 1 2")).is_err());
+        assert!(SavedGame::from_text(&format!("{HEADER}\npiece red rook 1 2 0")).is_err());
+        assert!(SavedGame::from_text(&format!("{HEADER}\npiece white duke 1 2 0")).is_err());
+        assert!(SavedGame::from_text(&format!("{HEADER}\nmove 1 2 3")).is_err());
+        assert!(SavedGame::from_text(&format!("{HEADER}\nmode bot white 1 2")).is_err());
+        assert!(SavedGame::from_text(&format!("{HEADER}\nmode chess-by-mail")).is_err());
+    }
+
+    #[test]
+    fn saves_check_their_board() {
+        let piece = PieceSnapshot {
+            kind: PieceKind::King,
+            side: Side::Black,
+            x: 4.5,
+            y: 7.5,
+            angle: 0.,
+        };
+        let saved = saved_game(Some(vec![piece]));
+        assert!(saved.check(&[piece]).is_ok());
+        assert!(saved.check(&[PieceSnapshot { x: 5.5, ..piece }]).is_err());
+        assert!(saved_game(None).check(&[]).is_ok());
+    }
+}
@@ -0,0 +1,193 @@
+//! Where one piece went over a game, and how it was turned, for looking back at long
+//! maneuvers.
+//!
+//! The emulator only knows pieces by their index on the board, which shifts as pieces are
+//! captured, so we follow a piece from board to board instead: the one an action moved or
+//! rotated is wherever the action put it, and any other piece stays exactly as it was.
+
+use crate::{history::Action, notation::PieceSnapshot};
+
+/// A piece as it was after some number of actions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stop {
+    /// How many actions had been played when the piece got here.
+    pub turn: usize,
+    pub piece: PieceSnapshot,
+}
+
+/// Every place a piece stood and every angle it held, in order, from where it started (or
+/// was promoted) to where it ended up (or was captured).
+///
+/// `boards` are the boards after each number of actions, so one more than there are
+/// `actions`, and the piece is the one at `piece_idx` on `boards[turn]`.
+pub fn piece_history(
+    boards: &[Vec<PieceSnapshot>],
+    actions: &[Action],
+    turn: usize,
+    piece_idx: usize,
+) -> Vec<Stop> {
+    let Some(&piece) = boards.get(turn).and_then(|board| board.get(piece_idx)) else {
+        return Vec::new();
+    };
+
+    let mut earlier = Vec::new();
+    let mut here = piece;
+    for t in (0..turn).rev() {
+        let Some(before) = trace_back(&boards[t], actions[t], here) else {
+            break;
+        };
+        earlier.push((t, before));
+        here = before;
+    }
+
+    let mut later = Vec::new();
+    let mut here = piece;
+    for t in turn..actions.len().min(boards.len() - 1) {
+        let Some(after) = follow(&boards[t], &boards[t + 1], actions[t], here) else {
+            break;
+        };
+        later.push((t + 1, after));
+        here = after;
+    }
+
+    let mut stops: Vec<Stop> = Vec::new();
+    let everything = earlier
+        .into_iter()
+        .rev()
+        .chain([(turn, piece)])
+        .chain(later);
+    for (turn, piece) in everything {
+        if stops.last().is_none_or(|stop| stop.piece != piece) {
+            stops.push(Stop { turn, piece });
+        }
+    }
+    stops
+}
+
+/// The action's own piece, if it's `piece`.
+fn acted_on(board: &[PieceSnapshot], action: Action, piece: PieceSnapshot) -> bool {
+    let (Action::Move(piece_idx, ..) | Action::Rotate(piece_idx, _)) = action;
+    board.get(piece_idx) == Some(&piece)
+}
+
+/// Whether two snapshots are a piece of one side standing on the same spot. No two pieces
+/// of a side can, so that's the same piece, even if it's been turned or promoted.
+fn same_spot(a: &PieceSnapshot, b: &PieceSnapshot) -> bool {
+    a.side == b.side && a.x == b.x && a.y == b.y
+}
+
+/// Where `piece`, on `before`, is after `action` turned it into `after`.
+fn follow(
+    before: &[PieceSnapshot],
+    after: &[PieceSnapshot],
+    action: Action,
+    piece: PieceSnapshot,
+) -> Option<PieceSnapshot> {
+    if !acted_on(before, action, piece) {
+        // captured, if it's gone.
+        return after.iter().find(|p| **p == piece).copied();
+    }
+    let landed = match action {
+        Action::Move(_, x, y) => PieceSnapshot { x, y, ..piece },
+        Action::Rotate(..) => piece,
+    };
+    after.iter().find(|p| same_spot(p, &landed)).copied()
+}
+
+/// Where `piece` was on `before`, the board right before `action`.
+fn trace_back(
+    before: &[PieceSnapshot],
+    action: Action,
+    piece: PieceSnapshot,
+) -> Option<PieceSnapshot> {
+    let (Action::Move(piece_idx, ..) | Action::Rotate(piece_idx, _)) = action;
+    let actor = before.get(piece_idx).copied();
+    let arrived = match action {
+        Action::Move(_, x, y) => actor.map(|actor| PieceSnapshot { x, y, ..actor }),
+        Action::Rotate(..) => actor,
+    };
+    if let (Some(actor), Some(arrived)) = (actor, arrived)
+        && same_spot(&arrived, &piece)
+    {
+        return Some(actor);
+    }
+    before.iter().find(|p| **p == piece).copied()
+}
+
+#[cfg(test)]
+mod test_piece_history {
+    use rotchess_core::piece::{PieceKind, Side};
+
+    use super::*;
+
+    fn piece(kind: PieceKind, side: Side, x: f32, y: f32) -> PieceSnapshot {
+        PieceSnapshot {
+            kind,
+            side,
+            x,
+            y,
+            angle: 0.,
+        }
+    }
+
+    #[test]
+    fn follows_a_piece_through_captures_that_shift_indexes() {
+        let rook = piece(PieceKind::Rook, Side::White, 0.5, 7.5);
+        let pawn = piece(PieceKind::Pawn, Side::Black, 3.5, 1.5);
+        let knight = piece(PieceKind::Knight, Side::Black, 0.5, 3.5);
+        let actions = [
+            Action::Move(0, 0.5, 3.5),
+            Action::Rotate(0, 1.),
+            Action::Move(1, 3.5, 2.5),
+            Action::Move(0, 3.5, 3.5),
+        ];
+        let rook_up = PieceSnapshot { y: 3.5, ..rook };
+        let rook_turned = PieceSnapshot {
+            angle: 1.,
+            ..rook_up
+        };
+        let pawn_down = PieceSnapshot { y: 2.5, ..pawn };
+        let rook_over = PieceSnapshot {
+            x: 3.5,
+            ..rook_turned
+        };
+        let boards = [
+            vec![rook, knight, pawn],
+            // the rook took the knight, so the pawn moved up an index.
+            vec![rook_up, pawn],
+            vec![rook_turned, pawn],
+            vec![rook_turned, pawn_down],
+            vec![rook_over, pawn_down],
+        ];
+
+        let rook_stops = |turn, piece_idx| {
+            piece_history(&boards, &actions, turn, piece_idx)
+                .iter()
+                .map(|stop| (stop.turn, stop.piece))
+                .collect::<Vec<_>>()
+        };
+        let expected = vec![(0, rook), (1, rook_up), (2, rook_turned), (4, rook_over)];
+        assert_eq!(rook_stops(0, 0), expected);
+        assert_eq!(rook_stops(3, 0), expected);
+
+        let pawn_stops = piece_history(&boards, &actions, 4, 1);
+        assert_eq!(
+            pawn_stops.iter().map(|stop| stop.turn).collect::<Vec<_>>(),
+            [0, 3]
+        );
+
+        let knight_stops = piece_history(&boards, &actions, 0, 1);
+        assert_eq!(
+            knight_stops,
+            [Stop {
+                turn: 0,
+                piece: knight
+            }]
+        );
+    }
+
+    #[test]
+    fn no_piece_no_history() {
+        assert_eq!(piece_history(&[Vec::new()], &[], 0, 0), []);
+    }
+}
@@ -0,0 +1,186 @@
+//! Playback mode: a replay (or saved game) played back at the pace it was played, for
+//! watching a game again rather than picking it up.
+//!
+//! The watcher can pause (space), speed up or slow down (`+`/`-`), and look back through
+//! what's been played so far with the arrow keys or the scrubber, like in an exhibition.
+//!
+//! Run with `cargo run -- --replay=replay-123.rotchess`.
+
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{history::Action, replay::Replay};
+
+/// How long to wait before an action the replay has no time for.
+const DEFAULT_WAIT: Duration = Duration::from_millis(800);
+
+/// The longest we wait before an action at normal speed, however long it took in the game.
+/// Nobody wants to rewatch a five minute think.
+const MAX_WAIT: Duration = Duration::from_secs(4);
+
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 16.;
+
+/// The replay to play back, from `--replay=path`.
+pub fn replay_path_from_args() -> Option<PathBuf> {
+    std::env::args().find_map(|arg| match arg.split_once("=") {
+        Some(("--replay", path)) => Some(PathBuf::from(path)),
+        _ => None,
+    })
+}
+
+/// A replay being played back.
+pub struct Playback {
+    actions: Vec<Action>,
+    /// How long each action took in the game, where the replay knows.
+    times: Vec<Duration>,
+    /// The index of the next action to play.
+    next: usize,
+    /// How many times faster than it was played the game plays back.
+    speed: f32,
+    paused: bool,
+    /// When the next action is due.
+    next_action_at: Instant,
+}
+
+impl Playback {
+    pub fn new(replay: &Replay, now: Instant) -> Self {
+        let mut playback = Self {
+            actions: replay.actions.clone(),
+            times: replay.times.clone(),
+            next: 0,
+            speed: 1.,
+            paused: false,
+            next_action_at: now,
+        };
+        playback.next_action_at = now + playback.wait_before(0);
+        playback
+    }
+
+    /// How long to wait before playing some action, at the current speed. (In f64, so that
+    /// waits at normal speed come out to the millisecond.)
+    fn wait_before(&self, i: usize) -> Duration {
+        self.times
+            .get(i)
+            .copied()
+            .unwrap_or(DEFAULT_WAIT)
+            .min(MAX_WAIT)
+            .div_f64(self.speed as f64)
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Whether every action has been played.
+    pub fn finished(&self) -> bool {
+        self.next >= self.actions.len()
+    }
+
+    /// How many actions have been played, and how many there are.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.next, self.actions.len())
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.next_action_at = Instant::now() + self.wait_before(self.next);
+    }
+
+    pub fn speed_up(&mut self) {
+        self.speed = (self.speed * 2.).min(MAX_SPEED);
+    }
+
+    pub fn slow_down(&mut self) {
+        self.speed = (self.speed / 2.).max(MIN_SPEED);
+    }
+
+    /// The next action, if it's time to play it. If so, the one after it is scheduled.
+    pub fn take_due_action(&mut self, now: Instant) -> Option<Action> {
+        if self.paused || now < self.next_action_at {
+            return None;
+        }
+        let action = *self.actions.get(self.next)?;
+        self.next += 1;
+        self.next_action_at = now + self.wait_before(self.next);
+        Some(action)
+    }
+}
+
+#[cfg(test)]
+mod test_playback {
+    use super::*;
+
+    fn replay(times: Vec<Duration>) -> Replay {
+        Replay {
+            actions: vec![
+                Action::Move(8, 0.5, 2.5),
+                Action::Rotate(8, 1.),
+                Action::Move(16, 0.5, 5.5),
+            ],
+            times,
+            ..Replay::default()
+        }
+    }
+
+    #[test]
+    fn actions_keep_their_pace() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut playback = Playback::new(&replay(vec![ms(100), ms(300), ms(50)]), start);
+        assert_eq!(playback.take_due_action(start), None);
+        assert!(matches!(
+            playback.take_due_action(start + ms(100)),
+            Some(Action::Move(8, ..))
+        ));
+        assert_eq!(playback.take_due_action(start + ms(300)), None);
+        assert!(matches!(
+            playback.take_due_action(start + ms(400)),
+            Some(Action::Rotate(8, _))
+        ));
+        assert!(playback.take_due_action(start + ms(450)).is_some());
+        assert!(playback.finished());
+        assert_eq!(playback.take_due_action(start + MAX_WAIT * 2), None);
+    }
+
+    #[test]
+    fn long_thinks_are_cut_short() {
+        let start = Instant::now();
+        let mut playback = Playback::new(&replay(vec![Duration::from_secs(300)]), start);
+        assert!(playback.take_due_action(start + MAX_WAIT).is_some());
+    }
+
+    #[test]
+    fn speed_stays_in_bounds() {
+        let mut playback = Playback::new(&replay(Vec::new()), Instant::now());
+        for _ in 0..20 {
+            playback.speed_up();
+        }
+        assert_eq!(playback.speed(), MAX_SPEED);
+        assert_eq!(
+            playback.wait_before(0),
+            DEFAULT_WAIT.div_f64(MAX_SPEED as f64)
+        );
+        for _ in 0..20 {
+            playback.slow_down();
+        }
+        assert_eq!(playback.speed(), MIN_SPEED);
+    }
+
+    #[test]
+    fn paused_playback_waits() {
+        let mut playback = Playback::new(&replay(Vec::new()), Instant::now());
+        playback.toggle_pause();
+        assert_eq!(
+            playback.take_due_action(Instant::now() + MAX_WAIT * 10),
+            None
+        );
+        assert_eq!(playback.progress(), (0, 3));
+    }
+}
@@ -0,0 +1,122 @@
+//! Rough timings of where each frame goes, shown over the board in debug builds (F3).
+//!
+//! This is a tiny homegrown take on what `puffin` does. We only want to know which of a
+//! handful of things is slow, and it's not worth a dependency and a UI toolkit for that.
+//!
+//! Like `puffin`, the profiler is global (well, per thread), so opening a span doesn't need
+//! to borrow anything from the app.
+
+use std::{
+    cell::RefCell,
+    time::{Duration, Instant},
+};
+
+/// A span that finished: what it was, how deeply it was nested, and how long it took.
+#[derive(Debug, Clone, Copy)]
+pub struct SpanTiming {
+    pub name: &'static str,
+    pub depth: usize,
+    pub duration: Duration,
+}
+
+#[derive(Default)]
+struct Profiler {
+    enabled: bool,
+    depth: usize,
+    /// Spans of this frame, in the order they were opened.
+    current: Vec<SpanTiming>,
+    last_frame: Vec<SpanTiming>,
+}
+
+thread_local! {
+    static PROFILER: RefCell<Profiler> = RefCell::default();
+}
+
+pub fn enabled() -> bool {
+    PROFILER.with_borrow(|p| p.enabled)
+}
+
+pub fn toggle() {
+    PROFILER.with_borrow_mut(|p| {
+        *p = Profiler {
+            enabled: !p.enabled,
+            ..Profiler::default()
+        }
+    });
+}
+
+/// Time everything until the returned span is dropped. Does nothing when disabled.
+pub fn span(name: &'static str) -> Span {
+    PROFILER.with_borrow_mut(|p| {
+        if !p.enabled {
+            return Span(None);
+        }
+        p.current.push(SpanTiming {
+            name,
+            depth: p.depth,
+            duration: Duration::ZERO,
+        });
+        p.depth += 1;
+        Span(Some((p.current.len() - 1, Instant::now())))
+    })
+}
+
+/// Call once a frame is drawn, to start timing the next one.
+pub fn finish_frame() {
+    PROFILER.with_borrow_mut(|p| {
+        p.last_frame = std::mem::take(&mut p.current);
+        p.depth = 0;
+    });
+}
+
+/// Spans from the last finished frame, in the order they were opened.
+pub fn last_frame() -> Vec<SpanTiming> {
+    PROFILER.with_borrow(|p| p.last_frame.clone())
+}
+
+/// An open span. It's timed until it's dropped.
+#[must_use = "the span ends as soon as it's dropped"]
+pub struct Span(Option<(usize, Instant)>);
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some((i, started)) = self.0 {
+            PROFILER.with_borrow_mut(|p| {
+                // the frame might've been finished while we were open. then we just missed it.
+                if let Some(span) = p.current.get_mut(i) {
+                    span.duration = started.elapsed();
+                }
+                p.depth = p.depth.saturating_sub(1);
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_profiler {
+    use super::*;
+
+    #[test]
+    fn spans_nest_in_open_order() {
+        toggle();
+        {
+            let _outer = span("outer");
+            let _inner = span("inner");
+        }
+        drop(span("after"));
+        finish_frame();
+
+        let names: Vec<_> = last_frame()
+            .iter()
+            .map(|span| (span.name, span.depth))
+            .collect();
+        assert_eq!(names, [("outer", 0), ("inner", 1), ("after", 0)]);
+    }
+
+    #[test]
+    fn disabled_records_nothing() {
+        drop(span("ignored"));
+        finish_frame();
+        assert!(last_frame().is_empty());
+    }
+}
@@ -0,0 +1,867 @@
+//! The wire format for turns sent over [`sfn_tpn`].
+//!
+//! Every message is exactly [`TURN_SIZE`] bytes. The first byte is a tag saying what kind
+//! of message it is, and the rest is the payload, if any.
+
+use std::{fmt, time::Duration};
+
+use rotchess_core::emulator::ThingHappened;
+
+use crate::{
+    constants::TURN_SIZE,
+    history::Action,
+    rules::{GameRules, RULES_SIZE},
+};
+
+/// Everything we can say to the other player.
+pub enum Message {
+    /// Something happened in our emulator, or nothing did (see [`ser_thing`]).
+    Thing(Option<ThingHappened>),
+    /// The sender closed their game. Nothing more will come over the wire.
+    Quit,
+    /// The sender hasn't touched their game in a while, though it's their turn.
+    ///
+    /// Sending this hands the turn over, so the receiver must hand it right back
+    /// with a `Thing(None)`.
+    Away,
+    /// The sender is back after being [`Message::Away`]. Also needs a `Thing(None)` in return.
+    Back,
+    /// The sender wants to rewind the game for good, back to when `actions` actions had
+    /// been played, always at the start of a turn.
+    ///
+    /// Looking back through the history is each player's own business and never goes over
+    /// the wire. This is for when both players want to play on from a past position. The
+    /// receiver answers with a [`Message::RollbackReply`], so this needs no `Thing(None)`.
+    ProposeRollback { actions: usize },
+    /// The sender got a turn it couldn't make sense of, and wants the receiver to send
+    /// over the whole game with a [`Message::Resync`].
+    ///
+    /// The receiver answers with the `Resync` itself, so this needs no `Thing(None)`.
+    RequestResync,
+    /// The sender is about to send every action of the game so far, one `Thing` each, and
+    /// each needing a `Thing(None)` in return, like this message.
+    ///
+    /// `your_turn` says whether it's the receiver's turn once they're all in. If it is, the
+    /// sender hands the turn over with one last `Thing(None)`.
+    Resync { actions: usize, your_turn: bool },
+    /// Sent by the host as its very first turn: the rules of the game, sides and all.
+    ///
+    /// The receiver answers with a [`Message::RulesAgreed`], so this needs no `Thing(None)`.
+    Rules(GameRules),
+    /// The sender wants to take back their last turn, say because they misclicked.
+    ///
+    /// Since they can't say anything while it's the receiver's turn, this is sent instead
+    /// of the `Thing(None)` for the receiver's next move, or if the receiver sent a whole
+    /// [`Message::Turn`], as the first thing the sender says on their own turn. The receiver
+    /// answers with a [`Message::TakebackReply`], so it needs no `Thing(None)` either.
+    RequestTakeback,
+    /// The answer to a [`Message::RequestTakeback`].
+    ///
+    /// If `accepted`, both players undo the receiver's last turn and the sender's move
+    /// after it (or the sender's whole turn, if that's what the receiver asked after), and
+    /// it's the receiver's turn again. If not, the receiver owes the sender the
+    /// `Thing(None)` for their move after all, or just carries on with their turn.
+    TakebackReply { accepted: bool },
+    /// The answer to [`Message::Rules`], with the [`GameRules::checksum`] of the rules as
+    /// the receiver understood them.
+    ///
+    /// If it matches, the game is on. White moves first, so if that's the receiver, the
+    /// sender hands the turn back with a `Thing(None)`.
+    RulesAgreed { checksum: u32 },
+    /// A whole turn at once: the piece moved to `(x, y)`, then rotated to `r`.
+    ///
+    /// This ends the sender's turn, so it needs no `Thing(None)`. The receiver applies the
+    /// move and rotation together, and it's their turn.
+    Turn {
+        piece_idx: usize,
+        x: f32,
+        y: f32,
+        r: f32,
+    },
+    /// What the sender is up to on their turn, so the receiver has signs of life while
+    /// waiting. Also needs a `Thing(None)` in return.
+    Activity(Activity),
+    /// How much time the sender has left on their clock, sent as their turn starts so both
+    /// clocks agree. Also needs a `Thing(None)` in return.
+    Clock(Duration),
+    /// The game is over, and the sender wants to play again by the same rules, with the
+    /// colors swapped.
+    ///
+    /// The receiver answers with a [`Message::RematchReply`], so this needs no `Thing(None)`.
+    Rematch,
+    /// The answer to a [`Message::Rematch`].
+    ///
+    /// If `accepted`, both players start over from the same layout as the last game, each
+    /// playing the other color. White moves first, so if that's the receiver, the sender
+    /// hands the turn back with a `Thing(None)`.
+    RematchReply { accepted: bool },
+    /// The answer to a [`Message::ProposeRollback`].
+    ///
+    /// If `accepted`, both players take back everything after the position proposed, and
+    /// it's whoever's turn it was then. If that's the sender, the receiver hands the turn
+    /// over with a `Thing(None)`. If not accepted, the receiver owes the sender the
+    /// `Thing(None)` for their move after all, or just carries on with their turn.
+    RollbackReply { accepted: bool },
+}
+
+/// What a player is doing on their turn, as far as their opponent needs to know.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    /// Nothing we can see, so presumably thinking.
+    Thinking,
+    /// Holding a piece, to move or rotate it.
+    HoldingPiece,
+    /// Typing a comment.
+    Typing,
+}
+
+/// A turn we couldn't make sense of, going by its tag.
+///
+/// This is whatever the opponent sent, so it's an error to handle rather than a bug.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MalformedTurn {
+    pub tag: u8,
+}
+
+impl fmt::Display for MalformedTurn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Received malformed data (tag {}) from opponent.",
+            self.tag
+        )
+    }
+}
+
+/// Serialize a Message into a netcode byte buffer turn.
+pub fn ser_message(message: &Message) -> [u8; TURN_SIZE] {
+    match message {
+        Message::Thing(thing) => ser_thing(thing.as_ref()),
+        Message::Quit => tag_only(8),
+        Message::Away => tag_only(9),
+        Message::Back => tag_only(10),
+        Message::ProposeRollback { actions } => {
+            let mut ans = tag_only(24);
+            let actions = u32::try_from(*actions).expect("Nobody plays four billion turns.");
+            ans[1..5].copy_from_slice(&actions.to_be_bytes());
+            ans
+        }
+        Message::RequestResync => tag_only(12),
+        Message::Resync { actions, your_turn } => {
+            let mut ans = tag_only(13);
+            let actions = u32::try_from(*actions).expect("Nobody plays four billion turns.");
+            ans[1..5].copy_from_slice(&actions.to_be_bytes());
+            ans[5] = *your_turn as u8;
+            ans
+        }
+        Message::Rules(rules) => {
+            let mut ans = tag_only(14);
+            ans[1..1 + RULES_SIZE].copy_from_slice(&rules.to_bytes());
+            ans
+        }
+        Message::RequestTakeback => tag_only(15),
+        Message::TakebackReply { accepted } => {
+            let mut ans = tag_only(16);
+            ans[1] = *accepted as u8;
+            ans
+        }
+        Message::RulesAgreed { checksum } => {
+            let mut ans = tag_only(17);
+            ans[1..5].copy_from_slice(&checksum.to_be_bytes());
+            ans
+        }
+        Message::Turn { piece_idx, x, y, r } => {
+            // the move's bytes, with the rotation tacked on.
+            let mut ans = ser_thing(Some(&ThingHappened::Move(*piece_idx, *x, *y)));
+            ans[0] = 18;
+            ans[11..15].copy_from_slice(&r.to_be_bytes());
+            ans
+        }
+        Message::Activity(activity) => {
+            let mut ans = tag_only(19);
+            ans[1] = match activity {
+                Activity::Thinking => 0,
+                Activity::HoldingPiece => 1,
+                Activity::Typing => 2,
+            };
+            ans
+        }
+        Message::Clock(remaining) => {
+            let mut ans = tag_only(20);
+            let millis = u32::try_from(remaining.as_millis()).unwrap_or(u32::MAX);
+            ans[1..5].copy_from_slice(&millis.to_be_bytes());
+            ans
+        }
+        Message::Rematch => tag_only(21),
+        Message::RematchReply { accepted } => {
+            let mut ans = tag_only(22);
+            ans[1] = *accepted as u8;
+            ans
+        }
+        Message::RollbackReply { accepted } => {
+            let mut ans = tag_only(23);
+            ans[1] = *accepted as u8;
+            ans
+        }
+    }
+}
+
+/// Deserialize a Message from a netcode byte buffer turn.
+pub fn de_message(message: &[u8; TURN_SIZE]) -> Result<Message, MalformedTurn> {
+    Ok(match message[0] {
+        8 => Message::Quit,
+        9 => Message::Away,
+        10 => Message::Back,
+        // used to be jumping to a turn, back when looking through the history went over
+        // the wire. an old build sending it would mean something else entirely now.
+        11 => return Err(MalformedTurn { tag: 11 }),
+        12 => Message::RequestResync,
+        13 => {
+            let mut actions_bytes = [0; size_of::<u32>()];
+            actions_bytes.copy_from_slice(&message[1..5]);
+            Message::Resync {
+                actions: u32::from_be_bytes(actions_bytes) as usize,
+                your_turn: message[5] != 0,
+            }
+        }
+        14 => {
+            let mut rules_bytes = [0; RULES_SIZE];
+            rules_bytes.copy_from_slice(&message[1..1 + RULES_SIZE]);
+            Message::Rules(GameRules::from_bytes(&rules_bytes).ok_or(MalformedTurn { tag: 14 })?)
+        }
+        15 => Message::RequestTakeback,
+        16 => Message::TakebackReply {
+            accepted: message[1] != 0,
+        },
+        17 => {
+            let mut checksum_bytes = [0; size_of::<u32>()];
+            checksum_bytes.copy_from_slice(&message[1..5]);
+            Message::RulesAgreed {
+                checksum: u32::from_be_bytes(checksum_bytes),
+            }
+        }
+        18 => {
+            let mut move_bytes = *message;
+            move_bytes[0] = 6;
+            let Ok(Some(ThingHappened::Move(piece_idx, x, y))) = de_thing(&move_bytes) else {
+                unreachable!("Tag 6 is always a move.");
+            };
+            let mut r_bytes = [0; size_of::<f32>()];
+            r_bytes.copy_from_slice(&message[11..15]);
+            Message::Turn {
+                piece_idx,
+                x,
+                y,
+                r: f32::from_be_bytes(r_bytes),
+            }
+        }
+        19 => Message::Activity(match message[1] {
+            0 => Activity::Thinking,
+            1 => Activity::HoldingPiece,
+            2 => Activity::Typing,
+            _ => return Err(MalformedTurn { tag: 19 }),
+        }),
+        20 => {
+            let mut millis_bytes = [0; size_of::<u32>()];
+            millis_bytes.copy_from_slice(&message[1..5]);
+            Message::Clock(Duration::from_millis(
+                u32::from_be_bytes(millis_bytes).into(),
+            ))
+        }
+        21 => Message::Rematch,
+        22 => Message::RematchReply {
+            accepted: message[1] != 0,
+        },
+        23 => Message::RollbackReply {
+            accepted: message[1] != 0,
+        },
+        24 => {
+            let mut actions_bytes = [0; size_of::<u32>()];
+            actions_bytes.copy_from_slice(&message[1..5]);
+            Message::ProposeRollback {
+                actions: u32::from_be_bytes(actions_bytes) as usize,
+            }
+        }
+        _ => Message::Thing(de_thing(message)?),
+    })
+}
+
+/// Serialize our whole game for an opponent who asked for a [`Message::Resync`]: the
+/// announcement, then each action in turn. If it's their turn after, a `Thing(None)` at the
+/// end hands it back to them.
+pub fn ser_resync(actions: &[Action], your_turn: bool) -> Vec<[u8; TURN_SIZE]> {
+    let mut turns = vec![ser_message(&Message::Resync {
+        actions: actions.len(),
+        your_turn,
+    })];
+    turns.extend(
+        actions
+            .iter()
+            .map(|&action| ser_thing(Some(&action.into()))),
+    );
+    if your_turn {
+        turns.push(ser_thing(None));
+    }
+    turns
+}
+
+/// A message with no payload.
+fn tag_only(tag: u8) -> [u8; TURN_SIZE] {
+    let mut ans = [0; TURN_SIZE];
+    ans[0] = tag;
+    ans
+}
+
+// yes, we're doing these manually. huzzah!
+
+/// Serialize a Thing into a netcode byte buffer turn.
+pub fn ser_thing(thing: Option<&ThingHappened>) -> [u8; TURN_SIZE] {
+    // we really don't need to have
+    // a usize be the piece index, we don't have enough pieces on
+    // the board. a u16 is plenty, even for modded boards. but for
+    // type convenience, we're leaving it as a usize. If someone
+    // manages to get more than 65536 pieces on the board, that
+    // probably violates some invariant somewhere. (aren't pieces
+    // supposed to not stack?)
+    let mut ans = [0; TURN_SIZE];
+    match thing {
+        Some(ThingHappened::FirstTurn) => ans[0] = 1,
+        Some(ThingHappened::PrevTurn) => ans[0] = 2,
+        Some(ThingHappened::NextTurn) => ans[0] = 3,
+        Some(ThingHappened::LastTurn) => ans[0] = 4,
+        Some(ThingHappened::Rotate(piece_idx, r)) => {
+            ans[0] = 5;
+            ans[1..3].copy_from_slice(&ser_piece_idx(*piece_idx));
+            ans[3..7].copy_from_slice(&r.to_be_bytes());
+        }
+        Some(ThingHappened::Move(piece_idx, x, y)) => {
+            ans[0] = 6;
+            ans[1..3].copy_from_slice(&ser_piece_idx(*piece_idx));
+            ans[3..7].copy_from_slice(&x.to_be_bytes());
+            ans[7..11].copy_from_slice(&y.to_be_bytes());
+        }
+        None => ans[0] = 7,
+    }
+    ans
+}
+
+/// Deserialize a Thing from a netcode byte buffer turn.
+pub fn de_thing(thing: &[u8; TURN_SIZE]) -> Result<Option<ThingHappened>, MalformedTurn> {
+    Ok(match thing[0] {
+        1 => Some(ThingHappened::FirstTurn),
+        2 => Some(ThingHappened::PrevTurn),
+        3 => Some(ThingHappened::NextTurn),
+        4 => Some(ThingHappened::LastTurn),
+        5 => {
+            let piece_idx = de_piece_idx(thing);
+
+            let mut r_bytes = [0; size_of::<f32>()];
+            r_bytes.copy_from_slice(&thing[3..7]);
+            let r = f32::from_be_bytes(r_bytes);
+
+            Some(ThingHappened::Rotate(piece_idx, r))
+        }
+        6 => {
+            let piece_idx = de_piece_idx(thing);
+
+            let mut x_bytes = [0; size_of::<f32>()];
+            x_bytes.copy_from_slice(&thing[3..7]);
+            let x = f32::from_be_bytes(x_bytes);
+
+            let mut y_bytes = [0; size_of::<f32>()];
+            y_bytes.copy_from_slice(&thing[7..11]);
+            let y = f32::from_be_bytes(y_bytes);
+
+            Some(ThingHappened::Move(piece_idx, x, y))
+        }
+        7 => None,
+        tag => return Err(MalformedTurn { tag }),
+    })
+}
+
+fn ser_piece_idx(piece_idx: usize) -> [u8; size_of::<u16>()] {
+    u16::try_from(piece_idx)
+        .expect("See the comment in ser_thing.")
+        .to_be_bytes()
+}
+
+/// The piece index of a Rotate or Move turn.
+///
+/// This is whatever the opponent sent, so check it against the board before using it.
+fn de_piece_idx(thing: &[u8; TURN_SIZE]) -> usize {
+    u16::from_be_bytes([thing[1], thing[2]]) as usize
+}
+
+#[cfg(test)]
+mod test_serde_thinghappened {
+    use super::*;
+    use parameterized::parameterized;
+    use proptest::prelude::*;
+    use rotchess_core::piece::Side;
+
+    /// A copy of [`ThingHappened`] we can compare, since the real one has no PartialEq.
+    ///
+    /// Floats are kept as their bits, so NaNs compare equal to themselves.
+    #[derive(Debug, Clone, PartialEq)]
+    enum Thing {
+        FirstTurn,
+        PrevTurn,
+        NextTurn,
+        LastTurn,
+        Rotate(usize, u32),
+        Move(usize, u32, u32),
+    }
+
+    impl From<&ThingHappened> for Thing {
+        fn from(thing: &ThingHappened) -> Self {
+            match thing {
+                ThingHappened::FirstTurn => Thing::FirstTurn,
+                ThingHappened::PrevTurn => Thing::PrevTurn,
+                ThingHappened::NextTurn => Thing::NextTurn,
+                ThingHappened::LastTurn => Thing::LastTurn,
+                ThingHappened::Rotate(i, r) => Thing::Rotate(*i, r.to_bits()),
+                ThingHappened::Move(i, x, y) => Thing::Move(*i, x.to_bits(), y.to_bits()),
+            }
+        }
+    }
+
+    impl From<&Thing> for ThingHappened {
+        fn from(thing: &Thing) -> Self {
+            match thing {
+                Thing::FirstTurn => ThingHappened::FirstTurn,
+                Thing::PrevTurn => ThingHappened::PrevTurn,
+                Thing::NextTurn => ThingHappened::NextTurn,
+                Thing::LastTurn => ThingHappened::LastTurn,
+                Thing::Rotate(i, r) => ThingHappened::Rotate(*i, f32::from_bits(*r)),
+                Thing::Move(i, x, y) => {
+                    ThingHappened::Move(*i, f32::from_bits(*x), f32::from_bits(*y))
+                }
+            }
+        }
+    }
+
+    /// A copy of [`Message`] we can compare, like [`Thing`].
+    #[derive(Debug, Clone, PartialEq)]
+    enum Msg {
+        Thing(Option<Thing>),
+        Quit,
+        Away,
+        Back,
+        ProposeRollback {
+            actions: usize,
+        },
+        RequestResync,
+        Resync {
+            actions: usize,
+            your_turn: bool,
+        },
+        Rules(GameRules),
+        RequestTakeback,
+        TakebackReply {
+            accepted: bool,
+        },
+        RulesAgreed {
+            checksum: u32,
+        },
+        Turn {
+            piece_idx: usize,
+            x: u32,
+            y: u32,
+            r: u32,
+        },
+        Activity(Activity),
+        Clock(Duration),
+        Rematch,
+        RematchReply {
+            accepted: bool,
+        },
+        RollbackReply {
+            accepted: bool,
+        },
+    }
+
+    impl From<&Message> for Msg {
+        fn from(message: &Message) -> Self {
+            match message {
+                Message::Thing(thing) => Msg::Thing(thing.as_ref().map(Thing::from)),
+                Message::Quit => Msg::Quit,
+                Message::Away => Msg::Away,
+                Message::Back => Msg::Back,
+                Message::ProposeRollback { actions } => Msg::ProposeRollback { actions: *actions },
+                Message::RequestResync => Msg::RequestResync,
+                Message::Resync { actions, your_turn } => Msg::Resync {
+                    actions: *actions,
+                    your_turn: *your_turn,
+                },
+                Message::Rules(rules) => Msg::Rules(*rules),
+                Message::RequestTakeback => Msg::RequestTakeback,
+                Message::TakebackReply { accepted } => Msg::TakebackReply {
+                    accepted: *accepted,
+                },
+                Message::RulesAgreed { checksum } => Msg::RulesAgreed {
+                    checksum: *checksum,
+                },
+                Message::Turn { piece_idx, x, y, r } => Msg::Turn {
+                    piece_idx: *piece_idx,
+                    x: x.to_bits(),
+                    y: y.to_bits(),
+                    r: r.to_bits(),
+                },
+                Message::Activity(activity) => Msg::Activity(*activity),
+                Message::Clock(remaining) => Msg::Clock(*remaining),
+                Message::Rematch => Msg::Rematch,
+                Message::RematchReply { accepted } => Msg::RematchReply {
+                    accepted: *accepted,
+                },
+                Message::RollbackReply { accepted } => Msg::RollbackReply {
+                    accepted: *accepted,
+                },
+            }
+        }
+    }
+
+    impl From<&Msg> for Message {
+        fn from(message: &Msg) -> Self {
+            match message {
+                Msg::Thing(thing) => Message::Thing(thing.as_ref().map(ThingHappened::from)),
+                Msg::Quit => Message::Quit,
+                Msg::Away => Message::Away,
+                Msg::Back => Message::Back,
+                Msg::ProposeRollback { actions } => Message::ProposeRollback { actions: *actions },
+                Msg::RequestResync => Message::RequestResync,
+                Msg::Resync { actions, your_turn } => Message::Resync {
+                    actions: *actions,
+                    your_turn: *your_turn,
+                },
+                Msg::Rules(rules) => Message::Rules(*rules),
+                Msg::RequestTakeback => Message::RequestTakeback,
+                Msg::TakebackReply { accepted } => Message::TakebackReply {
+                    accepted: *accepted,
+                },
+                Msg::RulesAgreed { checksum } => Message::RulesAgreed {
+                    checksum: *checksum,
+                },
+                Msg::Turn { piece_idx, x, y, r } => Message::Turn {
+                    piece_idx: *piece_idx,
+                    x: f32::from_bits(*x),
+                    y: f32::from_bits(*y),
+                    r: f32::from_bits(*r),
+                },
+                Msg::Activity(activity) => Message::Activity(*activity),
+                Msg::Clock(remaining) => Message::Clock(*remaining),
+                Msg::Rematch => Message::Rematch,
+                Msg::RematchReply { accepted } => Message::RematchReply {
+                    accepted: *accepted,
+                },
+                Msg::RollbackReply { accepted } => Message::RollbackReply {
+                    accepted: *accepted,
+                },
+            }
+        }
+    }
+
+    fn assert_deser_bijective(thing: Option<&ThingHappened>) {
+        assert_eq!(
+            de_thing(&ser_thing(thing))
+                .unwrap()
+                .as_ref()
+                .map(Thing::from),
+            thing.map(Thing::from)
+        )
+    }
+
+    fn arb_thing() -> impl Strategy<Value = Thing> {
+        let piece_idx = 0..=u16::MAX as usize;
+        prop_oneof![
+            Just(Thing::FirstTurn),
+            Just(Thing::PrevTurn),
+            Just(Thing::NextTurn),
+            Just(Thing::LastTurn),
+            (piece_idx.clone(), any::<u32>()).prop_map(|(i, r)| Thing::Rotate(i, r)),
+            (piece_idx, any::<u32>(), any::<u32>()).prop_map(|(i, x, y)| Thing::Move(i, x, y)),
+        ]
+    }
+
+    fn arb_message() -> impl Strategy<Value = Msg> {
+        let count = 0..=u32::MAX as usize;
+        prop_oneof![
+            proptest::option::of(arb_thing()).prop_map(Msg::Thing),
+            Just(Msg::Quit),
+            Just(Msg::Away),
+            Just(Msg::Back),
+            count
+                .clone()
+                .prop_map(|actions| Msg::ProposeRollback { actions }),
+            Just(Msg::RequestResync),
+            (count, any::<bool>())
+                .prop_map(|(actions, your_turn)| Msg::Resync { actions, your_turn }),
+            any::<[u8; RULES_SIZE]>()
+                .prop_filter_map("not rules", |bytes| GameRules::from_bytes(&bytes))
+                .prop_map(Msg::Rules),
+            Just(Msg::RequestTakeback),
+            any::<bool>().prop_map(|accepted| Msg::TakebackReply { accepted }),
+            any::<u32>().prop_map(|checksum| Msg::RulesAgreed { checksum }),
+            (
+                0..=u16::MAX as usize,
+                any::<u32>(),
+                any::<u32>(),
+                any::<u32>()
+            )
+                .prop_map(|(piece_idx, x, y, r)| Msg::Turn { piece_idx, x, y, r }),
+            prop_oneof![
+                Just(Activity::Thinking),
+                Just(Activity::HoldingPiece),
+                Just(Activity::Typing)
+            ]
+            .prop_map(Msg::Activity),
+            any::<u32>().prop_map(|millis| Msg::Clock(Duration::from_millis(millis.into()))),
+            Just(Msg::Rematch),
+            any::<bool>().prop_map(|accepted| Msg::RematchReply { accepted }),
+            any::<bool>().prop_map(|accepted| Msg::RollbackReply { accepted }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn any_thing_round_trips(thing in proptest::option::of(arb_thing())) {
+            let real = thing.as_ref().map(ThingHappened::from);
+            prop_assert_eq!(de_thing(&ser_thing(real.as_ref())).unwrap().as_ref().map(Thing::from), thing);
+        }
+
+        /// Whatever the opponent sends, decoding it doesn't panic, and anything that decodes
+        /// encodes back to the same message.
+        #[test]
+        fn decoding_arbitrary_frames_never_panics(frame in any::<[u8; TURN_SIZE]>()) {
+            match de_message(&frame) {
+                Ok(message) => {
+                    let message = Msg::from(&message);
+                    let again = de_message(&ser_message(&Message::from(&message))).unwrap();
+                    prop_assert_eq!(Msg::from(&again), message);
+                }
+                Err(MalformedTurn { tag }) => {
+                    prop_assert_eq!(tag, frame[0]);
+                    // bad rules and activities are the only payloads that can be malformed, and
+                    // 11 is reserved.
+                    prop_assert!(tag == 0 || tag == 11 || tag == 14 || tag > 18);
+                }
+            }
+        }
+
+        #[test]
+        fn any_message_round_trips(message in arb_message()) {
+            let bytes = ser_message(&Message::from(&message));
+            prop_assert_eq!(Msg::from(&de_message(&bytes).unwrap()), message);
+        }
+    }
+
+    #[test]
+    fn none_serialization_is_bijective() {
+        assert_deser_bijective(None);
+    }
+
+    #[test]
+    fn firstturn_serialization_is_bijective() {
+        assert_deser_bijective(Some(&ThingHappened::FirstTurn));
+    }
+
+    #[test]
+    fn prevturn_serialization_is_bijective() {
+        assert_deser_bijective(Some(&ThingHappened::PrevTurn));
+    }
+
+    #[test]
+    fn nextturn_serialization_is_bijective() {
+        assert_deser_bijective(Some(&ThingHappened::NextTurn));
+    }
+
+    #[test]
+    fn lastturn_serialization_is_bijective() {
+        assert_deser_bijective(Some(&ThingHappened::LastTurn));
+    }
+    #[parameterized(rotate_thing = {
+        &ThingHappened::Rotate(2, 91.246876218913),
+        &ThingHappened::Rotate(6, 01.797548620909),
+        &ThingHappened::Rotate(5, 08.147878140881),
+        &ThingHappened::Rotate(1, 21.581176862643),
+        &ThingHappened::Rotate(7, 32.217517844368),
+        &ThingHappened::Rotate(4, 90.522625314885),
+        &ThingHappened::Rotate(1, 23.927154940674),
+        &ThingHappened::Rotate(8, 53.959229741122),
+        &ThingHappened::Rotate(8, 60.743439712343),
+        &ThingHappened::Rotate(8, 82.152850235763),
+    })]
+    fn rotate_serialization_is_bijective(rotate_thing: &ThingHappened) {
+        assert_deser_bijective(Some(rotate_thing));
+    }
+
+    #[parameterized(move_thing = {
+        &ThingHappened::Move(28, 11.352279394256, 81.647432982848),
+        &ThingHappened::Move(74, 30.000701136234, 90.218648211692),
+        &ThingHappened::Move(47, 56.161192888566, 02.448786090013),
+        &ThingHappened::Move(86, 54.106803274653, 61.299734032137),
+        &ThingHappened::Move(86, 28.528662175474, 48.520872175935),
+        &ThingHappened::Move(26, 66.300609468152, 85.435537391159),
+        &ThingHappened::Move(77, 73.688001636818, 68.715058900751),
+        &ThingHappened::Move(10, 68.328589705709, 11.444493994595),
+        &ThingHappened::Move(29, 65.925913814140, 87.078698941045),
+        &ThingHappened::Move(85, 38.747317527971, 20.528927188939),
+    })]
+    fn move_serialization_is_bijective(move_thing: &ThingHappened) {
+        assert_deser_bijective(Some(move_thing));
+    }
+
+    #[parameterized(piece_idx = { 255, 256, 4242, 65535 })]
+    fn wide_piece_index_serialization_is_bijective(piece_idx: usize) {
+        assert!(matches!(
+            de_thing(&ser_thing(Some(&ThingHappened::Move(piece_idx, 1., 2.)))).unwrap(),
+            Some(ThingHappened::Move(i, _, _)) if i == piece_idx
+        ));
+        assert!(matches!(
+            de_thing(&ser_thing(Some(&ThingHappened::Rotate(piece_idx, 1.)))).unwrap(),
+            Some(ThingHappened::Rotate(i, _)) if i == piece_idx
+        ));
+    }
+
+    #[parameterized(tag = { 0, 11, 25, 200, 255 })]
+    fn unknown_tags_are_malformed(tag: u8) {
+        let mut frame = [0; TURN_SIZE];
+        frame[0] = tag;
+        assert_eq!(de_message(&frame).err(), Some(MalformedTurn { tag }));
+        assert_eq!(de_thing(&frame).err(), Some(MalformedTurn { tag }));
+    }
+
+    #[test]
+    fn quit_serialization_is_bijective() {
+        assert!(matches!(
+            de_message(&ser_message(&Message::Quit)).unwrap(),
+            Message::Quit
+        ));
+    }
+
+    #[test]
+    fn away_serialization_is_bijective() {
+        assert!(matches!(
+            de_message(&ser_message(&Message::Away)).unwrap(),
+            Message::Away
+        ));
+    }
+
+    #[test]
+    fn back_serialization_is_bijective() {
+        assert!(matches!(
+            de_message(&ser_message(&Message::Back)).unwrap(),
+            Message::Back
+        ));
+    }
+
+    #[test]
+    fn requestresync_serialization_is_bijective() {
+        assert!(matches!(
+            de_message(&ser_message(&Message::RequestResync)).unwrap(),
+            Message::RequestResync
+        ));
+    }
+
+    #[parameterized(actions = { 0, 1, 300, 70000 }, your_turn = { true, false, true, false })]
+    fn resync_serialization_is_bijective(actions: usize, your_turn: bool) {
+        assert!(matches!(
+            de_message(&ser_message(&Message::Resync { actions, your_turn })).unwrap(),
+            Message::Resync { actions: a, your_turn: y } if a == actions && y == your_turn
+        ));
+    }
+
+    #[test]
+    fn rules_serialization_is_bijective() {
+        let rules = GameRules {
+            host_side: Side::Black,
+            chess960_ordering: Some([0, 2, 1, 3, 4, 6, 5, 7]),
+            clock: Some((Duration::from_secs(600), Duration::from_secs(180))),
+            increment: Duration::from_secs(3),
+            takebacks: false,
+            auto_rotate: Some(Duration::from_secs(30)),
+        };
+        assert!(matches!(
+            de_message(&ser_message(&Message::Rules(rules))).unwrap(),
+            Message::Rules(r) if r == rules
+        ));
+    }
+
+    #[parameterized(millis = { 0, 1, 299_999, u32::MAX })]
+    fn clock_serialization_is_bijective(millis: u32) {
+        let remaining = Duration::from_millis(millis.into());
+        assert!(matches!(
+            de_message(&ser_message(&Message::Clock(remaining))).unwrap(),
+            Message::Clock(r) if r == remaining
+        ));
+    }
+
+    #[parameterized(accepted = { true, false })]
+    fn rematch_serialization_is_bijective(accepted: bool) {
+        assert!(matches!(
+            de_message(&ser_message(&Message::Rematch)).unwrap(),
+            Message::Rematch
+        ));
+        assert!(matches!(
+            de_message(&ser_message(&Message::RematchReply { accepted })).unwrap(),
+            Message::RematchReply { accepted: a } if a == accepted
+        ));
+    }
+
+    #[parameterized(accepted = { true, false })]
+    fn rollbackreply_serialization_is_bijective(accepted: bool) {
+        assert!(matches!(
+            de_message(&ser_message(&Message::RollbackReply { accepted })).unwrap(),
+            Message::RollbackReply { accepted: a } if a == accepted
+        ));
+    }
+
+    #[parameterized(checksum = { 0, 1, 0x811c9dc5, u32::MAX })]
+    fn rulesagreed_serialization_is_bijective(checksum: u32) {
+        assert!(matches!(
+            de_message(&ser_message(&Message::RulesAgreed { checksum })).unwrap(),
+            Message::RulesAgreed { checksum: c } if c == checksum
+        ));
+    }
+
+    #[test]
+    fn turn_serialization_is_bijective() {
+        let message = Message::Turn {
+            piece_idx: 300,
+            x: 3.5,
+            y: -0.25,
+            r: 1.75,
+        };
+        assert!(matches!(
+            de_message(&ser_message(&message)).unwrap(),
+            Message::Turn {
+                piece_idx: 300,
+                x: 3.5,
+                y: -0.25,
+                r: 1.75
+            }
+        ));
+    }
+
+    #[parameterized(activity = { Activity::Thinking, Activity::HoldingPiece, Activity::Typing })]
+    fn activity_serialization_is_bijective(activity: Activity) {
+        assert!(matches!(
+            de_message(&ser_message(&Message::Activity(activity))).unwrap(),
+            Message::Activity(a) if a == activity
+        ));
+    }
+
+    #[test]
+    fn unknown_activities_are_malformed() {
+        let mut frame = ser_message(&Message::Activity(Activity::Typing));
+        frame[1] = 3;
+        assert_eq!(de_message(&frame).err(), Some(MalformedTurn { tag: 19 }));
+    }
+
+    #[parameterized(n = { 0, 1, 12, 255, 256, 70000 })]
+    fn proposerollback_serialization_is_bijective(n: usize) {
+        assert!(matches!(
+            de_message(&ser_message(&Message::ProposeRollback { actions: n })).unwrap(),
+            Message::ProposeRollback { actions } if actions == n
+        ));
+    }
+}
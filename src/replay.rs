@@ -0,0 +1,258 @@
+//! Replay files: everything needed to watch a game again, including what was drawn on it.
+//!
+//! The format is plain text, one record per line, so it's easy to eyeball and diff:
+//!
+//! ```text
+//! rotchess-replay 1
+//! chess960 1 0 3 2 4 6 5 7
+//! move 12 3.5 4.5
+//! rotate 12 1.5707964
+//! arrow 1 3.5 6.5 3.5 4.5
+//! circle 2 4.5 4.5
+//! comment 2 the knight can't reach e4 anymore
+//! times 5300 1250
+//! winner black
+//! ```
+//!
+//! `chess960` is left out for standard games. Annotations and comments are tagged with the
+//! turn they were made on, counted like [`History::current`](crate::history::History::current).
+//! A comment runs to the end of its line. `times` is how many milliseconds each action
+//! took, and is left out (like `winner`) if we don't know.
+
+use std::{fmt::Write, path::Path, time::Duration};
+
+use ggez::{GameError, GameResult};
+use rotchess_core::piece::Side;
+
+use crate::{annotation::Annotation, history::Action, rules};
+
+pub const HEADER: &str = "rotchess-replay 1";
+
+#[derive(Default)]
+pub struct Replay {
+    /// The back rank ordering of a Chess960 game, or `None` for standard chess.
+    pub chess960_ordering: Option<[usize; 8]>,
+    pub actions: Vec<Action>,
+    pub annotations: Vec<(usize, Annotation)>,
+    pub comments: Vec<(usize, String)>,
+    /// How long each action took, from the one before it (or the start of the game).
+    pub times: Vec<Duration>,
+    /// Who won, if the game is over and someone did.
+    pub winner: Option<Side>,
+}
+
+impl Replay {
+    pub fn save(&self, path: &Path) -> GameResult {
+        Ok(std::fs::write(path, self.to_text())?)
+    }
+
+    pub fn load(path: &Path) -> GameResult<Self> {
+        Self::from_text(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{HEADER}\n");
+        // writing to a String can't fail, so the unwraps below are fine.
+        if let Some(ordering) = self.chess960_ordering {
+            let ordering: Vec<String> = ordering.iter().map(usize::to_string).collect();
+            writeln!(text, "chess960 {}", ordering.join(" ")).unwrap();
+        }
+        for action in &self.actions {
+            match action {
+                Action::Move(piece_idx, x, y) => writeln!(text, "move {piece_idx} {x} {y}"),
+                Action::Rotate(piece_idx, r) => writeln!(text, "rotate {piece_idx} {r}"),
+            }
+            .unwrap();
+        }
+        for (turn, annotation) in &self.annotations {
+            match annotation {
+                Annotation::Arrow { from, to } => {
+                    writeln!(text, "arrow {turn} {} {} {} {}", from.0, from.1, to.0, to.1)
+                }
+                Annotation::Circle { at } => writeln!(text, "circle {turn} {} {}", at.0, at.1),
+            }
+            .unwrap();
+        }
+        for (turn, comment) in &self.comments {
+            writeln!(text, "comment {turn} {comment}").unwrap();
+        }
+        if !self.times.is_empty() {
+            let times: Vec<String> = self
+                .times
+                .iter()
+                .map(|time| time.as_millis().to_string())
+                .collect();
+            writeln!(text, "times {}", times.join(" ")).unwrap();
+        }
+        match self.winner {
+            Some(Side::White) => writeln!(text, "winner white").unwrap(),
+            Some(Side::Black) => writeln!(text, "winner black").unwrap(),
+            None => (),
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> GameResult<Self> {
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+            return Err(malformed(1, "missing header"));
+        }
+
+        let mut replay = Self::default();
+        for (i, line) in lines {
+            let line_no = i + 1;
+            let mut words = line.split_whitespace();
+            let Some(kind) = words.next() else {
+                continue;
+            };
+            if kind == "comment" {
+                let (turn, comment) = line
+                    .trim_start()
+                    .trim_start_matches("comment")
+                    .trim_start()
+                    .split_once(' ')
+                    .ok_or_else(|| malformed(line_no, "empty comment"))?;
+                let turn = turn
+                    .parse()
+                    .map_err(|_| malformed(line_no, "expected a turn"))?;
+                replay.comments.push((turn, comment.to_string()));
+                continue;
+            }
+            if kind == "times" {
+                replay.times = words
+                    .map(|ms| ms.parse().map(Duration::from_millis))
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| malformed(line_no, "expected milliseconds"))?;
+                continue;
+            }
+            if kind == "winner" {
+                replay.winner = Some(match words.next() {
+                    Some("white") => Side::White,
+                    Some("black") => Side::Black,
+                    _ => return Err(malformed(line_no, "expected white or black")),
+                });
+                continue;
+            }
+            let fields: Vec<&str> = words.collect();
+            let arity = match kind {
+                "chess960" => 8,
+                "move" => 3,
+                "rotate" => 2,
+                "arrow" => 5,
+                "circle" => 3,
+                _ => return Err(malformed(line_no, "unknown record")),
+            };
+            if fields.len() != arity {
+                return Err(malformed(line_no, "wrong number of fields"));
+            }
+            let idx = |i: usize| -> GameResult<usize> {
+                fields[i]
+                    .parse()
+                    .map_err(|_| malformed(line_no, "expected a whole number"))
+            };
+            let num = |i: usize| -> GameResult<f32> {
+                fields[i]
+                    .parse()
+                    .map_err(|_| malformed(line_no, "expected a number"))
+            };
+
+            match kind {
+                "chess960" => {
+                    let mut ordering = [0; 8];
+                    for (i, file) in ordering.iter_mut().enumerate() {
+                        *file = idx(i)?;
+                    }
+                    if !rules::is_chess960_ordering(&ordering) {
+                        return Err(malformed(line_no, "not a Chess960 back rank"));
+                    }
+                    replay.chess960_ordering = Some(ordering);
+                }
+                "move" => replay.actions.push(Action::Move(idx(0)?, num(1)?, num(2)?)),
+                "rotate" => replay.actions.push(Action::Rotate(idx(0)?, num(1)?)),
+                "arrow" => replay.annotations.push((
+                    idx(0)?,
+                    Annotation::Arrow {
+                        from: (num(1)?, num(2)?),
+                        to: (num(3)?, num(4)?),
+                    },
+                )),
+                "circle" => replay.annotations.push((
+                    idx(0)?,
+                    Annotation::Circle {
+                        at: (num(1)?, num(2)?),
+                    },
+                )),
+                _ => unreachable!("Checked when finding the arity."),
+            }
+        }
+        Ok(replay)
+    }
+}
+
+fn malformed(line_no: usize, why: &str) -> GameError {
+    GameError::CustomError(format!("Malformed replay at line {line_no}: {why}."))
+}
+
+#[cfg(test)]
+mod test_replay_format {
+    use super::*;
+
+    #[test]
+    fn text_roundtrips() {
+        let replay = Replay {
+            chess960_ordering: Some([1, 0, 3, 2, 4, 6, 5, 7]),
+            actions: vec![Action::Move(12, 3.5, 4.25), Action::Rotate(12, 1.5707964)],
+            annotations: vec![
+                (
+                    1,
+                    Annotation::Arrow {
+                        from: (3.5, 6.5),
+                        to: (3.5, 4.5),
+                    },
+                ),
+                (2, Annotation::Circle { at: (0.1, 7.9) }),
+            ],
+            comments: vec![(2, "  what  a move 1 2 ".to_string())],
+            times: vec![Duration::from_millis(5300), Duration::from_millis(1250)],
+            winner: Some(Side::Black),
+        };
+        let parsed = Replay::from_text(&replay.to_text()).unwrap();
+        assert_eq!(parsed.chess960_ordering, replay.chess960_ordering);
+        assert_eq!(parsed.actions, replay.actions);
+        assert_eq!(parsed.annotations, replay.annotations);
+        assert_eq!(parsed.comments, replay.comments);
+        assert_eq!(parsed.times, replay.times);
+        assert_eq!(parsed.winner, replay.winner);
+    }
+
+    #[test]
+    fn garbage_is_an_error() {
+        assert!(Replay::from_text("not a replay").is_err());
+        assert!(Replay::from_text(&format!("{HEADER}\nmove 1 2")).is_err());
+        assert!(Replay::from_text(&format!("{HEADER}\nteleport 1 2 3")).is_err());
+        assert!(Replay::from_text(&format!("{HEADER}\ntimes 1 two")).is_err());
+        assert!(Replay::from_text(&format!("{HEADER}\nwinner nobody")).is_err());
+        assert!(Replay::from_text(&format!("{HEADER}\nmove 1.5 2 3")).is_err());
+        assert!(Replay::from_text(&format!("{HEADER}\nmove -1 2 3")).is_err());
+    }
+
+    #[test]
+    fn only_chess960_back_ranks_load() {
+        let load = |ordering: &str| Replay::from_text(&format!("{HEADER}\nchess960 {ordering}"));
+        assert_eq!(
+            load("1 0 3 2 4 6 5 7").unwrap().chess960_ordering,
+            Some([1, 0, 3, 2, 4, 6, 5, 7])
+        );
+        // not a permutation, out of range, not whole numbers, bishops on one color, and
+        // the king outside the rooks.
+        for ordering in [
+            "0 0 0 0 0 0 0 0",
+            "0 1 2 3 4 5 6 9",
+            "0.5 1 2 3 4 5 6 7",
+            "0 1 2 3 4 6 5 7",
+            "4 1 2 3 0 5 6 7",
+        ] {
+            assert!(load(ordering).is_err(), "{ordering}");
+        }
+    }
+}
@@ -0,0 +1,412 @@
+//! The rules of a networked game, which the host picks and sends over before the first move.
+//!
+//! The client decodes them, applies them, and answers with a checksum of what it understood.
+//! The host only starts the game if that checksum matches its own, so both sides provably
+//! play by the same rules.
+
+use std::time::Duration;
+
+use ggez::{GameError, GameResult};
+use rotchess_core::piece::Side;
+
+use crate::clock::{format_clock, time_control_from_args};
+
+/// Bytes [`GameRules::to_bytes`] takes.
+pub const RULES_SIZE: usize = 11;
+
+/// How many back rank orderings there are, i.e. 8!.
+const ORDERINGS: u16 = 40320;
+
+// flags, in the first byte of the serialized rules.
+const HOST_PLAYS_BLACK: u8 = 1 << 0;
+const NO_TAKEBACKS: u8 = 1 << 1;
+const HAS_CLOCK: u8 = 1 << 2;
+const CHESS960: u8 = 1 << 3;
+const AUTO_ROTATE: u8 = 1 << 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameRules {
+    pub host_side: Side,
+    /// The back rank ordering, if it's a Chess960 game.
+    pub chess960_ordering: Option<[usize; 8]>,
+    /// White's time, then black's, if the game has a clock. Whole seconds, at most
+    /// `u16::MAX` of them.
+    pub clock: Option<(Duration, Duration)>,
+    /// What each side gets back on its clock every turn. Whole seconds, like the clock.
+    pub increment: Duration,
+    /// Whether players may ask to take back their last turn.
+    pub takebacks: bool,
+    /// How long a player can leave a moved piece unrotated before it's rotated by 0° for
+    /// them, finishing their turn. Whole seconds, like the clock.
+    pub auto_rotate: Option<Duration>,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            host_side: Side::White,
+            chess960_ordering: None,
+            clock: None,
+            increment: Duration::ZERO,
+            takebacks: true,
+            auto_rotate: None,
+        }
+    }
+}
+
+impl GameRules {
+    /// The host's rules, from `--side=...`, `--time=...` (increment and all), `--chess960`, `--no-takebacks` and
+    /// `--auto-rotate=...`.
+    ///
+    /// Chess960 orderings are rolled by `roll_ordering`.
+    pub fn from_args(roll_ordering: impl FnOnce() -> [usize; 8]) -> GameResult<Self> {
+        let host_side = host_side_from_args()?;
+        let (clock, increment) = match time_control_from_args()? {
+            Some((mine, theirs, _)) if mine.max(theirs).as_secs() > u16::MAX.into() => {
+                return Err(GameError::CustomError(
+                    "Clocks can't run for more than 18 hours.".to_string(),
+                ));
+            }
+            Some((mine, theirs, increment)) => (
+                Some(match host_side {
+                    Side::White => (mine, theirs),
+                    Side::Black => (theirs, mine),
+                }),
+                increment,
+            ),
+            None => (None, Duration::ZERO),
+        };
+        let flag = |name: &str| std::env::args().any(|arg| arg == name);
+        Ok(Self {
+            host_side,
+            chess960_ordering: flag("--chess960").then(roll_ordering),
+            clock,
+            increment,
+            takebacks: !flag("--no-takebacks"),
+            auto_rotate: auto_rotate_from_args()?,
+        })
+    }
+
+    /// The side the host's opponent plays.
+    pub fn client_side(&self) -> Side {
+        match self.host_side {
+            Side::White => Side::Black,
+            Side::Black => Side::White,
+        }
+    }
+
+    /// The rules for a rematch: the same game, with the host playing the other color, and
+    /// everyone keeping the time they had.
+    pub fn swapped(&self) -> Self {
+        Self {
+            host_side: self.client_side(),
+            clock: self.clock.map(|(white, black)| (black, white)),
+            ..*self
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; RULES_SIZE] {
+        let mut bytes = [0; RULES_SIZE];
+        let secs = |time: Duration| u16::try_from(time.as_secs()).unwrap_or(u16::MAX);
+        if self.host_side == Side::Black {
+            bytes[0] |= HOST_PLAYS_BLACK;
+        }
+        if !self.takebacks {
+            bytes[0] |= NO_TAKEBACKS;
+        }
+        if let Some(ordering) = self.chess960_ordering {
+            bytes[0] |= CHESS960;
+            bytes[1..3].copy_from_slice(&encode_ordering(ordering).to_be_bytes());
+        }
+        if let Some((white, black)) = self.clock {
+            bytes[0] |= HAS_CLOCK;
+            bytes[3..5].copy_from_slice(&secs(white).to_be_bytes());
+            bytes[5..7].copy_from_slice(&secs(black).to_be_bytes());
+        }
+        if let Some(timeout) = self.auto_rotate {
+            bytes[0] |= AUTO_ROTATE;
+            bytes[7..9].copy_from_slice(&secs(timeout).to_be_bytes());
+        }
+        bytes[9..11].copy_from_slice(&secs(self.increment).to_be_bytes());
+        bytes
+    }
+
+    /// The rules some bytes describe, or `None` if they don't describe any, e.g. because
+    /// their back rank isn't one Chess960 allows.
+    ///
+    /// Flags we don't know about are ignored, so a client that doesn't know them ends up
+    /// with a different [`GameRules::checksum`] than the host.
+    pub fn from_bytes(bytes: &[u8; RULES_SIZE]) -> Option<Self> {
+        let u16_at = |i: usize| u16::from_be_bytes([bytes[i], bytes[i + 1]]);
+        let secs = |i: usize| Duration::from_secs(u16_at(i).into());
+        Some(Self {
+            host_side: if bytes[0] & HOST_PLAYS_BLACK != 0 {
+                Side::Black
+            } else {
+                Side::White
+            },
+            chess960_ordering: if bytes[0] & CHESS960 != 0 {
+                Some(decode_ordering(u16_at(1)).filter(is_chess960_ordering)?)
+            } else {
+                None
+            },
+            clock: (bytes[0] & HAS_CLOCK != 0).then(|| (secs(3), secs(5))),
+            increment: secs(9),
+            takebacks: bytes[0] & NO_TAKEBACKS == 0,
+            auto_rotate: (bytes[0] & AUTO_ROTATE != 0).then(|| secs(7)),
+        })
+    }
+
+    /// A fingerprint of the rules, for the client to prove it understood them.
+    pub fn checksum(&self) -> u32 {
+        fnv1a(&self.to_bytes())
+    }
+
+    /// A line about the rules, like "Chess960, 10:00 for white and 3:00 for black".
+    pub fn describe(&self) -> String {
+        let mut description = match self.chess960_ordering {
+            Some(_) => "Chess960".to_string(),
+            None => "Standard".to_string(),
+        };
+        if let Some((white, black)) = self.clock {
+            description.push_str(&format!(
+                ", {} for white and {} for black",
+                format_clock(white),
+                format_clock(black)
+            ));
+            if !self.increment.is_zero() {
+                description.push_str(&format!(" plus {}s a turn", self.increment.as_secs()));
+            }
+        }
+        if !self.takebacks {
+            description.push_str(", no takebacks");
+        }
+        if let Some(timeout) = self.auto_rotate {
+            description.push_str(&format!(
+                ", rotations time out after {}",
+                format_clock(timeout)
+            ));
+        }
+        description
+    }
+}
+
+/// The 32-bit FNV-1a hash of some bytes.
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5, |hash, byte| {
+        (hash ^ u32::from(*byte)).wrapping_mul(0x01000193)
+    })
+}
+
+/// Which side the host plays, from `--side=white`, `--side=black` or `--side=random`.
+///
+/// Defaults to white.
+fn host_side_from_args() -> GameResult<Side> {
+    for arg in std::env::args() {
+        if let Some(("--side", side)) = arg.split_once("=") {
+            return match side {
+                "white" => Ok(Side::White),
+                "black" => Ok(Side::Black),
+                "random" if rand::random() => Ok(Side::White),
+                "random" => Ok(Side::Black),
+                _ => Err(GameError::CustomError(format!(
+                    "Unknown side {side:?}. Pick white, black or random."
+                ))),
+            };
+        }
+    }
+    Ok(Side::White)
+}
+
+/// How long moved pieces wait to be rotated, from `--auto-rotate=30` (seconds), if
+/// they're rotated for players at all.
+fn auto_rotate_from_args() -> GameResult<Option<Duration>> {
+    for arg in std::env::args() {
+        if let Some(("--auto-rotate", secs)) = arg.split_once("=") {
+            return match secs.parse::<u16>() {
+                Ok(secs) if secs > 0 => Ok(Some(Duration::from_secs(secs.into()))),
+                _ => Err(GameError::CustomError(format!(
+                    "Bad rotation timeout {secs:?}. Try --auto-rotate=30, in seconds."
+                ))),
+            };
+        }
+    }
+    Ok(None)
+}
+
+/// Whether a back rank ordering is one Chess960 allows: a permutation of 0..8 giving the
+/// file of each piece of the standard back rank (rook, knight, bishop, queen, king, bishop,
+/// knight, rook), with the bishops on opposite colors and the king between the rooks.
+pub fn is_chess960_ordering(ordering: &[usize; 8]) -> bool {
+    let mut files = *ordering;
+    files.sort_unstable();
+    if files != [0, 1, 2, 3, 4, 5, 6, 7] {
+        return false;
+    }
+    let [rook, _, bishop, _, king, other_bishop, _, other_rook] = *ordering;
+    bishop % 2 != other_bishop % 2 && (rook.min(other_rook)..rook.max(other_rook)).contains(&king)
+}
+
+/// Number a back rank ordering (a permutation of 0..8) by its Lehmer code, which fits in a
+/// u16.
+fn encode_ordering(ordering: [usize; 8]) -> u16 {
+    let mut left: Vec<usize> = (0..8).collect();
+    let mut code = 0;
+    for (i, piece) in ordering.into_iter().enumerate() {
+        let rank = left
+            .iter()
+            .position(|&p| p == piece)
+            .expect("Back rank orderings are permutations.");
+        left.remove(rank);
+        code = code * (8 - i) + rank;
+    }
+    code as u16
+}
+
+/// The ordering [`encode_ordering`] numbered, if it numbered one.
+fn decode_ordering(code: u16) -> Option<[usize; 8]> {
+    if code >= ORDERINGS {
+        return None;
+    }
+    let mut ranks = [0; 8];
+    let mut code = usize::from(code);
+    for (i, rank) in ranks.iter_mut().enumerate().rev() {
+        *rank = code % (8 - i);
+        code /= 8 - i;
+    }
+    let mut left: Vec<usize> = (0..8).collect();
+    Some(ranks.map(|rank| left.remove(rank)))
+}
+
+#[cfg(test)]
+mod test_rules {
+    use super::*;
+    use parameterized::parameterized;
+    use proptest::prelude::*;
+
+    fn arb_ordering() -> impl Strategy<Value = [usize; 8]> {
+        Just((0..8).collect::<Vec<usize>>())
+            .prop_shuffle()
+            .prop_map(|ordering| ordering.try_into().unwrap())
+    }
+
+    fn arb_rules() -> impl Strategy<Value = GameRules> {
+        let secs = (0..=u16::MAX).prop_map(|secs| Duration::from_secs(secs.into()));
+        (
+            any::<bool>(),
+            proptest::option::of(arb_ordering().prop_filter("legal", is_chess960_ordering)),
+            proptest::option::of((secs.clone(), secs.clone())),
+            secs.clone(),
+            any::<bool>(),
+            proptest::option::of(secs),
+        )
+            .prop_map(
+                |(host_black, chess960_ordering, clock, increment, takebacks, auto_rotate)| {
+                    GameRules {
+                        host_side: if host_black { Side::Black } else { Side::White },
+                        chess960_ordering,
+                        clock,
+                        increment,
+                        takebacks,
+                        auto_rotate,
+                    }
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn any_ordering_round_trips(ordering in arb_ordering()) {
+            prop_assert!(encode_ordering(ordering) < ORDERINGS);
+            prop_assert_eq!(decode_ordering(encode_ordering(ordering)), Some(ordering));
+        }
+
+        #[test]
+        fn any_rules_round_trip(rules in arb_rules()) {
+            prop_assert_eq!(GameRules::from_bytes(&rules.to_bytes()), Some(rules));
+        }
+
+        #[test]
+        fn swapping_twice_changes_nothing(rules in arb_rules()) {
+            prop_assert_eq!(rules.swapped().swapped(), rules);
+        }
+    }
+
+    #[parameterized(ordering = {
+        [0, 1, 2, 3, 4, 5, 6, 7], [7, 6, 5, 4, 3, 2, 1, 0], [1, 0, 2, 3, 4, 5, 6, 7]
+    }, code = { 0, 40319, 5040 })]
+    fn orderings_are_numbered_in_order(ordering: [usize; 8], code: u16) {
+        assert_eq!(encode_ordering(ordering), code);
+    }
+
+    #[parameterized(ordering = {
+        [0, 1, 2, 3, 4, 5, 6, 7], [1, 0, 3, 2, 4, 6, 5, 7], [7, 1, 2, 3, 4, 5, 6, 0],
+        [0, 1, 2, 3, 4, 6, 5, 7], [4, 1, 2, 3, 0, 5, 6, 7], [0, 1, 2, 3, 4, 5, 6, 6],
+        [0, 1, 2, 3, 4, 5, 6, 8]
+    }, legal = { true, true, true, false, false, false, false })]
+    fn only_chess960_orderings_are_legal(ordering: [usize; 8], legal: bool) {
+        assert_eq!(is_chess960_ordering(&ordering), legal);
+    }
+
+    #[test]
+    fn illegal_orderings_dont_decode() {
+        let mut bytes = [0; RULES_SIZE];
+        bytes[0] = CHESS960;
+        // the bishops on one color.
+        bytes[1..3].copy_from_slice(&encode_ordering([0, 1, 2, 3, 4, 6, 5, 7]).to_be_bytes());
+        assert_eq!(GameRules::from_bytes(&bytes), None);
+        bytes[1..3].copy_from_slice(&encode_ordering([1, 0, 3, 2, 4, 6, 5, 7]).to_be_bytes());
+        assert!(GameRules::from_bytes(&bytes).is_some());
+    }
+
+    #[test]
+    fn out_of_range_orderings_dont_decode() {
+        let mut bytes = [0; RULES_SIZE];
+        bytes[0] = CHESS960;
+        bytes[1..3].copy_from_slice(&ORDERINGS.to_be_bytes());
+        assert_eq!(GameRules::from_bytes(&bytes), None);
+    }
+
+    #[test]
+    fn rematches_swap_colors_but_not_times() {
+        let rules = GameRules {
+            clock: Some((Duration::from_secs(600), Duration::from_secs(180))),
+            ..GameRules::default()
+        };
+        let swapped = rules.swapped();
+        assert_eq!(swapped.host_side, Side::Black);
+        assert_eq!(
+            swapped.clock,
+            Some((Duration::from_secs(180), Duration::from_secs(600)))
+        );
+        assert_eq!(swapped.chess960_ordering, rules.chess960_ordering);
+    }
+
+    #[test]
+    fn unknown_flags_change_the_checksum() {
+        let rules = GameRules::default();
+        let mut bytes = rules.to_bytes();
+        bytes[0] |= 1 << 7;
+        let understood = GameRules::from_bytes(&bytes).unwrap();
+        assert_eq!(understood, rules);
+        assert_ne!(understood.checksum(), fnv1a(&bytes));
+    }
+
+    #[test]
+    fn descriptions_mention_what_differs() {
+        assert_eq!(GameRules::default().describe(), "Standard");
+        let rules = GameRules {
+            chess960_ordering: Some([0, 1, 2, 3, 4, 5, 6, 7]),
+            clock: Some((Duration::from_secs(600), Duration::from_secs(180))),
+            increment: Duration::from_secs(3),
+            takebacks: false,
+            auto_rotate: Some(Duration::from_secs(30)),
+            ..GameRules::default()
+        };
+        assert_eq!(
+            rules.describe(),
+            "Chess960, 10:00 for white and 3:00 for black plus 3s a turn, no takebacks, \
+             rotations time out after 0:30"
+        );
+    }
+}
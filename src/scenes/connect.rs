@@ -0,0 +1,109 @@
+//! Where we wait for the ticket of a game we're hosting, before there's a game to show it in.
+//!
+//! If the relay can't be reached, or the user gets tired of waiting on it, we go back to the
+//! menu to play something that doesn't need it.
+
+use ggez::{
+    Context, GameResult,
+    event::EventHandler,
+    glam::Vec2,
+    graphics::{Canvas, DrawParam, Text, TextLayout},
+    input::keyboard::{Key, KeyInput, NamedKey},
+    winit::platform::modifier_supplement::KeyEventExtModifierSupplement,
+};
+use sfn_tpn::{Config, NetcodeInterface};
+use tokio::sync::oneshot::{self, error::TryRecvError};
+
+use super::SCENE_TEXT_SIZE;
+use crate::{constants::TURN_SIZE, rules::GameRules, theme::Theme};
+
+pub struct ConnectScene {
+    /// Taken once the game starts.
+    netcode: Option<NetcodeInterface<TURN_SIZE>>,
+    ticket: oneshot::Receiver<String>,
+    rules: GameRules,
+    /// Whether the user gave up on hosting.
+    cancelled: bool,
+}
+
+/// How setting up a hosted game is going.
+pub enum Hosting {
+    /// Still waiting on a ticket.
+    Waiting,
+    /// Everything needed to start the game.
+    Ready(NetcodeInterface<TURN_SIZE>, GameRules, String),
+    /// The relay couldn't be reached.
+    Failed,
+    /// The user stopped waiting for it.
+    Cancelled,
+}
+
+impl ConnectScene {
+    /// Start hosting a game played by some rules.
+    pub fn host(rules: GameRules) -> Self {
+        let (send, recv) = oneshot::channel();
+        Self {
+            netcode: Some(NetcodeInterface::new(Config::TicketSender(send))),
+            ticket: recv,
+            rules,
+            cancelled: false,
+        }
+    }
+
+    /// Once there's a ticket, everything needed to start the game.
+    pub fn take_hosted(&mut self) -> Hosting {
+        if self.cancelled {
+            return Hosting::Cancelled;
+        }
+        match self.ticket.try_recv() {
+            Ok(ticket) => {
+                println!(
+                    "hosting game. another player may join with \n\n\
+                    cargo run client --ticket={ticket}"
+                );
+                match self.netcode.take() {
+                    Some(netcode) => Hosting::Ready(netcode, self.rules, ticket),
+                    None => Hosting::Waiting,
+                }
+            }
+            Err(TryRecvError::Empty) => Hosting::Waiting,
+            Err(TryRecvError::Closed) => {
+                println!("Couldn't set up a game to host.");
+                Hosting::Failed
+            }
+        }
+    }
+}
+
+impl EventHandler for ConnectScene {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = Canvas::from_frame(ctx, Theme::DEFAULT.background);
+        let (width, height) = ctx.gfx.drawable_size();
+        let mut text = Text::new("Setting up a game to host...\n\n(Esc to go back)");
+        text.set_scale(SCENE_TEXT_SIZE)
+            .set_layout(TextLayout::center());
+        canvas.draw(
+            &text,
+            DrawParam::new()
+                .dest(Vec2::new(width / 2., height / 2.))
+                .color(Theme::DEFAULT.dialog_text),
+        );
+        canvas.finish(ctx)
+    }
+
+    fn key_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> GameResult {
+        if input.event.key_without_modifiers() == Key::Named(NamedKey::Escape) {
+            self.cancelled = true;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,538 @@
+//! The main menu: pick a layout, then host, join, play at this computer, or play the
+//! computer after setting up its personality.
+
+use ggez::{
+    Context, GameResult,
+    event::EventHandler,
+    glam::Vec2,
+    graphics::{Canvas, DrawParam, Text, TextLayout},
+    input::keyboard::{Key, KeyInput, NamedKey},
+    winit::platform::modifier_supplement::KeyEventExtModifierSupplement,
+};
+
+use super::SCENE_TEXT_SIZE;
+use crate::{engine::Personality, theme::Theme};
+
+/// How many characters of a ticket to show per line. Tickets are long, and would run off
+/// the window otherwise.
+const TICKET_LINE_LENGTH: usize = 48;
+/// How far left or right turns a personality knob.
+const KNOB_STEP: f32 = 0.1;
+/// What the menu says when a hosted game couldn't be set up.
+///
+/// Every networked game goes through the relay, joining by ticket, so there's no direct
+/// connection to offer instead. The netcode has no way to connect to an address.
+const OFFLINE_NOTICE: &str = "Couldn't reach the relay to host a game.\n\
+    Networked games always go through it,\n\
+    so there's no connecting directly instead.\n\
+    You can still play at this computer, or play the computer.";
+
+/// What the menu was left with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuChoice {
+    Host {
+        chess960: bool,
+    },
+    Join {
+        ticket: String,
+    },
+    HotSeat {
+        chess960: bool,
+    },
+    VsBot {
+        chess960: bool,
+        personality: Personality,
+    },
+}
+
+/// The lines of the menu, top to bottom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Item {
+    Layout,
+    Host,
+    Join,
+    HotSeat,
+    VsBot,
+}
+
+const ITEMS: [Item; 5] = [
+    Item::Layout,
+    Item::Host,
+    Item::Join,
+    Item::HotSeat,
+    Item::VsBot,
+];
+
+/// The lines of the computer's setup, top to bottom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Knob {
+    Aggressiveness,
+    RotationHappiness,
+    Randomness,
+    Start,
+}
+
+const KNOBS: [Knob; 4] = [
+    Knob::Aggressiveness,
+    Knob::RotationHappiness,
+    Knob::Randomness,
+    Knob::Start,
+];
+
+/// Setting up the computer's personality, once Play the computer was picked.
+#[derive(Debug, Default)]
+struct BotSetup {
+    /// Index into [`KNOBS`].
+    selected: usize,
+    personality: Personality,
+}
+
+impl BotSetup {
+    fn knob(&mut self, knob: Knob) -> Option<&mut f32> {
+        match knob {
+            Knob::Aggressiveness => Some(&mut self.personality.aggressiveness),
+            Knob::RotationHappiness => Some(&mut self.personality.rotation_happiness),
+            Knob::Randomness => Some(&mut self.personality.temperature),
+            Knob::Start => None,
+        }
+    }
+
+    /// Turn the selected knob some steps, keeping it between 0 and 1.
+    fn turn(&mut self, steps: f32) {
+        if let Some(value) = self.knob(KNOBS[self.selected]) {
+            // rounded, so it lands right back on 0.5 after some steps.
+            *value = ((*value + steps * KNOB_STEP).clamp(0., 1.) / KNOB_STEP).round() * KNOB_STEP;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MenuScene {
+    /// Index into [`ITEMS`].
+    selected: usize,
+    chess960: bool,
+    /// The ticket being typed in, once Join was picked.
+    ticket_draft: Option<String>,
+    /// Whether the last paste into the ticket couldn't get at the clipboard.
+    paste_failed: bool,
+    bot_setup: Option<BotSetup>,
+    choice: Option<MenuChoice>,
+    /// Why we're back at the menu, if something went wrong.
+    notice: Option<&'static str>,
+}
+
+impl MenuScene {
+    /// The menu, already asking for a ticket to join with.
+    pub fn joining() -> Self {
+        Self {
+            selected: ITEMS
+                .iter()
+                .position(|&item| item == Item::Join)
+                .unwrap_or(0),
+            ticket_draft: Some(String::new()),
+            ..Self::default()
+        }
+    }
+
+    /// The menu, after hosting a game didn't work out, with the games that don't need the
+    /// network picked out.
+    pub fn offline() -> Self {
+        Self {
+            selected: ITEMS
+                .iter()
+                .position(|&item| item == Item::HotSeat)
+                .unwrap_or(0),
+            notice: Some(OFFLINE_NOTICE),
+            ..Self::default()
+        }
+    }
+
+    /// What was picked, if anything has been yet.
+    pub fn take_choice(&mut self) -> Option<MenuChoice> {
+        self.choice.take()
+    }
+
+    pub fn press(&mut self, key: &Key) {
+        if let Some(draft) = &mut self.ticket_draft {
+            match key {
+                Key::Named(NamedKey::Enter) => {
+                    if let Some(ticket) = parse_ticket(draft) {
+                        self.choice = Some(MenuChoice::Join { ticket });
+                    }
+                }
+                Key::Named(NamedKey::Escape) => self.ticket_draft = None,
+                Key::Named(NamedKey::Backspace) => {
+                    draft.pop();
+                }
+                _ => (),
+            }
+            return;
+        }
+        if let Some(setup) = &mut self.bot_setup {
+            match key {
+                Key::Named(NamedKey::ArrowUp) => {
+                    setup.selected = (setup.selected + KNOBS.len() - 1) % KNOBS.len();
+                }
+                Key::Named(NamedKey::ArrowDown) => {
+                    setup.selected = (setup.selected + 1) % KNOBS.len()
+                }
+                Key::Named(NamedKey::ArrowLeft) => setup.turn(-1.),
+                Key::Named(NamedKey::ArrowRight) => setup.turn(1.),
+                Key::Named(NamedKey::Enter) => {
+                    self.choice = Some(MenuChoice::VsBot {
+                        chess960: self.chess960,
+                        personality: setup.personality,
+                    })
+                }
+                Key::Named(NamedKey::Escape) => self.bot_setup = None,
+                _ => (),
+            }
+            return;
+        }
+        match key {
+            Key::Named(NamedKey::ArrowUp) => {
+                self.selected = (self.selected + ITEMS.len() - 1) % ITEMS.len();
+            }
+            Key::Named(NamedKey::ArrowDown) => self.selected = (self.selected + 1) % ITEMS.len(),
+            Key::Named(NamedKey::ArrowLeft | NamedKey::ArrowRight)
+                if ITEMS[self.selected] == Item::Layout =>
+            {
+                self.chess960 = !self.chess960;
+            }
+            Key::Named(NamedKey::Enter) => match ITEMS[self.selected] {
+                Item::Layout => self.chess960 = !self.chess960,
+                Item::Host => {
+                    self.choice = Some(MenuChoice::Host {
+                        chess960: self.chess960,
+                    })
+                }
+                Item::Join => self.ticket_draft = Some(String::new()),
+                Item::HotSeat => {
+                    self.choice = Some(MenuChoice::HotSeat {
+                        chess960: self.chess960,
+                    })
+                }
+                Item::VsBot => self.bot_setup = Some(BotSetup::default()),
+            },
+            _ => (),
+        }
+    }
+
+    /// Type a character into the ticket, if one's being typed.
+    pub fn type_char(&mut self, character: char) {
+        if let Some(draft) = &mut self.ticket_draft
+            && !character.is_control()
+        {
+            draft.push(character);
+        }
+    }
+
+    /// Paste some text onto the end of the ticket, if one's being typed. Line breaks and
+    /// the like are left out, so a ticket copied with its newline still works.
+    pub fn paste(&mut self, text: &str) {
+        if let Some(draft) = &mut self.ticket_draft {
+            draft.extend(text.chars().filter(|c| !c.is_control()));
+            self.paste_failed = false;
+        }
+    }
+
+    /// Paste whatever's on the clipboard into the ticket.
+    fn paste_clipboard(&mut self) {
+        if self.ticket_draft.is_none() {
+            return;
+        }
+        match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(text) => self.paste(&text),
+            Err(e) => {
+                println!("Couldn't paste: {e}");
+                self.paste_failed = true;
+            }
+        }
+    }
+
+    /// What to show, line by line.
+    fn lines(&self) -> Vec<String> {
+        if let Some(draft) = &self.ticket_draft {
+            let mut lines = vec!["Paste (Ctrl+V) or type the host's ticket:".to_string()];
+            let chars: Vec<char> = format!("{draft}_").chars().collect();
+            lines.extend(
+                chars
+                    .chunks(TICKET_LINE_LENGTH)
+                    .map(|line| line.iter().collect()),
+            );
+            lines.push(String::new());
+            if self.paste_failed {
+                lines.push("Couldn't paste from the clipboard. Try typing it.".to_string());
+            }
+            lines.push("(Enter to join, Esc to go back)".to_string());
+            return lines;
+        }
+        if let Some(setup) = &self.bot_setup {
+            let mut lines = vec!["The computer's personality".to_string(), String::new()];
+            let personality = setup.personality;
+            for (i, knob) in KNOBS.iter().enumerate() {
+                let percent = |value: f32| format!("< {:.0}% >", value * 100.);
+                let label = match knob {
+                    Knob::Aggressiveness => {
+                        format!("Aggressiveness: {}", percent(personality.aggressiveness))
+                    }
+                    Knob::RotationHappiness => format!(
+                        "Rotation happiness: {}",
+                        percent(personality.rotation_happiness)
+                    ),
+                    Knob::Randomness => format!("Randomness: {}", percent(personality.temperature)),
+                    Knob::Start => "Start".to_string(),
+                };
+                lines.push(if i == setup.selected {
+                    format!("> {label} <")
+                } else {
+                    label
+                });
+            }
+            lines.push(String::new());
+            lines.push("(Enter to play, Esc to go back)".to_string());
+            return lines;
+        }
+        let mut lines = vec!["Rotating Chess".to_string(), String::new()];
+        if let Some(notice) = self.notice {
+            lines.extend(notice.lines().map(str::to_string));
+            lines.push(String::new());
+        }
+        for (i, item) in ITEMS.iter().enumerate() {
+            let label = match item {
+                Item::Layout if self.chess960 => "Layout: < Chess960 >",
+                Item::Layout => "Layout: < Standard >",
+                Item::Host => "Host a game",
+                Item::Join => "Join a game",
+                Item::HotSeat => "Play at this computer",
+                Item::VsBot => "Play the computer",
+            };
+            lines.push(if i == self.selected {
+                format!("> {label} <")
+            } else {
+                label.to_string()
+            });
+        }
+        lines
+    }
+}
+
+/// The ticket in what was typed or pasted, which might be the whole command the host
+/// printed (`cargo run client --ticket=...`) rather than just the ticket.
+fn parse_ticket(text: &str) -> Option<String> {
+    let last = text.split_whitespace().last()?;
+    let ticket = last.strip_prefix("--ticket=").unwrap_or(last);
+    (!ticket.is_empty()).then(|| ticket.to_string())
+}
+
+impl EventHandler for MenuScene {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = Canvas::from_frame(ctx, Theme::DEFAULT.background);
+        let (width, height) = ctx.gfx.drawable_size();
+        let mut text = Text::new(self.lines().join("\n"));
+        text.set_scale(SCENE_TEXT_SIZE)
+            .set_layout(TextLayout::center());
+        canvas.draw(
+            &text,
+            DrawParam::new()
+                .dest(Vec2::new(width / 2., height / 2.))
+                .color(Theme::DEFAULT.dialog_text),
+        );
+        canvas.finish(ctx)
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> GameResult {
+        let key = input.event.key_without_modifiers();
+        let mods = ctx.keyboard.active_mods();
+        if (mods.control_key() || mods.super_key())
+            && matches!(&key, Key::Character(c) if c.eq_ignore_ascii_case("v"))
+        {
+            self.paste_clipboard();
+            return Ok(());
+        }
+        self.press(&key);
+        Ok(())
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) -> GameResult {
+        self.type_char(character);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_menu {
+    use super::*;
+    use parameterized::parameterized;
+
+    fn press_all(menu: &mut MenuScene, keys: &[NamedKey]) {
+        for key in keys {
+            menu.press(&Key::Named(*key));
+        }
+    }
+
+    #[test]
+    fn hosting_takes_the_layout_along() {
+        let mut menu = MenuScene::default();
+        press_all(
+            &mut menu,
+            &[NamedKey::ArrowRight, NamedKey::ArrowDown, NamedKey::Enter],
+        );
+        assert_eq!(
+            menu.take_choice(),
+            Some(MenuChoice::Host { chess960: true })
+        );
+        assert_eq!(menu.take_choice(), None);
+    }
+
+    #[test]
+    fn selection_wraps_around() {
+        let mut menu = MenuScene::default();
+        press_all(
+            &mut menu,
+            &[NamedKey::ArrowUp, NamedKey::ArrowUp, NamedKey::Enter],
+        );
+        assert_eq!(
+            menu.take_choice(),
+            Some(MenuChoice::HotSeat { chess960: false })
+        );
+    }
+
+    #[test]
+    fn playing_the_computer_sets_up_its_personality() {
+        let mut menu = MenuScene::default();
+        press_all(&mut menu, &[NamedKey::ArrowUp, NamedKey::Enter]);
+        assert_eq!(menu.take_choice(), None);
+        press_all(
+            &mut menu,
+            &[
+                // as aggressive as it gets.
+                NamedKey::ArrowRight,
+                NamedKey::ArrowRight,
+                NamedKey::ArrowRight,
+                NamedKey::ArrowRight,
+                NamedKey::ArrowRight,
+                NamedKey::ArrowRight,
+                NamedKey::ArrowDown,
+                NamedKey::ArrowLeft,
+                NamedKey::Enter,
+            ],
+        );
+        let Some(MenuChoice::VsBot {
+            chess960: false,
+            personality,
+        }) = menu.take_choice()
+        else {
+            panic!("expected a game against the computer");
+        };
+        assert!((personality.aggressiveness - 1.).abs() < 1e-6);
+        assert!((personality.rotation_happiness - 0.4).abs() < 1e-6);
+        assert_eq!(personality.temperature, Personality::default().temperature);
+    }
+
+    #[test]
+    fn joining_asks_for_a_ticket() {
+        let mut menu = MenuScene::default();
+        press_all(
+            &mut menu,
+            &[NamedKey::ArrowDown, NamedKey::ArrowDown, NamedKey::Enter],
+        );
+        // nothing to join with yet.
+        press_all(&mut menu, &[NamedKey::Enter]);
+        assert_eq!(menu.take_choice(), None);
+
+        for c in " abcd\u{8}".chars() {
+            menu.type_char(c);
+        }
+        press_all(&mut menu, &[NamedKey::Backspace, NamedKey::Enter]);
+        assert_eq!(
+            menu.take_choice(),
+            Some(MenuChoice::Join {
+                ticket: "abc".to_string()
+            })
+        );
+    }
+
+    #[parameterized(pasted = {
+        "abc", "  abc\n", "--ticket=abc", "cargo run client --ticket=abc"
+    })]
+    fn tickets_can_be_pasted_with_the_command(pasted: &str) {
+        assert_eq!(parse_ticket(pasted).as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn pasting_adds_to_the_ticket() {
+        let mut menu = MenuScene::default();
+        // nowhere to paste yet.
+        menu.paste("abc");
+        assert_eq!(menu.ticket_draft, None);
+
+        let mut menu = MenuScene::joining();
+        menu.paste("cargo run client --ticket=bc\r\n");
+        assert_eq!(
+            menu.ticket_draft.as_deref(),
+            Some("cargo run client --ticket=bc")
+        );
+        press_all(&mut menu, &[NamedKey::Enter]);
+        assert_eq!(
+            menu.take_choice(),
+            Some(MenuChoice::Join {
+                ticket: "bc".to_string()
+            })
+        );
+    }
+
+    #[parameterized(pasted = { "", "   ", "--ticket=" })]
+    fn blank_tickets_arent_tickets(pasted: &str) {
+        assert_eq!(parse_ticket(pasted), None);
+    }
+
+    #[test]
+    fn long_tickets_wrap() {
+        let mut menu = MenuScene::joining();
+        for _ in 0..TICKET_LINE_LENGTH + 1 {
+            menu.type_char('a');
+        }
+        let lines = menu.lines();
+        assert_eq!(lines[1].len(), TICKET_LINE_LENGTH);
+        assert_eq!(lines[2], "a_");
+    }
+
+    #[test]
+    fn escape_leaves_the_ticket() {
+        let mut menu = MenuScene::default();
+        press_all(
+            &mut menu,
+            &[NamedKey::ArrowDown, NamedKey::ArrowDown, NamedKey::Enter],
+        );
+        menu.type_char('x');
+        press_all(
+            &mut menu,
+            &[NamedKey::Escape, NamedKey::ArrowDown, NamedKey::Enter],
+        );
+        assert_eq!(
+            menu.take_choice(),
+            Some(MenuChoice::HotSeat { chess960: false })
+        );
+    }
+
+    #[test]
+    fn offline_menu_says_why_and_offers_local_play() {
+        let mut menu = MenuScene::offline();
+        assert!(menu.lines().iter().any(|line| line.contains("relay")));
+        press_all(&mut menu, &[NamedKey::Enter]);
+        assert_eq!(
+            menu.take_choice(),
+            Some(MenuChoice::HotSeat { chess960: false })
+        );
+    }
+}
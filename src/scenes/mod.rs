@@ -0,0 +1,195 @@
+//! What's on screen: a main menu to pick a game from, a screen to wait on while a hosted
+//! game gets set up, and then the game itself, an [`App`].
+//!
+//! Asking for a game on the command line (`server`, `client`, `--local` and so on) skips
+//! straight to it.
+
+mod connect;
+mod menu;
+
+use ggez::{Context, GameResult, event::EventHandler, input::keyboard::KeyInput};
+use sfn_tpn::{Config, NetcodeInterface};
+
+pub use connect::{ConnectScene, Hosting};
+pub use menu::{MenuChoice, MenuScene};
+
+use crate::{
+    app::{App, roll_chess960_ordering},
+    bot::resign_threshold_from_args,
+    rules::GameRules,
+};
+
+/// Font size in pixels of the menu and connect screens.
+const SCENE_TEXT_SIZE: f32 = 28.;
+
+pub enum Scene {
+    Menu(MenuScene),
+    Connect(ConnectScene),
+    Game(Box<App>),
+}
+
+/// Runs whichever scene is up, moving on to the next when it's done.
+pub struct Scenes {
+    scene: Scene,
+}
+
+impl Scenes {
+    pub fn menu() -> Self {
+        Self {
+            scene: Scene::Menu(MenuScene::default()),
+        }
+    }
+
+    /// The menu, saying hosting didn't work out, for servers that couldn't reach the relay.
+    pub fn offline() -> Self {
+        Self {
+            scene: Scene::Menu(MenuScene::offline()),
+        }
+    }
+
+    /// The menu, asking for a ticket, for clients started without one.
+    pub fn joining() -> Self {
+        Self {
+            scene: Scene::Menu(MenuScene::joining()),
+        }
+    }
+
+    pub fn playing(app: App) -> Self {
+        Self {
+            scene: Scene::Game(Box::new(app)),
+        }
+    }
+
+    fn current(&mut self) -> &mut dyn EventHandler {
+        match &mut self.scene {
+            Scene::Menu(menu) => menu,
+            Scene::Connect(connect) => connect,
+            Scene::Game(app) => app.as_mut(),
+        }
+    }
+
+    /// Move on from the current scene, if it's done.
+    fn advance(&mut self, ctx: &mut Context) -> GameResult {
+        let next = match &mut self.scene {
+            Scene::Menu(menu) => match menu.take_choice() {
+                Some(choice) => Some(start(ctx, choice)?),
+                None => None,
+            },
+            Scene::Connect(connect) => match connect.take_hosted() {
+                Hosting::Waiting => None,
+                Hosting::Ready(netcode, rules, ticket) => Some(Scene::Game(Box::new(
+                    App::networked(ctx, netcode, rules, Some(ticket)),
+                ))),
+                Hosting::Failed => Some(Scene::Menu(MenuScene::offline())),
+                Hosting::Cancelled => Some(Scene::Menu(MenuScene::default())),
+            },
+            Scene::Game(_) => None,
+        };
+        if let Some(next) = next {
+            self.scene = next;
+        }
+        Ok(())
+    }
+}
+
+/// The scene that plays what was picked from the menu.
+fn start(ctx: &mut Context, choice: MenuChoice) -> GameResult<Scene> {
+    let rules = |chess960: bool| -> GameResult<GameRules> {
+        Ok(GameRules {
+            chess960_ordering: chess960.then(roll_chess960_ordering),
+            ..GameRules::from_args(roll_chess960_ordering)?
+        })
+    };
+    Ok(match choice {
+        MenuChoice::Host { chess960 } => Scene::Connect(ConnectScene::host(rules(chess960)?)),
+        MenuChoice::Join { ticket } => Scene::Game(Box::new(App::networked(
+            ctx,
+            NetcodeInterface::new(Config::Ticket(ticket)),
+            // the host's rules are the ones that count.
+            GameRules::default(),
+            None,
+        ))),
+        MenuChoice::HotSeat { chess960 } => {
+            Scene::Game(Box::new(App::hot_seat(ctx, rules(chess960)?)))
+        }
+        MenuChoice::VsBot {
+            chess960,
+            personality,
+        } => Scene::Game(Box::new(App::vs_bot(
+            ctx,
+            rules(chess960)?,
+            personality,
+            resign_threshold_from_args()?,
+        ))),
+    })
+}
+
+impl EventHandler for Scenes {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        self.current().update(ctx)?;
+        self.advance(ctx)
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        self.current().draw(ctx)
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: ggez::winit::event::MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        self.current().mouse_button_down_event(ctx, button, x, y)
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        ctx: &mut Context,
+        button: ggez::winit::event::MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        self.current().mouse_button_up_event(ctx, button, x, y)
+    }
+
+    fn mouse_motion_event(
+        &mut self,
+        ctx: &mut Context,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    ) -> GameResult {
+        self.current().mouse_motion_event(ctx, x, y, dx, dy)
+    }
+
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, x: f32, y: f32) -> GameResult {
+        self.current().mouse_wheel_event(ctx, x, y)
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeated: bool) -> GameResult {
+        self.current().key_down_event(ctx, input, repeated)
+    }
+
+    fn key_up_event(&mut self, ctx: &mut Context, input: KeyInput) -> GameResult {
+        self.current().key_up_event(ctx, input)
+    }
+
+    fn text_input_event(&mut self, ctx: &mut Context, character: char) -> GameResult {
+        self.current().text_input_event(ctx, character)
+    }
+
+    fn focus_event(&mut self, ctx: &mut Context, gained: bool) -> GameResult {
+        self.current().focus_event(ctx, gained)
+    }
+
+    fn quit_event(&mut self, ctx: &mut Context) -> GameResult<bool> {
+        self.current().quit_event(ctx)
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        self.current().resize_event(ctx, width, height)
+    }
+}
@@ -0,0 +1,289 @@
+//! Knobs for how the board looks, read from [`SETTINGS_PATH`] in the working directory.
+//!
+//! The file is plain text, one `name value` pair per line, and anything left out keeps its
+//! default:
+//!
+//! ```text
+//! piece_scale 0.9
+//! outline_thickness 1
+//! indicator_size 0.12
+//! highlight_tolerance 0.5
+//! white_tint #ffffff
+//! black_tint #ffffff
+//! turn_chime bell
+//! turn_chime_volume 1
+//! effects_volume 1
+//! reduced_motion off
+//! rotation_step 5
+//! fine_rotation_step 1
+//! coarse_rotation_step 45
+//! pause_when_unfocused on
+//! ```
+//!
+//! Tints are `#rrggbb` or `#rrggbbaa` colors. The turn chime is `bell`, `blip` or `off`, and
+//! `reduced_motion` and `pause_when_unfocused` are `on` or `off`. Rotation steps are in degrees.
+//! Lines starting with `#` are comments.
+
+use std::path::Path;
+
+use ggez::{GameError, GameResult, graphics::Color};
+
+use crate::audio::TurnChime;
+
+pub const SETTINGS_PATH: &str = "rotchess-settings.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Settings {
+    /// How much of a tile a piece image takes up.
+    pub piece_scale: f32,
+    /// Width of piece and travel point outlines, in pixels.
+    pub outline_thickness: f32,
+    /// Radius of the dots (and triangles) marking where a piece can go, in rotchess units.
+    pub indicator_size: f32,
+    /// How far past a piece's outline its highlight goes, in pixels.
+    ///
+    /// Without this, background pokes through between the highlight and outline.
+    pub highlight_tolerance: f32,
+    /// Multiplied into the white piece images, to recolor that side. White leaves them be.
+    pub white_tint: Color,
+    /// Multiplied into the black piece images, like `white_tint`.
+    pub black_tint: Color,
+    /// What plays when the opponent hands us the turn.
+    pub turn_chime: TurnChime,
+    /// How loud the turn chime is, where 1 is as loud as it was recorded.
+    pub turn_chime_volume: f32,
+    /// How loud the sounds for moves, captures and rotations are, like `turn_chime_volume`.
+    pub effects_volume: f32,
+    /// Whether things happen at once instead of moving, shaking or fading, for anyone motion
+    /// bothers. Pieces jump into place, and effects show steadily until they're gone.
+    pub reduced_motion: bool,
+    /// How far the keys and scroll wheel turn a piece at a time, in degrees.
+    pub rotation_step: f32,
+    /// Like `rotation_step`, with ctrl held.
+    pub fine_rotation_step: f32,
+    /// Like `rotation_step`, with shift held.
+    pub coarse_rotation_step: f32,
+    /// Whether the clocks and the computer stop while the window isn't focused, in games
+    /// that aren't over the network.
+    pub pause_when_unfocused: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            piece_scale: 0.9,
+            outline_thickness: 1.,
+            indicator_size: 0.12,
+            highlight_tolerance: 0.5,
+            white_tint: Color::WHITE,
+            black_tint: Color::WHITE,
+            turn_chime: TurnChime::Bell,
+            turn_chime_volume: 1.,
+            effects_volume: 1.,
+            reduced_motion: false,
+            rotation_step: 5.,
+            fine_rotation_step: 1.,
+            coarse_rotation_step: 45.,
+            pause_when_unfocused: true,
+        }
+    }
+}
+
+impl Settings {
+    /// Load the settings file, or the defaults if there isn't one.
+    pub fn load(path: &Path) -> GameResult<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::from_text(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// These settings, blown up so the back of a classroom can see them on a projector.
+    ///
+    /// Pieces get a quarter bigger, up to a tile and a half across, past which they'd hide
+    /// their neighbours.
+    pub fn for_projector(self) -> Self {
+        Self {
+            piece_scale: f32::min(self.piece_scale * 1.25, 1.5),
+            outline_thickness: self.outline_thickness * 3.,
+            indicator_size: self.indicator_size * 1.75,
+            highlight_tolerance: self.highlight_tolerance * 3.,
+            ..self
+        }
+    }
+
+    pub fn from_text(text: &str) -> GameResult<Self> {
+        let mut settings = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| malformed(line_no, "expected a name and a value"))?;
+            let value = value.trim();
+            match name {
+                "piece_scale" => settings.piece_scale = parse_size(line_no, value)?,
+                "outline_thickness" => settings.outline_thickness = parse_size(line_no, value)?,
+                "indicator_size" => settings.indicator_size = parse_size(line_no, value)?,
+                "highlight_tolerance" => settings.highlight_tolerance = parse_size(line_no, value)?,
+                "white_tint" => settings.white_tint = parse_color(line_no, value)?,
+                "black_tint" => settings.black_tint = parse_color(line_no, value)?,
+                "turn_chime" => {
+                    settings.turn_chime = TurnChime::from_name(value)
+                        .ok_or_else(|| malformed(line_no, "expected bell, blip or off"))?
+                }
+                "turn_chime_volume" => settings.turn_chime_volume = parse_size(line_no, value)?,
+                "effects_volume" => settings.effects_volume = parse_size(line_no, value)?,
+                "reduced_motion" => settings.reduced_motion = parse_switch(line_no, value)?,
+                "rotation_step" => settings.rotation_step = parse_size(line_no, value)?,
+                "fine_rotation_step" => settings.fine_rotation_step = parse_size(line_no, value)?,
+                "coarse_rotation_step" => {
+                    settings.coarse_rotation_step = parse_size(line_no, value)?
+                }
+                "pause_when_unfocused" => {
+                    settings.pause_when_unfocused = parse_switch(line_no, value)?
+                }
+                _ => return Err(malformed(line_no, "unknown setting")),
+            }
+        }
+        Ok(settings)
+    }
+}
+
+fn parse_size(line_no: usize, value: &str) -> GameResult<f32> {
+    let value: f32 = value
+        .parse()
+        .map_err(|_| malformed(line_no, "expected a number"))?;
+    if !value.is_finite() || value < 0. {
+        return Err(malformed(line_no, "expected a positive number"));
+    }
+    Ok(value)
+}
+
+fn parse_switch(line_no: usize, value: &str) -> GameResult<bool> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(malformed(line_no, "expected on or off")),
+    }
+}
+
+fn parse_color(line_no: usize, value: &str) -> GameResult<Color> {
+    hex_color(value).ok_or_else(|| malformed(line_no, "expected a color like #ff0000"))
+}
+
+/// Parse a `#rrggbb` or `#rrggbbaa` color.
+pub fn hex_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    let hex = match hex.len() {
+        6 => format!("{hex}ff"),
+        8 => hex.to_string(),
+        _ => return None,
+    };
+    let rgba = u32::from_str_radix(&hex, 16).ok()?;
+    let [r, g, b, a] = rgba.to_be_bytes().map(|c| c as f32 / 255.);
+    Some(Color::new(r, g, b, a))
+}
+
+fn malformed(line_no: usize, why: &str) -> GameError {
+    GameError::CustomError(format!("Malformed settings at line {line_no}: {why}."))
+}
+
+#[cfg(test)]
+mod test_settings_format {
+    use super::*;
+
+    #[test]
+    fn missing_settings_keep_defaults() {
+        let settings = Settings::from_text("# big pieces\npiece_scale 1.2\n").unwrap();
+        assert_eq!(
+            settings,
+            Settings {
+                piece_scale: 1.2,
+                ..Settings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn garbage_is_an_error() {
+        assert!(Settings::from_text("piece_scale").is_err());
+        assert!(Settings::from_text("piece_scale big").is_err());
+        assert!(Settings::from_text("piece_scale -1").is_err());
+        assert!(Settings::from_text("piece_colour 1").is_err());
+        assert!(Settings::from_text("white_tint red").is_err());
+        assert!(Settings::from_text("white_tint #ff00").is_err());
+        assert!(Settings::from_text("white_tint #gg0000").is_err());
+        assert!(Settings::from_text("turn_chime gong").is_err());
+        assert!(Settings::from_text("reduced_motion yes").is_err());
+    }
+
+    #[test]
+    fn projectors_blow_pieces_up() {
+        let big = |piece_scale| {
+            Settings {
+                piece_scale,
+                ..Settings::default()
+            }
+            .for_projector()
+            .piece_scale
+        };
+        assert_eq!(big(0.8), 1.);
+        assert!(big(1.) > 1.);
+        assert_eq!(big(2.), 1.5);
+    }
+
+    #[test]
+    fn tints_are_hex_colors() {
+        let settings = Settings::from_text("white_tint #ff0000\nblack_tint #0000ff80").unwrap();
+        assert_eq!(settings.white_tint, Color::new(1., 0., 0., 1.));
+        assert_eq!(settings.black_tint, Color::new(0., 0., 1., 128. / 255.));
+    }
+
+    #[test]
+    fn reduced_motion_switches() {
+        assert!(
+            Settings::from_text("reduced_motion on")
+                .unwrap()
+                .reduced_motion
+        );
+        assert!(
+            !Settings::from_text("reduced_motion off")
+                .unwrap()
+                .reduced_motion
+        );
+    }
+
+    #[test]
+    fn pausing_when_unfocused_switches() {
+        assert!(Settings::default().pause_when_unfocused);
+        assert!(
+            !Settings::from_text("pause_when_unfocused off")
+                .unwrap()
+                .pause_when_unfocused
+        );
+    }
+
+    #[test]
+    fn turn_chimes_go_by_name() {
+        let settings = Settings::from_text("turn_chime blip\nturn_chime_volume 0.5").unwrap();
+        assert_eq!(settings.turn_chime, TurnChime::Blip);
+        assert_eq!(settings.turn_chime_volume, 0.5);
+        assert_eq!(
+            Settings::from_text("turn_chime off").unwrap().turn_chime,
+            TurnChime::Off
+        );
+    }
+
+    #[test]
+    fn rotation_steps_are_degrees() {
+        let settings = Settings::from_text("rotation_step 15\ncoarse_rotation_step 90").unwrap();
+        assert_eq!(settings.rotation_step, 15.);
+        assert_eq!(settings.fine_rotation_step, 1.);
+        assert_eq!(settings.coarse_rotation_step, 90.);
+    }
+}
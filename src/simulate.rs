@@ -0,0 +1,263 @@
+//! Headless batches of bot games, for balancing variants and shaking out bugs at scale.
+//!
+//! Run with `cargo run -- simulate [games] [--turns=n] [--seed=n] [--chess960] [--out=dir]`.
+//! No window is opened. Each game is saved as a replay in the output directory (so the
+//! game library can open them), and a line per game goes to `results.csv` there.
+//!
+//! A game ends when a king is taken, when the side to move has nowhere to go, or after
+//! the turn limit.
+
+use std::path::PathBuf;
+
+use ggez::{GameError, GameResult};
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+use rotchess_core::{
+    RotchessEmulator,
+    emulator::Event,
+    piece::{PieceKind, Pieces, Side},
+};
+
+use crate::{
+    bot::{self, Bot},
+    history::Action,
+    library::LibraryEntry,
+    notation,
+    persistence::Mode,
+    replay::Replay,
+    stats::{self, GameStats},
+};
+
+/// How many games to play if `simulate` isn't told.
+pub const DEFAULT_GAMES: usize = 10;
+
+/// How many turns a game can last before it's called off, if `--turns` isn't given.
+pub const DEFAULT_MAX_TURNS: usize = 200;
+
+/// A batch of games to play.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    pub games: usize,
+    pub max_turns: usize,
+    /// The seed for the first game. Each game after it gets the next one.
+    pub seed: u64,
+    pub chess960: bool,
+    /// Where the replays and results go.
+    pub out: PathBuf,
+}
+
+/// How a simulated game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ending {
+    KingTaken {
+        winner: Side,
+    },
+    /// The side to move couldn't.
+    Stuck(Side),
+    TurnLimit,
+}
+
+impl Simulation {
+    /// The simulation asked for on the command line, or `None` if we shouldn't simulate.
+    pub fn from_args() -> Option<GameResult<Self>> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    fn parse(args: impl Iterator<Item = String>) -> Option<GameResult<Self>> {
+        let mut args = args.skip_while(|arg| arg != "simulate");
+        args.next()?;
+        let mut simulation = Self {
+            games: DEFAULT_GAMES,
+            max_turns: DEFAULT_MAX_TURNS,
+            seed: rand::random(),
+            chess960: false,
+            out: PathBuf::from("simulations"),
+        };
+        let bad = |arg: &str| {
+            Some(Err(GameError::CustomError(format!(
+                "Bad simulate argument {arg:?}. Try simulate 100 --turns=50 --seed=7 \
+                 --chess960 --out=dir."
+            ))))
+        };
+        for arg in args {
+            let parsed = match arg.split_once('=') {
+                Some(("--turns", n)) => n.parse().map(|n| simulation.max_turns = n).ok(),
+                Some(("--seed", n)) => n.parse().map(|n| simulation.seed = n).ok(),
+                Some(("--out", dir)) => {
+                    simulation.out = PathBuf::from(dir);
+                    Some(())
+                }
+                None if arg == "--chess960" => {
+                    simulation.chess960 = true;
+                    Some(())
+                }
+                None => arg.parse().map(|n| simulation.games = n).ok(),
+                Some(_) => None,
+            };
+            if parsed.is_none() {
+                return bad(&arg);
+            }
+        }
+        Some(Ok(simulation))
+    }
+
+    /// Play every game, saving each and the results as we go.
+    pub fn run(&self) -> GameResult {
+        std::fs::create_dir_all(&self.out)?;
+        let mut results = Vec::new();
+        let (mut white_wins, mut black_wins) = (0, 0);
+        for i in 0..self.games {
+            let seed = self.seed.wrapping_add(i as u64);
+            let (replay, ending) = play_game(seed, self.chess960, self.max_turns);
+            println!("game {i} (seed {seed}): {}", describe_ending(ending));
+            match replay.winner {
+                Some(Side::White) => white_wins += 1,
+                Some(Side::Black) => black_wins += 1,
+                None => (),
+            }
+
+            let path = self.out.join(format!("sim-{i}.rotchess"));
+            replay.save(&path)?;
+            results.push(GameStats::of(&LibraryEntry {
+                path,
+                saved: None,
+                replay,
+                board: None,
+                mode: Mode::HotSeat,
+            }));
+        }
+        std::fs::write(self.out.join("results.csv"), stats::to_csv(&results))?;
+        println!(
+            "white won {}, black won {}, {} undecided. saved to {}",
+            white_wins,
+            black_wins,
+            self.games - white_wins - black_wins,
+            self.out.display()
+        );
+        Ok(())
+    }
+}
+
+pub fn describe_ending(ending: Ending) -> String {
+    match ending {
+        Ending::KingTaken { winner } => format!("{} took the king", notation::side_name(winner)),
+        Ending::Stuck(side) => format!("{} had nowhere to go", notation::side_name(side)),
+        Ending::TurnLimit => "hit the turn limit".to_string(),
+    }
+}
+
+/// The board a game with some seed starts from, and its back rank ordering if it's a
+/// Chess960 game.
+pub fn starting_board(seed: u64, chess960: bool) -> (Pieces, Option<[usize; 8]>) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut chess960_ordering = None;
+    let pieces = if chess960 {
+        Pieces::chess960_board(|| {
+            let mut ordering: [usize; 8] = std::array::from_fn(|i| i);
+            ordering.shuffle(&mut rng);
+            chess960_ordering = Some(ordering);
+            ordering
+        })
+    } else {
+        Pieces::standard_board()
+    };
+    (pieces, chess960_ordering)
+}
+
+/// Play a game between two bots, the same way every time for the same seed.
+pub fn play_game(seed: u64, chess960: bool, max_turns: usize) -> (Replay, Ending) {
+    let (pieces, chess960_ordering) = starting_board(seed, chess960);
+    let mut chess = RotchessEmulator::with(pieces);
+    // the same pair of seeds an exhibition would use.
+    let mut bots = [Bot::new(seed), Bot::new(seed.wrapping_add(1))];
+    let mut replay = Replay {
+        chess960_ordering,
+        ..Replay::default()
+    };
+
+    let mut ending = Ending::TurnLimit;
+    for turn in 0..max_turns {
+        let side = if turn.is_multiple_of(2) {
+            Side::White
+        } else {
+            Side::Black
+        };
+        let bot = &mut bots[turn % 2];
+
+        let options = bot::move_options(&mut chess, side);
+        let Some(choice) = bot.choose_move(&options) else {
+            ending = Ending::Stuck(side);
+            break;
+        };
+        let before = notation::snapshot(chess.pieces());
+        chess.handle_event(Event::MoveUnchecked(choice.piece_idx, choice.x, choice.y));
+        replay
+            .actions
+            .push(Action::Move(choice.piece_idx, choice.x, choice.y));
+        if notation::captured_by(&before, choice.piece_idx, choice.x, choice.y)
+            .is_some_and(|captured| captured.kind == PieceKind::King)
+        {
+            ending = Ending::KingTaken { winner: side };
+            replay.winner = Some(side);
+            break;
+        }
+
+        let r = bot.choose_rotation(chess.pieces()[choice.piece_idx].angle());
+        chess.handle_event(Event::RotateUnchecked(choice.piece_idx, r));
+        replay.actions.push(Action::Rotate(choice.piece_idx, r));
+    }
+    (replay, ending)
+}
+
+#[cfg(test)]
+mod test_simulate {
+    use super::*;
+
+    fn parse(args: &str) -> Option<GameResult<Simulation>> {
+        Simulation::parse(args.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn only_simulates_when_asked() {
+        assert!(parse("server").is_none());
+        assert!(parse("").is_none());
+    }
+
+    #[test]
+    fn defaults_fill_in_the_blanks() {
+        let simulation = parse("simulate --seed=3").unwrap().unwrap();
+        assert_eq!(
+            simulation,
+            Simulation {
+                games: DEFAULT_GAMES,
+                max_turns: DEFAULT_MAX_TURNS,
+                seed: 3,
+                chess960: false,
+                out: PathBuf::from("simulations"),
+            }
+        );
+    }
+
+    #[test]
+    fn everything_can_be_set() {
+        let simulation = parse("simulate 50 --turns=20 --seed=9 --chess960 --out=runs")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            simulation,
+            Simulation {
+                games: 50,
+                max_turns: 20,
+                seed: 9,
+                chess960: true,
+                out: PathBuf::from("runs"),
+            }
+        );
+    }
+
+    #[test]
+    fn bad_arguments_are_errors() {
+        assert!(parse("simulate lots").unwrap().is_err());
+        assert!(parse("simulate --turns=many").unwrap().is_err());
+        assert!(parse("simulate --speed=fast").unwrap().is_err());
+    }
+}
@@ -0,0 +1,100 @@
+//! Snapping dragged rotations to the nearest eighth or quarter turn, while alt is held.
+//!
+//! The emulator works out a rotation from wherever the mouse was let go, so a piece meant
+//! to face straight ahead tends to end up a degree or two off. Snapping rounds the angle it
+//! gives us before anyone else hears about it.
+
+use std::f32::consts::TAU;
+
+use ggez::winit::keyboard::ModifiersState;
+
+/// How far apart the angles a rotation snaps to are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapStep {
+    /// Every 45°, with alt held.
+    Eighth,
+    /// Every 90°, with alt and shift held.
+    Quarter,
+}
+
+impl SnapStep {
+    /// The snapping the held modifiers ask for, if any.
+    pub fn from_mods(mods: ModifiersState) -> Option<Self> {
+        match (mods.alt_key(), mods.shift_key()) {
+            (false, _) => None,
+            (true, false) => Some(Self::Eighth),
+            (true, true) => Some(Self::Quarter),
+        }
+    }
+
+    pub fn radians(self) -> f32 {
+        match self {
+            SnapStep::Eighth => TAU / 8.,
+            SnapStep::Quarter => TAU / 4.,
+        }
+    }
+
+    /// Every angle there is to snap to, from 0 around.
+    pub fn angles(self) -> impl Iterator<Item = f32> {
+        let step = self.radians();
+        let count = (TAU / step).round() as usize;
+        (0..count).map(move |i| i as f32 * step)
+    }
+
+    /// The angle to snap to nearest `angle`, between 0 and a full turn.
+    pub fn snap(self, angle: f32) -> f32 {
+        let step = self.radians();
+        ((angle / step).round() * step).rem_euclid(TAU)
+    }
+}
+
+#[cfg(test)]
+mod test_snapping {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn snaps_to_the_nearest_step() {
+        assert!(close(SnapStep::Eighth.snap(50f32.to_radians()), TAU / 8.));
+        assert!(close(SnapStep::Eighth.snap(70f32.to_radians()), TAU / 4.));
+        assert!(close(SnapStep::Quarter.snap(50f32.to_radians()), TAU / 4.));
+        assert!(close(SnapStep::Quarter.snap(40f32.to_radians()), 0.));
+    }
+
+    #[test]
+    fn snapped_angles_stay_within_a_turn() {
+        assert!(close(SnapStep::Quarter.snap(-10f32.to_radians()), 0.));
+        assert!(close(SnapStep::Quarter.snap(355f32.to_radians()), 0.));
+        assert!(close(
+            SnapStep::Eighth.snap(-50f32.to_radians()),
+            7. * TAU / 8.
+        ));
+    }
+
+    #[test]
+    fn every_snapped_angle_is_a_tick() {
+        assert_eq!(SnapStep::Eighth.angles().count(), 8);
+        assert_eq!(SnapStep::Quarter.angles().count(), 4);
+        for angle in [0.3, 1.7, 4.4, 6.] {
+            let snapped = SnapStep::Eighth.snap(angle);
+            assert!(SnapStep::Eighth.angles().any(|tick| close(tick, snapped)));
+        }
+    }
+
+    #[test]
+    fn alt_snaps_and_shift_snaps_coarser() {
+        assert_eq!(SnapStep::from_mods(ModifiersState::empty()), None);
+        assert_eq!(SnapStep::from_mods(ModifiersState::SHIFT), None);
+        assert_eq!(
+            SnapStep::from_mods(ModifiersState::ALT),
+            Some(SnapStep::Eighth)
+        );
+        assert_eq!(
+            SnapStep::from_mods(ModifiersState::ALT | ModifiersState::SHIFT),
+            Some(SnapStep::Quarter)
+        );
+    }
+}
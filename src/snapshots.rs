@@ -0,0 +1,166 @@
+//! Snapshot mode: draw some known positions offscreen and check them against reference
+//! images, so a change to the drawing helpers that changes what's on the board doesn't go
+//! unnoticed.
+//!
+//! Run with `cargo run -- --snapshots`, or `ROTCHESS_GPU_TESTS=1 cargo test --test snapshots`
+//! (`tests/snapshots.rs`). The references live in `resources/snapshots/`. When
+//! a change to how things look is on purpose, `cargo run -- --snapshots --bless` draws new
+//! ones into the user data directory, to be copied over the old ones.
+
+use ggez::{
+    Context, GameError, GameResult,
+    graphics::{Image, ImageEncodingFormat},
+};
+
+use crate::app::App;
+
+/// How big a snapshot is along each side, in pixels.
+pub const SNAPSHOT_SIZE: u32 = 256;
+
+/// How far apart any channel of a pixel can be from the reference before the pixel counts as
+/// different. Not every GPU rounds the same.
+pub const CHANNEL_TOLERANCE: u8 = 4;
+
+/// How many pixels can be different before a snapshot doesn't match, for the odd edge that
+/// gets antialiased differently.
+pub const DIFFERENT_PIXELS_ALLOWED: usize = 16;
+
+/// Whether to check the snapshots or draw new references.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotMode {
+    Check,
+    Bless,
+}
+
+/// Parse `--snapshots [--bless]` out of the command line arguments.
+///
+/// Returns `None` if we shouldn't take snapshots.
+pub fn snapshot_mode_from_args() -> Option<SnapshotMode> {
+    if !std::env::args().any(|arg| arg == "--snapshots") {
+        return None;
+    }
+    Some(if std::env::args().any(|arg| arg == "--bless") {
+        SnapshotMode::Bless
+    } else {
+        SnapshotMode::Check
+    })
+}
+
+/// A position to take a snapshot of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scenario {
+    /// The standard starting position, with nothing selected.
+    Start,
+    /// A few turns in, with a knight selected to show its travel points.
+    MidGame,
+}
+
+impl Scenario {
+    pub const ALL: [Scenario; 2] = [Scenario::Start, Scenario::MidGame];
+
+    /// What the scenario's reference image is called, without the extension.
+    pub fn name(self) -> &'static str {
+        match self {
+            Scenario::Start => "start",
+            Scenario::MidGame => "mid_game",
+        }
+    }
+}
+
+/// How many pixels of two RGBA images are different, going by [`CHANNEL_TOLERANCE`]. Images
+/// of different sizes are different everywhere.
+pub fn different_pixels(expected: &[u8], actual: &[u8]) -> usize {
+    if expected.len() != actual.len() {
+        return expected.len().max(actual.len()) / 4;
+    }
+    expected
+        .chunks_exact(4)
+        .zip(actual.chunks_exact(4))
+        .filter(|(expected, actual)| {
+            expected
+                .iter()
+                .zip(actual.iter())
+                .any(|(e, a)| e.abs_diff(*a) > CHANNEL_TOLERANCE)
+        })
+        .count()
+}
+
+/// Take every snapshot, then check them or write them out. Errors if any didn't match.
+pub fn run(ctx: &mut Context, mode: SnapshotMode) -> GameResult {
+    let mut mismatched = Vec::new();
+    for scenario in Scenario::ALL {
+        let name = scenario.name();
+        let image = App::snapshot(ctx, scenario)?;
+        match mode {
+            SnapshotMode::Bless => {
+                let file = format!("snapshot-{name}.png");
+                image.encode(ctx, ImageEncodingFormat::Png, format!("/{file}"))?;
+                println!(
+                    "Drew {name} to {}. Copy it to resources/snapshots/{name}.png to make it the reference.",
+                    ctx.fs.user_data_dir().join(file).display()
+                );
+            }
+            SnapshotMode::Check => {
+                let reference = match Image::from_path(ctx, format!("/snapshots/{name}.png")) {
+                    Ok(reference) => reference,
+                    Err(e) => {
+                        println!("{name}: no reference to check against ({e}).");
+                        mismatched.push(name);
+                        continue;
+                    }
+                };
+                let different =
+                    different_pixels(&reference.to_pixels(ctx)?, &image.to_pixels(ctx)?);
+                if different > DIFFERENT_PIXELS_ALLOWED {
+                    println!("{name}: {different} pixels are different from the reference.");
+                    mismatched.push(name);
+                } else {
+                    println!("{name}: matches.");
+                }
+            }
+        }
+    }
+    if mismatched.is_empty() {
+        Ok(())
+    } else {
+        Err(GameError::CustomError(format!(
+            "Some snapshots don't match their references: {}.",
+            mismatched.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test_snapshots {
+    use super::*;
+
+    #[test]
+    fn small_differences_are_the_same_pixel() {
+        let expected = [10, 20, 30, 255, 0, 0, 0, 255];
+        let actual = [12, 16, 30, 255, 0, 0, 0, 255];
+        assert_eq!(different_pixels(&expected, &actual), 0);
+    }
+
+    #[test]
+    fn counts_pixels_not_channels() {
+        let expected = [0; 12];
+        let actual = [0, 0, 0, 0, 255, 255, 255, 255, 0, 0, 100, 0];
+        assert_eq!(different_pixels(&expected, &actual), 2);
+    }
+
+    #[test]
+    fn different_sizes_are_different_everywhere() {
+        assert_eq!(different_pixels(&[0; 16], &[0; 8]), 4);
+    }
+
+    #[test]
+    fn scenarios_have_their_own_references() {
+        let mut names: Vec<_> = Scenario::ALL
+            .iter()
+            .map(|scenario| scenario.name())
+            .collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), Scenario::ALL.len());
+    }
+}
@@ -0,0 +1,162 @@
+//! Watching a networked game from a third computer, with
+//! `cargo run -- --spectate --ticket=...`.
+//!
+//! The netcode only connects two computers, taking turns to talk, so the host opens a
+//! second connection just for a spectator and relays the game down it: the rules, then
+//! every action as it's played. The spectator answers each turn with a `Thing(None)` and
+//! never says anything else, so it can't play.
+//!
+//! If the game's history changes under the spectator (after a takeback, say), or they've
+//! fallen a long way behind (by joining late), they get a whole fresh copy of the game with
+//! a [`Message::Resync`] instead.
+
+use std::collections::VecDeque;
+
+use sfn_tpn::{Config, NetcodeInterface};
+use tokio::sync::oneshot;
+
+use crate::{
+    constants::TURN_SIZE,
+    history::Action,
+    protocol::{self, Message},
+    rules::GameRules,
+};
+
+/// The most actions a spectator can be behind by and still get them one by one, each
+/// played out with its sound. Any more, and they get a quiet resync.
+pub const CATCH_UP_LIMIT: usize = 2;
+
+/// How to bring a spectator's copy of the game up to date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatchUp {
+    /// Send the actions from this index on.
+    From(usize),
+    /// Send the whole game over again.
+    Resync,
+}
+
+/// How to bring a spectator who has `relayed` up to `actions`.
+pub fn catch_up(relayed: &[Action], actions: &[Action]) -> CatchUp {
+    if actions.starts_with(relayed) && actions.len() - relayed.len() <= CATCH_UP_LIMIT {
+        CatchUp::From(relayed.len())
+    } else {
+        CatchUp::Resync
+    }
+}
+
+/// The host's end of a spectator's connection.
+pub struct Relay {
+    netcode: NetcodeInterface<TURN_SIZE>,
+    /// The ticket a spectator joins with. Taken once we've shown it.
+    ticket: Option<oneshot::Receiver<String>>,
+    /// The actions the spectator has, or will once `outgoing` is sent.
+    relayed: Vec<Action>,
+    /// Turns waiting to go out, one per turn the spectator hands back.
+    outgoing: VecDeque<[u8; TURN_SIZE]>,
+    /// Whether we've told the spectator the game is over.
+    quit_sent: bool,
+    /// Whether the spectator left.
+    gone: bool,
+}
+
+impl Relay {
+    /// Open a connection for a spectator to watch a game played by `rules`.
+    pub fn host(rules: GameRules) -> Self {
+        let (send, recv) = oneshot::channel();
+        Self {
+            netcode: NetcodeInterface::new(Config::TicketSender(send)),
+            ticket: Some(recv),
+            relayed: Vec::new(),
+            outgoing: VecDeque::from([protocol::ser_message(&Message::Rules(rules))]),
+            quit_sent: false,
+            gone: false,
+        }
+    }
+
+    /// Show the ticket once there is one, then send the spectator whatever they're missing
+    /// of `actions`, one turn at a time as they hand them back. Once the game is over, say
+    /// so.
+    pub fn step(&mut self, actions: &[Action], game_over: bool) {
+        if let Some(ticket) = &mut self.ticket
+            && let Ok(ticket) = ticket.try_recv()
+        {
+            println!(
+                "a spectator may watch with \n\n\
+                cargo run -- --spectate --ticket={ticket}"
+            );
+            self.ticket = None;
+        }
+        if self.gone || (self.quit_sent && self.outgoing.is_empty()) {
+            return;
+        }
+        if !self.netcode.my_turn() {
+            if let Ok(turn) = self.netcode.try_recv_turn()
+                && let Ok(Message::Quit) = protocol::de_message(&turn)
+            {
+                println!("The spectator left.");
+                self.gone = true;
+            }
+            return;
+        }
+        // what to send is only worked out now, so a spectator who joins late gets the game
+        // as it is, not every action since it started.
+        if self.outgoing.is_empty() {
+            self.queue_catch_up(actions);
+        }
+        if self.outgoing.is_empty() && game_over {
+            self.outgoing
+                .push_back(protocol::ser_message(&Message::Quit));
+            self.quit_sent = true;
+        }
+        if let Some(turn) = self.outgoing.pop_front() {
+            self.netcode.send_turn(&turn);
+        }
+    }
+
+    fn queue_catch_up(&mut self, actions: &[Action]) {
+        let from = match catch_up(&self.relayed, actions) {
+            CatchUp::From(from) => from,
+            CatchUp::Resync => {
+                self.outgoing
+                    .push_back(protocol::ser_message(&Message::Resync {
+                        actions: actions.len(),
+                        your_turn: false,
+                    }));
+                0
+            }
+        };
+        for action in &actions[from..] {
+            self.outgoing
+                .push_back(protocol::ser_thing(Some(&(*action).into())));
+        }
+        self.relayed = actions.to_vec();
+    }
+}
+
+#[cfg(test)]
+mod test_spectate {
+    use super::*;
+
+    #[test]
+    fn new_actions_are_sent_on_their_own() {
+        let relayed = [Action::Move(0, 0.5, 5.5)];
+        let actions = [relayed[0], Action::Rotate(0, 1.), Action::Move(9, 1.5, 2.5)];
+        assert_eq!(catch_up(&relayed, &actions), CatchUp::From(1));
+        assert_eq!(catch_up(&actions, &actions), CatchUp::From(3));
+    }
+
+    #[test]
+    fn rewritten_or_long_histories_are_resent() {
+        let actions = [Action::Move(0, 0.5, 5.5), Action::Rotate(0, 1.)];
+        // taken back.
+        assert_eq!(catch_up(&actions, &actions[..1]), CatchUp::Resync);
+        // taken back and played differently.
+        assert_eq!(
+            catch_up(&actions, &[actions[0], Action::Rotate(0, 2.)]),
+            CatchUp::Resync
+        );
+        // joined late.
+        let long = [actions[0], actions[1], actions[0], actions[1]];
+        assert_eq!(catch_up(&[], &long), CatchUp::Resync);
+    }
+}
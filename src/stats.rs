@@ -0,0 +1,156 @@
+//! Per-game statistics for the games in the library, as CSV for a spreadsheet (`s` in the
+//! library writes them out).
+
+use std::time::Duration;
+
+use rotchess_core::piece::Side;
+
+use crate::{history::Action, library::LibraryEntry};
+
+/// The columns of [`to_csv`].
+const CSV_HEADER: &str =
+    "game,variant,winner,moves,rotations,total_seconds,seconds_per_move,seconds_per_rotation";
+
+/// What happened in one game, in numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameStats {
+    pub name: String,
+    pub chess960: bool,
+    pub winner: Option<Side>,
+    pub moves: usize,
+    pub rotations: usize,
+    /// How long all the actions we have times for took.
+    pub total_time: Duration,
+    /// How long a move took on average, if we have times for any.
+    pub time_per_move: Option<Duration>,
+    /// How long a rotation took on average, if we have times for any.
+    pub time_per_rotation: Option<Duration>,
+}
+
+impl GameStats {
+    pub fn of(entry: &LibraryEntry) -> Self {
+        let replay = &entry.replay;
+        let mut moves = Vec::new();
+        let mut rotations = Vec::new();
+        for (i, action) in replay.actions.iter().enumerate() {
+            let time = replay.times.get(i).copied();
+            match action {
+                Action::Move(..) => moves.push(time),
+                Action::Rotate(..) => rotations.push(time),
+            }
+        }
+        Self {
+            name: entry.name(),
+            chess960: replay.chess960_ordering.is_some(),
+            winner: replay.winner,
+            moves: moves.len(),
+            rotations: rotations.len(),
+            total_time: replay.times.iter().take(replay.actions.len()).sum(),
+            time_per_move: mean(&moves),
+            time_per_rotation: mean(&rotations),
+        }
+    }
+}
+
+/// The mean of the times we know.
+fn mean(times: &[Option<Duration>]) -> Option<Duration> {
+    let known: Vec<Duration> = times.iter().flatten().copied().collect();
+    let count = u32::try_from(known.len()).ok().filter(|&count| count > 0)?;
+    Some(known.iter().sum::<Duration>() / count)
+}
+
+/// Quote a CSV field if it needs it.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// A header, then one line per game. Unknown values are left empty.
+pub fn to_csv(games: &[GameStats]) -> String {
+    let secs = |time: Option<Duration>| {
+        time.map_or_else(String::new, |t| format!("{:.1}", t.as_secs_f64()))
+    };
+    let mut csv = format!("{CSV_HEADER}\n");
+    for game in games {
+        let row = [
+            csv_field(&game.name),
+            if game.chess960 {
+                "chess960"
+            } else {
+                "standard"
+            }
+            .to_string(),
+            match game.winner {
+                Some(Side::White) => "white",
+                Some(Side::Black) => "black",
+                None => "",
+            }
+            .to_string(),
+            game.moves.to_string(),
+            game.rotations.to_string(),
+            secs(Some(game.total_time)),
+            secs(game.time_per_move),
+            secs(game.time_per_rotation),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod test_stats {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{persistence::Mode, replay::Replay};
+
+    fn entry(name: &str, replay: Replay) -> LibraryEntry {
+        LibraryEntry {
+            path: PathBuf::from(format!("{name}.rotchess")),
+            saved: None,
+            replay,
+            board: None,
+            mode: Mode::HotSeat,
+        }
+    }
+
+    #[test]
+    fn times_are_split_by_kind() {
+        let stats = GameStats::of(&entry(
+            "game",
+            Replay {
+                actions: vec![
+                    Action::Move(0, 0.5, 2.5),
+                    Action::Rotate(0, 1.),
+                    Action::Move(9, 0.5, 5.5),
+                    Action::Rotate(9, 1.),
+                ],
+                times: [4000, 1000, 2000, 3000].map(Duration::from_millis).to_vec(),
+                winner: Some(Side::Black),
+                ..Replay::default()
+            },
+        ));
+        assert_eq!(stats.moves, 2);
+        assert_eq!(stats.rotations, 2);
+        assert_eq!(stats.total_time, Duration::from_secs(10));
+        assert_eq!(stats.time_per_move, Some(Duration::from_secs(3)));
+        assert_eq!(stats.time_per_rotation, Some(Duration::from_secs(2)));
+        assert_eq!(
+            to_csv(&[stats]),
+            format!("{CSV_HEADER}\ngame,standard,black,2,2,10.0,3.0,2.0\n")
+        );
+    }
+
+    #[test]
+    fn unknowns_are_left_empty() {
+        let stats = GameStats::of(&entry("a, \"b\"", Replay::default()));
+        assert_eq!(
+            to_csv(&[stats]),
+            format!("{CSV_HEADER}\n\"a, \"\"b\"\"\",standard,,0,0,0.0,,\n")
+        );
+    }
+}
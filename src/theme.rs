@@ -0,0 +1,233 @@
+//! How the board is colored, and which pieces go on it, read from files in
+//! `resources/themes/`.
+//!
+//! A theme file looks like a settings file, one `name value` pair per line, and anything
+//! left out is taken from [`Theme::DEFAULT`]:
+//!
+//! ```text
+//! dark_tile #b58863
+//! light_tile #f0d9b5
+//! background #e6e6e6
+//! dialog_text #000000
+//! selected #ffff99c8
+//! move_outline #adfff4
+//! move_highlight #adfff4c8
+//! capture_outline #ff0000
+//! capture_highlight #ff0000c8
+//! last_move #9acd3296
+//! coordinates #4d331f
+//! scrubber_track #b58863
+//! scrubber_knob #664429
+//! pieces /pieces_png
+//! ```
+//!
+//! Colors are `#rrggbb` or `#rrggbbaa`, and `pieces` is the ggez resource directory of a
+//! piece set (see [`crate::assets`]). Lines starting with `#` are comments.
+
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use ggez::{Context, GameError, GameResult, graphics::Color};
+
+use crate::settings::hex_color;
+
+/// The ggez resource directory themes are found in.
+pub const THEMES_DIR: &str = "themes";
+/// The extension of theme files.
+pub const THEME_EXTENSION: &str = "txt";
+/// The ggez resource path of the theme we start with, which is [`Theme::DEFAULT`] written out.
+pub const DEFAULT_THEME: &str = "/themes/classic.txt";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub dark_tile: Color,
+    pub light_tile: Color,
+    /// Behind the board, and behind dialogs.
+    pub background: Color,
+    /// The words in dialogs, on `background`.
+    pub dialog_text: Color,
+    /// Under the selected piece, and the picked game in the library.
+    pub selected: Color,
+    /// Around the travel points a piece can move to.
+    pub move_outline: Color,
+    /// Filling a hovered travel point a piece can move to.
+    pub move_highlight: Color,
+    /// Like `move_outline`, for travel points that capture.
+    pub capture_outline: Color,
+    /// Like `move_highlight`, for travel points that capture.
+    pub capture_highlight: Color,
+    /// Under the piece that moved last, in projector mode and diagrams.
+    pub last_move: Color,
+    /// The file and rank labels on the board.
+    pub coordinates: Color,
+    /// The line of the history scrubber under the board.
+    pub scrubber_track: Color,
+    pub scrubber_knob: Color,
+    /// The ggez resource directory of the piece set to draw with, or `None` for the one we
+    /// ship with.
+    pub pieces: Option<PathBuf>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Theme {
+    /// The brown wooden board rotchess has always had.
+    pub const DEFAULT: Self = Self {
+        dark_tile: Color::new(0.70980, 0.53333, 0.38824, 1.00000),
+        light_tile: Color::new(0.94118, 0.85098, 0.70980, 1.00000),
+        background: Color::new(0.90196, 0.90196, 0.90196, 1.00000),
+        dialog_text: Color::BLACK,
+        // yellowish
+        selected: Color::new(1.00000, 1.00000, 0.60000, 0.78431),
+        // cyanish
+        move_outline: Color::new(0.67843, 1.00000, 0.95686, 1.00000),
+        move_highlight: Color::new(0.67843, 1.00000, 0.95686, 0.78431),
+        // red
+        capture_outline: Color::new(1.00000, 0.00000, 0.00000, 1.00000),
+        capture_highlight: Color::new(1.00000, 0.00000, 0.00000, 0.78431),
+        // translucent green
+        last_move: Color::new(0.60392, 0.80392, 0.19608, 0.58824),
+        // dark brown
+        coordinates: Color::new(0.30196, 0.20000, 0.12157, 1.00000),
+        scrubber_track: Color::new(0.70980, 0.53333, 0.38824, 1.00000),
+        scrubber_knob: Color::new(0.40000, 0.26667, 0.16078, 1.00000),
+        pieces: None,
+    };
+
+    /// Load a theme from a file in ggez's resource directories.
+    pub fn load(ctx: &Context, path: &Path) -> GameResult<Self> {
+        let mut text = String::new();
+        ctx.fs.open(path)?.read_to_string(&mut text)?;
+        Self::from_text(&text)
+    }
+
+    pub fn from_text(text: &str) -> GameResult<Self> {
+        let mut theme = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| malformed(line_no, "expected a name and a value"))?;
+            let value = value.trim();
+            if name == "pieces" {
+                theme.pieces = Some(PathBuf::from(value));
+                continue;
+            }
+            let color = match name {
+                "dark_tile" => &mut theme.dark_tile,
+                "light_tile" => &mut theme.light_tile,
+                "background" => &mut theme.background,
+                "dialog_text" => &mut theme.dialog_text,
+                "selected" => &mut theme.selected,
+                "move_outline" => &mut theme.move_outline,
+                "move_highlight" => &mut theme.move_highlight,
+                "capture_outline" => &mut theme.capture_outline,
+                "capture_highlight" => &mut theme.capture_highlight,
+                "last_move" => &mut theme.last_move,
+                "coordinates" => &mut theme.coordinates,
+                "scrubber_track" => &mut theme.scrubber_track,
+                "scrubber_knob" => &mut theme.scrubber_knob,
+                _ => return Err(malformed(line_no, "unknown color")),
+            };
+            *color = hex_color(value)
+                .ok_or_else(|| malformed(line_no, "expected a color like #ff0000"))?;
+        }
+        Ok(theme)
+    }
+}
+
+/// Find the theme files in a resource directory on disk, as ggez resource paths.
+pub fn discover(resources: &Path) -> Vec<PathBuf> {
+    let Ok(files) = std::fs::read_dir(resources.join(THEMES_DIR)) else {
+        return Vec::new();
+    };
+    let mut themes: Vec<PathBuf> = files
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == THEME_EXTENSION))
+        .filter_map(|path| Some(Path::new("/").join(THEMES_DIR).join(path.file_name()?)))
+        .collect();
+    themes.sort();
+    themes
+}
+
+fn malformed(line_no: usize, why: &str) -> GameError {
+    GameError::CustomError(format!("Malformed theme at line {line_no}: {why}."))
+}
+
+#[cfg(test)]
+mod test_theme {
+    use super::*;
+
+    #[test]
+    fn missing_colors_keep_defaults() {
+        let theme = Theme::from_text("# green\ndark_tile #769656\npieces /pack").unwrap();
+        assert_eq!(
+            theme,
+            Theme {
+                dark_tile: Color::new(118. / 255., 150. / 255., 86. / 255., 1.),
+                pieces: Some(PathBuf::from("/pack")),
+                ..Theme::DEFAULT
+            }
+        );
+    }
+
+    #[test]
+    fn garbage_is_an_error() {
+        assert!(Theme::from_text("dark_tile").is_err());
+        assert!(Theme::from_text("dark_tile brown").is_err());
+        assert!(Theme::from_text("dark_tiles #769656").is_err());
+    }
+
+    #[test]
+    fn default_theme_file_is_the_default() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("resources")
+            .join(DEFAULT_THEME.trim_start_matches('/'));
+        let theme = Theme::from_text(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let close = |a: Color, b: Color| {
+            (a.r - b.r).abs() < 1e-3
+                && (a.g - b.g).abs() < 1e-3
+                && (a.b - b.b).abs() < 1e-3
+                && (a.a - b.a).abs() < 1e-3
+        };
+        let pairs = [
+            (theme.dark_tile, Theme::DEFAULT.dark_tile),
+            (theme.light_tile, Theme::DEFAULT.light_tile),
+            (theme.background, Theme::DEFAULT.background),
+            (theme.dialog_text, Theme::DEFAULT.dialog_text),
+            (theme.selected, Theme::DEFAULT.selected),
+            (theme.move_outline, Theme::DEFAULT.move_outline),
+            (theme.move_highlight, Theme::DEFAULT.move_highlight),
+            (theme.capture_outline, Theme::DEFAULT.capture_outline),
+            (theme.capture_highlight, Theme::DEFAULT.capture_highlight),
+            (theme.last_move, Theme::DEFAULT.last_move),
+            (theme.coordinates, Theme::DEFAULT.coordinates),
+            (theme.scrubber_track, Theme::DEFAULT.scrubber_track),
+            (theme.scrubber_knob, Theme::DEFAULT.scrubber_knob),
+        ];
+        assert!(pairs.into_iter().all(|(a, b)| close(a, b)));
+    }
+
+    #[test]
+    fn shipped_themes_parse() {
+        let resources = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources");
+        let themes = discover(&resources);
+        assert!(!themes.is_empty());
+        for theme in themes {
+            let path = resources.join(theme.strip_prefix("/").unwrap());
+            let text = std::fs::read_to_string(&path).unwrap();
+            assert!(Theme::from_text(&text).is_ok(), "{}", path.display());
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! A few lines along the bottom of the board announcing what just happened, newest last.
+//! Older lines scroll up and fade away as new ones come in.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::effects::Fade;
+
+/// How long a line stays on the ticker.
+pub const TICKER_LINE_DURATION: Duration = Duration::from_secs(8);
+
+/// How many lines the ticker shows at once.
+pub const TICKER_LINES: usize = 4;
+
+#[derive(Default)]
+pub struct Ticker {
+    lines: VecDeque<(String, Fade)>,
+}
+
+impl Ticker {
+    pub fn announce(&mut self, line: String) {
+        self.lines
+            .push_back((line, Fade::new(TICKER_LINE_DURATION)));
+        if self.lines.len() > TICKER_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    /// Let go of lines that have faded away.
+    pub fn step(&mut self, now: Instant) {
+        self.lines.retain(|(_, fade)| !fade.is_done(now));
+    }
+
+    /// The lines to show, oldest first, and how visible each is.
+    pub fn lines(&self, now: Instant, reduced_motion: bool) -> impl Iterator<Item = (&str, f32)> {
+        self.lines
+            .iter()
+            .map(move |(line, fade)| (line.as_str(), fade.visibility(now, reduced_motion)))
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+#[cfg(test)]
+mod test_ticker {
+    use super::*;
+
+    #[test]
+    fn keeps_only_the_newest_lines() {
+        let mut ticker = Ticker::default();
+        for i in 0..TICKER_LINES + 2 {
+            ticker.announce(i.to_string());
+        }
+        let lines: Vec<_> = ticker
+            .lines(Instant::now(), false)
+            .map(|(line, _)| line)
+            .collect();
+        assert_eq!(lines, ["2", "3", "4", "5"]);
+    }
+
+    #[test]
+    fn lines_fade_away() {
+        let mut ticker = Ticker::default();
+        ticker.announce("White pawn moves".to_string());
+        ticker.step(Instant::now() + TICKER_LINE_DURATION);
+        assert_eq!(ticker.lines(Instant::now(), false).count(), 0);
+    }
+}
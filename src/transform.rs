@@ -0,0 +1,318 @@
+//! Where the board is on screen: how big, where, and which way round. Drawing, hit-testing
+//! and turning clicks into board positions all go through the same [`BoardTransform`], so
+//! anything that changes how the board is shown moves where clicks land along with it.
+//!
+//! Positions on the board are in rotchess units (runits), 8 to a side, and positions on
+//! screen are in world units, which are pixels.
+
+use std::f32::consts::TAU;
+
+use ggez::{glam::Vec2, graphics::Rect};
+
+use crate::constants::{MAX_ZOOM, MIN_ZOOM, SCRUBBER_HEIGHT};
+
+/// The middle of the board along either axis, in runits.
+const BOARD_MIDDLE: f32 = 4.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoardTransform {
+    /// World units per runit, before zooming.
+    pub scale: f32,
+    /// Where the top left corner of the board's [frame](BoardTransform::frame) is, say to
+    /// letterbox it in a wide window.
+    pub offset: Vec2,
+    /// Whether the board is mirrored left to right.
+    pub flipped: bool,
+    /// How many times the board is turned a quarter turn clockwise, after any flip.
+    pub quarter_turns: u8,
+    /// How much bigger the board is drawn than its frame, around the middle of it.
+    pub zoom: f32,
+    /// How far the middle of the board is moved from the middle of its frame, in world
+    /// units, say by dragging it around.
+    pub pan: Vec2,
+}
+
+impl Default for BoardTransform {
+    fn default() -> Self {
+        Self {
+            scale: 1.,
+            offset: Vec2::ZERO,
+            flipped: false,
+            quarter_turns: 0,
+            zoom: 1.,
+            pan: Vec2::ZERO,
+        }
+    }
+}
+
+impl BoardTransform {
+    /// How many world units a runit should be for the board and scrubber to fit a window.
+    pub fn fitting_scale(screen_width: f32, screen_height: f32) -> f32 {
+        f32::min(screen_width, screen_height - SCRUBBER_HEIGHT) / 8.
+    }
+
+    /// Where the board sits on screen, in world units, whatever the zoom. Everything laid
+    /// out around the board, like the scrubber under it, goes by this.
+    pub fn frame(&self) -> Rect {
+        Rect::new(
+            self.offset.x,
+            self.offset.y,
+            8. * self.scale,
+            8. * self.scale,
+        )
+    }
+
+    /// Where the board is drawn, in world units, zoom, pan and all.
+    pub fn board_rect(&self) -> Rect {
+        let size = self.world_len(8.);
+        let middle = self.middle();
+        Rect::new(middle.x - size / 2., middle.y - size / 2., size, size)
+    }
+
+    /// A length in runits, in world units.
+    pub fn world_len(&self, a: f32) -> f32 {
+        a * self.scale * self.zoom
+    }
+
+    /// The inverse of [`BoardTransform::world_len`].
+    pub fn board_len(&self, a: f32) -> f32 {
+        a / (self.scale * self.zoom)
+    }
+
+    /// Where a place on the board, in runits, is on screen.
+    ///
+    /// Use [`BoardTransform::world_len`] for lengths, and this for anything that's a
+    /// place on the board.
+    pub fn world_pos(&self, x: f32, y: f32) -> Vec2 {
+        let (mut u, mut v) = (x - BOARD_MIDDLE, y - BOARD_MIDDLE);
+        if self.flipped {
+            u = -u;
+        }
+        for _ in 0..self.quarter_turns {
+            (u, v) = (-v, u);
+        }
+        self.middle() + Vec2::new(self.world_len(u), self.world_len(v))
+    }
+
+    /// The inverse of [`BoardTransform::world_pos`]: the place on the board, in runits,
+    /// under some point on screen.
+    pub fn board_pos(&self, x: f32, y: f32) -> (f32, f32) {
+        let from_middle = Vec2::new(x, y) - self.middle();
+        let (mut u, mut v) = (self.board_len(from_middle.x), self.board_len(from_middle.y));
+        for _ in 0..self.quarter_turns {
+            (u, v) = (v, -u);
+        }
+        if self.flipped {
+            u = -u;
+        }
+        (u + BOARD_MIDDLE, v + BOARD_MIDDLE)
+    }
+
+    /// How far clockwise something at some angle on the board is turned on screen.
+    ///
+    /// Pieces are drawn turned the other way from their angle, and a mirrored board turns
+    /// them the other way again.
+    pub fn screen_angle(&self, angle: f32) -> f32 {
+        let angle = if self.flipped { angle } else { -angle };
+        (self.quarter_turns as f32 * TAU / 4. + angle).rem_euclid(TAU)
+    }
+
+    /// Turn the board a quarter turn clockwise.
+    pub fn turn(&mut self) {
+        self.quarter_turns = (self.quarter_turns + 1) % 4;
+    }
+
+    /// Turn the board around, to look at it from the other side.
+    pub fn turn_around(&mut self) {
+        self.quarter_turns = (self.quarter_turns + 2) % 4;
+    }
+
+    /// Zoom in (or out, for a factor under 1) around a point on screen, which stays over the
+    /// same place on the board. The zoom stays between [`MIN_ZOOM`] and [`MAX_ZOOM`].
+    pub fn zoom_at(&mut self, point: Vec2, factor: f32) {
+        let (x, y) = self.board_pos(point.x, point.y);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.pan += point - self.world_pos(x, y);
+    }
+
+    /// Move the board some way across the screen, in world units.
+    pub fn pan_by(&mut self, delta: Vec2) {
+        self.pan += delta;
+    }
+
+    /// Move the board so a place on it, in runits, is in the middle of its frame.
+    pub fn center_on(&mut self, x: f32, y: f32) {
+        let frame = self.frame();
+        let frame_middle = Vec2::new(frame.x + frame.w / 2., frame.y + frame.h / 2.);
+        self.pan += frame_middle - self.world_pos(x, y);
+    }
+
+    /// Whether the board's been zoomed or panned away from filling its frame.
+    pub fn camera_moved(&self) -> bool {
+        self.zoom != 1. || self.pan != Vec2::ZERO
+    }
+
+    /// Put the board back in its frame, unzoomed.
+    pub fn reset_camera(&mut self) {
+        self.zoom = 1.;
+        self.pan = Vec2::ZERO;
+    }
+
+    /// The middle of the board on screen.
+    fn middle(&self) -> Vec2 {
+        let frame = self.frame();
+        Vec2::new(frame.x + frame.w / 2., frame.y + frame.h / 2.) + self.pan
+    }
+}
+
+#[cfg(test)]
+mod test_transform {
+    use super::*;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).hypot(a.1 - b.1) < 1e-4
+    }
+
+    fn every_view(scale: f32) -> impl Iterator<Item = BoardTransform> {
+        [false, true].into_iter().flat_map(move |flipped| {
+            (0..4).flat_map(move |quarter_turns| {
+                [1., 2.5].into_iter().map(move |zoom| BoardTransform {
+                    scale,
+                    offset: Vec2::new(30., 12.),
+                    flipped,
+                    quarter_turns,
+                    zoom,
+                    pan: Vec2::new(-7., 15.),
+                })
+            })
+        })
+    }
+
+    #[test]
+    fn the_default_view_is_a_plain_scale() {
+        let transform = BoardTransform {
+            scale: 50.,
+            ..BoardTransform::default()
+        };
+        assert_eq!(transform.world_pos(0., 0.), Vec2::ZERO);
+        assert_eq!(transform.world_pos(4.5, 6.5), Vec2::new(225., 325.));
+        assert_eq!(transform.board_rect(), transform.frame());
+        assert_eq!(transform.frame(), Rect::new(0., 0., 400., 400.));
+    }
+
+    #[test]
+    fn clicks_land_where_things_are_drawn() {
+        for transform in every_view(60.) {
+            for (x, y) in [(0., 0.), (4.5, 6.5), (7.9, 0.2), (-1., 9.)] {
+                let drawn = transform.world_pos(x, y);
+                assert!(
+                    close(transform.board_pos(drawn.x, drawn.y), (x, y)),
+                    "{transform:?} moved ({x}, {y})"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn turning_and_flipping_move_corners_around() {
+        let mut transform = BoardTransform {
+            scale: 10.,
+            ..BoardTransform::default()
+        };
+        transform.turn();
+        // a quarter turn clockwise puts the top left corner at the top right.
+        assert_eq!(transform.world_pos(0., 0.), Vec2::new(80., 0.));
+        transform.quarter_turns = 0;
+        transform.flipped = true;
+        assert_eq!(transform.world_pos(0., 0.), Vec2::new(80., 0.));
+        assert_eq!(transform.world_pos(0., 8.), Vec2::new(80., 80.));
+    }
+
+    #[test]
+    fn zooming_keeps_the_middle_in_place() {
+        let transform = BoardTransform {
+            scale: 10.,
+            zoom: 2.,
+            ..BoardTransform::default()
+        };
+        assert_eq!(transform.world_pos(4., 4.), Vec2::new(40., 40.));
+        assert_eq!(transform.world_pos(2., 2.), Vec2::ZERO);
+        assert_eq!(transform.board_rect(), Rect::new(-40., -40., 160., 160.));
+        assert_eq!(transform.frame(), Rect::new(0., 0., 80., 80.));
+    }
+
+    #[test]
+    fn flipped_boards_turn_pieces_the_other_way() {
+        let mut transform = BoardTransform::default();
+        assert!((transform.screen_angle(1.) - (TAU - 1.)).abs() < 1e-5);
+        transform.flipped = true;
+        assert!((transform.screen_angle(1.) - 1.).abs() < 1e-5);
+        transform.turn();
+        assert!((transform.screen_angle(1.) - (TAU / 4. + 1.)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn zooming_keeps_the_point_under_the_cursor() {
+        for mut transform in every_view(60.) {
+            let cursor = Vec2::new(100., 350.);
+            let under = transform.board_pos(cursor.x, cursor.y);
+            transform.zoom_at(cursor, 1.5);
+            assert!(
+                close(transform.board_pos(cursor.x, cursor.y), under),
+                "{transform:?} slid out from under the cursor"
+            );
+        }
+    }
+
+    #[test]
+    fn centering_puts_a_place_in_the_middle_of_the_frame() {
+        for mut transform in every_view(60.) {
+            transform.center_on(1.5, 6.);
+            let frame = transform.frame();
+            let drawn = transform.world_pos(1.5, 6.);
+            assert!(
+                close(
+                    (drawn.x, drawn.y),
+                    (frame.x + frame.w / 2., frame.y + frame.h / 2.)
+                ),
+                "{transform:?} isn't centered"
+            );
+        }
+    }
+
+    #[test]
+    fn zoom_has_limits_and_resets() {
+        let mut transform = BoardTransform {
+            scale: 10.,
+            ..BoardTransform::default()
+        };
+        assert!(!transform.camera_moved());
+        transform.zoom_at(Vec2::new(40., 40.), 1000.);
+        assert_eq!(transform.zoom, MAX_ZOOM);
+        transform.zoom_at(Vec2::new(40., 40.), 0.);
+        assert_eq!(transform.zoom, MIN_ZOOM);
+        transform.pan_by(Vec2::new(5., 0.));
+        assert_eq!(transform.world_pos(4., 4.), Vec2::new(45., 40.));
+        transform.reset_camera();
+        assert!(!transform.camera_moved());
+    }
+
+    #[test]
+    fn turning_around_swaps_corners_and_back() {
+        let mut transform = BoardTransform {
+            scale: 10.,
+            ..BoardTransform::default()
+        };
+        transform.turn_around();
+        assert_eq!(transform.world_pos(0., 0.), Vec2::new(80., 80.));
+        assert_eq!(transform.board_pos(5., 5.), (7.5, 7.5));
+        transform.turn_around();
+        assert_eq!(
+            transform,
+            BoardTransform {
+                scale: 10.,
+                ..BoardTransform::default()
+            }
+        );
+    }
+}
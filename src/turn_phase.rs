@@ -0,0 +1,248 @@
+//! Whose turn it is, and which half of it.
+//!
+//! A player's turn is a move and then a rotation, in that order. The emulator doesn't know
+//! about this, so anything that plays turns (a person over the network, a person on this
+//! computer, a bot) goes through a [`TurnPhaseMachine`] to keep them honest.
+
+use std::fmt;
+
+use rotchess_core::emulator::ThingHappened;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnPhase {
+    Move,
+    Rotate,
+    /// It's someone else's turn.
+    Wait,
+}
+
+/// Something was done out of turn, and should be taken back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhaseViolation {
+    MoveInRotatePhase,
+    RotateInMovePhase,
+    NotYourTurn,
+}
+
+impl fmt::Display for PhaseViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhaseViolation::MoveInRotatePhase => write!(
+                f,
+                "Player turns consist of a move and a rotation in that order. \
+                 No moving in your rotation phase!"
+            ),
+            PhaseViolation::RotateInMovePhase => write!(
+                f,
+                "Player turns consist of a move and a rotation in that order. \
+                 No rotating in your move phase!"
+            ),
+            PhaseViolation::NotYourTurn => write!(f, "It's not your turn!"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TurnPhaseMachine {
+    phase: TurnPhase,
+    /// Whether finishing a turn hands it to someone somewhere else, who we then wait on.
+    ///
+    /// If not, the next player is sitting right here, and just starts their move.
+    hands_over: bool,
+}
+
+impl TurnPhaseMachine {
+    /// A machine for a player who goes first or not, and who does or doesn't hand the turn
+    /// to someone elsewhere at the end of it.
+    pub fn new(goes_first: bool, hands_over: bool) -> Self {
+        Self {
+            phase: if goes_first {
+                TurnPhase::Move
+            } else {
+                TurnPhase::Wait
+            },
+            hands_over,
+        }
+    }
+
+    pub fn phase(&self) -> TurnPhase {
+        self.phase
+    }
+
+    /// Whether we're waiting on someone else.
+    pub fn waiting(&self) -> bool {
+        self.phase == TurnPhase::Wait
+    }
+
+    /// Check something that just happened on our side against the phase, moving on to the
+    /// next phase if it's allowed.
+    ///
+    /// If it isn't, the phase stays put and the caller should take the thing back.
+    /// Going through the history is always allowed.
+    pub fn act(&mut self, thing: &ThingHappened) -> Result<(), PhaseViolation> {
+        match (thing, self.phase) {
+            (ThingHappened::Move(..) | ThingHappened::Rotate(..), TurnPhase::Wait) => {
+                Err(PhaseViolation::NotYourTurn)
+            }
+            (ThingHappened::Move(..), TurnPhase::Rotate) => Err(PhaseViolation::MoveInRotatePhase),
+            (ThingHappened::Rotate(..), TurnPhase::Move) => Err(PhaseViolation::RotateInMovePhase),
+            (ThingHappened::Move(..), TurnPhase::Move) => {
+                self.phase = TurnPhase::Rotate;
+                Ok(())
+            }
+            (ThingHappened::Rotate(..), TurnPhase::Rotate) => {
+                self.end_turn();
+                Ok(())
+            }
+            (
+                ThingHappened::FirstTurn
+                | ThingHappened::PrevTurn
+                | ThingHappened::NextTurn
+                | ThingHappened::LastTurn,
+                _,
+            ) => Ok(()),
+        }
+    }
+
+    /// Check something the player we're waiting on just sent against the phase. Their
+    /// rotation finishes their turn, which makes it ours.
+    ///
+    /// If it isn't their turn, something went missing on the way, and the caller should get
+    /// a fresh copy of the game from them rather than play it.
+    pub fn receive(&mut self, thing: &ThingHappened) -> Result<(), PhaseViolation> {
+        match (thing, self.phase) {
+            (ThingHappened::Move(..), TurnPhase::Wait) => Ok(()),
+            (ThingHappened::Rotate(..), TurnPhase::Wait) => {
+                self.start_turn();
+                Ok(())
+            }
+            (ThingHappened::Move(..) | ThingHappened::Rotate(..), _) => {
+                Err(PhaseViolation::NotYourTurn)
+            }
+            (
+                ThingHappened::FirstTurn
+                | ThingHappened::PrevTurn
+                | ThingHappened::NextTurn
+                | ThingHappened::LastTurn,
+                _,
+            ) => Ok(()),
+        }
+    }
+
+    /// Our turn is over, whether we rotated or something else finished it for us.
+    pub fn end_turn(&mut self) {
+        self.phase = if self.hands_over {
+            TurnPhase::Wait
+        } else {
+            TurnPhase::Move
+        };
+    }
+
+    /// The player we were waiting on finished their turn, so it's ours now.
+    pub fn start_turn(&mut self) {
+        debug_assert!(self.waiting(), "We can't start a turn we already have.");
+        self.phase = TurnPhase::Move;
+    }
+
+    /// Start our turn over from its move, after some of it (or the turn before) was taken
+    /// back.
+    pub fn restart_turn(&mut self) {
+        self.phase = TurnPhase::Move;
+    }
+
+    /// Drop whatever we were doing and wait for someone else, e.g. while getting a copy of
+    /// the game from them.
+    pub fn wait(&mut self) {
+        self.phase = TurnPhase::Wait;
+    }
+
+    /// We've caught up on someone's copy of the game, and it's our turn or theirs as they
+    /// said it was.
+    pub fn finish_resync(&mut self, your_turn: bool) {
+        self.phase = if your_turn {
+            TurnPhase::Move
+        } else {
+            TurnPhase::Wait
+        };
+    }
+}
+
+#[cfg(test)]
+mod test_turn_phase_machine {
+    use super::*;
+
+    const MOVE: ThingHappened = ThingHappened::Move(0, 1., 1.);
+    const ROTATE: ThingHappened = ThingHappened::Rotate(0, 1.);
+
+    #[test]
+    fn a_turn_is_a_move_then_a_rotation() {
+        let mut turn = TurnPhaseMachine::new(true, true);
+        assert_eq!(turn.act(&MOVE), Ok(()));
+        assert_eq!(turn.phase(), TurnPhase::Rotate);
+        assert_eq!(turn.act(&ROTATE), Ok(()));
+        assert_eq!(turn.phase(), TurnPhase::Wait);
+        turn.start_turn();
+        assert_eq!(turn.phase(), TurnPhase::Move);
+    }
+
+    #[test]
+    fn out_of_order_actions_are_violations() {
+        let mut turn = TurnPhaseMachine::new(true, true);
+        assert_eq!(turn.act(&ROTATE), Err(PhaseViolation::RotateInMovePhase));
+        assert_eq!(turn.phase(), TurnPhase::Move);
+        turn.act(&MOVE).unwrap();
+        assert_eq!(turn.act(&MOVE), Err(PhaseViolation::MoveInRotatePhase));
+        assert_eq!(turn.phase(), TurnPhase::Rotate);
+
+        let mut turn = TurnPhaseMachine::new(false, true);
+        assert_eq!(turn.act(&MOVE), Err(PhaseViolation::NotYourTurn));
+        assert_eq!(turn.act(&ROTATE), Err(PhaseViolation::NotYourTurn));
+        assert!(turn.waiting());
+    }
+
+    #[test]
+    fn history_navigation_is_always_fine() {
+        for goes_first in [true, false] {
+            let mut turn = TurnPhaseMachine::new(goes_first, true);
+            let phase = turn.phase();
+            assert_eq!(turn.act(&ThingHappened::PrevTurn), Ok(()));
+            assert_eq!(turn.act(&ThingHappened::LastTurn), Ok(()));
+            assert_eq!(turn.phase(), phase);
+        }
+    }
+
+    #[test]
+    fn the_opponents_rotation_hands_us_the_turn() {
+        let mut turn = TurnPhaseMachine::new(false, true);
+        assert_eq!(turn.receive(&MOVE), Ok(()));
+        assert!(turn.waiting());
+        assert_eq!(turn.receive(&ROTATE), Ok(()));
+        assert_eq!(turn.phase(), TurnPhase::Move);
+        // they can't act on our turn, in either half of it.
+        assert_eq!(turn.receive(&MOVE), Err(PhaseViolation::NotYourTurn));
+        turn.act(&MOVE).unwrap();
+        assert_eq!(turn.receive(&ROTATE), Err(PhaseViolation::NotYourTurn));
+        assert_eq!(turn.phase(), TurnPhase::Rotate);
+    }
+
+    #[test]
+    fn without_handing_over_the_next_turn_starts_right_away() {
+        let mut turn = TurnPhaseMachine::new(true, false);
+        turn.act(&MOVE).unwrap();
+        turn.act(&ROTATE).unwrap();
+        assert_eq!(turn.phase(), TurnPhase::Move);
+    }
+
+    #[test]
+    fn taken_back_turns_start_over() {
+        let mut turn = TurnPhaseMachine::new(true, true);
+        turn.act(&MOVE).unwrap();
+        turn.restart_turn();
+        assert_eq!(turn.act(&ROTATE), Err(PhaseViolation::RotateInMovePhase));
+        assert_eq!(turn.act(&MOVE), Ok(()));
+
+        let mut turn = TurnPhaseMachine::new(false, true);
+        turn.restart_turn();
+        assert_eq!(turn.phase(), TurnPhase::Move);
+    }
+}
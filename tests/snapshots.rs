@@ -0,0 +1,23 @@
+//! The snapshot check from `cargo run -- --snapshots`, as a test (see
+//! [`rotchess_ggez::snapshots`]).
+//!
+//! It draws on the GPU, so it only runs with `ROTCHESS_GPU_TESTS=1 cargo test --test snapshots`,
+//! and otherwise says it's skipping. winit wants its event loop on the main thread, which the
+//! test harness doesn't run tests on, so this is a plain `main` (`harness = false`).
+
+use std::path::PathBuf;
+
+use ggez::GameResult;
+use rotchess_ggez::snapshots::{self, SnapshotMode};
+
+fn main() -> GameResult {
+    if std::env::var_os("ROTCHESS_GPU_TESTS").is_none() {
+        println!("skipping the snapshot check. set ROTCHESS_GPU_TESTS=1 to run it on a GPU.");
+        return Ok(());
+    }
+    // the same ids as the game, so references blessed either way land in the same place.
+    let (mut ctx, _event_loop) = ggez::ContextBuilder::new("super_simple", "ggez")
+        .add_resource_path(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources"))
+        .build()?;
+    snapshots::run(&mut ctx, SnapshotMode::Check)
+}